@@ -647,7 +647,11 @@ class ManagementService(
             .mapEmpty()
 
     suspend fun applySettingsPatch(json: String): Either<SettingsPatchError, Unit> =
-        Either.catch { grpc.applyJsonSettings(StringValue.of(json)) }
+        Either.catch {
+                grpc.applyJsonSettings(
+                    ManagementInterface.JsonSettingsPatch.newBuilder().setPatch(json).build()
+                )
+            }
             .mapLeftStatus {
                 when (it.status.code) {
                     // Currently we only get invalid argument errors from daemon via gRPC
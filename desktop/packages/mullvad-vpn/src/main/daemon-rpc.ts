@@ -647,7 +647,9 @@ export class DaemonRpc extends GrpcClient {
   }
 
   public async applyJsonSettings(settings: string): Promise<void> {
-    await this.callString(this.client.applyJsonSettings, settings);
+    const patch = new grpcTypes.JsonSettingsPatch();
+    patch.setPatch(settings);
+    await this.call<grpcTypes.JsonSettingsPatch, Empty>(this.client.applyJsonSettings, patch);
   }
 
   public async clearAllRelayOverrides(): Promise<void> {
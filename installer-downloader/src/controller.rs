@@ -213,6 +213,7 @@ where
         });
         let version_params = VersionParameters {
             architecture,
+            package_format: None,
             // For the downloader, the rollout version is always preferred
             rollout: mullvad_update::version::IGNORE,
             // The downloader allows any version
@@ -313,6 +313,7 @@ pub struct Runtime {
     address_cache: AddressCache,
     api_availability: availability::ApiAvailability,
     endpoint: ApiEndpoint,
+    request_stats: rest::RequestStats,
     #[cfg(target_os = "android")]
     socket_bypass_tx: Option<mpsc::Sender<SocketBypassRequest>>,
 }
@@ -344,6 +345,7 @@ impl Runtime {
             address_cache: AddressCache::new(endpoint, None),
             api_availability: ApiAvailability::default(),
             endpoint: endpoint.clone(),
+            request_stats: rest::RequestStats::default(),
             #[cfg(target_os = "android")]
             socket_bypass_tx,
         }
@@ -405,6 +407,7 @@ impl Runtime {
             address_cache,
             api_availability,
             endpoint: endpoint.clone(),
+            request_stats: rest::RequestStats::default(),
             #[cfg(target_os = "android")]
             socket_bypass_tx,
         })
@@ -455,6 +458,7 @@ impl Runtime {
             self.api_availability.clone(),
             connection_mode_provider,
             dns_resolver,
+            self.request_stats.clone(),
             #[cfg(target_os = "android")]
             socket_bypass_tx,
             #[cfg(any(feature = "api-override", test))]
@@ -462,6 +466,11 @@ impl Runtime {
         )
     }
 
+    /// Returns the request counters shared by all request services created by this runtime.
+    pub fn request_stats(&self) -> rest::RequestStats {
+        self.request_stats.clone()
+    }
+
     pub fn handle(&self) -> &tokio::runtime::Handle {
         &self.handle
     }
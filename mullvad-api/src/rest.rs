@@ -24,8 +24,11 @@ use std::{
     convert::Infallible,
     error::Error as StdError,
     str::FromStr,
-    sync::{Arc, Weak},
-    time::Duration,
+    sync::{
+        Arc, Weak,
+        atomic::{AtomicU64, Ordering},
+    },
+    time::{Duration, Instant},
 };
 use talpid_types::ErrorExt;
 
@@ -154,6 +157,7 @@ pub(crate) struct RequestService<T: ConnectionModeProvider> {
     connection_mode_provider: T,
     connection_mode_generation: usize,
     api_availability: ApiAvailability,
+    stats: RequestStats,
 }
 
 impl<T: ConnectionModeProvider + 'static> RequestService<T> {
@@ -162,6 +166,7 @@ impl<T: ConnectionModeProvider + 'static> RequestService<T> {
         api_availability: ApiAvailability,
         connection_mode_provider: T,
         dns_resolver: Arc<dyn DnsResolver>,
+        stats: RequestStats,
         #[cfg(target_os = "android")] socket_bypass_tx: Option<mpsc::Sender<SocketBypassRequest>>,
         #[cfg(any(feature = "api-override", test))] disable_tls: bool,
     ) -> RequestServiceHandle {
@@ -190,6 +195,7 @@ impl<T: ConnectionModeProvider + 'static> RequestService<T> {
             connection_mode_provider,
             connection_mode_generation: 0,
             api_availability,
+            stats,
         };
         let handle = RequestServiceHandle { tx: command_tx };
         tokio::spawn(service.into_future());
@@ -248,9 +254,15 @@ impl<T: ConnectionModeProvider + 'static> RequestService<T> {
             .into_future(self.client.clone(), api_availability.clone());
 
         let connection_mode_generation = self.connection_mode_generation;
+        let stats = self.stats.clone();
 
         tokio::spawn(async move {
+            let start = Instant::now();
             let response = request_future.await.map_err(|error| error.map_aborted());
+            match &response {
+                Err(error) if error.is_aborted() => (),
+                response => stats.record(start.elapsed(), response.is_err()),
+            }
 
             // Switch API endpoint if the request failed due to a network error
             if let Err(err) = &response
@@ -269,6 +281,79 @@ impl<T: ConnectionModeProvider + 'static> RequestService<T> {
     }
 }
 
+/// Upper bounds of the buckets that request latencies are sorted into by [`RequestStats`].
+pub const REQUEST_LATENCY_BUCKETS: [Duration; 8] = [
+    Duration::from_millis(100),
+    Duration::from_millis(250),
+    Duration::from_millis(500),
+    Duration::from_secs(1),
+    Duration::from_millis(2500),
+    Duration::from_secs(5),
+    Duration::from_secs(10),
+    Duration::from_secs(30),
+];
+
+/// Counts requests executed by request services, and how long they took. Cancelled requests are
+/// not counted.
+#[derive(Clone, Default, Debug)]
+pub struct RequestStats(Arc<RequestStatsInner>);
+
+#[derive(Default, Debug)]
+struct RequestStatsInner {
+    failures: AtomicU64,
+    /// Number of requests per latency bucket. The last bucket holds requests slower than the
+    /// largest bound in [`REQUEST_LATENCY_BUCKETS`].
+    latencies: [AtomicU64; REQUEST_LATENCY_BUCKETS.len() + 1],
+    latency_sum_micros: AtomicU64,
+}
+
+/// A copy of the counters in [`RequestStats`].
+#[derive(Clone, Debug)]
+pub struct RequestStatsSnapshot {
+    /// Total number of completed requests.
+    pub requests: u64,
+    /// Number of requests that returned an error, including unexpected HTTP status codes.
+    pub failures: u64,
+    /// Number of requests that completed within each bound in [`REQUEST_LATENCY_BUCKETS`].
+    pub latency_buckets: [u64; REQUEST_LATENCY_BUCKETS.len()],
+    /// Sum of the latencies of all completed requests.
+    pub latency_sum: Duration,
+}
+
+impl RequestStats {
+    fn record(&self, latency: Duration, failed: bool) {
+        if failed {
+            self.0.failures.fetch_add(1, Ordering::Relaxed);
+        }
+        let bucket = REQUEST_LATENCY_BUCKETS
+            .iter()
+            .position(|bound| latency <= *bound)
+            .unwrap_or(REQUEST_LATENCY_BUCKETS.len());
+        self.0.latencies[bucket].fetch_add(1, Ordering::Relaxed);
+        let micros = u64::try_from(latency.as_micros()).unwrap_or(u64::MAX);
+        self.0
+            .latency_sum_micros
+            .fetch_add(micros, Ordering::Relaxed);
+    }
+
+    pub fn snapshot(&self) -> RequestStatsSnapshot {
+        let mut latency_buckets = [0; REQUEST_LATENCY_BUCKETS.len()];
+        let mut requests = 0;
+        for (i, count) in self.0.latencies.iter().enumerate() {
+            requests += count.load(Ordering::Relaxed);
+            if let Some(bucket) = latency_buckets.get_mut(i) {
+                *bucket = requests;
+            }
+        }
+        RequestStatsSnapshot {
+            requests,
+            failures: self.0.failures.load(Ordering::Relaxed),
+            latency_buckets,
+            latency_sum: Duration::from_micros(self.0.latency_sum_micros.load(Ordering::Relaxed)),
+        }
+    }
+}
+
 #[derive(Clone)]
 /// A handle to interact with a spawned `RequestService`.
 pub struct RequestServiceHandle {
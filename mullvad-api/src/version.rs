@@ -64,6 +64,7 @@ impl AppVersionProxy {
         &self,
         platform: &str,
         architecture: mullvad_update::format::Architecture,
+        package_format: Option<mullvad_update::format::PackageFormat>,
         rollout: f32,
         lowest_metadata_version: usize,
     ) -> impl Future<Output = Result<AppVersionResponse2, rest::Error>> + use<> {
@@ -84,6 +85,7 @@ impl AppVersionProxy {
 
             let params = VersionParameters {
                 architecture,
+                package_format,
                 rollout,
                 lowest_metadata_version,
             };
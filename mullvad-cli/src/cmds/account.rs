@@ -2,9 +2,15 @@ use anyhow::{Result, anyhow};
 use clap::Subcommand;
 use itertools::Itertools;
 use mullvad_management_interface::MullvadProxyClient;
-use mullvad_types::{account::AccountNumber, device::DeviceState};
+use mullvad_types::{
+    account::{AccountData, AccountNumber},
+    device::DeviceState,
+};
+use serde::Serialize;
 use std::io::{self, Write};
 
+use crate::{format, print_text};
+
 const NOT_LOGGED_IN_MESSAGE: &str = "Not logged in on any account";
 const REVOKED_MESSAGE: &str = "The current device has been revoked";
 
@@ -83,19 +89,19 @@ impl Account {
 
     async fn create(rpc: &mut MullvadProxyClient) -> Result<()> {
         rpc.create_new_account().await?;
-        println!("New account created!");
+        print_text!("New account created!");
         Self::get(rpc, false).await
     }
 
     async fn login(rpc: &mut MullvadProxyClient, account_number: AccountNumber) -> Result<()> {
         rpc.login_account(account_number.clone()).await?;
-        println!("Mullvad account \"{account_number}\" set");
+        print_text!("Mullvad account \"{account_number}\" set");
         Ok(())
     }
 
     async fn logout(rpc: &mut MullvadProxyClient) -> Result<()> {
         rpc.logout_account().await?;
-        println!("Removed device from Mullvad account");
+        print_text!("Removed device from Mullvad account");
         Ok(())
     }
 
@@ -104,6 +110,19 @@ impl Account {
 
         let state = rpc.get_device().await?;
 
+        if format::is_json() {
+            let account_data = match &state {
+                DeviceState::LoggedIn(device) => {
+                    Some(rpc.get_account_data(device.account_number.clone()).await?)
+                }
+                DeviceState::LoggedOut | DeviceState::Revoked => None,
+            };
+            return format::print_json(&AccountInfo {
+                device_state: state,
+                account_data,
+            });
+        }
+
         match state {
             DeviceState::LoggedIn(device) => {
                 println!("{:<20}{}", "Mullvad account:", device.account_number);
@@ -146,9 +165,13 @@ impl Account {
     ) -> Result<()> {
         let account_number = account_else_current(rpc, account).await?;
         let mut device_list = rpc.list_devices(account_number).await?;
+        device_list.sort_unstable_by_key(|dev| dev.created.timestamp());
+
+        if format::is_json() {
+            return format::print_json(&device_list);
+        }
 
         println!("Devices on the account:");
-        device_list.sort_unstable_by_key(|dev| dev.created.timestamp());
         for device in device_list {
             if verbose {
                 println!();
@@ -184,7 +207,7 @@ impl Account {
             .ok_or(mullvad_management_interface::Error::DeviceNotFound)?;
 
         rpc.remove_device(account_number, device_id).await?;
-        println!("Removed device");
+        print_text!("Removed device");
         Ok(())
     }
 
@@ -192,6 +215,9 @@ impl Account {
         voucher.retain(|c| c.is_alphanumeric());
 
         let submission = rpc.submit_voucher(voucher).await?;
+        if format::is_json() {
+            return format::print_json(&submission);
+        }
         println!(
             "Added {} to the account",
             format_duration(submission.time_added)
@@ -204,6 +230,14 @@ impl Account {
     }
 }
 
+/// JSON output of `account get`.
+#[derive(Serialize)]
+struct AccountInfo {
+    device_state: DeviceState,
+    /// `null` unless logged in.
+    account_data: Option<AccountData>,
+}
+
 async fn account_else_current(
     rpc: &mut MullvadProxyClient,
     account_number: Option<String>,
@@ -1,8 +1,13 @@
 use anyhow::Result;
+#[cfg(target_os = "linux")]
+use clap::Args;
 use clap::Subcommand;
 use mullvad_management_interface::MullvadProxyClient;
+#[cfg(target_os = "linux")]
+use mullvad_types::auto_connect::{AutoConnectAction, AutoConnectRule, NetworkMatcher};
 
 use super::BooleanOption;
+use crate::{format, print_text};
 
 #[derive(Subcommand, Debug)]
 pub enum AutoConnect {
@@ -10,6 +15,67 @@ pub enum AutoConnect {
     Get,
     /// Change auto-connect setting
     Set { policy: BooleanOption },
+
+    /// Manage rules that connect, disconnect or block when joining specific networks
+    #[cfg(target_os = "linux")]
+    #[clap(subcommand)]
+    Rule(AutoConnectRuleCommand),
+}
+
+#[cfg(target_os = "linux")]
+#[derive(Subcommand, Debug)]
+pub enum AutoConnectRuleCommand {
+    /// List all auto-connect rules. The first rule that matches the current network is applied
+    List,
+
+    /// Add a rule to the end of the list
+    Add {
+        #[clap(flatten)]
+        network: NetworkArgs,
+
+        /// What to do when joining a matching network
+        action: AutoConnectAction,
+    },
+
+    /// Remove a rule
+    Remove {
+        /// Index of the rule to remove, as shown by `list`
+        index: u32,
+    },
+}
+
+#[cfg(target_os = "linux")]
+#[derive(Args, Debug)]
+#[group(required = true, multiple = false)]
+pub struct NetworkArgs {
+    /// Match Wi-Fi networks with this SSID
+    #[arg(long)]
+    ssid: Option<String>,
+
+    /// Match networks whose default gateway has this MAC address
+    #[arg(long)]
+    gateway_mac: Option<String>,
+
+    /// Match when this network interface is used to reach the internet
+    #[arg(long)]
+    interface: Option<String>,
+}
+
+#[cfg(target_os = "linux")]
+impl NetworkArgs {
+    fn into_matcher(self) -> Result<NetworkMatcher> {
+        if let Some(ssid) = self.ssid {
+            return Ok(NetworkMatcher::Ssid(ssid));
+        }
+        if let Some(mac) = self.gateway_mac {
+            return NetworkMatcher::gateway_mac(&mac)
+                .ok_or_else(|| anyhow::anyhow!("Invalid MAC address: {mac}"));
+        }
+        if let Some(interface) = self.interface {
+            return Ok(NetworkMatcher::Interface(interface));
+        }
+        unreachable!("clap requires one of the network arguments")
+    }
 }
 
 impl AutoConnect {
@@ -17,20 +83,86 @@ impl AutoConnect {
         match self {
             AutoConnect::Get => Self::get().await,
             AutoConnect::Set { policy } => Self::set(policy).await,
+            #[cfg(target_os = "linux")]
+            AutoConnect::Rule(AutoConnectRuleCommand::List) => Self::list_rules().await,
+            #[cfg(target_os = "linux")]
+            AutoConnect::Rule(AutoConnectRuleCommand::Add { network, action }) => {
+                Self::add_rule(AutoConnectRule {
+                    network: network.into_matcher()?,
+                    action,
+                })
+                .await
+            }
+            #[cfg(target_os = "linux")]
+            AutoConnect::Rule(AutoConnectRuleCommand::Remove { index }) => {
+                Self::remove_rule(index).await
+            }
         }
     }
 
     async fn set(policy: BooleanOption) -> Result<()> {
         let mut rpc = MullvadProxyClient::new().await?;
         rpc.set_auto_connect(*policy).await?;
-        println!("Changed auto-connect setting");
+        print_text!("Changed auto-connect setting");
         Ok(())
     }
 
     async fn get() -> Result<()> {
         let mut rpc = MullvadProxyClient::new().await?;
-        let auto_connect = BooleanOption::from(rpc.get_settings().await?.auto_connect);
+        let settings = rpc.get_settings().await?;
+        if format::is_json() {
+            return format::print_json(&serde_json::json!({
+                "auto_connect": settings.auto_connect,
+                "auto_connect_rules": settings.auto_connect_rules,
+            }));
+        }
+        let auto_connect = BooleanOption::from(settings.auto_connect);
         println!("Autoconnect: {auto_connect}");
+        #[cfg(target_os = "linux")]
+        if !settings.auto_connect_rules.is_empty() {
+            println!("Rules:");
+            print_rules(&settings.auto_connect_rules);
+        }
+        Ok(())
+    }
+
+    #[cfg(target_os = "linux")]
+    async fn list_rules() -> Result<()> {
+        let mut rpc = MullvadProxyClient::new().await?;
+        let rules = rpc.get_settings().await?.auto_connect_rules;
+        if format::is_json() {
+            return format::print_json(&rules);
+        }
+        if rules.is_empty() {
+            println!("No auto-connect rules");
+        }
+        print_rules(&rules);
+        Ok(())
+    }
+
+    #[cfg(target_os = "linux")]
+    async fn add_rule(rule: AutoConnectRule) -> Result<()> {
+        let mut rpc = MullvadProxyClient::new().await?;
+        rpc.add_auto_connect_rule(rule).await?;
+        print_text!("Added auto-connect rule");
         Ok(())
     }
+
+    #[cfg(target_os = "linux")]
+    async fn remove_rule(index: u32) -> Result<()> {
+        let Some(index) = index.checked_sub(1) else {
+            anyhow::bail!("Rule indices start at 1");
+        };
+        let mut rpc = MullvadProxyClient::new().await?;
+        rpc.remove_auto_connect_rule(index).await?;
+        print_text!("Removed auto-connect rule");
+        Ok(())
+    }
+}
+
+#[cfg(target_os = "linux")]
+fn print_rules(rules: &[AutoConnectRule]) {
+    for (index, rule) in rules.iter().enumerate() {
+        println!("{}. {rule}", index + 1);
+    }
 }
@@ -11,7 +11,7 @@ use mullvad_types::{
 };
 use talpid_types::net::proxy::{CustomProxy, Shadowsocks, Socks5Local, Socks5Remote};
 
-use crate::cmds::proxies::pp::CustomProxyFormatter;
+use crate::{cmds::proxies::pp::CustomProxyFormatter, format, print_text};
 
 use super::{
     proxies::{ProxyEditParams, ShadowsocksAdd, Socks5LocalAdd, Socks5RemoteAdd},
@@ -138,7 +138,7 @@ impl Bridge {
         match subcmd {
             SetCommands::State { policy } => {
                 rpc.set_bridge_state(policy).await?;
-                println!("Updated bridge state");
+                print_text!("Updated bridge state");
                 Ok(())
             }
             SetCommands::Location(location_constraint_args) => {
@@ -176,6 +176,12 @@ impl Bridge {
     async fn get() -> Result<()> {
         let mut rpc = MullvadProxyClient::new().await?;
         let settings = rpc.get_settings().await?;
+        if format::is_json() {
+            return format::print_json(&serde_json::json!({
+                "bridge_state": settings.bridge_state,
+                "bridge_settings": settings.bridge_settings,
+            }));
+        }
         println!("Bridge state: {}", settings.bridge_state);
         println!(
             "Active bridge type: {}",
@@ -227,14 +233,23 @@ impl Bridge {
         }
 
         countries.sort_by(|c1, c2| natord::compare_ignore_case(&c1.name, &c2.name));
-        for mut country in countries {
+        for country in &mut countries {
             country
                 .cities
                 .sort_by(|c1, c2| natord::compare_ignore_case(&c1.name, &c2.name));
-            println!("{} ({})", country.name, country.code);
-            for mut city in country.cities {
+            for city in &mut country.cities {
                 city.relays
                     .sort_by(|r1, r2| natord::compare_ignore_case(&r1.hostname, &r2.hostname));
+            }
+        }
+
+        if format::is_json() {
+            return format::print_json(&countries);
+        }
+
+        for country in countries {
+            println!("{} ({})", country.name, country.code);
+            for city in country.cities {
                 println!(
                     "\t{} ({}) @ {:.5}°N, {:.5}°W",
                     city.name, city.code, city.latitude, city.longitude
@@ -277,7 +292,7 @@ impl Bridge {
 
         rpc.set_bridge_settings(settings).await?;
 
-        println!("Updated bridge settings");
+        print_text!("Updated bridge settings");
 
         Ok(())
     }
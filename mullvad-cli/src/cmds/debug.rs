@@ -5,6 +5,8 @@ use mullvad_types::{
     relay_constraints::{RelayConstraints, RelaySettings},
 };
 
+use crate::format;
+
 #[derive(clap::Subcommand, Debug)]
 pub enum DebugCommands {
     /// Block all internet connection by setting an invalid relay constraint.
@@ -12,6 +14,12 @@ pub enum DebugCommands {
     /// Relay
     #[clap(subcommand)]
     Relay(RelayDebugCommands),
+    /// Try to leak traffic outside the tunnel. Only available while connected.
+    LeakCheck {
+        /// Show the results of the most recent leak checks instead of running a new one
+        #[arg(long)]
+        history: bool,
+    },
 }
 
 #[derive(clap::Subcommand, Debug)]
@@ -66,6 +74,23 @@ impl DebugCommands {
                 println!("{relay} is now marked as active");
                 Ok(())
             }
+            DebugCommands::LeakCheck { history: true } => {
+                let mut rpc = MullvadProxyClient::new().await?;
+                let history = rpc.get_leak_check_history().await?;
+                if history.is_empty() {
+                    println!("No leak checks have been run");
+                }
+                for result in &history {
+                    format::print_leak_check_result(result);
+                }
+                Ok(())
+            }
+            DebugCommands::LeakCheck { history: false } => {
+                let mut rpc = MullvadProxyClient::new().await?;
+                let result = rpc.run_leak_check().await?;
+                format::print_leak_check_result(&result);
+                Ok(())
+            }
         }
     }
 }
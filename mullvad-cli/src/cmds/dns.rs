@@ -2,8 +2,12 @@ use anyhow::Result;
 use clap::Subcommand;
 use mullvad_management_interface::MullvadProxyClient;
 use mullvad_types::settings::{CustomDnsOptions, DefaultDnsOptions, DnsOptions, DnsState};
+#[cfg(target_os = "linux")]
+use mullvad_types::settings::{DomainDnsRule, EncryptedDnsProtocol, EncryptedDnsServer};
 use std::net::IpAddr;
 
+use crate::{format, print_text};
+
 #[derive(Subcommand, Debug)]
 pub enum Dns {
     /// Display the current DNS settings
@@ -14,6 +18,35 @@ pub enum Dns {
         #[clap(subcommand)]
         cmd: DnsSet,
     },
+
+    /// Manage DNS servers to use for specific domains
+    #[cfg(target_os = "linux")]
+    #[clap(subcommand)]
+    Rule(DnsRule),
+}
+
+#[cfg(target_os = "linux")]
+#[derive(Subcommand, Debug, Clone)]
+pub enum DnsRule {
+    /// List all per-domain DNS rules
+    List,
+
+    /// Send queries for a domain and its subdomains to specific DNS servers. This replaces any
+    /// existing rule for the domain
+    Add {
+        /// Domain that the rule applies to, e.g. corp.example or *.corp.example
+        domain: String,
+
+        /// One or more IP addresses of the DNS servers to use for the domain
+        #[arg(required(true), num_args = 1..)]
+        servers: Vec<IpAddr>,
+    },
+
+    /// Remove the rule for a domain
+    Remove {
+        /// Domain of the rule to remove
+        domain: String,
+    },
 }
 
 #[derive(Subcommand, Debug, Clone)]
@@ -52,6 +85,43 @@ pub enum DnsSet {
         #[arg(required(true), num_args = 1..)]
         servers: Vec<IpAddr>,
     },
+
+    /// Set a list of custom DNS-over-HTTPS or DNS-over-TLS servers. Queries are forwarded to
+    /// them by a local resolver
+    #[cfg(target_os = "linux")]
+    Encrypted {
+        /// Protocol used to reach the servers
+        #[arg(value_enum)]
+        protocol: EncryptedProtocol,
+
+        /// Name used to verify the certificates of the servers, e.g. dns.example.com
+        hostname: String,
+
+        /// One or more IP addresses of the servers
+        #[arg(required(true), num_args = 1..)]
+        servers: Vec<IpAddr>,
+
+        /// Port that the servers listen on. Defaults to 443 for HTTPS and 853 for TLS
+        #[arg(long)]
+        port: Option<u16>,
+    },
+}
+
+#[cfg(target_os = "linux")]
+#[derive(clap::ValueEnum, Debug, Clone, Copy)]
+pub enum EncryptedProtocol {
+    Https,
+    Tls,
+}
+
+#[cfg(target_os = "linux")]
+impl From<EncryptedProtocol> for EncryptedDnsProtocol {
+    fn from(protocol: EncryptedProtocol) -> Self {
+        match protocol {
+            EncryptedProtocol::Https => EncryptedDnsProtocol::Https,
+            EncryptedProtocol::Tls => EncryptedDnsProtocol::Tls,
+        }
+    }
 }
 
 impl Dns {
@@ -82,12 +152,33 @@ impl Dns {
             Dns::Set {
                 cmd: DnsSet::Custom { servers },
             } => Self::set_custom(servers).await,
+            #[cfg(target_os = "linux")]
+            Dns::Set {
+                cmd:
+                    DnsSet::Encrypted {
+                        protocol,
+                        hostname,
+                        servers,
+                        port,
+                    },
+            } => Self::set_encrypted(protocol.into(), hostname, servers, port).await,
+            #[cfg(target_os = "linux")]
+            Dns::Rule(DnsRule::List) => Self::list_rules().await,
+            #[cfg(target_os = "linux")]
+            Dns::Rule(DnsRule::Add { domain, servers }) => {
+                Self::add_rule(DomainDnsRule::new(&domain, servers)?).await
+            }
+            #[cfg(target_os = "linux")]
+            Dns::Rule(DnsRule::Remove { domain }) => Self::remove_rule(&domain).await,
         }
     }
 
     async fn get() -> Result<()> {
         let mut rpc = MullvadProxyClient::new().await?;
         let options = rpc.get_settings().await?.tunnel_options.dns_options;
+        if format::is_json() {
+            return format::print_json(&serde_json::json!({ "dns_options": options }));
+        }
 
         match options.state {
             DnsState::Default => {
@@ -110,6 +201,18 @@ impl Dns {
                 for server in &options.custom_options.addresses {
                     println!("{server}");
                 }
+                if !options.custom_options.encrypted_servers.is_empty() {
+                    println!("Encrypted servers:");
+                    for server in &options.custom_options.encrypted_servers {
+                        println!("{server}");
+                    }
+                }
+            }
+        }
+        if !options.domain_rules.is_empty() {
+            println!("Domain rules:");
+            for rule in &options.domain_rules {
+                println!("{rule}");
             }
         }
 
@@ -139,7 +242,7 @@ impl Dns {
             ..settings.tunnel_options.dns_options
         })
         .await?;
-        println!("Updated DNS settings");
+        print_text!("Updated DNS settings");
         Ok(())
     }
 
@@ -148,11 +251,93 @@ impl Dns {
         let settings = rpc.get_settings().await?;
         rpc.set_dns_options(DnsOptions {
             state: DnsState::Custom,
-            custom_options: CustomDnsOptions { addresses: servers },
+            custom_options: CustomDnsOptions {
+                addresses: servers,
+                encrypted_servers: vec![],
+            },
             ..settings.tunnel_options.dns_options
         })
         .await?;
-        println!("Updated DNS settings");
+        print_text!("Updated DNS settings");
+        Ok(())
+    }
+
+    #[cfg(target_os = "linux")]
+    async fn set_encrypted(
+        protocol: EncryptedDnsProtocol,
+        hostname: String,
+        servers: Vec<IpAddr>,
+        port: Option<u16>,
+    ) -> Result<()> {
+        let mut rpc = MullvadProxyClient::new().await?;
+        let settings = rpc.get_settings().await?;
+        let port = port.unwrap_or(protocol.default_port());
+        let encrypted_servers = servers
+            .into_iter()
+            .map(|address| EncryptedDnsServer {
+                protocol,
+                address,
+                port,
+                hostname: hostname.clone(),
+            })
+            .collect();
+        let dns_options = settings.tunnel_options.dns_options;
+        rpc.set_dns_options(DnsOptions {
+            state: DnsState::Custom,
+            custom_options: CustomDnsOptions {
+                encrypted_servers,
+                ..dns_options.custom_options.clone()
+            },
+            ..dns_options
+        })
+        .await?;
+        print_text!("Updated DNS settings");
+        Ok(())
+    }
+
+    #[cfg(target_os = "linux")]
+    async fn list_rules() -> Result<()> {
+        let mut rpc = MullvadProxyClient::new().await?;
+        let options = rpc.get_settings().await?.tunnel_options.dns_options;
+        if format::is_json() {
+            return format::print_json(&options.domain_rules);
+        }
+        if options.domain_rules.is_empty() {
+            println!("No domain rules");
+        }
+        for rule in &options.domain_rules {
+            println!("{rule}");
+        }
+        Ok(())
+    }
+
+    #[cfg(target_os = "linux")]
+    async fn add_rule(rule: DomainDnsRule) -> Result<()> {
+        let mut rpc = MullvadProxyClient::new().await?;
+        let mut dns_options = rpc.get_settings().await?.tunnel_options.dns_options;
+        dns_options
+            .domain_rules
+            .retain(|existing| existing.domain != rule.domain);
+        dns_options.domain_rules.push(rule);
+        rpc.set_dns_options(dns_options).await?;
+        print_text!("Updated DNS settings");
+        Ok(())
+    }
+
+    #[cfg(target_os = "linux")]
+    async fn remove_rule(domain: &str) -> Result<()> {
+        let domain = DomainDnsRule::new(domain, vec![])?.domain;
+        let mut rpc = MullvadProxyClient::new().await?;
+        let mut dns_options = rpc.get_settings().await?.tunnel_options.dns_options;
+        let num_rules = dns_options.domain_rules.len();
+        dns_options
+            .domain_rules
+            .retain(|existing| existing.domain != domain);
+        if dns_options.domain_rules.len() == num_rules {
+            anyhow::bail!("There is no rule for the domain {domain}");
+        }
+        rpc.set_dns_options(dns_options).await?;
+        print_text!("Updated DNS settings");
         Ok(())
     }
 }
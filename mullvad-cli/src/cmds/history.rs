@@ -0,0 +1,24 @@
+use anyhow::Result;
+use mullvad_management_interface::MullvadProxyClient;
+
+use crate::format;
+
+pub async fn handle(count: Option<usize>) -> Result<()> {
+    let mut rpc = MullvadProxyClient::new().await?;
+    let mut history = rpc.get_connection_history().await?;
+    if let Some(count) = count {
+        history.drain(..history.len().saturating_sub(count));
+    }
+
+    if format::is_json() {
+        return format::print_json(&history);
+    }
+
+    if history.is_empty() {
+        println!("No tunnel sessions have been recorded");
+    }
+    for session in &history {
+        format::print_connection_session(session);
+    }
+    Ok(())
+}
@@ -1,8 +1,13 @@
 use anyhow::Result;
 use clap::Subcommand;
+#[cfg(target_os = "linux")]
+use ipnetwork::IpNetwork;
 use mullvad_management_interface::MullvadProxyClient;
+#[cfg(target_os = "linux")]
+use talpid_types::net::{AllowedInterface, FirewallException, TransportProtocol};
 
 use super::BooleanOption;
+use crate::{format, print_text};
 
 #[derive(Subcommand, Debug)]
 pub enum Lan {
@@ -14,6 +19,74 @@ pub enum Lan {
         #[arg(value_parser = BooleanOption::custom_parser("allow", "block"))]
         policy: BooleanOption,
     },
+
+    /// Manage exceptions that allow traffic to specific networks outside the tunnel
+    #[cfg(target_os = "linux")]
+    #[clap(subcommand)]
+    Exception(ExceptionCommand),
+
+    /// Manage interfaces of other VPNs or overlay networks, such as Tailscale or Docker bridges,
+    /// that should neither be blocked nor bypassed by the tunnel
+    #[cfg(target_os = "linux")]
+    #[clap(subcommand)]
+    Interface(InterfaceCommand),
+}
+
+#[cfg(target_os = "linux")]
+#[derive(Subcommand, Debug)]
+pub enum ExceptionCommand {
+    /// List all firewall exceptions
+    List,
+
+    /// Allow traffic to and from a network outside the tunnel, in every tunnel state
+    Add {
+        /// Network to allow, such as 192.0.2.10 or 100.64.0.0/10
+        network: IpNetwork,
+
+        /// Only allow this transport protocol
+        #[arg(long)]
+        protocol: Option<TransportProtocol>,
+
+        /// Only allow connections to this port on the remote hosts. Incoming connections are
+        /// only allowed if no port is given
+        #[arg(long)]
+        port: Option<u16>,
+
+        /// Only allow traffic over this interface, and leave routing to the routing table.
+        /// Otherwise, the traffic is routed outside the tunnel
+        #[arg(long)]
+        interface: Option<String>,
+    },
+
+    /// Remove an exception
+    Remove {
+        /// Index of the exception to remove, as shown by `list`
+        index: u32,
+    },
+}
+
+#[cfg(target_os = "linux")]
+#[derive(Subcommand, Debug)]
+pub enum InterfaceCommand {
+    /// List all allowed interfaces
+    List,
+
+    /// Allow all traffic on an interface, replacing any existing entry for it
+    Add {
+        /// Name of the interface, such as tailscale0 or docker0
+        name: String,
+
+        /// Network to route through the interface rather than the tunnel, such as
+        /// 100.64.0.0/10. May be given multiple times
+        #[arg(long = "network")]
+        networks: Vec<IpNetwork>,
+    },
+
+    /// Remove an allowed interface
+    Remove {
+        /// Name of the interface to remove
+        name: String,
+    },
 }
 
 impl Lan {
@@ -21,21 +94,120 @@ impl Lan {
         match self {
             Lan::Get => Self::get().await,
             Lan::Set { policy } => Self::set(policy).await,
+            #[cfg(target_os = "linux")]
+            Lan::Exception(ExceptionCommand::List) => Self::list_exceptions().await,
+            #[cfg(target_os = "linux")]
+            Lan::Exception(ExceptionCommand::Add {
+                network,
+                protocol,
+                port,
+                interface,
+            }) => {
+                Self::add_exception(FirewallException {
+                    network,
+                    protocol,
+                    port,
+                    interface,
+                })
+                .await
+            }
+            #[cfg(target_os = "linux")]
+            Lan::Exception(ExceptionCommand::Remove { index }) => {
+                Self::remove_exception(index).await
+            }
+            #[cfg(target_os = "linux")]
+            Lan::Interface(InterfaceCommand::List) => Self::list_interfaces().await,
+            #[cfg(target_os = "linux")]
+            Lan::Interface(InterfaceCommand::Add { name, networks }) => {
+                Self::add_interface(AllowedInterface { name, networks }).await
+            }
+            #[cfg(target_os = "linux")]
+            Lan::Interface(InterfaceCommand::Remove { name }) => Self::remove_interface(name).await,
         }
     }
 
     async fn set(policy: BooleanOption) -> Result<()> {
         let mut rpc = MullvadProxyClient::new().await?;
         rpc.set_allow_lan(*policy).await?;
-        println!("Changed local network sharing setting");
+        print_text!("Changed local network sharing setting");
         Ok(())
     }
 
     async fn get() -> Result<()> {
         let mut rpc = MullvadProxyClient::new().await?;
-        let allow_lan =
-            BooleanOption::with_labels(rpc.get_settings().await?.allow_lan, "allow", "block");
+        let allow_lan = rpc.get_settings().await?.allow_lan;
+        if format::is_json() {
+            return format::print_json(&serde_json::json!({ "allow_lan": allow_lan }));
+        }
+        let allow_lan = BooleanOption::with_labels(allow_lan, "allow", "block");
         println!("Local network sharing setting: {allow_lan}");
         Ok(())
     }
+
+    #[cfg(target_os = "linux")]
+    async fn list_exceptions() -> Result<()> {
+        let mut rpc = MullvadProxyClient::new().await?;
+        let exceptions = rpc.get_settings().await?.firewall_exceptions;
+        if format::is_json() {
+            return format::print_json(&exceptions);
+        }
+        if exceptions.is_empty() {
+            println!("No firewall exceptions");
+        }
+        for (index, exception) in exceptions.iter().enumerate() {
+            println!("{}. {exception}", index + 1);
+        }
+        Ok(())
+    }
+
+    #[cfg(target_os = "linux")]
+    async fn add_exception(exception: FirewallException) -> Result<()> {
+        let mut rpc = MullvadProxyClient::new().await?;
+        rpc.add_firewall_exception(exception).await?;
+        print_text!("Added firewall exception");
+        Ok(())
+    }
+
+    #[cfg(target_os = "linux")]
+    async fn remove_exception(index: u32) -> Result<()> {
+        let Some(index) = index.checked_sub(1) else {
+            anyhow::bail!("Exception indices start at 1");
+        };
+        let mut rpc = MullvadProxyClient::new().await?;
+        rpc.remove_firewall_exception(index).await?;
+        print_text!("Removed firewall exception");
+        Ok(())
+    }
+
+    #[cfg(target_os = "linux")]
+    async fn list_interfaces() -> Result<()> {
+        let mut rpc = MullvadProxyClient::new().await?;
+        let interfaces = rpc.get_settings().await?.allowed_interfaces;
+        if format::is_json() {
+            return format::print_json(&interfaces);
+        }
+        if interfaces.is_empty() {
+            println!("No allowed interfaces");
+        }
+        for interface in interfaces {
+            println!("{interface}");
+        }
+        Ok(())
+    }
+
+    #[cfg(target_os = "linux")]
+    async fn add_interface(interface: AllowedInterface) -> Result<()> {
+        let mut rpc = MullvadProxyClient::new().await?;
+        rpc.add_allowed_interface(interface).await?;
+        print_text!("Added allowed interface");
+        Ok(())
+    }
+
+    #[cfg(target_os = "linux")]
+    async fn remove_interface(name: String) -> Result<()> {
+        let mut rpc = MullvadProxyClient::new().await?;
+        rpc.remove_allowed_interface(name).await?;
+        print_text!("Removed allowed interface");
+        Ok(())
+    }
 }
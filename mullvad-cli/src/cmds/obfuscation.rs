@@ -8,6 +8,8 @@ use mullvad_types::{
     },
 };
 
+use crate::{format, print_text};
+
 #[derive(Subcommand, Debug)]
 pub enum Obfuscation {
     /// Get current obfuscation settings
@@ -44,6 +46,11 @@ impl Obfuscation {
             Obfuscation::Get => {
                 let mut rpc = MullvadProxyClient::new().await?;
                 let obfuscation_settings = rpc.get_settings().await?.obfuscation_settings;
+                if format::is_json() {
+                    return format::print_json(
+                        &serde_json::json!({ "obfuscation_settings": obfuscation_settings }),
+                    );
+                }
                 println!(
                     "Obfuscation mode: {}",
                     obfuscation_settings.selected_obfuscation
@@ -84,7 +91,7 @@ impl Obfuscation {
             }
         }
 
-        println!("Updated obfuscation settings");
+        print_text!("Updated obfuscation settings");
 
         Ok(())
     }
@@ -5,12 +5,16 @@ use std::{
     io::{BufReader, read_to_string, stdin},
 };
 
+use crate::print_text;
+
 /// Read a settings patch and send it to the daemon for validation and
 /// application.
 ///
 /// * If `source` is "-", read the patch from standard input
 /// * Otherwise, interpret `source` as a filepath and read from the provided file
-pub async fn import(source: String) -> Result<()> {
+///
+/// Sensitive settings are rejected by the daemon unless `allow_sensitive` is set.
+pub async fn import(source: String, allow_sensitive: bool) -> Result<()> {
     let json_blob = tokio::task::spawn_blocking(move || match source.as_str() {
         "-" => read_to_string(BufReader::new(stdin())).context("Failed to read from stdin"),
         _ => read_to_string(File::open(&source)?)
@@ -20,11 +24,11 @@ pub async fn import(source: String) -> Result<()> {
     .unwrap()?;
 
     let mut rpc = MullvadProxyClient::new().await?;
-    rpc.apply_json_settings(json_blob)
+    rpc.apply_json_settings(json_blob, allow_sensitive)
         .await
         .context("Error applying patch")?;
 
-    println!("Settings applied");
+    print_text!("Settings applied");
 
     Ok(())
 }
@@ -33,10 +37,12 @@ pub async fn import(source: String) -> Result<()> {
 ///
 /// * If `source` is "-", write the patch to standard output
 /// * Otherwise, interpret `source` as a filepath and write to the provided file
-pub async fn export(dest: String) -> Result<()> {
+///
+/// Sensitive settings are only included if `include_sensitive` is set.
+pub async fn export(dest: String, include_sensitive: bool) -> Result<()> {
     let mut rpc = MullvadProxyClient::new().await?;
     let blob = rpc
-        .export_json_settings()
+        .export_json_settings(include_sensitive)
         .await
         .context("Error exporting patch")?;
 
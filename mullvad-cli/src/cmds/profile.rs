@@ -0,0 +1,83 @@
+use anyhow::Result;
+use clap::Subcommand;
+use mullvad_management_interface::MullvadProxyClient;
+
+use crate::{format, print_text};
+
+#[derive(Subcommand, Debug)]
+pub enum Profile {
+    /// List all settings profiles
+    List,
+
+    /// Save the relay, obfuscation, DNS and tunnel settings as a profile. An existing profile
+    /// with the same name is replaced
+    Save {
+        /// Name of the profile
+        name: String,
+    },
+
+    /// Apply the settings in a profile. The tunnel is reconnected once, if needed
+    Activate {
+        /// Name of the profile
+        name: String,
+    },
+
+    /// Delete a profile
+    Delete {
+        /// Name of the profile
+        name: String,
+    },
+}
+
+impl Profile {
+    pub async fn handle(self) -> Result<()> {
+        match self {
+            Profile::List => Self::list().await,
+            Profile::Save { name } => Self::save(name).await,
+            Profile::Activate { name } => Self::activate(name).await,
+            Profile::Delete { name } => Self::delete(name).await,
+        }
+    }
+
+    async fn list() -> Result<()> {
+        let mut rpc = MullvadProxyClient::new().await?;
+        let profiles = rpc.list_profiles().await?;
+        if format::is_json() {
+            return format::print_json(&profiles);
+        }
+        if profiles.is_empty() {
+            println!("No settings profiles");
+            return Ok(());
+        }
+        let settings = rpc.get_settings().await?;
+        for profile in profiles {
+            if profile.is_active(&settings) {
+                println!("{} (active)", profile.name);
+            } else {
+                println!("{}", profile.name);
+            }
+        }
+        Ok(())
+    }
+
+    async fn save(name: String) -> Result<()> {
+        let mut rpc = MullvadProxyClient::new().await?;
+        rpc.save_profile(name.clone()).await?;
+        print_text!("Saved settings profile \"{name}\"");
+        Ok(())
+    }
+
+    async fn activate(name: String) -> Result<()> {
+        let mut rpc = MullvadProxyClient::new().await?;
+        rpc.activate_profile(name.clone()).await?;
+        print_text!("Activated settings profile \"{name}\"");
+        Ok(())
+    }
+
+    async fn delete(name: String) -> Result<()> {
+        let mut rpc = MullvadProxyClient::new().await?;
+        rpc.delete_profile(name.clone()).await?;
+        print_text!("Deleted settings profile \"{name}\"");
+        Ok(())
+    }
+}
@@ -9,19 +9,22 @@ use mullvad_types::{
     relay_constraints::{
         GeographicLocationConstraint, LocationConstraint, LocationConstraintFormatter,
         OpenVpnConstraints, Ownership, Provider, Providers, RelayConstraints, RelayOverride,
-        RelaySettings, TransportPort, WireguardConstraints, allowed_ip::AllowedIps,
+        RelaySettings, SelectionStrategy, TransportPort, WireguardConstraints,
+        allowed_ip::AllowedIps,
     },
     relay_list::{RelayEndpointData, RelayListCountry},
+    wg_quick::WgQuickConfig,
 };
 use std::{
     collections::HashMap,
     io::BufRead,
     net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr},
+    path::{Path, PathBuf},
 };
 use talpid_types::net::{Endpoint, IpVersion, TransportProtocol, TunnelType, openvpn, wireguard};
 
 use super::{BooleanOption, relay_constraints::LocationArgs};
-use crate::{cmds::receive_confirmation, print_option};
+use crate::{cmds::receive_confirmation, format, print_option, print_text};
 
 #[derive(Subcommand, Debug)]
 pub enum Relay {
@@ -33,7 +36,12 @@ pub enum Relay {
     Set(SetCommands),
 
     /// List available relays
-    List,
+    List {
+        /// Show the relays that recently failed to connect instead. These are less likely to
+        /// be selected until the failures have been forgotten.
+        #[arg(long)]
+        health: bool,
+    },
 
     /// Update the relay list
     Update,
@@ -90,6 +98,11 @@ pub enum SetCommands {
         ownership: Constraint<Ownership>,
     },
 
+    /// Set how to choose between relays that match the constraints:
+    /// 'random', or 'fastest'. 'fastest' picks the WireGuard relay with
+    /// the lowest measured latency.
+    Strategy { strategy: SelectionStrategy },
+
     /// Set tunnel protocol specific constraints
     #[clap(subcommand)]
     Tunnel(SetTunnelCommands),
@@ -194,21 +207,32 @@ pub enum SetCustomCommands {
     #[clap(arg_required_else_help = true)]
     Wireguard {
         /// Hostname or IP
-        host: String,
+        #[arg(required_unless_present = "from_file")]
+        host: Option<String>,
         /// Remote port
-        port: u16,
+        #[arg(required_unless_present = "from_file")]
+        port: Option<u16>,
         /// Base64 encoded public key of remote peer
-        #[arg(value_parser = wireguard::PublicKey::from_base64)]
-        peer_pubkey: wireguard::PublicKey,
+        #[arg(
+            value_parser = wireguard::PublicKey::from_base64,
+            required_unless_present = "from_file"
+        )]
+        peer_pubkey: Option<wireguard::PublicKey>,
         /// IP addresses of local tunnel interface
-        #[arg(required = true, num_args = 1..)]
+        #[arg(required_unless_present = "from_file", num_args = 1..)]
         tunnel_ip: Vec<IpAddr>,
-        /// IPv4 gateway address
-        #[arg(long)]
-        v4_gateway: Ipv4Addr,
-        /// IPv6 gateway address
+        /// IPv4 gateway address. With --from-file, it defaults to the first IPv4 DNS server
+        #[arg(long, required_unless_present = "from_file")]
+        v4_gateway: Option<Ipv4Addr>,
+        /// IPv6 gateway address. With --from-file, it defaults to the first IPv6 DNS server
         #[arg(long)]
         v6_gateway: Option<Ipv6Addr>,
+        /// Import a wg-quick configuration file, such as wg0.conf, instead of giving the
+        /// relay as arguments. The private key, addresses, DNS servers and the peer, including
+        /// its preshared key and allowed IPs, are read from the file. If the file has more than
+        /// one peer, the others are used if the first one stops responding
+        #[arg(long, conflicts_with_all = ["host", "port", "peer_pubkey", "tunnel_ip"])]
+        from_file: Option<PathBuf>,
     },
 }
 
@@ -260,7 +284,8 @@ impl Relay {
     pub async fn handle(self) -> Result<()> {
         match self {
             Relay::Get => Self::get().await,
-            Relay::List => Self::list().await,
+            Relay::List { health: false } => Self::list().await,
+            Relay::List { health: true } => Self::list_health().await,
             Relay::Update => Self::update().await,
             Relay::Set(subcmd) => Self::set(subcmd).await,
             Relay::Override(subcmd) => Self::r#override(subcmd).await,
@@ -270,6 +295,11 @@ impl Relay {
     async fn get() -> Result<()> {
         let mut rpc = MullvadProxyClient::new().await?;
         let settings = rpc.get_settings().await?;
+        if format::is_json() {
+            return format::print_json(
+                &serde_json::json!({ "relay_settings": settings.relay_settings }),
+            );
+        }
         let relay_settings = settings.relay_settings;
 
         match relay_settings {
@@ -295,6 +325,7 @@ impl Relay {
 
                 print_option!("Provider(s)", constraints.providers,);
                 print_option!("Ownership", constraints.ownership,);
+                print_option!("Selection strategy", constraints.selection_strategy,);
 
                 println!("OpenVPN constraints");
 
@@ -343,14 +374,23 @@ impl Relay {
     async fn list() -> Result<()> {
         let mut countries = get_active_relays().await?;
         countries.sort_by(|c1, c2| natord::compare_ignore_case(&c1.name, &c2.name));
-        for mut country in countries {
+        for country in &mut countries {
             country
                 .cities
                 .sort_by(|c1, c2| natord::compare_ignore_case(&c1.name, &c2.name));
-            println!("{} ({})", country.name, country.code);
-            for mut city in country.cities {
+            for city in &mut country.cities {
                 city.relays
                     .sort_by(|r1, r2| natord::compare_ignore_case(&r1.hostname, &r2.hostname));
+            }
+        }
+
+        if format::is_json() {
+            return format::print_json(&countries);
+        }
+
+        for country in countries {
+            println!("{} ({})", country.name, country.code);
+            for city in country.cities {
                 println!(
                     "\t{} ({}) @ {:.5}°N, {:.5}°W",
                     city.name, city.code, city.latitude, city.longitude
@@ -384,12 +424,32 @@ impl Relay {
         Ok(())
     }
 
+    async fn list_health() -> Result<()> {
+        let mut health = MullvadProxyClient::new().await?.get_relay_health().await?;
+        health.sort_by(|r1, r2| natord::compare_ignore_case(&r1.hostname, &r2.hostname));
+        if format::is_json() {
+            return format::print_json(&health);
+        }
+        if health.is_empty() {
+            println!("No relays have failed to connect recently");
+            return Ok(());
+        }
+        for relay in health {
+            let last_failure = relay.last_failure.with_timezone(&chrono::Local);
+            println!(
+                "{} - {} recent failure(s), last at {last_failure}, weight x{:.2}",
+                relay.hostname, relay.failures, relay.weight_factor
+            );
+        }
+        Ok(())
+    }
+
     async fn update() -> Result<()> {
         MullvadProxyClient::new()
             .await?
             .update_relay_locations()
             .await?;
-        println!("Updating relay list in the background...");
+        print_text!("Updating relay list in the background...");
         Ok(())
     }
 
@@ -402,14 +462,14 @@ impl Relay {
         let mut constraints = match relay_settings {
             RelaySettings::Normal(normal) => normal,
             RelaySettings::CustomTunnelEndpoint(_custom) => {
-                println!("Removing custom relay settings");
+                print_text!("Removing custom relay settings");
                 RelayConstraints::default()
             }
         };
         update_fn(&mut constraints);
         rpc.set_relay_settings(RelaySettings::Normal(constraints))
             .await?;
-        println!("Relay constraints updated");
+        print_text!("Relay constraints updated");
         Ok(())
     }
 
@@ -422,6 +482,7 @@ impl Relay {
             }
             SetCommands::Provider { providers } => Self::set_providers(providers).await,
             SetCommands::Ownership { ownership } => Self::set_ownership(ownership).await,
+            SetCommands::Strategy { strategy } => Self::set_selection_strategy(strategy).await,
             SetCommands::Tunnel(subcmd) => Self::set_tunnel(subcmd).await,
             SetCommands::TunnelProtocol { protocol } => Self::set_tunnel_protocol(protocol).await,
         }
@@ -457,12 +518,19 @@ impl Relay {
                 Self::read_custom_openvpn_relay(host, port, username, password, transport_protocol)
             }
             SetCustomCommands::Wireguard {
-                host,
-                port,
-                peer_pubkey,
-                tunnel_ip,
+                from_file: Some(path),
                 v4_gateway,
                 v6_gateway,
+                ..
+            } => Self::read_wg_quick_relay(&path, v4_gateway, v6_gateway)?,
+            SetCustomCommands::Wireguard {
+                host: Some(host),
+                port: Some(port),
+                peer_pubkey: Some(peer_pubkey),
+                tunnel_ip,
+                v4_gateway: Some(v4_gateway),
+                v6_gateway,
+                from_file: None,
             } => {
                 Self::read_custom_wireguard_relay(
                     host,
@@ -474,11 +542,14 @@ impl Relay {
                 )
                 .await?
             }
+            SetCustomCommands::Wireguard { .. } => {
+                bail!("Either the relay or a configuration file must be given")
+            }
         };
         let mut rpc = MullvadProxyClient::new().await?;
         rpc.set_relay_settings(RelaySettings::CustomTunnelEndpoint(custom_endpoint))
             .await?;
-        println!("Relay constraints updated");
+        print_text!("Relay constraints updated");
         Ok(())
     }
 
@@ -499,6 +570,9 @@ impl Relay {
                 username,
                 password,
             }),
+            psk: None,
+            dns_servers: vec![],
+            fallback_peers: vec![],
         }
     }
 
@@ -510,7 +584,7 @@ impl Relay {
         ipv4_gateway: Ipv4Addr,
         ipv6_gateway: Option<Ipv6Addr>,
     ) -> Result<CustomTunnelEndpoint> {
-        println!("Reading private key from standard input");
+        print_text!("Reading private key from standard input");
 
         let private_key_str = tokio::task::spawn_blocking(|| {
             let mut private_key_str = String::new();
@@ -548,9 +622,24 @@ impl Relay {
                 #[cfg(target_os = "linux")]
                 fwmark: None,
             }),
+            psk: None,
+            dns_servers: vec![],
+            fallback_peers: vec![],
         })
     }
 
+    fn read_wg_quick_relay(
+        path: &Path,
+        ipv4_gateway: Option<Ipv4Addr>,
+        ipv6_gateway: Option<Ipv6Addr>,
+    ) -> Result<CustomTunnelEndpoint> {
+        let config = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        WgQuickConfig::parse(&config)
+            .and_then(|config| config.into_custom_tunnel_endpoint(ipv4_gateway, ipv6_gateway))
+            .context("Invalid WireGuard configuration")
+    }
+
     async fn set_location(location_constraint_args: LocationArgs) -> Result<()> {
         let mut rpc = MullvadProxyClient::new().await?;
         let relay_settings = rpc.get_settings().await?.get_relay_settings();
@@ -613,6 +702,13 @@ impl Relay {
         .await
     }
 
+    async fn set_selection_strategy(strategy: SelectionStrategy) -> Result<()> {
+        Self::update_constraints(|constraints| {
+            constraints.selection_strategy = strategy;
+        })
+        .await
+    }
+
     async fn set_openvpn_constraints(
         port: Option<Constraint<u16>>,
         protocol: Option<Constraint<TransportProtocol>>,
@@ -633,7 +729,7 @@ impl Relay {
         match rpc.get_settings().await?.relay_settings {
             RelaySettings::Normal(settings) => Ok(settings.openvpn_constraints),
             RelaySettings::CustomTunnelEndpoint(_settings) => {
-                println!("Clearing custom tunnel constraints");
+                print_text!("Clearing custom tunnel constraints");
                 Ok(OpenVpnConstraints::default())
             }
         }
@@ -704,7 +800,7 @@ impl Relay {
         match rpc.get_settings().await?.relay_settings {
             RelaySettings::Normal(settings) => Ok(settings.wireguard_constraints),
             RelaySettings::CustomTunnelEndpoint(_settings) => {
-                println!("Clearing custom tunnel constraints");
+                print_text!("Clearing custom tunnel constraints");
                 Ok(WireguardConstraints::default())
             }
         }
@@ -745,7 +841,7 @@ impl Relay {
         update_fn(&mut element);
 
         rpc.set_relay_override(element).await?;
-        println!("Updated override options for {hostname}");
+        print_text!("Updated override options for {hostname}");
         Ok(())
     }
 
@@ -754,6 +850,11 @@ impl Relay {
             OverrideCommands::Get => {
                 let mut rpc = MullvadProxyClient::new().await?;
                 let settings = rpc.get_settings().await?;
+                if format::is_json() {
+                    return format::print_json(
+                        &serde_json::json!({ "relay_overrides": settings.relay_overrides }),
+                    );
+                }
 
                 let mut overrides = HashMap::new();
                 for relay_override in settings.relay_overrides {
@@ -872,7 +973,7 @@ impl Relay {
                 {
                     let mut rpc = MullvadProxyClient::new().await?;
                     rpc.clear_all_relay_overrides().await?;
-                    println!("All overrides unset");
+                    print_text!("All overrides unset");
                 }
             }
         }
@@ -898,7 +999,7 @@ fn parse_transport_port(
     match (port, protocol) {
         (port, Constraint::Any) => {
             if port.is_only() {
-                println!("The port constraint was set to 'any'");
+                print_text!("The port constraint was set to 'any'");
             }
             Constraint::Any
         }
@@ -1,4 +1,5 @@
 use super::receive_confirmation;
+use crate::print_text;
 use anyhow::Result;
 use mullvad_management_interface::MullvadProxyClient;
 
@@ -9,6 +10,6 @@ pub async fn handle() -> Result<()> {
     let mut rpc = MullvadProxyClient::new().await?;
     rpc.factory_reset().await?;
     #[cfg(target_os = "linux")]
-    println!("If you're running systemd, to remove all logs, you must use journalctl");
+    print_text!("If you're running systemd, to remove all logs, you must use journalctl");
     Ok(())
 }
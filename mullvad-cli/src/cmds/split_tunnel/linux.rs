@@ -1,11 +1,23 @@
 use anyhow::Result;
 use clap::Subcommand;
 use mullvad_management_interface::MullvadProxyClient;
+use std::path::PathBuf;
+
+use super::super::BooleanOption;
+use crate::{format, print_text};
 
 /// Manage split tunneling. To launch applications outside the tunnel, use the program
 /// 'mullvad-exclude' instead of this command
 #[derive(Subcommand, Debug)]
 pub enum SplitTunnel {
+    /// Display the split tunnel status and excluded apps
+    Get,
+    /// Enable or disable excluding apps from the tunnel
+    Set { policy: BooleanOption },
+    /// Manage applications to exclude from the tunnel. Processes running these executables are
+    /// excluded automatically whenever they start
+    #[clap(subcommand)]
+    App(App),
     /// List all processes that are excluded from the tunnel
     List,
     /// Add a PID to exclude from the tunnel
@@ -16,14 +28,54 @@ pub enum SplitTunnel {
     Clear,
 }
 
+#[derive(Subcommand, Debug)]
+pub enum App {
+    /// List applications that are excluded from the tunnel
+    List,
+    /// Exclude an application from the tunnel
+    Add { path: PathBuf },
+    /// Stop excluding an application from the tunnel
+    Remove { path: PathBuf },
+    /// Stop excluding all applications from the tunnel
+    Clear,
+}
+
 impl SplitTunnel {
     pub async fn handle(self) -> Result<()> {
         match self {
+            SplitTunnel::Get => {
+                let mut rpc = MullvadProxyClient::new().await?;
+                let settings = rpc.get_settings().await?.split_tunnel;
+                if format::is_json() {
+                    return format::print_json(&serde_json::json!({ "split_tunnel": settings }));
+                }
+
+                let enable_exclusions = BooleanOption::from(settings.enable_exclusions);
+
+                println!("Split tunneling state: {enable_exclusions}");
+
+                println!("Excluded applications:");
+                for path in &settings.apps {
+                    println!("{}", path.display());
+                }
+
+                Ok(())
+            }
+            SplitTunnel::Set { policy } => {
+                let mut rpc = MullvadProxyClient::new().await?;
+                rpc.set_split_tunnel_state(*policy).await?;
+                print_text!("Split tunnel policy: {policy}");
+                Ok(())
+            }
+            SplitTunnel::App(subcmd) => Self::app(subcmd).await,
             SplitTunnel::List => {
                 let pids = MullvadProxyClient::new()
                     .await?
                     .get_split_tunnel_processes()
                     .await?;
+                if format::is_json() {
+                    return format::print_json(&pids);
+                }
 
                 println!("Excluded PIDs:");
                 for pid in &pids {
@@ -37,7 +89,7 @@ impl SplitTunnel {
                     .await?
                     .add_split_tunnel_process(pid)
                     .await?;
-                println!("Excluding process");
+                print_text!("Excluding process");
                 Ok(())
             }
             SplitTunnel::Delete { pid } => {
@@ -45,7 +97,7 @@ impl SplitTunnel {
                     .await?
                     .remove_split_tunnel_process(pid)
                     .await?;
-                println!("Stopped excluding process");
+                print_text!("Stopped excluding process");
                 Ok(())
             }
             SplitTunnel::Clear => {
@@ -53,7 +105,55 @@ impl SplitTunnel {
                     .await?
                     .clear_split_tunnel_processes()
                     .await?;
-                println!("Stopped excluding all processes");
+                print_text!("Stopped excluding all processes");
+                Ok(())
+            }
+        }
+    }
+
+    async fn app(subcmd: App) -> Result<()> {
+        match subcmd {
+            App::List => {
+                let mut rpc = MullvadProxyClient::new().await?;
+                let settings = rpc.get_settings().await?.split_tunnel;
+                if format::is_json() {
+                    return format::print_json(&settings.apps);
+                }
+
+                println!("Excluded applications:");
+                for path in &settings.apps {
+                    println!("{}", path.display());
+                }
+                if !settings.enable_exclusions {
+                    println!(
+                        "Note: Split tunneling is disabled. Enable it with 'mullvad split-tunnel set on'"
+                    );
+                }
+
+                Ok(())
+            }
+            App::Add { path } => {
+                MullvadProxyClient::new()
+                    .await?
+                    .add_split_tunnel_app(path)
+                    .await?;
+                print_text!("Added path to excluded apps list");
+                Ok(())
+            }
+            App::Remove { path } => {
+                MullvadProxyClient::new()
+                    .await?
+                    .remove_split_tunnel_app(path)
+                    .await?;
+                print_text!("Stopped excluding app from tunnel");
+                Ok(())
+            }
+            App::Clear => {
+                MullvadProxyClient::new()
+                    .await?
+                    .clear_split_tunnel_apps()
+                    .await?;
+                print_text!("Stopped excluding all apps");
                 Ok(())
             }
         }
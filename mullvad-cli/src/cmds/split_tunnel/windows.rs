@@ -8,6 +8,7 @@ use clap::Subcommand;
 use mullvad_management_interface::MullvadProxyClient;
 
 use super::super::BooleanOption;
+use crate::{format, print_text};
 
 /// Set options for applications to exclude from the tunnel.
 #[derive(Subcommand, Debug)]
@@ -42,6 +43,17 @@ impl SplitTunnel {
             SplitTunnel::Get { list_processes } => {
                 let mut rpc = MullvadProxyClient::new().await?;
                 let settings = rpc.get_settings().await?.split_tunnel;
+                if format::is_json() {
+                    let excluded_processes = if list_processes {
+                        Some(rpc.get_excluded_processes().await?)
+                    } else {
+                        None
+                    };
+                    return format::print_json(&serde_json::json!({
+                        "split_tunnel": settings,
+                        "excluded_processes": excluded_processes,
+                    }));
+                }
 
                 let enable_exclusions = BooleanOption::from(settings.enable_exclusions);
 
@@ -72,7 +84,7 @@ impl SplitTunnel {
             SplitTunnel::Set { policy } => {
                 let mut rpc = MullvadProxyClient::new().await?;
                 rpc.set_split_tunnel_state(*policy).await?;
-                println!("Split tunnel policy: {policy}");
+                print_text!("Split tunnel policy: {policy}");
                 Ok(())
             }
             SplitTunnel::App(subcmd) => Self::app(subcmd).await,
@@ -86,7 +98,7 @@ impl SplitTunnel {
                     .await?
                     .add_split_tunnel_app(path)
                     .await?;
-                println!("Added path to excluded apps list");
+                print_text!("Added path to excluded apps list");
                 Ok(())
             }
             App::Remove { path } => {
@@ -94,7 +106,7 @@ impl SplitTunnel {
                     .await?
                     .remove_split_tunnel_app(path)
                     .await?;
-                println!("Stopped excluding app from tunnel");
+                print_text!("Stopped excluding app from tunnel");
                 Ok(())
             }
             App::Clear => {
@@ -102,7 +114,7 @@ impl SplitTunnel {
                     .await?
                     .clear_split_tunnel_apps()
                     .await?;
-                println!("Stopped excluding all apps");
+                print_text!("Stopped excluding all apps");
                 Ok(())
             }
         }
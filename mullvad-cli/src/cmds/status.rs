@@ -6,6 +6,7 @@ use mullvad_types::{device::DeviceState, states::TunnelState};
 use serde::Serialize;
 use std::fmt::Debug;
 
+use super::tunnel::{STATS_INTERVAL, next_tunnel_stats};
 use crate::format;
 
 #[derive(Subcommand, Debug, PartialEq)]
@@ -24,9 +25,13 @@ pub struct StatusArgs {
     #[arg(long, short = 'd', conflicts_with_all = ["verbose", "json"])]
     debug: bool,
 
-    /// Format output as JSON
+    /// Format output as JSON. Same as the global `--output json` option
     #[arg(long, short = 'j', conflicts_with_all = ["verbose", "debug"])]
     json: bool,
+
+    /// Show traffic statistics for the active WireGuard tunnel
+    #[arg(long, short = 's')]
+    stats: bool,
 }
 
 impl Status {
@@ -68,18 +73,29 @@ impl Status {
                 DaemonEvent::NewAccessMethod(access_method) => {
                     print_debug_or_json(&args, "New access method", &access_method)?;
                 }
+                DaemonEvent::LeakDetected(result) => {
+                    if args.debug || args.json {
+                        print_debug_or_json(&args, "Leak detected", &result)?;
+                    } else {
+                        format::print_leak_check_result(&result);
+                    }
+                }
             }
         }
         Ok(())
     }
 }
 
-pub async fn handle(cmd: Option<Status>, args: StatusArgs) -> Result<()> {
+pub async fn handle(cmd: Option<Status>, mut args: StatusArgs) -> Result<()> {
+    args.json |= format::is_json() && !args.debug;
+
     let mut rpc = MullvadProxyClient::new().await?;
     let state = rpc.get_tunnel_state().await?;
     let device = rpc.get_device().await?;
 
-    print_account_logged_out(&state, &device);
+    if !args.json {
+        print_account_logged_out(&state, &device);
+    }
 
     if args.debug {
         println!("Tunnel state: {state:#?}");
@@ -90,12 +106,31 @@ pub async fn handle(cmd: Option<Status>, args: StatusArgs) -> Result<()> {
         format::print_state(&state, None, args.verbose);
     }
 
+    if args.stats {
+        print_tunnel_stats(&mut rpc, &args).await?;
+    }
+
     if cmd == Some(Status::Listen) {
         Status::listen(rpc, args, state).await?;
     }
     Ok(())
 }
 
+async fn print_tunnel_stats(rpc: &mut MullvadProxyClient, args: &StatusArgs) -> Result<()> {
+    let mut stats_stream = rpc.tunnel_stats_listen(STATS_INTERVAL).await?;
+    let stats = next_tunnel_stats(&mut stats_stream).await?;
+
+    if args.debug {
+        println!("Tunnel stats: {stats:#?}");
+    } else if args.json {
+        let json = serde_json::to_string(&stats).context("Failed to format output as JSON")?;
+        println!("{json}");
+    } else {
+        format::print_tunnel_stats(&stats);
+    }
+    Ok(())
+}
+
 fn print_account_logged_out(state: &TunnelState, device: &DeviceState) {
     match state {
         TunnelState::Connecting { .. } | TunnelState::Connected { .. } | TunnelState::Error(_) => {
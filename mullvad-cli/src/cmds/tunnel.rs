@@ -1,14 +1,20 @@
-use anyhow::Result;
+use anyhow::{Context, Result};
 use clap::Subcommand;
-use mullvad_management_interface::MullvadProxyClient;
+use futures::{Stream, StreamExt};
+use mullvad_management_interface::{MullvadProxyClient, client};
 use mullvad_types::{
     constraints::Constraint,
     relay_constraints::{AllowedIps, RelaySettings, WireguardConstraints},
-    wireguard::{DEFAULT_ROTATION_INTERVAL, QuantumResistantState, RotationInterval},
+    wireguard::{DEFAULT_ROTATION_INTERVAL, QuantumResistantState, RotationInterval, TunnelStats},
 };
+use std::time::Duration;
+use talpid_types::net::wireguard::Backend;
 
 use super::BooleanOption;
-use crate::print_option;
+use crate::{format, print_option, print_text};
+
+/// How often tunnel stats are sampled when printing them
+pub const STATS_INTERVAL: Duration = Duration::from_secs(1);
 
 #[derive(Subcommand, Debug)]
 pub enum Tunnel {
@@ -18,6 +24,13 @@ pub enum Tunnel {
     /// Set tunnel options
     #[clap(subcommand)]
     Set(TunnelOptions),
+
+    /// Show traffic statistics for the active WireGuard tunnel
+    Stats {
+        /// Keep printing statistics until interrupted
+        #[arg(long, short = 'w')]
+        watch: bool,
+    },
 }
 
 #[derive(Subcommand, Debug, Clone)]
@@ -39,6 +52,11 @@ pub enum TunnelOptions {
         /// Configure quantum-resistant key exchange
         #[arg(long)]
         quantum_resistant: Option<QuantumResistantState>,
+        /// Configure the WireGuard implementation to use: auto, kernel, wireguard-go or boringtun.
+        /// Each build includes either wireguard-go or boringtun, and connecting fails if the
+        /// other one is selected
+        #[arg(long)]
+        backend: Option<Backend>,
         /// Configure whether to enable DAITA
         #[arg(long)]
         daita: Option<BooleanOption>,
@@ -78,12 +96,45 @@ impl Tunnel {
         match self {
             Tunnel::Get => Self::get().await,
             Tunnel::Set(options) => Self::set(options).await,
+            Tunnel::Stats { watch } => Self::stats(watch).await,
         }
     }
 
+    async fn stats(watch: bool) -> Result<()> {
+        let mut rpc = MullvadProxyClient::new().await?;
+        let mut stats_stream = rpc.tunnel_stats_listen(STATS_INTERVAL).await?;
+
+        if !watch {
+            let stats = next_tunnel_stats(&mut stats_stream).await?;
+            if format::is_json() {
+                return format::print_json(&stats);
+            }
+            format::print_tunnel_stats(&stats);
+            return Ok(());
+        }
+
+        while let Some(stats) = stats_stream.next().await {
+            let stats = stats?;
+            if format::is_json() {
+                format::print_json(&stats)?;
+                continue;
+            }
+            format::print_tunnel_stats(&stats);
+            println!();
+        }
+        Ok(())
+    }
+
     async fn get() -> Result<()> {
         let mut rpc = MullvadProxyClient::new().await?;
         let tunnel_options = rpc.get_settings().await?.tunnel_options;
+        if format::is_json() {
+            let wireguard_key = rpc.get_wireguard_key().await?;
+            return format::print_json(&serde_json::json!({
+                "tunnel_options": tunnel_options,
+                "wireguard_key": wireguard_key,
+            }));
+        }
 
         println!("OpenVPN options");
 
@@ -110,6 +161,7 @@ impl Tunnel {
             "Quantum resistance",
             tunnel_options.wireguard.quantum_resistant,
         );
+        print_option!("Backend", tunnel_options.wireguard.backend);
 
         print_option!("DAITA", tunnel_options.wireguard.daita.enabled);
 
@@ -163,6 +215,7 @@ impl Tunnel {
             TunnelOptions::Wireguard {
                 mtu,
                 quantum_resistant,
+                backend,
                 daita,
                 daita_direct_only,
                 allowed_ips,
@@ -172,6 +225,7 @@ impl Tunnel {
                 Self::handle_wireguard(
                     mtu,
                     quantum_resistant,
+                    backend,
                     daita,
                     daita_direct_only,
                     allowed_ips,
@@ -187,7 +241,7 @@ impl Tunnel {
     async fn handle_ipv6(state: BooleanOption) -> Result<()> {
         let mut rpc = MullvadProxyClient::new().await?;
         rpc.set_enable_ipv6(*state).await?;
-        println!("IPv6: {state}");
+        print_text!("IPv6: {state}");
         Ok(())
     }
 
@@ -196,7 +250,7 @@ impl Tunnel {
 
         if let Some(mssfix) = mssfix {
             rpc.set_openvpn_mssfix(mssfix.option()).await?;
-            println!("mssfix parameter has been updated");
+            print_text!("mssfix parameter has been updated");
         }
 
         Ok(())
@@ -205,6 +259,7 @@ impl Tunnel {
     async fn handle_wireguard(
         mtu: Option<Constraint<u16>>,
         quantum_resistant: Option<QuantumResistantState>,
+        backend: Option<Backend>,
         daita: Option<BooleanOption>,
         daita_direct_only: Option<BooleanOption>,
         allowed_ips: Option<String>,
@@ -215,41 +270,46 @@ impl Tunnel {
 
         if let Some(mtu) = mtu {
             rpc.set_wireguard_mtu(mtu.option()).await?;
-            println!("MTU parameter has been updated");
+            print_text!("MTU parameter has been updated");
         }
 
         if let Some(quantum_resistant) = quantum_resistant {
             rpc.set_quantum_resistant_tunnel(quantum_resistant).await?;
-            println!("Quantum resistant setting has been updated");
+            print_text!("Quantum resistant setting has been updated");
+        }
+
+        if let Some(backend) = backend {
+            rpc.set_wireguard_backend(backend).await?;
+            print_text!("WireGuard backend setting has been updated");
         }
 
         if let Some(allowed_ips_str) = allowed_ips {
             let ips = AllowedIps::parse(allowed_ips_str.split(','))?;
 
             rpc.set_wireguard_allowed_ips(ips).await?;
-            println!("WireGuard allowed IPs have been updated")
+            print_text!("WireGuard allowed IPs have been updated")
         }
 
         if let Some(enable_daita) = daita {
             rpc.set_enable_daita(*enable_daita).await?;
-            println!("DAITA setting has been updated");
-            println!("Direct only setting has been updated");
+            print_text!("DAITA setting has been updated");
+            print_text!("Direct only setting has been updated");
         }
 
         if let Some(daita_direct_only) = daita_direct_only {
             rpc.set_daita_direct_only(*daita_direct_only).await?;
-            println!("Direct only setting has been updated");
+            print_text!("Direct only setting has been updated");
         }
 
         if let Some(interval) = rotation_interval {
             match interval {
                 Constraint::Only(interval) => {
                     rpc.set_wireguard_rotation_interval(interval).await?;
-                    println!("Set key rotation interval to {interval}");
+                    print_text!("Set key rotation interval to {interval}");
                 }
                 Constraint::Any => {
                     rpc.reset_wireguard_rotation_interval().await?;
-                    println!(
+                    print_text!(
                         "Reset key rotation interval to {}",
                         RotationInterval::new(DEFAULT_ROTATION_INTERVAL).unwrap()
                     );
@@ -259,9 +319,24 @@ impl Tunnel {
 
         if matches!(rotate_key, Some(RotateKey::RotateKey)) {
             rpc.rotate_wireguard_key().await?;
-            println!("Rotated WireGuard key");
+            print_text!("Rotated WireGuard key");
         }
 
         Ok(())
     }
 }
+
+/// Return the second sample from a tunnel stats stream. The first sample has no throughput
+/// since there is nothing to compare it to.
+pub async fn next_tunnel_stats(
+    stats_stream: &mut (impl Stream<Item = client::Result<TunnelStats>> + Unpin),
+) -> Result<TunnelStats> {
+    let mut stats = None;
+    for _ in 0..2 {
+        match stats_stream.next().await {
+            Some(sample) => stats = Some(sample?),
+            None => break,
+        }
+    }
+    stats.context("Tunnel stats stream ended unexpectedly")
+}
@@ -1,4 +1,4 @@
-use crate::format;
+use crate::{format, print_text};
 use anyhow::{Result, anyhow};
 use futures::{Stream, StreamExt};
 use mullvad_management_interface::{MullvadProxyClient, client::DaemonEvent};
@@ -82,7 +82,11 @@ async fn wait_for_tunnel_state(
 ) -> Result<()> {
     while let Some(state) = event_stream.next().await {
         if let DaemonEvent::TunnelState(new_state) = state? {
-            format::print_state(&new_state, None, false);
+            if format::is_json() {
+                format::print_json(&new_state)?;
+            } else {
+                format::print_state(&new_state, None, false);
+            }
             if matches_event(&new_state)? {
                 return Ok(());
             }
@@ -100,12 +104,12 @@ async fn wait_for_tunnel_state(
 /// the user when they inevitably will go troubleshooting.
 fn print_account_loggedout(state: &DeviceState) {
     match state {
-        DeviceState::LoggedOut => println!("Warning: You are not logged in to an account."),
-        DeviceState::Revoked => println!("Warning: This device has been revoked"),
+        DeviceState::LoggedOut => print_text!("Warning: You are not logged in to an account."),
+        DeviceState::Revoked => print_text!("Warning: This device has been revoked"),
         DeviceState::LoggedIn(_) => return, // Normal case, do nothing.
     };
 
-    println!(
+    print_text!(
         "Mullvad is blocking all network traffic until you perform one of the following actions:
 
 1. Login to a Mullvad account with available time/credits.
@@ -1,15 +1,63 @@
-use std::collections::HashMap;
+use std::{collections::HashMap, sync::OnceLock};
 
+use anyhow::Context;
 use itertools::Itertools;
 use mullvad_types::{
-    auth_failed::AuthFailed, features::FeatureIndicators, location::GeoIpLocation,
+    auth_failed::AuthFailed,
+    connection_history::{ConnectionSession, SessionEndReason},
+    features::FeatureIndicators,
+    leak_check::LeakCheckResult,
+    location::GeoIpLocation,
     states::TunnelState,
+    wireguard::{PeerStats, TunnelStats},
 };
 use talpid_types::{
     net::{Endpoint, TunnelEndpoint},
     tunnel::{ActionAfterDisconnect, ErrorState},
 };
 
+static OUTPUT_FORMAT: OnceLock<OutputFormat> = OnceLock::new();
+
+/// Format of the output of commands, selected with the global `--output` option.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum OutputFormat {
+    /// Human-readable text
+    #[default]
+    Text,
+    /// One JSON document per line. See docs/cli-json-output.md for the schema
+    Json,
+}
+
+/// Set the output format of all commands. Must be called before any output is printed.
+pub fn set_output_format(format: OutputFormat) {
+    OUTPUT_FORMAT
+        .set(format)
+        .expect("Output format should only be set once");
+}
+
+/// Returns whether commands should print JSON rather than text.
+pub fn is_json() -> bool {
+    OUTPUT_FORMAT.get() == Some(&OutputFormat::Json)
+}
+
+/// Print `value` as a single line of JSON.
+pub fn print_json<T: serde::Serialize + ?Sized>(value: &T) -> anyhow::Result<()> {
+    let json = serde_json::to_string(value).context("Failed to format output as JSON")?;
+    println!("{json}");
+    Ok(())
+}
+
+/// Print a human-readable message, such as the confirmation of a changed setting. Nothing is
+/// printed when the output format is JSON, since standard output may only contain JSON then.
+#[macro_export]
+macro_rules! print_text {
+    ($($arg:tt)*) => {{
+        if !$crate::format::is_json() {
+            println!($($arg)*);
+        }
+    }};
+}
+
 #[macro_export]
 macro_rules! print_option {
     ($value:expr_2021 $(,)?) => {{ println!("{:<4}{:<24}{}", "", "", $value,) }};
@@ -153,6 +201,11 @@ fn connection_information(
         .filter(|_| verbose)
         .and_then(|endpoint| endpoint.tunnel_interface.clone());
     info.insert("Tunnel interface", tunnel_interface_fmt);
+    let wireguard_backend_fmt = endpoint
+        .filter(|_| verbose)
+        .and_then(|endpoint| endpoint.wireguard_backend)
+        .map(|backend| backend.to_string());
+    info.insert("WireGuard backend", wireguard_backend_fmt);
 
     let bridge_type_fmt = endpoint
         .filter(|_| verbose)
@@ -323,3 +376,132 @@ const fn get_auth_failed_message(auth_failed: AuthFailed) -> &'static str {
         AuthFailed::Unknown => UNKNOWN_MSG,
     }
 }
+
+pub fn print_tunnel_stats(stats: &TunnelStats) {
+    if stats.entry.is_none() && stats.exit.is_none() {
+        println!("No WireGuard tunnel is up");
+        return;
+    }
+    if let Some(entry) = &stats.entry {
+        println!("Entry peer");
+        print_peer_stats(entry);
+    }
+    if let Some(exit) = &stats.exit {
+        println!("Exit peer");
+        print_peer_stats(exit);
+    }
+}
+
+fn print_peer_stats(stats: &PeerStats) {
+    print_option!("Public key", stats.public_key);
+    print_option!(
+        "Sent",
+        format!(
+            "{} ({}/s)",
+            format_bytes(stats.tx_bytes as f64),
+            format_bytes(stats.tx_rate)
+        )
+    );
+    print_option!(
+        "Received",
+        format!(
+            "{} ({}/s)",
+            format_bytes(stats.rx_bytes as f64),
+            format_bytes(stats.rx_rate)
+        )
+    );
+    print_option!(
+        "Latest handshake",
+        match stats.last_handshake {
+            Some(time) => time.with_timezone(&chrono::Local).to_string(),
+            None => "never".to_string(),
+        }
+    );
+}
+
+fn format_bytes(bytes: f64) -> String {
+    const UNITS: [&str; 5] = ["B", "KiB", "MiB", "GiB", "TiB"];
+    let mut value = bytes;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{value:.0} {}", UNITS[unit])
+    } else {
+        format!("{value:.2} {}", UNITS[unit])
+    }
+}
+
+pub fn print_leak_check_result(result: &LeakCheckResult) {
+    let timestamp = result.timestamp.with_timezone(&chrono::Local);
+    match &result.leak {
+        None => println!("{timestamp}: No leak detected"),
+        Some(leak) => {
+            println!("{timestamp}: Leak detected!");
+            if let Some(interface) = &leak.interface {
+                print_option!("Interface", interface);
+            }
+            print_option!(
+                "Reachable nodes",
+                leak.reachable_nodes
+                    .iter()
+                    .map(ToString::to_string)
+                    .join(", ")
+            );
+        }
+    }
+    print_option!("Relay", result.relay);
+}
+
+pub fn print_connection_session(session: &ConnectionSession) {
+    let start = session.start.with_timezone(&chrono::Local);
+    match session.end {
+        Some(end) => {
+            let duration = (end - session.start).to_std().unwrap_or_default();
+            println!(
+                "{start} - {} ({})",
+                end.with_timezone(&chrono::Local),
+                format_duration(duration)
+            );
+        }
+        None => println!("{start} -"),
+    }
+
+    if let Some(location) = &session.location {
+        match &location.hostname {
+            Some(hostname) => print_option!("Relay", hostname),
+            None => print_option!("Relay", "unknown"),
+        }
+        print_option!("Location", format_location(location));
+    }
+    if let Some(obfuscation) = &session.obfuscation {
+        print_option!("Obfuscation", obfuscation);
+    }
+    if !session.feature_indicators.is_empty() {
+        print_option!("Features", session.feature_indicators);
+    }
+    print_option!("Sent", format_bytes(session.tx_bytes as f64));
+    print_option!("Received", format_bytes(session.rx_bytes as f64));
+    print_option!("Reconnects", session.reconnects);
+    match &session.end_reason {
+        None => print_option!("Ended", "ongoing"),
+        Some(SessionEndReason::Disconnected) => print_option!("Ended", "disconnected"),
+        Some(SessionEndReason::Error(error_state)) => {
+            print_option!("Ended", format!("blocked: {}", error_state.cause()))
+        }
+        Some(SessionEndReason::Interrupted) => {
+            print_option!("Ended", "the daemon stopped unexpectedly")
+        }
+    }
+}
+
+fn format_duration(duration: std::time::Duration) -> String {
+    let seconds = duration.as_secs();
+    match (seconds / 3600, seconds % 3600 / 60) {
+        (0, 0) => format!("{seconds}s"),
+        (0, minutes) => format!("{minutes}m"),
+        (hours, minutes) => format!("{hours}h {minutes}m"),
+    }
+}
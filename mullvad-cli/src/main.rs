@@ -1,5 +1,5 @@
 use anyhow::Result;
-use clap::Parser;
+use clap::{Parser, Subcommand};
 
 mod cmds;
 mod format;
@@ -10,7 +10,17 @@ pub const BIN_NAME: &str = env!("CARGO_BIN_NAME");
 #[derive(Debug, Parser)]
 #[command(author, version = mullvad_version::VERSION, about, long_about = None)]
 #[command(propagate_version = true)]
-enum Cli {
+struct Cli {
+    /// Output format of the command
+    #[arg(long, global = true, value_enum, default_value_t)]
+    output: format::OutputFormat,
+
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Debug, Subcommand)]
+enum Command {
     /// Control and display information about your Mullvad account
     #[clap(subcommand)]
     Account(account::Account),
@@ -112,6 +122,13 @@ enum Cli {
     #[clap(subcommand)]
     Tunnel(tunnel::Tunnel),
 
+    /// Show a summary of the most recent tunnel sessions, oldest first
+    History {
+        /// Only show this many of the most recent sessions
+        #[arg(long, short = 'n')]
+        count: Option<usize>,
+    },
+
     /// Show information about the current Mullvad version
     /// and available versions
     Version,
@@ -135,11 +152,20 @@ enum Cli {
     #[clap(subcommand)]
     CustomList(custom_list::CustomList),
 
+    /// Manage named sets of relay, obfuscation, DNS and tunnel settings
+    #[clap(subcommand)]
+    Profile(profile::Profile),
+
     /// Apply a JSON patch generated by 'export-settings'
     #[clap(arg_required_else_help = true)]
     ImportSettings {
         /// File to read from. If this is "-", read from standard input
         file: String,
+
+        /// Also apply settings that may contain secrets or weaken security, such as custom DNS
+        /// servers and API access methods
+        #[arg(long)]
+        allow_sensitive: bool,
     },
 
     /// Export a JSON patch based on the current settings
@@ -147,6 +173,11 @@ enum Cli {
     ExportSettings {
         /// File to write to. If this is "-", write to standard output
         file: String,
+
+        /// Also export settings that may contain secrets, such as custom DNS servers and API
+        /// access methods
+        #[arg(long)]
+        include_sensitive: bool,
     },
 }
 
@@ -158,32 +189,43 @@ async fn main() -> Result<()> {
     #[cfg(unix)]
     handle_sigpipe().unwrap();
 
-    match Cli::parse() {
-        Cli::Account(cmd) => cmd.handle().await,
-        Cli::Bridge(cmd) => cmd.handle().await,
-        Cli::Connect { wait } => tunnel_state::connect(wait).await,
-        Cli::Reconnect { wait } => tunnel_state::reconnect(wait).await,
-        Cli::Debug(cmd) => cmd.handle().await,
-        Cli::Disconnect { wait } => tunnel_state::disconnect(wait).await,
-        Cli::AutoConnect(cmd) => cmd.handle().await,
-        Cli::BetaProgram(cmd) => cmd.handle().await,
-        Cli::LockdownMode(cmd) => cmd.handle().await,
-        Cli::Dns(cmd) => cmd.handle().await,
-        Cli::Lan(cmd) => cmd.handle().await,
-        Cli::Obfuscation(cmd) => cmd.handle().await,
-        Cli::ApiAccess(cmd) => cmd.handle().await,
-        Cli::Version => version::print().await,
-        Cli::FactoryReset => reset::handle().await,
-        Cli::Relay(cmd) => cmd.handle().await,
-        Cli::Tunnel(cmd) => cmd.handle().await,
-        Cli::SplitTunnel(cmd) => cmd.handle().await,
-        Cli::Status { cmd, args } => status::handle(cmd, args).await,
-        Cli::CustomList(cmd) => cmd.handle().await,
-        Cli::ImportSettings { file } => patch::import(file).await,
-        Cli::ExportSettings { file } => patch::export(file).await,
+    let cli = Cli::parse();
+    format::set_output_format(cli.output);
+
+    match cli.command {
+        Command::Account(cmd) => cmd.handle().await,
+        Command::Bridge(cmd) => cmd.handle().await,
+        Command::Connect { wait } => tunnel_state::connect(wait).await,
+        Command::Reconnect { wait } => tunnel_state::reconnect(wait).await,
+        Command::Debug(cmd) => cmd.handle().await,
+        Command::Disconnect { wait } => tunnel_state::disconnect(wait).await,
+        Command::AutoConnect(cmd) => cmd.handle().await,
+        Command::BetaProgram(cmd) => cmd.handle().await,
+        Command::LockdownMode(cmd) => cmd.handle().await,
+        Command::Dns(cmd) => cmd.handle().await,
+        Command::Lan(cmd) => cmd.handle().await,
+        Command::Obfuscation(cmd) => cmd.handle().await,
+        Command::ApiAccess(cmd) => cmd.handle().await,
+        Command::Version => version::print().await,
+        Command::FactoryReset => reset::handle().await,
+        Command::Relay(cmd) => cmd.handle().await,
+        Command::Tunnel(cmd) => cmd.handle().await,
+        Command::History { count } => history::handle(count).await,
+        Command::SplitTunnel(cmd) => cmd.handle().await,
+        Command::Status { cmd, args } => status::handle(cmd, args).await,
+        Command::CustomList(cmd) => cmd.handle().await,
+        Command::Profile(cmd) => cmd.handle().await,
+        Command::ImportSettings {
+            file,
+            allow_sensitive,
+        } => patch::import(file, allow_sensitive).await,
+        Command::ExportSettings {
+            file,
+            include_sensitive,
+        } => patch::export(file, include_sensitive).await,
 
         #[cfg(all(unix, not(target_os = "android")))]
-        Cli::ShellCompletions { shell, dir } => {
+        Command::ShellCompletions { shell, dir } => {
             use anyhow::Context;
             use clap::CommandFactory;
 
@@ -34,9 +34,9 @@ fn main() {
     println!("cargo::rustc-check-cfg=cfg(daita)");
     println!(r#"cargo::rustc-cfg=daita"#);
 
-    // Enable in-app upgrades on macOS and Windows
+    // Enable in-app upgrades on desktop
     println!("cargo::rustc-check-cfg=cfg(in_app_upgrade)");
-    if matches!(target_os(), Os::Windows | Os::Macos) {
+    if matches!(target_os(), Os::Windows | Os::Macos | Os::Linux) {
         println!(r#"cargo::rustc-cfg=in_app_upgrade"#);
     }
 }
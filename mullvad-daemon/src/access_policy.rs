@@ -0,0 +1,202 @@
+//! Authorization of management interface callers.
+//!
+//! Every RPC requires a [`Role`]. On Unix, the role of a caller is derived from the UID and GID
+//! of the calling process, which are read from the management interface socket, using the policy
+//! in [`POLICY_FILENAME`] in the settings directory. Without a policy file, every caller is an
+//! administrator.
+//!
+//! The policy is a JSON object such as the following:
+//!
+//! ```json
+//! {
+//!     "default_role": "read_only",
+//!     "users": { "alice": "admin", "1001": "operator" },
+//!     "groups": { "netdev": "operator" }
+//! }
+//! ```
+//!
+//! Users and groups may be given by name or by ID. A rule for the user of the caller takes
+//! precedence over rules for its groups. If several group rules match, the most privileged role
+//! is used. The root user is always an administrator.
+
+use serde::Deserialize;
+use std::{collections::HashMap, fmt, io, path::Path};
+
+/// Name of the file in the settings directory that contains the access policy.
+pub const POLICY_FILENAME: &str = "management-access.json";
+
+#[derive(thiserror::Error, Debug)]
+pub enum Error {
+    #[error("Failed to read access policy")]
+    Read(#[source] io::Error),
+
+    #[error("Failed to parse access policy")]
+    Parse(#[source] serde_json::Error),
+}
+
+/// What a management interface caller is allowed to do. Each role may do everything that less
+/// privileged roles may do.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Role {
+    /// Read the state of the daemon, such as the tunnel state, events and the relay list. Events
+    /// that contain the account number are withheld.
+    ReadOnly,
+    /// Connect, disconnect and reconnect the tunnel.
+    Operator,
+    /// Change settings, manage the account and everything else.
+    Admin,
+}
+
+impl fmt::Display for Role {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Role::ReadOnly => f.write_str("read-only"),
+            Role::Operator => f.write_str("operator"),
+            Role::Admin => f.write_str("admin"),
+        }
+    }
+}
+
+/// Identity of the process on the other end of the management interface socket.
+#[derive(Debug, Clone, Copy)]
+#[cfg_attr(not(unix), allow(dead_code))]
+pub struct Credentials {
+    pub uid: u32,
+    pub gid: u32,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct AccessPolicy {
+    /// Role of callers that no other rule applies to.
+    #[serde(default = "default_role")]
+    default_role: Role,
+    /// Roles by user name or UID.
+    #[serde(default)]
+    users: HashMap<String, Role>,
+    /// Roles by group name or GID. A rule applies if the group is the primary group of the
+    /// calling process, or if the user of the caller is a member of the group.
+    #[serde(default)]
+    groups: HashMap<String, Role>,
+}
+
+fn default_role() -> Role {
+    Role::ReadOnly
+}
+
+/// Decides the role of management interface callers.
+#[derive(Debug, Default)]
+pub struct AccessControl {
+    policy: Option<AccessPolicy>,
+}
+
+impl AccessControl {
+    /// Load the access policy from `settings_dir`. If there is no policy file, all callers are
+    /// administrators.
+    pub fn load(settings_dir: &Path) -> Result<Self, Error> {
+        let path = settings_dir.join(POLICY_FILENAME);
+        let policy = match std::fs::read_to_string(&path) {
+            Ok(policy) => policy,
+            Err(error) if error.kind() == io::ErrorKind::NotFound => {
+                return Ok(Self::default());
+            }
+            Err(error) => return Err(Error::Read(error)),
+        };
+        let policy = serde_json::from_str(&policy).map_err(Error::Parse)?;
+        log::info!(
+            "Loaded management interface access policy from {}",
+            path.display()
+        );
+        Ok(AccessControl {
+            policy: Some(policy),
+        })
+    }
+
+    /// Returns the role of a caller. Callers whose credentials are unknown get the default role.
+    pub fn role(&self, caller: Option<Credentials>) -> Role {
+        let Some(policy) = &self.policy else {
+            return Role::Admin;
+        };
+        match caller {
+            #[cfg(unix)]
+            Some(caller) => policy.role(caller),
+            #[cfg(not(unix))]
+            Some(_) => policy.default_role,
+            None => policy.default_role,
+        }
+    }
+}
+
+#[cfg(unix)]
+impl AccessPolicy {
+    fn role(&self, caller: Credentials) -> Role {
+        use nix::unistd::{Gid, Group, Uid, User};
+
+        if caller.uid == 0 {
+            return Role::Admin;
+        }
+
+        let user_name = User::from_uid(Uid::from_raw(caller.uid))
+            .ok()
+            .flatten()
+            .map(|user| user.name);
+        let is_user = |key: &str| {
+            key.parse::<u32>().ok() == Some(caller.uid) || user_name.as_deref() == Some(key)
+        };
+        if let Some(role) = self
+            .users
+            .iter()
+            .find_map(|(key, role)| is_user(key).then_some(*role))
+        {
+            return role;
+        }
+
+        let is_member = |key: &str| {
+            let group = match key.parse::<u32>() {
+                Ok(gid) => Group::from_gid(Gid::from_raw(gid)),
+                Err(_) => Group::from_name(key),
+            };
+            let Ok(Some(group)) = group else {
+                return false;
+            };
+            group.gid.as_raw() == caller.gid
+                || user_name
+                    .as_ref()
+                    .is_some_and(|name| group.mem.contains(name))
+        };
+        self.groups
+            .iter()
+            .filter(|(key, _)| is_member(key))
+            .map(|(_, role)| *role)
+            .max()
+            .unwrap_or(self.default_role)
+    }
+}
+
+#[cfg(all(test, unix))]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_role() {
+        let policy: AccessPolicy = serde_json::from_str(
+            r#"{
+                "default_role": "operator",
+                "users": { "4242": "read_only" }
+            }"#,
+        )
+        .unwrap();
+        let access = AccessControl {
+            policy: Some(policy),
+        };
+
+        let caller = |uid| Some(Credentials { uid, gid: uid });
+        assert_eq!(access.role(caller(0)), Role::Admin);
+        assert_eq!(access.role(caller(4242)), Role::ReadOnly);
+        assert_eq!(access.role(caller(4243)), Role::Operator);
+        assert_eq!(access.role(None), Role::Operator);
+
+        assert_eq!(AccessControl::default().role(caller(4242)), Role::Admin);
+    }
+}
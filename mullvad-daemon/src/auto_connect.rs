@@ -0,0 +1,221 @@
+//! Rule-based auto-connect. Whenever the network that the host uses to reach the internet changes,
+//! the auto-connect rules in the settings are evaluated in order, and the action of the first
+//! matching rule is taken. Rules are only evaluated when the network changes, so the user may still
+//! override the action manually until the next change.
+
+use crate::DaemonEventSender;
+use futures::{StreamExt, channel::mpsc};
+use mullvad_types::auto_connect::{AutoConnectAction, AutoConnectRule, NetworkMatcher};
+use std::{
+    fmt,
+    net::{IpAddr, Ipv4Addr},
+};
+use talpid_core::mpsc::Sender;
+use talpid_dbus::network_manager::NetworkManager;
+use talpid_routing::RouteManagerHandle;
+use talpid_types::{ErrorExt, net::Connectivity};
+
+/// Public address used to find the route that traffic outside the tunnel takes.
+const PUBLIC_INTERNET_ADDRESS: IpAddr = IpAddr::V4(Ipv4Addr::new(193, 138, 218, 78));
+
+const ARP_TABLE_PATH: &str = "/proc/net/arp";
+
+/// MAC address of incomplete entries in the ARP table.
+const INCOMPLETE_MAC: &str = "00:00:00:00:00:00";
+
+/// The network that the host uses to reach the internet. Every property is optional, since they
+/// can't always be determined.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct CurrentNetwork {
+    pub interface: Option<String>,
+    pub gateway_mac: Option<String>,
+    pub ssid: Option<String>,
+}
+
+impl CurrentNetwork {
+    fn matches(&self, matcher: &NetworkMatcher) -> bool {
+        match matcher {
+            NetworkMatcher::Ssid(ssid) => self.ssid.as_ref() == Some(ssid),
+            NetworkMatcher::GatewayMac(mac) => self.gateway_mac.as_ref() == Some(mac),
+            NetworkMatcher::Interface(interface) => self.interface.as_ref() == Some(interface),
+        }
+    }
+}
+
+impl fmt::Display for CurrentNetwork {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let unknown = "unknown";
+        write!(
+            f,
+            "interface: {}, gateway MAC: {}, SSID: {}",
+            self.interface.as_deref().unwrap_or(unknown),
+            self.gateway_mac.as_deref().unwrap_or(unknown),
+            self.ssid.as_deref().unwrap_or(unknown),
+        )
+    }
+}
+
+/// Returns the action of the first rule in `rules` that matches `network`, if any.
+pub fn matching_action(
+    rules: &[AutoConnectRule],
+    network: &CurrentNetwork,
+) -> Option<AutoConnectAction> {
+    rules
+        .iter()
+        .find(|rule| network.matches(&rule.network))
+        .map(|rule| rule.action)
+}
+
+/// Forward connectivity updates from `connectivity_rx` to the returned receiver. On every update,
+/// the current network is detected and sent to the daemon, or `None` if the host is offline.
+pub fn watch_network(
+    mut connectivity_rx: mpsc::UnboundedReceiver<Connectivity>,
+    route_manager: RouteManagerHandle,
+    event_tx: DaemonEventSender<Option<CurrentNetwork>>,
+) -> mpsc::UnboundedReceiver<Connectivity> {
+    let (connectivity_tx, forwarded_rx) = mpsc::unbounded();
+    tokio::spawn(async move {
+        while let Some(connectivity) = connectivity_rx.next().await {
+            let _ = connectivity_tx.unbounded_send(connectivity);
+
+            let network = if connectivity.is_online() {
+                Some(detect_network(&route_manager).await)
+            } else {
+                None
+            };
+            if event_tx.send(network).is_err() {
+                break;
+            }
+        }
+    });
+    forwarded_rx
+}
+
+async fn detect_network(route_manager: &RouteManagerHandle) -> CurrentNetwork {
+    let route = match route_manager
+        .get_destination_route(PUBLIC_INTERNET_ADDRESS, Some(mullvad_types::TUNNEL_FWMARK))
+        .await
+    {
+        Ok(Some(route)) => route,
+        Ok(None) => return CurrentNetwork::default(),
+        Err(error) => {
+            log::error!(
+                "{}",
+                error.display_chain_with_msg("Failed to look up default route")
+            );
+            return CurrentNetwork::default();
+        }
+    };
+
+    let interface = route.get_node().get_device().map(str::to_owned);
+    let gateway_mac = match route.get_node().get_address() {
+        Some(IpAddr::V4(gateway)) => get_gateway_mac(gateway).await,
+        _ => None,
+    };
+    let ssid = match &interface {
+        Some(interface) => get_ssid(interface.clone()).await,
+        None => None,
+    };
+
+    CurrentNetwork {
+        interface,
+        gateway_mac,
+        ssid,
+    }
+}
+
+async fn get_gateway_mac(gateway: Ipv4Addr) -> Option<String> {
+    let arp_table = tokio::fs::read_to_string(ARP_TABLE_PATH)
+        .await
+        .inspect_err(|error| {
+            log::error!(
+                "{}",
+                error.display_chain_with_msg("Failed to read ARP table")
+            )
+        })
+        .ok()?;
+    parse_arp_table(&arp_table, gateway)
+}
+
+/// Find the MAC address of `address` in the contents of `/proc/net/arp`.
+fn parse_arp_table(arp_table: &str, address: Ipv4Addr) -> Option<String> {
+    arp_table.lines().skip(1).find_map(|line| {
+        // Columns: IP address, HW type, Flags, HW address, Mask, Device
+        let mut columns = line.split_whitespace();
+        let ip: Ipv4Addr = columns.next()?.parse().ok()?;
+        let mac = columns.nth(2)?.to_lowercase();
+        (ip == address && mac != INCOMPLETE_MAC).then_some(mac)
+    })
+}
+
+async fn get_ssid(interface: String) -> Option<String> {
+    let result = tokio::task::spawn_blocking(move || NetworkManager::new()?.get_ssid(&interface))
+        .await
+        .ok()?;
+    result
+        .inspect_err(|error| {
+            log::debug!(
+                "{}",
+                error.display_chain_with_msg("Failed to obtain SSID from NetworkManager")
+            )
+        })
+        .ok()
+        .flatten()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const ARP_TABLE: &str = "\
+IP address       HW type     Flags       HW address            Mask     Device
+192.168.1.1      0x1         0x2         AA:BB:CC:DD:EE:FF     *        wlan0
+192.168.1.20     0x1         0x0         00:00:00:00:00:00     *        wlan0
+";
+
+    #[test]
+    fn test_parse_arp_table() {
+        assert_eq!(
+            parse_arp_table(ARP_TABLE, Ipv4Addr::new(192, 168, 1, 1)).as_deref(),
+            Some("aa:bb:cc:dd:ee:ff")
+        );
+        assert_eq!(
+            parse_arp_table(ARP_TABLE, Ipv4Addr::new(192, 168, 1, 20)),
+            None
+        );
+        assert_eq!(parse_arp_table(ARP_TABLE, Ipv4Addr::new(10, 0, 0, 1)), None);
+    }
+
+    #[test]
+    fn test_first_matching_rule_wins() {
+        let rules = [
+            AutoConnectRule {
+                network: NetworkMatcher::Ssid("Home".to_owned()),
+                action: AutoConnectAction::Disconnect,
+            },
+            AutoConnectRule {
+                network: NetworkMatcher::Interface("wlan0".to_owned()),
+                action: AutoConnectAction::Connect,
+            },
+            AutoConnectRule {
+                network: NetworkMatcher::Ssid("Cafe".to_owned()),
+                action: AutoConnectAction::Block,
+            },
+        ];
+        let network = |ssid: &str| CurrentNetwork {
+            interface: Some("wlan0".to_owned()),
+            gateway_mac: None,
+            ssid: Some(ssid.to_owned()),
+        };
+
+        assert_eq!(
+            matching_action(&rules, &network("Home")),
+            Some(AutoConnectAction::Disconnect)
+        );
+        assert_eq!(
+            matching_action(&rules, &network("Cafe")),
+            Some(AutoConnectAction::Connect)
+        );
+        assert_eq!(matching_action(&rules, &CurrentNetwork::default()), None);
+    }
+}
@@ -13,6 +13,9 @@ static ENV_DESC: LazyLock<String> = LazyLock::new(|| {
     MULLVAD_RPC_SOCKET_PATH    Location of the management interface device.
                                It refers to Unix domain socket on Unix based platforms, and named pipe on Windows.
                                [Default: {}]
+    MULLVAD_METRICS_ENDPOINT   Serve Prometheus metrics at /metrics on a loopback address, e.g.
+                               127.0.0.1:9180, or on a Unix domain socket, e.g. unix:/run/mullvad-metrics.sock.
+                               [Default: disabled]
 
 ",
         mullvad_paths::get_default_resource_dir().display(),
@@ -0,0 +1,438 @@
+//! Keeps a journal of recent tunnel sessions in the cache directory, so that connection problems
+//! can be looked into after the fact.
+
+use chrono::{DateTime, Utc};
+use mullvad_types::{
+    connection_history::{ConnectionSession, SessionEndReason},
+    states::TunnelState,
+};
+use std::{
+    collections::VecDeque,
+    path::{Path, PathBuf},
+    sync::Weak,
+    time::{Duration, Instant},
+};
+use talpid_core::tunnel_state_machine::TunnelCommand;
+use talpid_types::{ErrorExt, net::wireguard::TunnelStats};
+use tokio::{
+    fs,
+    sync::{mpsc, oneshot},
+    time::{MissedTickBehavior, interval},
+};
+
+const HISTORY_FILE: &str = "connection-history.json";
+
+/// Number of sessions to remember, including the ongoing one.
+const HISTORY_SIZE: usize = 100;
+
+/// How often to sample the traffic counters of the tunnel while connected.
+const STATS_INTERVAL: Duration = Duration::from_secs(30);
+
+/// How often to save the ongoing session while connected.
+const SAVE_INTERVAL: Duration = Duration::from_secs(5 * 60);
+
+/// An actor that records tunnel sessions and saves them to disk.
+pub struct ConnectionHistory {
+    task_event_tx: mpsc::UnboundedSender<TaskEvent>,
+}
+
+/// [ConnectionHistory] internal task state.
+struct Task {
+    events_rx: mpsc::UnboundedReceiver<TaskEvent>,
+    tunnel_command_tx: Weak<futures::channel::mpsc::UnboundedSender<TunnelCommand>>,
+    path: PathBuf,
+    journal: Journal,
+    last_save: Instant,
+}
+
+enum TaskEvent {
+    NewTunnelState(TunnelState),
+    GetHistory(oneshot::Sender<Vec<ConnectionSession>>),
+}
+
+impl ConnectionHistory {
+    pub async fn new(
+        cache_dir: &Path,
+        tunnel_command_tx: Weak<futures::channel::mpsc::UnboundedSender<TunnelCommand>>,
+    ) -> Self {
+        let (task_event_tx, events_rx) = mpsc::unbounded_channel();
+
+        let path = cache_dir.join(HISTORY_FILE);
+        let journal = Journal::load(&path).await;
+        let task = Task {
+            events_rx,
+            tunnel_command_tx,
+            path,
+            journal,
+            last_save: Instant::now(),
+        };
+
+        tokio::task::spawn(task.run());
+
+        ConnectionHistory { task_event_tx }
+    }
+
+    /// Call when we transition to a new tunnel state.
+    pub fn on_tunnel_state(&self, tunnel_state: TunnelState) {
+        self.send(TaskEvent::NewTunnelState(tunnel_state))
+    }
+
+    /// Return the most recent sessions, oldest first. The last session may be ongoing.
+    pub fn get_history(&self) -> oneshot::Receiver<Vec<ConnectionSession>> {
+        let (tx, rx) = oneshot::channel();
+        self.send(TaskEvent::GetHistory(tx));
+        rx
+    }
+
+    /// Send a [TaskEvent] to the running [Task];
+    fn send(&self, event: TaskEvent) {
+        if self.task_event_tx.send(event).is_err() {
+            log::error!("Connection history task is not running");
+        }
+    }
+}
+
+impl Task {
+    async fn run(mut self) {
+        let mut stats_interval = interval(STATS_INTERVAL);
+        stats_interval.set_missed_tick_behavior(MissedTickBehavior::Delay);
+
+        loop {
+            tokio::select! {
+                event = self.events_rx.recv() => match event {
+                    Some(TaskEvent::NewTunnelState(tunnel_state)) => {
+                        // The tunnel is going down, so this is the last chance to count its
+                        // traffic
+                        if self.journal.connected
+                            && matches!(tunnel_state, TunnelState::Disconnecting(_))
+                        {
+                            self.sample_tunnel_stats().await;
+                        }
+                        if self.journal.on_tunnel_state(tunnel_state, Utc::now()) {
+                            self.save().await;
+                        }
+                    }
+                    Some(TaskEvent::GetHistory(tx)) => {
+                        let _ = tx.send(self.journal.sessions.iter().cloned().collect());
+                    }
+                    None => break, // All ConnectionHistory handles dropped.
+                },
+                _ = stats_interval.tick(), if self.journal.connected => {
+                    self.sample_tunnel_stats().await;
+                    if self.last_save.elapsed() >= SAVE_INTERVAL {
+                        self.save().await;
+                    }
+                }
+            }
+        }
+
+        if self
+            .journal
+            .end_session(SessionEndReason::Interrupted, Utc::now())
+        {
+            self.save().await;
+        }
+    }
+
+    async fn sample_tunnel_stats(&mut self) {
+        let Some(command_tx) = self.tunnel_command_tx.upgrade() else {
+            return;
+        };
+        let (tx, rx) = futures::channel::oneshot::channel();
+        if command_tx
+            .unbounded_send(TunnelCommand::GetTunnelStats(tx))
+            .is_err()
+        {
+            return;
+        }
+        drop(command_tx);
+
+        if let Ok(Some(stats)) = rx.await {
+            self.journal.on_tunnel_stats(&stats);
+        }
+    }
+
+    async fn save(&mut self) {
+        self.last_save = Instant::now();
+        match serde_json::to_string(&self.journal.sessions) {
+            Ok(data) => {
+                if let Err(error) = fs::write(&self.path, data).await {
+                    log::error!(
+                        "{}",
+                        error.display_chain_with_msg("Failed to write connection history")
+                    );
+                }
+            }
+            Err(error) => {
+                log::error!(
+                    "{}",
+                    error.display_chain_with_msg("Failed to serialize connection history")
+                );
+            }
+        }
+    }
+}
+
+/// Sessions that have been recorded, oldest first. The last one may be ongoing.
+#[derive(Default)]
+struct Journal {
+    sessions: VecDeque<ConnectionSession>,
+    /// Whether the ongoing session currently has a tunnel up.
+    connected: bool,
+    /// Whether the most recent tunnel of the ongoing session came up. Only attempts to replace
+    /// such a tunnel count as reconnects.
+    tunnel_was_up: bool,
+    /// Traffic of the previous tunnels in the ongoing session, as `(rx_bytes, tx_bytes)`. The
+    /// counters start over with each new tunnel.
+    previous_traffic: (u64, u64),
+}
+
+impl Journal {
+    async fn load(path: &Path) -> Self {
+        let sessions = match fs::read_to_string(path).await {
+            Ok(data) => serde_json::from_str(&data).unwrap_or_else(|error: serde_json::Error| {
+                log::warn!(
+                    "{}",
+                    error.display_chain_with_msg("Failed to parse connection history")
+                );
+                VecDeque::new()
+            }),
+            Err(error) if error.kind() == std::io::ErrorKind::NotFound => VecDeque::new(),
+            Err(error) => {
+                log::warn!(
+                    "{}",
+                    error.display_chain_with_msg("Failed to read connection history")
+                );
+                VecDeque::new()
+            }
+        };
+
+        let mut journal = Journal {
+            sessions,
+            ..Journal::default()
+        };
+        // The daemon did not get to end the session the last time it ran. It was last known to be
+        // ongoing when the journal was saved.
+        if let Some(start) = journal.ongoing_session().map(|session| session.start) {
+            let last_saved = fs::metadata(path)
+                .await
+                .and_then(|metadata| metadata.modified())
+                .map(DateTime::<Utc>::from)
+                .unwrap_or(start);
+            journal.end_session(SessionEndReason::Interrupted, last_saved.max(start));
+        }
+        journal
+    }
+
+    /// Update the ongoing session, or start a new one. Returns whether the journal changed.
+    fn on_tunnel_state(&mut self, tunnel_state: TunnelState, now: DateTime<Utc>) -> bool {
+        let connected = tunnel_state.is_connected();
+        let tunnel_was_up = std::mem::replace(&mut self.tunnel_was_up, connected);
+        match tunnel_state {
+            TunnelState::Connecting {
+                endpoint,
+                location,
+                feature_indicators,
+            }
+            | TunnelState::Connected {
+                endpoint,
+                location,
+                feature_indicators,
+            } => {
+                match self.ongoing_session() {
+                    Some(session) if !connected && tunnel_was_up => session.reconnects += 1,
+                    Some(_) => (),
+                    None => self.start_session(now),
+                }
+                let session = self.ongoing_session().expect("no ongoing session");
+                session.location = location;
+                session.obfuscation = endpoint
+                    .obfuscation
+                    .map(|obfuscation| obfuscation.obfuscation_type);
+                session.feature_indicators = feature_indicators;
+                let traffic = (session.rx_bytes, session.tx_bytes);
+                if !connected {
+                    // A new tunnel is being set up
+                    self.previous_traffic = traffic;
+                }
+                self.connected = connected;
+                true
+            }
+            TunnelState::Disconnecting(_) => {
+                self.tunnel_was_up = tunnel_was_up;
+                self.connected = false;
+                false
+            }
+            TunnelState::Disconnected { .. } => {
+                self.end_session(SessionEndReason::Disconnected, now)
+            }
+            TunnelState::Error(error_state) => {
+                self.end_session(SessionEndReason::Error(error_state), now)
+            }
+        }
+    }
+
+    fn on_tunnel_stats(&mut self, stats: &TunnelStats) {
+        let (previous_rx, previous_tx) = self.previous_traffic;
+        let Some(peer_stats) = stats.exit.as_ref().or(stats.entry.as_ref()) else {
+            return;
+        };
+        // Never count less traffic than before, in case the stats were read from a tunnel that
+        // replaced the one being accounted for
+        if let Some(session) = self.ongoing_session() {
+            session.rx_bytes = session.rx_bytes.max(previous_rx + peer_stats.rx_bytes);
+            session.tx_bytes = session.tx_bytes.max(previous_tx + peer_stats.tx_bytes);
+        }
+    }
+
+    fn start_session(&mut self, now: DateTime<Utc>) {
+        if self.sessions.len() >= HISTORY_SIZE {
+            self.sessions.pop_front();
+        }
+        self.sessions.push_back(ConnectionSession {
+            start: now,
+            end: None,
+            location: None,
+            obfuscation: None,
+            feature_indicators: Default::default(),
+            rx_bytes: 0,
+            tx_bytes: 0,
+            reconnects: 0,
+            end_reason: None,
+        });
+        self.tunnel_was_up = false;
+        self.previous_traffic = (0, 0);
+    }
+
+    /// End the ongoing session, if there is one. Returns whether there was one.
+    fn end_session(&mut self, reason: SessionEndReason, now: DateTime<Utc>) -> bool {
+        self.connected = false;
+        self.tunnel_was_up = false;
+        let Some(session) = self.ongoing_session() else {
+            return false;
+        };
+        session.end = Some(now);
+        session.end_reason = Some(reason);
+        true
+    }
+
+    fn ongoing_session(&mut self) -> Option<&mut ConnectionSession> {
+        self.sessions
+            .back_mut()
+            .filter(|session| session.is_ongoing())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use talpid_types::{
+        net::{Endpoint, TransportProtocol, TunnelEndpoint, TunnelType, wireguard},
+        tunnel::ActionAfterDisconnect,
+    };
+
+    fn tunnel_state(connected: bool) -> TunnelState {
+        let endpoint = TunnelEndpoint {
+            endpoint: Endpoint::new([10, 0, 0, 1], 51820, TransportProtocol::Udp),
+            tunnel_type: TunnelType::Wireguard,
+            quantum_resistant: false,
+            proxy: None,
+            obfuscation: None,
+            entry_endpoint: None,
+            tunnel_interface: None,
+            wireguard_backend: None,
+            #[cfg(daita)]
+            daita: false,
+        };
+        let location = None;
+        let feature_indicators = Default::default();
+        if connected {
+            TunnelState::Connected {
+                endpoint,
+                location,
+                feature_indicators,
+            }
+        } else {
+            TunnelState::Connecting {
+                endpoint,
+                location,
+                feature_indicators,
+            }
+        }
+    }
+
+    fn tunnel_stats(rx_bytes: u64, tx_bytes: u64) -> TunnelStats {
+        TunnelStats {
+            entry: None,
+            exit: Some(wireguard::PeerStats {
+                public_key: wireguard::PrivateKey::new_from_random().public_key(),
+                rx_bytes,
+                tx_bytes,
+                last_handshake: None,
+            }),
+        }
+    }
+
+    /// Reconnects should be counted as part of the same session, and the traffic of each tunnel
+    /// should be added up.
+    #[test]
+    fn test_session_with_reconnect() {
+        let mut journal = Journal::default();
+        let now = Utc::now();
+
+        journal.on_tunnel_state(tunnel_state(false), now);
+        journal.on_tunnel_state(tunnel_state(true), now);
+        journal.on_tunnel_stats(&tunnel_stats(100, 10));
+        journal.on_tunnel_state(
+            TunnelState::Disconnecting(ActionAfterDisconnect::Reconnect),
+            now,
+        );
+        journal.on_tunnel_state(tunnel_state(false), now);
+        journal.on_tunnel_state(tunnel_state(true), now);
+        journal.on_tunnel_stats(&tunnel_stats(50, 5));
+        assert!(journal.connected);
+
+        let disconnected = TunnelState::Disconnected {
+            location: None,
+            #[cfg(not(target_os = "android"))]
+            locked_down: false,
+        };
+        assert!(journal.on_tunnel_state(disconnected.clone(), now));
+        // There is no session left to end
+        assert!(!journal.on_tunnel_state(disconnected, now));
+
+        assert_eq!(journal.sessions.len(), 1);
+        let session = &journal.sessions[0];
+        assert_eq!(session.reconnects, 1);
+        assert_eq!((session.rx_bytes, session.tx_bytes), (150, 15));
+        assert!(matches!(
+            session.end_reason,
+            Some(SessionEndReason::Disconnected)
+        ));
+    }
+
+    /// Only attempts to replace a tunnel that came up should count as reconnects, and the traffic
+    /// of a replaced tunnel should not lower the counters.
+    #[test]
+    fn test_failed_attempts_are_not_reconnects() {
+        let mut journal = Journal::default();
+        let now = Utc::now();
+
+        journal.on_tunnel_state(tunnel_state(false), now);
+        journal.on_tunnel_state(tunnel_state(false), now);
+        journal.on_tunnel_state(tunnel_state(true), now);
+        journal.on_tunnel_stats(&tunnel_stats(100, 10));
+        // Stats sampled when the tunnel went down, but answered by its replacement
+        journal.on_tunnel_stats(&tunnel_stats(5, 1));
+        journal.on_tunnel_state(
+            TunnelState::Disconnecting(ActionAfterDisconnect::Reconnect),
+            now,
+        );
+        journal.on_tunnel_state(tunnel_state(false), now);
+        journal.on_tunnel_state(tunnel_state(false), now);
+
+        let session = journal.ongoing_session().unwrap();
+        assert_eq!(session.reconnects, 1);
+        assert_eq!((session.rx_bytes, session.tx_bytes), (100, 10));
+    }
+}
@@ -1,4 +1,7 @@
-use mullvad_types::settings::{DnsOptions, DnsState};
+use mullvad_types::{
+    relay_constraints::RelaySettings,
+    settings::{DnsOptions, DnsState, Settings},
+};
 use std::net::{IpAddr, Ipv4Addr};
 use talpid_core::{dns::DnsConfig, firewall::is_local_address};
 
@@ -13,8 +16,52 @@ const DNS_ADULT_BLOCKING_IP_BIT: u8 = 1 << 3; // 0b00001000
 const DNS_GAMBLING_BLOCKING_IP_BIT: u8 = 1 << 4; // 0b00010000
 const DNS_SOCIAL_MEDIA_BLOCKING_IP_BIT: u8 = 1 << 5; // 0b00100000
 
+/// Return the DNS resolvers to use with `settings`. The DNS servers of a custom tunnel endpoint
+/// are used unless custom DNS servers are set. Content blockers only work with Mullvad relays, so
+/// they do not apply to them.
+pub fn addresses_from_settings(settings: &Settings) -> DnsConfig {
+    let options = &settings.tunnel_options.dns_options;
+    match &settings.relay_settings {
+        RelaySettings::CustomTunnelEndpoint(endpoint)
+            if options.state == DnsState::Default && !endpoint.dns_servers.is_empty() =>
+        {
+            // The servers of a custom endpoint are usually only reachable through the tunnel, even
+            // when their addresses are private, so they must not be treated as LAN servers
+            DnsConfig::from_addresses(&endpoint.dns_servers, &[])
+        }
+        _ => addresses_from_options(options),
+    }
+}
+
 /// Return the DNS resolvers to use
 pub fn addresses_from_options(options: &DnsOptions) -> DnsConfig {
+    // Per-domain rules are only supported on Linux
+    #[cfg(target_os = "linux")]
+    {
+        // Rules are validated when they are set, but may have been read from an invalid settings
+        // file. Never apply a rule that could capture every query.
+        let domain_rules: Vec<_> = options
+            .domain_rules
+            .iter()
+            .filter(|rule| match rule.validate() {
+                Ok(()) => true,
+                Err(error) => {
+                    log::warn!("Ignoring DNS rule: {error}");
+                    false
+                }
+            })
+            .cloned()
+            .collect();
+        default_addresses_from_options(options).with_domain_rules(&domain_rules)
+    }
+    #[cfg(not(target_os = "linux"))]
+    {
+        default_addresses_from_options(options)
+    }
+}
+
+/// Return the DNS resolvers to use for domains that no domain rule applies to
+fn default_addresses_from_options(options: &DnsOptions) -> DnsConfig {
     match options.state {
         DnsState::Default => {
             // Check if we should use a custom blocking DNS resolver.
@@ -48,6 +95,11 @@ pub fn addresses_from_options(options: &DnsOptions) -> DnsConfig {
                 DnsConfig::default()
             }
         }
+        // Encrypted DNS servers are only supported by the local resolver on Linux
+        #[cfg(target_os = "linux")]
+        DnsState::Custom if !options.custom_options.encrypted_servers.is_empty() => {
+            DnsConfig::from_encrypted_servers(&options.custom_options.encrypted_servers)
+        }
         DnsState::Custom if options.custom_options.addresses.is_empty() => DnsConfig::default(),
         DnsState::Custom => {
             let (non_tunnel_config, tunnel_config): (Vec<_>, Vec<_>) = options
@@ -73,6 +125,7 @@ mod test {
             state: DnsState::Default,
             custom_options: CustomDnsOptions::default(),
             default_options: DefaultDnsOptions::default(),
+            domain_rules: vec![],
         };
 
         assert_eq!(addresses_from_options(&public_cfg), DnsConfig::default());
@@ -87,6 +140,7 @@ mod test {
                 block_ads: true,
                 ..DefaultDnsOptions::default()
             },
+            domain_rules: vec![],
         };
 
         assert_eq!(
@@ -104,8 +158,10 @@ mod test {
             state: DnsState::Custom,
             custom_options: CustomDnsOptions {
                 addresses: vec![public_ip, private_ip],
+                ..CustomDnsOptions::default()
             },
             default_options: DefaultDnsOptions::default(),
+            domain_rules: vec![],
         };
 
         assert_eq!(
@@ -113,4 +169,85 @@ mod test {
             DnsConfig::from_addresses(&[public_ip], &[private_ip],)
         );
     }
+
+    // Encrypted servers take precedence over plain addresses
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_encrypted_dns() {
+        use mullvad_types::settings::{EncryptedDnsProtocol, EncryptedDnsServer};
+
+        let server = EncryptedDnsServer {
+            protocol: EncryptedDnsProtocol::Https,
+            address: "9.9.9.9".parse().unwrap(),
+            port: 443,
+            hostname: "dns.quad9.net".to_owned(),
+        };
+        let public_cfg = DnsOptions {
+            state: DnsState::Custom,
+            custom_options: CustomDnsOptions {
+                addresses: vec!["1.2.3.4".parse().unwrap()],
+                encrypted_servers: vec![server.clone()],
+            },
+            default_options: DefaultDnsOptions::default(),
+            domain_rules: vec![],
+        };
+
+        assert_eq!(
+            addresses_from_options(&public_cfg),
+            DnsConfig::from_encrypted_servers(&[server])
+        );
+    }
+
+    // Domain rules apply in addition to the regular DNS servers
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_domain_rules() {
+        use mullvad_types::settings::DomainDnsRule;
+
+        let rule =
+            DomainDnsRule::new("*.corp.example", vec!["10.0.0.53".parse().unwrap()]).unwrap();
+        let public_cfg = DnsOptions {
+            state: DnsState::Default,
+            custom_options: CustomDnsOptions::default(),
+            default_options: DefaultDnsOptions::default(),
+            domain_rules: vec![rule.clone()],
+        };
+
+        assert_eq!(
+            addresses_from_options(&public_cfg),
+            DnsConfig::default().with_domain_rules(&[rule])
+        );
+    }
+
+    // Servers of a custom endpoint are tunneled even if their addresses are private
+    #[test]
+    fn test_custom_endpoint_dns() {
+        use crate::dns::addresses_from_settings;
+        use mullvad_types::{settings::Settings, wg_quick::WgQuickConfig};
+
+        let config = WgQuickConfig::parse(
+            r#"
+            [Interface]
+            PrivateKey = mPzI8CrXCXnNC1xTvOn5qh5jWvLm2B0/3D4lDMcfomk=
+            Address = 10.8.0.2/24
+            DNS = 10.8.0.1
+
+            [Peer]
+            PublicKey = 6oeNfINvBoTsnF/4EAfhUbl8tQjH6Tt/A6qz38yNZUw=
+            AllowedIPs = 0.0.0.0/0
+            Endpoint = 192.0.2.1:51820
+            "#,
+        )
+        .unwrap();
+        let mut settings = Settings::default();
+        settings.relay_settings = config
+            .into_custom_tunnel_endpoint(None, None)
+            .unwrap()
+            .into();
+
+        assert_eq!(
+            addresses_from_settings(&settings),
+            DnsConfig::from_addresses(&["10.8.0.1".parse().unwrap()], &[])
+        );
+    }
 }
@@ -1,5 +1,6 @@
 use mullvad_daemon::settings::{self, SettingsPersister};
 use talpid_core::firewall::{self, Firewall, FirewallPolicy};
+use talpid_types::net::{AllowedInterface, FirewallException};
 
 #[derive(thiserror::Error, Debug)]
 pub enum Error {
@@ -15,27 +16,35 @@ pub enum Error {
 
 pub async fn initialize_firewall() -> Result<(), Error> {
     let mut firewall = Firewall::new(mullvad_types::TUNNEL_FWMARK)?;
-    let allow_lan = get_allow_lan().await.unwrap_or_else(|err| {
-        log::info!(
-            "Not allowing LAN traffic due to failing to read settings: {}",
-            err
-        );
-        false
-    });
+    let (allow_lan, firewall_exceptions, allowed_interfaces) =
+        get_settings().await.unwrap_or_else(|err| {
+            log::info!(
+                "Not allowing LAN traffic due to failing to read settings: {}",
+                err
+            );
+            (false, vec![], vec![])
+        });
     let policy = FirewallPolicy::Blocked {
         allow_lan,
         allowed_endpoint: None,
+        firewall_exceptions,
+        allowed_interfaces,
     };
     log::info!("Applying firewall policy {policy}");
     firewall.apply_policy(policy)?;
     Ok(())
 }
 
-async fn get_allow_lan() -> Result<bool, Error> {
+/// Return the allow LAN setting, the user-defined firewall exceptions and the allowed interfaces.
+async fn get_settings() -> Result<(bool, Vec<FirewallException>, Vec<AllowedInterface>), Error> {
     let path = mullvad_paths::settings_dir()?;
     // NOTE: This may fail if the daemon has not been restarted after an upgrade.
     //       This will cause `allow_lan` to be disabled during early boot. This
     //       is probably acceptable.
     let settings = SettingsPersister::read_only(&path).await;
-    Ok(settings.allow_lan)
+    Ok((
+        settings.allow_lan,
+        settings.valid_firewall_exceptions(),
+        settings.valid_allowed_interfaces(),
+    ))
 }
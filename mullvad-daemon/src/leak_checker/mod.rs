@@ -1,9 +1,32 @@
+use chrono::Utc;
 use futures::{FutureExt, select};
-pub use mullvad_leak_checker::LeakInfo;
-use std::time::Duration;
+use mullvad_leak_checker::LeakInfo;
+use mullvad_types::leak_check::{self, LeakCheckResult};
+use std::{collections::VecDeque, time::Duration};
 use talpid_routing::RouteManagerHandle;
 use talpid_types::{net::Endpoint, tunnel::TunnelStateTransition};
-use tokio::sync::mpsc;
+use tokio::sync::{mpsc, oneshot};
+
+/// Number of leak check results to remember.
+const HISTORY_SIZE: usize = 100;
+
+/// Time to let a new connection settle before starting a leak test.
+const SETTLE_DELAY: Duration = Duration::from_millis(5000);
+
+#[derive(thiserror::Error, Debug, Clone)]
+pub enum Error {
+    #[error("The leak checker is not running")]
+    Unavailable,
+
+    #[error("Leak checks can only be run while connected")]
+    NotConnected,
+
+    #[error("The tunnel state changed during the leak check")]
+    Interrupted,
+
+    #[error("Leak check failed: {0}")]
+    CheckFailed(String),
+}
 
 /// An actor that tries to leak traffic outside the tunnel while we are connected.
 pub struct LeakChecker {
@@ -15,11 +38,19 @@ struct Task {
     events_rx: mpsc::UnboundedReceiver<TaskEvent>,
     route_manager: RouteManagerHandle,
     callbacks: Vec<Box<dyn LeakCheckerCallback>>,
+    /// The most recent tunnel state.
+    tunnel_state: Option<TunnelStateTransition>,
+    /// Results of the most recent leak checks, oldest first.
+    history: VecDeque<LeakCheckResult>,
+    /// On-demand requests waiting for the result of the current leak check.
+    pending_checks: Vec<oneshot::Sender<Result<LeakCheckResult, Error>>>,
 }
 
 enum TaskEvent {
     NewTunnelState(TunnelStateTransition),
     AddCallback(Box<dyn LeakCheckerCallback>),
+    GetHistory(oneshot::Sender<Vec<LeakCheckResult>>),
+    RunCheck(oneshot::Sender<Result<LeakCheckResult, Error>>),
 }
 
 #[derive(PartialEq, Eq)]
@@ -32,7 +63,7 @@ pub enum CallbackResult {
 }
 
 pub trait LeakCheckerCallback: Send + 'static {
-    fn on_leak(&mut self, info: LeakInfo) -> CallbackResult;
+    fn on_leak(&mut self, result: LeakCheckResult) -> CallbackResult;
 }
 
 impl LeakChecker {
@@ -43,6 +74,9 @@ impl LeakChecker {
             events_rx,
             route_manager,
             callbacks: vec![],
+            tunnel_state: None,
+            history: VecDeque::with_capacity(HISTORY_SIZE),
+            pending_checks: vec![],
         };
 
         tokio::task::spawn(task.run());
@@ -60,6 +94,21 @@ impl LeakChecker {
         self.send(TaskEvent::AddCallback(Box::new(callback)))
     }
 
+    /// Return the results of the most recent leak checks, oldest first.
+    pub fn get_history(&mut self) -> oneshot::Receiver<Vec<LeakCheckResult>> {
+        let (tx, rx) = oneshot::channel();
+        self.send(TaskEvent::GetHistory(tx));
+        rx
+    }
+
+    /// Run a leak check right away, and return its result once it completes. If a leak check is
+    /// already in progress, the result of that check is returned instead.
+    pub fn run_check(&mut self) -> oneshot::Receiver<Result<LeakCheckResult, Error>> {
+        let (tx, rx) = oneshot::channel();
+        self.send(TaskEvent::RunCheck(tx));
+        rx
+    }
+
     /// Send a [TaskEvent] to the running [Task];
     fn send(&mut self, event: TaskEvent) {
         if self.task_event_tx.send(event).is_err() {
@@ -76,8 +125,22 @@ impl Task {
             };
 
             match event {
-                TaskEvent::NewTunnelState(s) => self.on_new_tunnel_state(s).await,
+                TaskEvent::NewTunnelState(s) => {
+                    self.tunnel_state = Some(s.clone());
+                    self.on_new_tunnel_state(s, SETTLE_DELAY).await
+                }
                 TaskEvent::AddCallback(c) => self.on_add_callback(c),
+                TaskEvent::GetHistory(tx) => self.on_get_history(tx),
+                TaskEvent::RunCheck(tx) => {
+                    let Some(tunnel_state @ TunnelStateTransition::Connected(..)) =
+                        self.tunnel_state.clone()
+                    else {
+                        let _ = tx.send(Err(Error::NotConnected));
+                        continue;
+                    };
+                    self.pending_checks.push(tx);
+                    self.on_new_tunnel_state(tunnel_state, Duration::ZERO).await
+                }
             }
         }
     }
@@ -86,17 +149,26 @@ impl Task {
         self.callbacks.push(c);
     }
 
-    async fn on_new_tunnel_state(&mut self, mut tunnel_state: TunnelStateTransition) {
+    fn on_get_history(&self, tx: oneshot::Sender<Vec<LeakCheckResult>>) {
+        let _ = tx.send(self.history.iter().cloned().collect());
+    }
+
+    async fn on_new_tunnel_state(
+        &mut self,
+        mut tunnel_state: TunnelStateTransition,
+        mut settle_delay: Duration,
+    ) {
         'leak_test: loop {
             let TunnelStateTransition::Connected(tunnel) = &tunnel_state else {
+                self.respond_to_pending_checks(Err(Error::Interrupted));
                 break 'leak_test;
             };
 
             let ping_destination = tunnel.endpoint;
             let route_manager = self.route_manager.clone();
-            let leak_test = async {
+            let leak_test = async move {
                 // Give the connection a little time to settle before starting the test.
-                tokio::time::sleep(Duration::from_millis(5000)).await;
+                tokio::time::sleep(settle_delay).await;
 
                 check_for_leaks(&route_manager, ping_destination).await
             };
@@ -111,8 +183,19 @@ impl Task {
                             self.on_add_callback(c);
                             continue 'listen_for_events;
                         }
+                        TaskEvent::GetHistory(tx) => {
+                            self.on_get_history(tx);
+                            continue 'listen_for_events;
+                        }
+                        TaskEvent::RunCheck(tx) => {
+                            // Answer with the result of the test that is already running
+                            self.pending_checks.push(tx);
+                            continue 'listen_for_events;
+                        }
                     };
 
+                    self.tunnel_state = Some(new_state.clone());
+
                     if let TunnelStateTransition::Connected(..) = new_state {
                         // Still connected, all is well...
                     } else {
@@ -125,31 +208,57 @@ impl Task {
 
             let leak_result = select! {
                 // If tunnel state changes, restart the test.
-                _ = another_tunnel_state.fuse() => continue 'leak_test,
+                _ = another_tunnel_state.fuse() => {
+                    settle_delay = SETTLE_DELAY;
+                    continue 'leak_test;
+                },
 
                 leak_result = leak_test.fuse() => leak_result,
             };
 
             let leak_info = match leak_result {
-                Ok(Some(leak_info)) => leak_info,
-                Ok(None) => {
-                    log::debug!("No leak detected");
-                    break 'leak_test;
-                }
+                Ok(leak_info) => leak_info,
                 Err(e) => {
                     log::debug!("Leak check errored: {e:#?}");
+                    self.respond_to_pending_checks(Err(Error::CheckFailed(format!("{e:#}"))));
                     break 'leak_test;
                 }
             };
 
+            let result = LeakCheckResult {
+                timestamp: Utc::now(),
+                relay: ping_destination.address.ip(),
+                leak: leak_info.as_ref().map(|info| leak_check::LeakInfo {
+                    interface: info.interface().map(ToString::to_string),
+                    reachable_nodes: info.reachable_nodes(),
+                }),
+            };
+
+            if self.history.len() >= HISTORY_SIZE {
+                self.history.pop_front();
+            }
+            self.history.push_back(result.clone());
+            self.respond_to_pending_checks(Ok(result.clone()));
+
+            let Some(leak_info) = leak_info else {
+                log::debug!("No leak detected");
+                break 'leak_test;
+            };
+
             log::debug!("Leak detected: {leak_info:?}");
 
             self.callbacks
-                .retain_mut(|callback| callback.on_leak(leak_info.clone()) == CallbackResult::Ok);
+                .retain_mut(|callback| callback.on_leak(result.clone()) == CallbackResult::Ok);
 
             break 'leak_test;
         }
     }
+
+    fn respond_to_pending_checks(&mut self, result: Result<LeakCheckResult, Error>) {
+        for tx in self.pending_checks.drain(..) {
+            let _ = tx.send(result.clone());
+        }
+    }
 }
 
 #[cfg(target_os = "android")]
@@ -248,10 +357,10 @@ async fn check_for_leaks(
 
 impl<T> LeakCheckerCallback for T
 where
-    T: FnMut(LeakInfo) -> bool + Send + 'static,
+    T: FnMut(LeakCheckResult) -> bool + Send + 'static,
 {
-    fn on_leak(&mut self, info: LeakInfo) -> CallbackResult {
-        if self(info) {
+    fn on_leak(&mut self, result: LeakCheckResult) -> CallbackResult {
+        if self(result) {
             CallbackResult::Ok
         } else {
             CallbackResult::Drop
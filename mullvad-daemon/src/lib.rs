@@ -2,12 +2,16 @@
 #![allow(rustdoc::private_intra_doc_links)]
 
 mod access_method;
+mod access_policy;
 pub mod account_history;
 mod android_dns;
 mod api;
 mod api_address_updater;
+#[cfg(target_os = "linux")]
+mod auto_connect;
 #[cfg(not(target_os = "android"))]
 mod cleanup;
+mod connection_history;
 mod custom_list;
 pub mod device;
 mod dns;
@@ -18,7 +22,9 @@ pub mod logging;
 #[cfg(target_os = "macos")]
 mod macos;
 pub mod management_interface;
+pub mod metrics;
 mod migrations;
+mod relay_latency;
 mod relay_list;
 #[cfg(not(target_os = "android"))]
 pub mod rpc_uniqueness_check;
@@ -31,6 +37,7 @@ pub mod version;
 
 use crate::target_state::PersistentTargetState;
 use api::DaemonAccessMethodResolver;
+use connection_history::ConnectionHistory;
 use device::{AccountEvent, PrivateAccountAndDevice, PrivateDeviceEvent};
 use futures::{
     StreamExt,
@@ -38,15 +45,16 @@ use futures::{
     future::{AbortHandle, Future, abortable},
 };
 use geoip::GeoIpHandler;
-use leak_checker::{LeakChecker, LeakInfo};
+use leak_checker::LeakChecker;
 use management_interface::ManagementInterfaceServer;
 use mullvad_api::{ApiEndpoint, access_mode::AccessMethodEvent, proxy::ApiConnectionMode};
 use mullvad_encrypted_dns_proxy::state::EncryptedDnsProxyState;
 use mullvad_relay_selector::{RelaySelector, SelectorConfig};
 #[cfg(target_os = "android")]
 use mullvad_types::account::{PlayPurchase, PlayPurchasePaymentToken};
+#[cfg(target_os = "linux")]
+use mullvad_types::auto_connect::{AutoConnectAction, AutoConnectRule};
 use mullvad_types::relay_constraints::GeographicLocationConstraint;
-#[cfg(any(windows, target_os = "android", target_os = "macos"))]
 use mullvad_types::settings::SplitApp;
 #[cfg(daita)]
 use mullvad_types::wireguard::DaitaSettings;
@@ -54,26 +62,27 @@ use mullvad_types::{
     access_method::{AccessMethod, AccessMethodSetting},
     account::{AccountData, AccountNumber, VoucherSubmission},
     auth_failed::AuthFailed,
+    connection_history::ConnectionSession,
     constraints::Constraint,
     custom_list::CustomList,
     device::{Device, DeviceEvent, DeviceEventCause, DeviceId, DeviceState, RemoveDeviceEvent},
     features::{FeatureIndicator, FeatureIndicators, compute_feature_indicators},
+    leak_check::LeakCheckResult,
     location::{GeoIpLocation, LocationEventData},
+    profile::SettingsProfile,
     relay_constraints::{
         BridgeSettings, BridgeState, BridgeType, ObfuscationSettings, RelayOverride, RelaySettings,
         allowed_ip::AllowedIps,
     },
-    relay_list::RelayList,
+    relay_list::{RelayHealth, RelayList},
     settings::{DnsOptions, Settings},
     states::{Secured, TargetState, TargetStateStrict, TunnelState},
     version::AppVersionInfo,
     wireguard::{PublicKey, QuantumResistantState, RotationInterval},
 };
 use relay_list::{RELAYS_FILENAME, RelayListUpdater, RelayListUpdaterHandle};
-use settings::SettingsPersister;
-use std::collections::BTreeSet;
-#[cfg(any(windows, target_os = "android", target_os = "macos"))]
-use std::collections::HashSet;
+use settings::{SettingsPersister, policy::SettingsPolicy};
+use std::collections::{BTreeSet, HashSet};
 #[cfg(target_os = "android")]
 use std::os::unix::io::RawFd;
 use std::{
@@ -99,7 +108,7 @@ use talpid_types::android::AndroidContext;
 use talpid_types::split_tunnel::ExcludedProcess;
 use talpid_types::{
     ErrorExt,
-    net::{IpVersion, TunnelType},
+    net::{AllowedInterface, FirewallException, IpVersion, TunnelType},
     tunnel::{ErrorStateCause, TunnelStateTransition},
 };
 use tokio::io;
@@ -169,7 +178,6 @@ pub enum Error {
     #[error("Unable to initialize split tunneling")]
     InitSplitTunneling(#[source] split_tunnel::Error),
 
-    #[cfg(any(windows, target_os = "android", target_os = "macos"))]
     #[error("Split tunneling error")]
     SplitTunnelError(#[source] split_tunnel::Error),
 
@@ -185,6 +193,9 @@ pub enum Error {
     #[error("Settings error")]
     SettingsError(#[source] settings::Error),
 
+    #[error("Unable to load settings policy")]
+    SettingsPolicy(#[source] settings::policy::Error),
+
     #[error("Account history error")]
     AccountHistory(#[source] account_history::Error),
 
@@ -206,8 +217,24 @@ pub enum Error {
     #[error("Access method error")]
     AccessMethodError(#[source] access_method::Error),
 
+    #[error("Leak check error")]
+    LeakCheck(#[source] leak_checker::Error),
+
     #[error("API connection mode error")]
     ApiConnectionModeError(#[source] mullvad_api::access_mode::Error),
+
+    #[error("No auto-connect rule at index {0}")]
+    AutoConnectRuleNotFound(usize),
+
+    #[error("No firewall exception at index {0}")]
+    FirewallExceptionNotFound(usize),
+
+    #[error("No allowed interface named \"{0}\"")]
+    AllowedInterfaceNotFound(String),
+
+    #[error("No settings profile named \"{0}\"")]
+    ProfileNotFound(String),
+
     #[error("No custom bridge has been specified")]
     NoCustomProxySaved,
 
@@ -232,6 +259,8 @@ pub enum DaemonCommand {
     Reconnect(oneshot::Sender<bool>),
     /// Request the current state.
     GetState(oneshot::Sender<TunnelState>),
+    /// Request traffic statistics for the current WireGuard tunnel, if there is one.
+    GetTunnelStats(oneshot::Sender<Option<talpid_types::net::wireguard::TunnelStats>>),
     CreateNewAccount(ResponseTx<String, Error>),
     /// Request the metadata for an account.
     GetAccountData(
@@ -251,6 +280,8 @@ pub enum DaemonCommand {
     /// Trigger an asynchronous relay list update. This returns before the relay list is actually
     /// updated.
     UpdateRelayLocations,
+    /// Get the relays that recently failed to connect.
+    GetRelayHealth(oneshot::Sender<Vec<RelayHealth>>),
     /// Log in with a given account and create a new device.
     LoginAccount(ResponseTx<(), Error>, AccountNumber),
     /// Log out of the current account and remove the device, if they exist.
@@ -267,6 +298,14 @@ pub enum DaemonCommand {
     SetRelaySettings(ResponseTx<(), settings::Error>, RelaySettings),
     /// Set the allow LAN setting.
     SetAllowLan(ResponseTx<(), settings::Error>, bool),
+    /// Append an exception to the user-defined firewall exceptions
+    AddFirewallException(ResponseTx<(), settings::Error>, FirewallException),
+    /// Remove the firewall exception at the given index
+    RemoveFirewallException(ResponseTx<(), Error>, usize),
+    /// Add an interface to the allowed interfaces, replacing any entry with the same name
+    AddAllowedInterface(ResponseTx<(), settings::Error>, AllowedInterface),
+    /// Remove the allowed interface with the given name
+    RemoveAllowedInterface(ResponseTx<(), Error>, String),
     /// Set the beta program setting.
     SetShowBetaReleases(ResponseTx<(), settings::Error>, bool),
     /// Set the block_when_disconnected setting.
@@ -274,6 +313,19 @@ pub enum DaemonCommand {
     SetBlockWhenDisconnected(ResponseTx<(), settings::Error>, bool),
     /// Set the auto-connect setting.
     SetAutoConnect(ResponseTx<(), settings::Error>, bool),
+    /// Append a rule to the list of auto-connect rules
+    #[cfg(target_os = "linux")]
+    AddAutoConnectRule(ResponseTx<(), settings::Error>, AutoConnectRule),
+    /// Remove the auto-connect rule at the given index
+    RemoveAutoConnectRule(ResponseTx<(), Error>, usize),
+    /// Return all settings profiles
+    ListProfiles(oneshot::Sender<Vec<SettingsProfile>>),
+    /// Save the current settings as a profile, replacing any profile with the same name
+    SaveProfile(ResponseTx<(), settings::Error>, String),
+    /// Apply a settings profile
+    ActivateProfile(ResponseTx<(), Error>, String),
+    /// Remove a settings profile
+    DeleteProfile(ResponseTx<(), Error>, String),
     /// Set the mssfix argument for OpenVPN
     SetOpenVpnMssfix(ResponseTx<(), settings::Error>, Option<u16>),
     /// Set proxy details for OpenVPN
@@ -286,6 +338,11 @@ pub enum DaemonCommand {
     SetEnableRecents(ResponseTx<(), settings::Error>, bool),
     /// Set whether to enable PQ PSK exchange in the tunnel
     SetQuantumResistantTunnel(ResponseTx<(), settings::Error>, QuantumResistantState),
+    /// Set the WireGuard implementation to use
+    SetWireguardBackend(
+        ResponseTx<(), settings::Error>,
+        talpid_types::net::wireguard::Backend,
+    ),
     /// Set DAITA settings for the tunnel
     #[cfg(daita)]
     SetEnableDaita(ResponseTx<(), settings::Error>, bool),
@@ -375,16 +432,12 @@ pub enum DaemonCommand {
     #[cfg(target_os = "linux")]
     ClearSplitTunnelProcesses(ResponseTx<(), split_tunnel::Error>),
     /// Exclude traffic of an application from the tunnel
-    #[cfg(any(windows, target_os = "android", target_os = "macos"))]
     AddSplitTunnelApp(ResponseTx<(), Error>, SplitApp),
     /// Remove application from list of apps to exclude from the tunnel
-    #[cfg(any(windows, target_os = "android", target_os = "macos"))]
     RemoveSplitTunnelApp(ResponseTx<(), Error>, SplitApp),
     /// Clear list of apps to exclude from the tunnel
-    #[cfg(any(windows, target_os = "android", target_os = "macos"))]
     ClearSplitTunnelApps(ResponseTx<(), Error>),
     /// Enable or disable split tunneling
-    #[cfg(any(windows, target_os = "android", target_os = "macos"))]
     SetSplitTunnelState(ResponseTx<(), Error>, bool),
     /// Returns all processes currently being excluded from the tunnel
     #[cfg(windows)]
@@ -407,12 +460,15 @@ pub enum DaemonCommand {
     /// Verify that a google play payment was successful through the API.
     #[cfg(target_os = "android")]
     VerifyPlayPurchase(ResponseTx<(), Error>, PlayPurchase),
-    /// Patch the settings using a JSON patch
-    ApplyJsonSettings(ResponseTx<(), settings::patch::Error>, String),
-    /// Return a JSON blob containing all overridable settings, if there are any
-    ExportJsonSettings(ResponseTx<String, settings::patch::Error>),
+    /// Patch the settings using a JSON patch. The flag allows sensitive settings to be patched.
+    ApplyJsonSettings(ResponseTx<(), settings::patch::Error>, String, bool),
+    /// Return a JSON blob containing all overridable settings, if there are any. The flag includes
+    /// sensitive settings.
+    ExportJsonSettings(ResponseTx<String, settings::patch::Error>, bool),
     /// Request the current feature indicators.
     GetFeatureIndicators(oneshot::Sender<FeatureIndicators>),
+    /// Return the most recent tunnel sessions, oldest first.
+    GetConnectionHistory(oneshot::Sender<Vec<ConnectionSession>>),
 
     // Debug features
     DisableRelay {
@@ -423,6 +479,10 @@ pub enum DaemonCommand {
         relay: String,
         tx: oneshot::Sender<()>,
     },
+    /// Return the results of the most recent leak checks, oldest first.
+    GetLeakCheckHistory(oneshot::Sender<Vec<LeakCheckResult>>),
+    /// Run a leak check right away and return its result.
+    RunLeakCheck(ResponseTx<LeakCheckResult, Error>),
     // App upgrade
     /// Prompt the daemon to start an app version upgrade.
     ///
@@ -459,13 +519,14 @@ pub(crate) enum InternalDaemonEvent {
     /// A generic event for when any settings change.
     SettingsChanged,
     /// The split tunnel paths or state were updated.
-    #[cfg(any(windows, target_os = "android", target_os = "macos"))]
     ExcludedPathsEvent(ExcludedPathsUpdate, oneshot::Sender<Result<(), Error>>),
     /// A network leak was detected.
-    LeakDetected(LeakInfo),
+    LeakDetected(LeakCheckResult),
+    /// The network used to reach the internet changed. `None` means that the host is offline.
+    #[cfg(target_os = "linux")]
+    NetworkChanged(Option<auto_connect::CurrentNetwork>),
 }
 
-#[cfg(any(windows, target_os = "android", target_os = "macos"))]
 pub(crate) enum ExcludedPathsUpdate {
     SetState(bool),
     SetPaths(HashSet<SplitApp>),
@@ -495,6 +556,13 @@ impl From<AccountEvent> for InternalDaemonEvent {
     }
 }
 
+#[cfg(target_os = "linux")]
+impl From<Option<auto_connect::CurrentNetwork>> for InternalDaemonEvent {
+    fn from(network: Option<auto_connect::CurrentNetwork>) -> Self {
+        InternalDaemonEvent::NetworkChanged(network)
+    }
+}
+
 impl From<(AccessMethodEvent, oneshot::Sender<()>)> for InternalDaemonEvent {
     fn from(event: (AccessMethodEvent, oneshot::Sender<()>)) -> Self {
         InternalDaemonEvent::AccessMethodEvent {
@@ -654,6 +722,8 @@ pub struct Daemon {
     version_handle: version::router::VersionRouterHandle,
     relay_selector: RelaySelector,
     relay_list_updater: RelayListUpdaterHandle,
+    latency_prober: relay_latency::LatencyProber,
+    metrics: metrics::Metrics,
     parameters_generator: tunnel::ParametersGenerator,
     shutdown_tasks: Vec<Pin<Box<dyn Future<Output = ()> + Send + Sync>>>,
     tunnel_state_machine_handle: TunnelStateMachineHandle,
@@ -661,6 +731,12 @@ pub struct Daemon {
     volume_update_tx: mpsc::UnboundedSender<()>,
     location_handler: GeoIpHandler,
     leak_checker: LeakChecker,
+    connection_history: ConnectionHistory,
+    #[cfg(target_os = "linux")]
+    current_network: Option<auto_connect::CurrentNetwork>,
+    /// Whether an auto-connect rule currently forces traffic to be blocked while disconnected.
+    #[cfg(target_os = "linux")]
+    auto_connect_blocking: bool,
     cache_dir: PathBuf,
 }
 pub struct DaemonConfig {
@@ -670,6 +746,7 @@ pub struct DaemonConfig {
     pub cache_dir: PathBuf,
     pub rpc_socket_path: PathBuf,
     pub endpoint: ApiEndpoint,
+    pub metrics_endpoint: Option<metrics::MetricsEndpoint>,
     #[cfg(target_os = "android")]
     pub android_context: AndroidContext,
 }
@@ -685,8 +762,9 @@ impl Daemon {
         let command_sender = daemon_command_channel.sender();
         let app_upgrade_broadcast = tokio::sync::broadcast::channel(32).0;
         let management_interface = ManagementInterfaceServer::start(
-            command_sender,
+            command_sender.clone(),
             config.rpc_socket_path,
+            &config.settings_dir,
             app_upgrade_broadcast.clone(),
         )
         .map_err(Error::ManagementInterfaceError)?;
@@ -728,7 +806,10 @@ impl Daemon {
             });
 
         let settings_event_listener = management_interface.notifier().clone();
-        let mut settings = SettingsPersister::load(&config.settings_dir).await;
+        let settings_policy =
+            SettingsPolicy::load(&config.settings_dir).map_err(Error::SettingsPolicy)?;
+        let update_policy = settings_policy.update_policy().clone();
+        let mut settings = SettingsPersister::load(&config.settings_dir, settings_policy).await;
         settings.register_change_listener(move |settings| {
             // Notify management interface server of changes to the settings
             settings_event_listener.notify_settings(settings.to_owned());
@@ -823,6 +904,28 @@ impl Daemon {
             PersistentTargetState::new(&config.cache_dir).await
         };
 
+        #[cfg(target_os = "linux")]
+        let exclude_pids = {
+            let mut exclude_pids = split_tunnel::PidManager::default();
+            if settings.split_tunnel.enable_exclusions
+                && let Err(error) = exclude_pids.set_excluded_apps(
+                    settings
+                        .split_tunnel
+                        .apps
+                        .iter()
+                        .cloned()
+                        .map(|app| PathBuf::from(app.to_tunnel_command_repr()))
+                        .collect(),
+                )
+            {
+                log::error!(
+                    "{}",
+                    error.display_chain_with_msg("Failed to exclude split tunnel apps")
+                );
+            }
+            exclude_pids
+        };
+
         #[cfg(any(windows, target_os = "android", target_os = "macos"))]
         let exclude_paths = if settings.split_tunnel.enable_exclusions {
             settings
@@ -836,12 +939,29 @@ impl Daemon {
             vec![]
         };
 
+        let metrics = metrics::Metrics::new();
         let parameters_generator = tunnel::ParametersGenerator::new(
             account_manager.clone(),
             relay_selector.clone(),
             settings.tunnel_options.clone(),
+            metrics.clone(),
         );
 
+        if let Some(endpoint) = config.metrics_endpoint
+            && let Err(error) = metrics::spawn_server(
+                endpoint,
+                metrics.clone(),
+                api_runtime.request_stats(),
+                command_sender,
+            )
+            .await
+        {
+            log::error!(
+                "{}",
+                error.display_chain_with_msg("Failed to start metrics endpoint")
+            );
+        }
+
         let param_gen = parameters_generator.clone();
         let (param_gen_tx, mut param_gen_rx) = mpsc::unbounded();
         tokio::spawn(async move {
@@ -877,11 +997,15 @@ impl Daemon {
         let tunnel_state_machine_handle = tunnel_state_machine::spawn(
             tunnel_state_machine::InitialTunnelState {
                 allow_lan: settings.allow_lan,
+                #[cfg(target_os = "linux")]
+                firewall_exceptions: settings.valid_firewall_exceptions(),
+                #[cfg(target_os = "linux")]
+                allowed_interfaces: settings.valid_allowed_interfaces(),
                 #[cfg(not(target_os = "android"))]
                 block_when_disconnected: BlockWhenDisconnected::from(
                     settings.block_when_disconnected,
                 ),
-                dns_config: dns::addresses_from_options(&settings.tunnel_options.dns_options),
+                dns_config: dns::addresses_from_settings(&settings),
                 allowed_endpoint: access_mode_handler
                     .get_current()
                     .await
@@ -912,6 +1036,12 @@ impl Daemon {
         .await
         .map_err(Error::TunnelError)?;
 
+        #[cfg(target_os = "linux")]
+        let offline_state_rx = auto_connect::watch_network(
+            offline_state_rx,
+            route_manager.clone(),
+            internal_event_tx.to_specialized_sender(),
+        );
         api::forward_offline_state(api_availability.clone(), offline_state_rx);
 
         let relay_list_listener = management_interface.notifier().clone();
@@ -933,6 +1063,7 @@ impl Daemon {
             internal_event_tx.to_specialized_sender(),
             settings.show_beta_releases,
             app_upgrade_broadcast,
+            update_policy,
         );
 
         // Attempt to download a fresh relay list
@@ -951,14 +1082,20 @@ impl Daemon {
         let leak_checker = {
             let mut leak_checker = LeakChecker::new(route_manager);
             let internal_event_tx = internal_event_tx.clone();
-            leak_checker.add_leak_callback(move |info| {
+            leak_checker.add_leak_callback(move |result| {
                 internal_event_tx
-                    .send(InternalDaemonEvent::LeakDetected(info))
+                    .send(InternalDaemonEvent::LeakDetected(result))
                     .is_ok()
             });
             leak_checker
         };
 
+        let connection_history = ConnectionHistory::new(
+            &config.cache_dir,
+            Arc::downgrade(tunnel_state_machine_handle.command_tx()),
+        )
+        .await;
+
         let daemon = Daemon {
             tunnel_state: TunnelState::Disconnected {
                 location: None,
@@ -967,7 +1104,7 @@ impl Daemon {
             },
             target_state,
             #[cfg(target_os = "linux")]
-            exclude_pids: split_tunnel::PidManager::default(),
+            exclude_pids,
             rx: internal_event_rx,
             tx: internal_event_tx,
             reconnection_job: None,
@@ -981,6 +1118,8 @@ impl Daemon {
             api_runtime,
             api_handle,
             version_handle,
+            latency_prober: relay_latency::LatencyProber::new(relay_selector.clone()),
+            metrics,
             relay_selector,
             relay_list_updater,
             parameters_generator,
@@ -990,6 +1129,11 @@ impl Daemon {
             volume_update_tx,
             location_handler,
             leak_checker,
+            connection_history,
+            #[cfg(target_os = "linux")]
+            current_network: None,
+            #[cfg(target_os = "linux")]
+            auto_connect_blocking: false,
             cache_dir: config.cache_dir,
         };
 
@@ -1122,12 +1266,16 @@ impl Daemon {
             SettingsChanged => {
                 self.update_feature_indicators_on_settings_changed();
             }
-            #[cfg(any(windows, target_os = "android", target_os = "macos"))]
             ExcludedPathsEvent(update, tx) => self.handle_new_excluded_paths(update, tx).await,
-            LeakDetected(leak_info) => {
+            LeakDetected(result) => {
                 log::warn!("Network leak detected! Please contact Mullvad support.");
-                log::warn!("{leak_info:?}")
+                log::warn!("{result:?}");
+                self.management_interface
+                    .notifier()
+                    .notify_leak_detected(result);
             }
+            #[cfg(target_os = "linux")]
+            NetworkChanged(network) => self.handle_network_changed(network).await,
         }
         should_stop
     }
@@ -1138,6 +1286,8 @@ impl Daemon {
     ) {
         self.leak_checker
             .on_tunnel_state_transition(tunnel_state_transition.clone());
+        self.latency_prober
+            .on_tunnel_state_transition(tunnel_state_transition.clone());
 
         self.reset_rpc_sockets_on_tunnel_state_transition(&tunnel_state_transition);
         self.device_checker
@@ -1230,6 +1380,10 @@ impl Daemon {
             _ => {}
         }
 
+        self.metrics
+            .on_tunnel_state_transition(&self.tunnel_state, &tunnel_state);
+        self.connection_history
+            .on_tunnel_state(tunnel_state.clone());
         self.tunnel_state = tunnel_state.clone();
         self.management_interface
             .notifier()
@@ -1394,12 +1548,14 @@ impl Daemon {
             SetTargetState(tx, state) => self.on_set_target_state(tx, state).await,
             Reconnect(tx) => self.on_reconnect(tx),
             GetState(tx) => self.on_get_state(tx),
+            GetTunnelStats(tx) => self.send_tunnel_command(TunnelCommand::GetTunnelStats(tx)),
             CreateNewAccount(tx) => self.on_create_new_account(tx),
             GetAccountData(tx, account_number) => self.on_get_account_data(tx, account_number),
             GetWwwAuthToken(tx) => self.on_get_www_auth_token(tx).await,
             SubmitVoucher(tx, voucher) => self.on_submit_voucher(tx, voucher),
             GetRelayLocations(tx) => self.on_get_relay_locations(tx),
             UpdateRelayLocations => self.on_update_relay_locations().await,
+            GetRelayHealth(tx) => self.on_get_relay_health(tx),
             LoginAccount(tx, account_number) => self.on_login_account(tx, account_number),
             LogoutAccount(tx) => self.on_logout_account(tx),
             GetDevice(tx) => self.on_get_device(tx),
@@ -1412,6 +1568,16 @@ impl Daemon {
             ClearAccountHistory(tx) => self.on_clear_account_history(tx).await,
             SetRelaySettings(tx, update) => self.on_set_relay_settings(tx, update).await,
             SetAllowLan(tx, allow_lan) => self.on_set_allow_lan(tx, allow_lan).await,
+            AddFirewallException(tx, exception) => {
+                self.on_add_firewall_exception(tx, exception).await
+            }
+            RemoveFirewallException(tx, index) => {
+                self.on_remove_firewall_exception(tx, index).await
+            }
+            AddAllowedInterface(tx, interface) => {
+                self.on_add_allowed_interface(tx, interface).await
+            }
+            RemoveAllowedInterface(tx, name) => self.on_remove_allowed_interface(tx, name).await,
             SetShowBetaReleases(tx, enabled) => self.on_set_show_beta_releases(tx, enabled).await,
             #[cfg(not(target_os = "android"))]
             SetBlockWhenDisconnected(tx, block_when_disconnected) => {
@@ -1419,6 +1585,13 @@ impl Daemon {
                     .await
             }
             SetAutoConnect(tx, auto_connect) => self.on_set_auto_connect(tx, auto_connect).await,
+            #[cfg(target_os = "linux")]
+            AddAutoConnectRule(tx, rule) => self.on_add_auto_connect_rule(tx, rule).await,
+            RemoveAutoConnectRule(tx, index) => self.on_remove_auto_connect_rule(tx, index).await,
+            ListProfiles(tx) => self.on_list_profiles(tx),
+            SaveProfile(tx, name) => self.on_save_profile(tx, name).await,
+            ActivateProfile(tx, name) => self.on_activate_profile(tx, name).await,
+            DeleteProfile(tx, name) => self.on_delete_profile(tx, name).await,
             SetOpenVpnMssfix(tx, mssfix_arg) => self.on_set_openvpn_mssfix(tx, mssfix_arg).await,
             SetBridgeSettings(tx, bridge_settings) => {
                 self.on_set_bridge_settings(tx, bridge_settings).await
@@ -1432,6 +1605,7 @@ impl Daemon {
                 self.on_set_quantum_resistant_tunnel(tx, quantum_resistant_state)
                     .await
             }
+            SetWireguardBackend(tx, backend) => self.on_set_wireguard_backend(tx, backend).await,
             #[cfg(daita)]
             SetEnableDaita(tx, value) => self.on_set_daita_enabled(tx, value).await,
             #[cfg(daita)]
@@ -1490,13 +1664,9 @@ impl Daemon {
             RemoveSplitTunnelProcess(tx, pid) => self.on_remove_split_tunnel_process(tx, pid),
             #[cfg(target_os = "linux")]
             ClearSplitTunnelProcesses(tx) => self.on_clear_split_tunnel_processes(tx),
-            #[cfg(any(windows, target_os = "android", target_os = "macos"))]
             AddSplitTunnelApp(tx, app) => self.on_add_split_tunnel_app(tx, app),
-            #[cfg(any(windows, target_os = "android", target_os = "macos"))]
             RemoveSplitTunnelApp(tx, path) => self.on_remove_split_tunnel_app(tx, path),
-            #[cfg(any(windows, target_os = "android", target_os = "macos"))]
             ClearSplitTunnelApps(tx) => self.on_clear_split_tunnel_apps(tx),
-            #[cfg(any(windows, target_os = "android", target_os = "macos"))]
             SetSplitTunnelState(tx, enabled) => self.on_set_split_tunnel_state(tx, enabled),
             #[cfg(windows)]
             GetSplitTunnelProcesses(tx) => self.on_get_split_tunnel_processes(tx),
@@ -1514,11 +1684,18 @@ impl Daemon {
             VerifyPlayPurchase(tx, play_purchase) => {
                 self.on_verify_play_purchase(tx, play_purchase)
             }
-            ApplyJsonSettings(tx, blob) => self.on_apply_json_settings(tx, blob).await,
-            ExportJsonSettings(tx) => self.on_export_json_settings(tx),
+            ApplyJsonSettings(tx, blob, allow_sensitive) => {
+                self.on_apply_json_settings(tx, blob, allow_sensitive).await
+            }
+            ExportJsonSettings(tx, include_sensitive) => {
+                self.on_export_json_settings(tx, include_sensitive)
+            }
             GetFeatureIndicators(tx) => self.on_get_feature_indicators(tx),
+            GetConnectionHistory(tx) => self.on_get_connection_history(tx),
             DisableRelay { relay, tx } => self.on_toggle_relay(relay, false, tx),
             EnableRelay { relay, tx } => self.on_toggle_relay(relay, true, tx),
+            GetLeakCheckHistory(tx) => self.on_get_leak_check_history(tx),
+            RunLeakCheck(tx) => self.on_run_leak_check(tx),
             AppUpgrade(tx) => self.on_app_upgrade(tx).await,
             AppUpgradeAbort(tx) => self.on_app_upgrade_abort(tx).await,
             GetAppUpgradeCacheDir(tx) => self.on_get_app_upgrade_cache_dir(tx).await,
@@ -1700,7 +1877,6 @@ impl Daemon {
         });
     }
 
-    #[cfg(any(windows, target_os = "android", target_os = "macos"))]
     async fn handle_new_excluded_paths(
         &mut self,
         update: ExcludedPathsUpdate,
@@ -1863,6 +2039,10 @@ impl Daemon {
         self.relay_list_updater.update().await;
     }
 
+    fn on_get_relay_health(&mut self, tx: oneshot::Sender<Vec<RelayHealth>>) {
+        Self::oneshot_send(tx, self.relay_selector.relay_health(), "relay health");
+    }
+
     fn on_login_account(&mut self, tx: ResponseTx<(), Error>, account_number: String) {
         let account_manager = self.account_manager.clone();
         let availability = self.api_runtime.availability_handle();
@@ -2172,6 +2352,43 @@ impl Daemon {
         }
     }
 
+    /// Update the split app paths in both the settings and the app monitor
+    #[cfg(target_os = "linux")]
+    fn set_split_tunnel_paths(
+        &mut self,
+        tx: ResponseTx<(), Error>,
+        response_msg: &'static str,
+        settings: Settings,
+        update: ExcludedPathsUpdate,
+    ) {
+        let excluded_apps = match update {
+            ExcludedPathsUpdate::SetPaths(ref paths) if settings.split_tunnel.enable_exclusions => {
+                paths.clone()
+            }
+            ExcludedPathsUpdate::SetState(true) => settings.split_tunnel.apps.clone(),
+            _ => HashSet::new(),
+        };
+
+        let result = self.exclude_pids.set_excluded_apps(
+            excluded_apps
+                .into_iter()
+                .map(|app| PathBuf::from(app.to_tunnel_command_repr()))
+                .collect(),
+        );
+        if let Err(error) = result {
+            log::error!(
+                "{}",
+                error.display_chain_with_msg("Failed to set excluded apps list")
+            );
+            Self::oneshot_send(tx, Err(Error::SplitTunnelError(error)), response_msg);
+            return;
+        }
+
+        let _ = self
+            .tx
+            .send(InternalDaemonEvent::ExcludedPathsEvent(update, tx));
+    }
+
     /// Update the split app paths in both the settings and tunnel
     #[cfg(target_os = "macos")]
     fn set_split_tunnel_paths(
@@ -2222,7 +2439,6 @@ impl Daemon {
         });
     }
 
-    #[cfg(any(target_os = "windows", target_os = "macos", target_os = "android"))]
     fn on_add_split_tunnel_app(&mut self, tx: ResponseTx<(), Error>, app: SplitApp) {
         let settings = self.settings.to_settings();
 
@@ -2240,7 +2456,6 @@ impl Daemon {
         );
     }
 
-    #[cfg(any(windows, target_os = "android", target_os = "macos"))]
     fn on_remove_split_tunnel_app(&mut self, tx: ResponseTx<(), Error>, app: impl Into<SplitApp>) {
         let settings = self.settings.to_settings();
 
@@ -2258,7 +2473,6 @@ impl Daemon {
         );
     }
 
-    #[cfg(any(windows, target_os = "android", target_os = "macos"))]
     fn on_clear_split_tunnel_apps(&mut self, tx: ResponseTx<(), Error>) {
         let settings = self.settings.to_settings();
         let new_list = HashSet::new();
@@ -2270,7 +2484,6 @@ impl Daemon {
         );
     }
 
-    #[cfg(any(windows, target_os = "android", target_os = "macos"))]
     fn on_set_split_tunnel_state(&mut self, tx: ResponseTx<(), Error>, state: bool) {
         let settings = self.settings.to_settings();
         self.set_split_tunnel_paths(
@@ -2315,6 +2528,12 @@ impl Daemon {
             Ok(settings_changed) => {
                 Self::oneshot_send(tx, Ok(()), "set_relay_settings response");
                 if settings_changed {
+                    // A custom tunnel endpoint may have its own DNS servers
+                    let resolvers = dns::addresses_from_settings(&self.settings);
+                    let (dns_tx, _dns_rx) = oneshot::channel();
+                    self.send_tunnel_command(TunnelCommand::Dns(resolvers, dns_tx));
+                    // The set of relays that may be selected could have changed
+                    self.latency_prober.probe();
                     log::info!("Initiating tunnel restart because the relay settings changed");
                     self.reconnect_tunnel();
                 }
@@ -2351,6 +2570,155 @@ impl Daemon {
         }
     }
 
+    async fn on_add_firewall_exception(
+        &mut self,
+        tx: ResponseTx<(), settings::Error>,
+        exception: FirewallException,
+    ) {
+        match self
+            .settings
+            .try_update(move |settings| {
+                exception.validate()?;
+                settings.firewall_exceptions.push(exception);
+                Ok::<_, talpid_types::net::InvalidException>(())
+            })
+            .await
+        {
+            Ok(_settings_changed) => {
+                Self::oneshot_send(tx, Ok(()), "add firewall exception response");
+                self.send_firewall_exceptions_to_tunnel();
+            }
+            Err(e) => {
+                log::error!("{}", e.display_chain_with_msg("Unable to save settings"));
+                Self::oneshot_send(tx, Err(e), "add firewall exception response");
+            }
+        }
+    }
+
+    async fn on_remove_firewall_exception(&mut self, tx: ResponseTx<(), Error>, index: usize) {
+        if index >= self.settings.firewall_exceptions.len() {
+            Self::oneshot_send(
+                tx,
+                Err(Error::FirewallExceptionNotFound(index)),
+                "remove firewall exception response",
+            );
+            return;
+        }
+        match self
+            .settings
+            .update(move |settings| {
+                settings.firewall_exceptions.remove(index);
+            })
+            .await
+        {
+            Ok(_settings_changed) => {
+                Self::oneshot_send(tx, Ok(()), "remove firewall exception response");
+                self.send_firewall_exceptions_to_tunnel();
+            }
+            Err(e) => {
+                log::error!("{}", e.display_chain_with_msg("Unable to save settings"));
+                Self::oneshot_send(
+                    tx,
+                    Err(Error::SettingsError(e)),
+                    "remove firewall exception response",
+                );
+            }
+        }
+    }
+
+    /// Send the firewall exceptions to the tunnel state machine. They are only enforced on Linux.
+    fn send_firewall_exceptions_to_tunnel(&mut self) {
+        #[cfg(target_os = "linux")]
+        {
+            let (tx, _rx) = oneshot::channel();
+            self.send_tunnel_command(TunnelCommand::FirewallExceptions(
+                self.settings.valid_firewall_exceptions(),
+                tx,
+            ));
+        }
+    }
+
+    async fn on_add_allowed_interface(
+        &mut self,
+        tx: ResponseTx<(), settings::Error>,
+        interface: AllowedInterface,
+    ) {
+        match self
+            .settings
+            .try_update(move |settings| {
+                interface.validate()?;
+                let interfaces = &mut settings.allowed_interfaces;
+                match interfaces.iter_mut().find(|i| i.name == interface.name) {
+                    Some(existing) => *existing = interface,
+                    None => interfaces.push(interface),
+                }
+                Ok::<_, talpid_types::net::InvalidException>(())
+            })
+            .await
+        {
+            Ok(settings_changed) => {
+                Self::oneshot_send(tx, Ok(()), "add allowed interface response");
+                if settings_changed {
+                    self.send_allowed_interfaces_to_tunnel();
+                }
+            }
+            Err(e) => {
+                log::error!("{}", e.display_chain_with_msg("Unable to save settings"));
+                Self::oneshot_send(tx, Err(e), "add allowed interface response");
+            }
+        }
+    }
+
+    async fn on_remove_allowed_interface(&mut self, tx: ResponseTx<(), Error>, name: String) {
+        if !self
+            .settings
+            .allowed_interfaces
+            .iter()
+            .any(|interface| interface.name == name)
+        {
+            Self::oneshot_send(
+                tx,
+                Err(Error::AllowedInterfaceNotFound(name)),
+                "remove allowed interface response",
+            );
+            return;
+        }
+        match self
+            .settings
+            .update(move |settings| {
+                settings
+                    .allowed_interfaces
+                    .retain(|interface| interface.name != name);
+            })
+            .await
+        {
+            Ok(_settings_changed) => {
+                Self::oneshot_send(tx, Ok(()), "remove allowed interface response");
+                self.send_allowed_interfaces_to_tunnel();
+            }
+            Err(e) => {
+                log::error!("{}", e.display_chain_with_msg("Unable to save settings"));
+                Self::oneshot_send(
+                    tx,
+                    Err(Error::SettingsError(e)),
+                    "remove allowed interface response",
+                );
+            }
+        }
+    }
+
+    /// Send the allowed interfaces to the tunnel state machine. They are only supported on Linux.
+    fn send_allowed_interfaces_to_tunnel(&mut self) {
+        #[cfg(target_os = "linux")]
+        {
+            let (tx, _rx) = oneshot::channel();
+            self.send_tunnel_command(TunnelCommand::AllowedInterfaces(
+                self.settings.valid_allowed_interfaces(),
+                tx,
+            ));
+        }
+    }
+
     async fn on_set_show_beta_releases(
         &mut self,
         tx: ResponseTx<(), settings::Error>,
@@ -2393,7 +2761,7 @@ impl Daemon {
             Ok(settings_changed) => {
                 if settings_changed {
                     self.send_tunnel_command(TunnelCommand::BlockWhenDisconnected(
-                        BlockWhenDisconnected::from(block_when_disconnected),
+                        BlockWhenDisconnected::from(self.block_when_disconnected()),
                         oneshot_map(tx, |tx, ()| {
                             Self::oneshot_send(tx, Ok(()), "set_block_when_disconnected response");
                         }),
@@ -2429,6 +2797,212 @@ impl Daemon {
         }
     }
 
+    #[cfg(target_os = "linux")]
+    async fn on_add_auto_connect_rule(
+        &mut self,
+        tx: ResponseTx<(), settings::Error>,
+        rule: AutoConnectRule,
+    ) {
+        match self
+            .settings
+            .update(move |settings| settings.auto_connect_rules.push(rule))
+            .await
+        {
+            Ok(_settings_changed) => {
+                Self::oneshot_send(tx, Ok(()), "add auto-connect rule response");
+                self.apply_auto_connect_rules().await;
+            }
+            Err(e) => {
+                log::error!("{}", e.display_chain_with_msg("Unable to save settings"));
+                Self::oneshot_send(tx, Err(e), "add auto-connect rule response");
+            }
+        }
+    }
+
+    async fn on_remove_auto_connect_rule(&mut self, tx: ResponseTx<(), Error>, index: usize) {
+        if index >= self.settings.auto_connect_rules.len() {
+            Self::oneshot_send(
+                tx,
+                Err(Error::AutoConnectRuleNotFound(index)),
+                "remove auto-connect rule response",
+            );
+            return;
+        }
+        match self
+            .settings
+            .update(move |settings| {
+                settings.auto_connect_rules.remove(index);
+            })
+            .await
+        {
+            Ok(_settings_changed) => {
+                Self::oneshot_send(tx, Ok(()), "remove auto-connect rule response");
+                #[cfg(target_os = "linux")]
+                self.apply_auto_connect_rules().await;
+            }
+            Err(e) => {
+                log::error!("{}", e.display_chain_with_msg("Unable to save settings"));
+                Self::oneshot_send(
+                    tx,
+                    Err(Error::SettingsError(e)),
+                    "remove auto-connect rule response",
+                );
+            }
+        }
+    }
+
+    fn on_list_profiles(&self, tx: oneshot::Sender<Vec<SettingsProfile>>) {
+        Self::oneshot_send(tx, self.settings.profiles.clone(), "list profiles response");
+    }
+
+    async fn on_save_profile(&mut self, tx: ResponseTx<(), settings::Error>, name: String) {
+        let result = self
+            .settings
+            .update(move |settings| {
+                let profile = SettingsProfile::from_settings(name, settings);
+                match settings
+                    .profiles
+                    .iter_mut()
+                    .find(|p| p.name == profile.name)
+                {
+                    Some(existing) => *existing = profile,
+                    None => settings.profiles.push(profile),
+                }
+            })
+            .await
+            .map(|_| ())
+            .inspect_err(|e| {
+                log::error!("{}", e.display_chain_with_msg("Unable to save settings"));
+            });
+        Self::oneshot_send(tx, result, "save profile response");
+    }
+
+    /// Apply all settings in a profile in a single update, so that the tunnel is only reconnected
+    /// once.
+    async fn on_activate_profile(&mut self, tx: ResponseTx<(), Error>, name: String) {
+        let Some(profile) = self
+            .settings
+            .profiles
+            .iter()
+            .find(|p| p.name == name)
+            .cloned()
+        else {
+            Self::oneshot_send(
+                tx,
+                Err(Error::ProfileNotFound(name)),
+                "activate profile response",
+            );
+            return;
+        };
+        match self
+            .settings
+            .update(|settings| profile.apply_to(settings))
+            .await
+        {
+            Ok(settings_changed) => {
+                if settings_changed {
+                    log::info!("Activated settings profile \"{name}\"");
+                    let resolvers = dns::addresses_from_settings(&self.settings);
+                    let (dns_tx, _dns_rx) = oneshot::channel();
+                    self.send_tunnel_command(TunnelCommand::Dns(resolvers, dns_tx));
+                    // The set of relays that may be selected could have changed
+                    self.latency_prober.probe();
+                    self.reconnect_tunnel();
+                }
+                Self::oneshot_send(tx, Ok(()), "activate profile response");
+            }
+            Err(e) => {
+                log::error!("{}", e.display_chain_with_msg("Unable to save settings"));
+                Self::oneshot_send(
+                    tx,
+                    Err(Error::SettingsError(e)),
+                    "activate profile response",
+                );
+            }
+        }
+    }
+
+    async fn on_delete_profile(&mut self, tx: ResponseTx<(), Error>, name: String) {
+        if !self.settings.profiles.iter().any(|p| p.name == name) {
+            Self::oneshot_send(
+                tx,
+                Err(Error::ProfileNotFound(name)),
+                "delete profile response",
+            );
+            return;
+        }
+        let result = self
+            .settings
+            .update(move |settings| settings.profiles.retain(|p| p.name != name))
+            .await
+            .map(|_| ())
+            .map_err(|e| {
+                log::error!("{}", e.display_chain_with_msg("Unable to save settings"));
+                Error::SettingsError(e)
+            });
+        Self::oneshot_send(tx, result, "delete profile response");
+    }
+
+    #[cfg(target_os = "linux")]
+    async fn handle_network_changed(&mut self, network: Option<auto_connect::CurrentNetwork>) {
+        if network == self.current_network {
+            return;
+        }
+        match &network {
+            Some(network) => log::debug!("Current network: {network}"),
+            None => log::debug!("Current network: offline"),
+        }
+        self.current_network = network;
+        self.apply_auto_connect_rules().await;
+    }
+
+    /// Take the action of the first auto-connect rule that matches the current network. If no
+    /// rule matches, the target state is left alone.
+    #[cfg(target_os = "linux")]
+    async fn apply_auto_connect_rules(&mut self) {
+        let action = self.current_network.as_ref().and_then(|network| {
+            let action = auto_connect::matching_action(&self.settings.auto_connect_rules, network)?;
+            log::info!("Network ({network}) matches auto-connect rule. Action: {action}");
+            Some(action)
+        });
+
+        self.set_auto_connect_blocking(action == Some(AutoConnectAction::Block));
+        match action {
+            Some(AutoConnectAction::Connect) => {
+                self.set_target_state(TargetState::Secured).await;
+            }
+            Some(AutoConnectAction::Disconnect | AutoConnectAction::Block) => {
+                self.set_target_state(TargetState::Unsecured).await;
+            }
+            None => (),
+        }
+    }
+
+    /// Block traffic while disconnected, without changing the lockdown mode setting.
+    #[cfg(target_os = "linux")]
+    fn set_auto_connect_blocking(&mut self, blocking: bool) {
+        if blocking == self.auto_connect_blocking {
+            return;
+        }
+        self.auto_connect_blocking = blocking;
+        let (tx, _rx) = oneshot::channel();
+        self.send_tunnel_command(TunnelCommand::BlockWhenDisconnected(
+            BlockWhenDisconnected::from(self.block_when_disconnected()),
+            tx,
+        ));
+    }
+
+    /// Whether the tunnel state machine should block traffic while disconnected, taking
+    /// auto-connect rules into account.
+    #[cfg(not(target_os = "android"))]
+    fn block_when_disconnected(&self) -> bool {
+        #[cfg(target_os = "linux")]
+        if self.auto_connect_blocking {
+            return true;
+        }
+        self.settings.block_when_disconnected
+    }
+
     async fn on_set_openvpn_mssfix(
         &mut self,
         tx: ResponseTx<(), settings::Error>,
@@ -2627,6 +3201,31 @@ impl Daemon {
         }
     }
 
+    async fn on_set_wireguard_backend(
+        &mut self,
+        tx: ResponseTx<(), settings::Error>,
+        backend: talpid_types::net::wireguard::Backend,
+    ) {
+        match self
+            .settings
+            .update(|settings| settings.tunnel_options.wireguard.backend = backend)
+            .await
+        {
+            Ok(settings_changed) => {
+                Self::oneshot_send(tx, Ok(()), "set_wireguard_backend response");
+                if settings_changed && self.get_target_tunnel_type() == Some(TunnelType::Wireguard)
+                {
+                    log::info!("Reconnecting because the WireGuard backend setting changed");
+                    self.reconnect_tunnel();
+                }
+            }
+            Err(e) => {
+                log::error!("{}", e.display_chain_with_msg("Unable to save settings"));
+                Self::oneshot_send(tx, Err(e), "set_wireguard_backend response");
+            }
+        }
+    }
+
     #[cfg(daita)]
     async fn on_set_daita_enabled(&mut self, tx: ResponseTx<(), settings::Error>, value: bool) {
         let result = self
@@ -2729,14 +3328,18 @@ impl Daemon {
     ) {
         match self
             .settings
-            .update(move |settings| settings.tunnel_options.dns_options = dns_options)
+            .try_update(move |settings| {
+                for rule in &dns_options.domain_rules {
+                    rule.validate()?;
+                }
+                settings.tunnel_options.dns_options = dns_options;
+                Ok::<_, talpid_types::net::dns::InvalidDomain>(())
+            })
             .await
         {
             Ok(settings_changed) => {
                 if settings_changed {
-                    let settings = self.settings.settings();
-                    let resolvers =
-                        dns::addresses_from_options(&settings.tunnel_options.dns_options);
+                    let resolvers = dns::addresses_from_settings(self.settings.settings());
                     self.send_tunnel_command(TunnelCommand::Dns(
                         resolvers,
                         oneshot_map(tx, |tx, ()| {
@@ -3118,20 +3721,36 @@ impl Daemon {
             self.send_tunnel_command(TunnelCommand::SetExcludedApps(tx, vec![]));
         }
 
+        #[cfg(target_os = "linux")]
+        if let Err(error) = self.exclude_pids.set_excluded_apps(HashSet::new()) {
+            log::error!(
+                "{}",
+                error.display_chain_with_msg("Failed to clear excluded apps")
+            );
+        }
+
+        // The auto-connect rules have been removed, so they no longer block traffic
+        #[cfg(target_os = "linux")]
+        {
+            self.auto_connect_blocking = false;
+        }
+
         #[cfg(not(target_os = "android"))]
         {
             let (tx, _rx) = oneshot::channel();
             self.send_tunnel_command(TunnelCommand::BlockWhenDisconnected(
-                BlockWhenDisconnected::from(self.settings.block_when_disconnected),
+                BlockWhenDisconnected::from(self.block_when_disconnected()),
                 tx,
             ));
         }
 
         let (tx, _rx) = oneshot::channel();
         self.send_tunnel_command(TunnelCommand::AllowLan(self.settings.allow_lan, tx));
+        self.send_firewall_exceptions_to_tunnel();
+        self.send_allowed_interfaces_to_tunnel();
 
         let (tx, _rx) = oneshot::channel();
-        let dns = dns::addresses_from_options(&self.settings.tunnel_options.dns_options);
+        let dns = dns::addresses_from_settings(&self.settings);
         self.send_tunnel_command(TunnelCommand::Dns(dns, tx));
 
         let version_handle = self.version_handle.clone();
@@ -3273,16 +3892,43 @@ impl Daemon {
         &mut self,
         tx: ResponseTx<(), settings::patch::Error>,
         blob: String,
+        allow_sensitive: bool,
     ) {
-        let result = settings::patch::merge_validate_patch(&mut self.settings, &blob).await;
+        let result =
+            settings::patch::merge_validate_patch(&mut self.settings, &blob, allow_sensitive).await;
         if result.is_ok() {
+            self.send_patched_settings_to_tunnel();
             self.reconnect_tunnel();
         }
         Self::oneshot_send(tx, result, "apply_json_settings response");
     }
 
-    fn on_export_json_settings(&mut self, tx: ResponseTx<String, settings::patch::Error>) {
-        let result = settings::patch::export_settings(&self.settings);
+    /// Send the settings that the tunnel state machine keeps its own copy of, since a settings
+    /// patch may have changed any of them.
+    fn send_patched_settings_to_tunnel(&mut self) {
+        let allow_lan = self.settings.allow_lan;
+        let resolvers = dns::addresses_from_settings(&self.settings);
+
+        let (tx, _rx) = oneshot::channel();
+        self.send_tunnel_command(TunnelCommand::AllowLan(allow_lan, tx));
+        #[cfg(not(target_os = "android"))]
+        {
+            let (tx, _rx) = oneshot::channel();
+            self.send_tunnel_command(TunnelCommand::BlockWhenDisconnected(
+                BlockWhenDisconnected::from(self.block_when_disconnected()),
+                tx,
+            ));
+        }
+        let (tx, _rx) = oneshot::channel();
+        self.send_tunnel_command(TunnelCommand::Dns(resolvers, tx));
+    }
+
+    fn on_export_json_settings(
+        &mut self,
+        tx: ResponseTx<String, settings::patch::Error>,
+        include_sensitive: bool,
+    ) {
+        let result = settings::patch::export_settings(&self.settings, include_sensitive);
         Self::oneshot_send(tx, result, "export_json_settings response");
     }
 
@@ -3299,6 +3945,35 @@ impl Daemon {
         Self::oneshot_send(tx, feature_indicators, "get_feature_indicators response");
     }
 
+    fn on_get_connection_history(&mut self, tx: oneshot::Sender<Vec<ConnectionSession>>) {
+        let history_rx = self.connection_history.get_history();
+        tokio::spawn(async move {
+            let history = history_rx.await.unwrap_or_default();
+            Self::oneshot_send(tx, history, "get_connection_history response");
+        });
+    }
+
+    /// Respond with the leak checks kept by the leak checker.
+    fn on_get_leak_check_history(&mut self, tx: oneshot::Sender<Vec<LeakCheckResult>>) {
+        let history_rx = self.leak_checker.get_history();
+        tokio::spawn(async move {
+            let history = history_rx.await.unwrap_or_default();
+            Self::oneshot_send(tx, history, "get_leak_check_history response");
+        });
+    }
+
+    /// Run a leak check immediately and return its result.
+    fn on_run_leak_check(&mut self, tx: ResponseTx<LeakCheckResult, Error>) {
+        let result_rx = self.leak_checker.run_check();
+        tokio::spawn(async move {
+            let result = result_rx
+                .await
+                .unwrap_or(Err(leak_checker::Error::Unavailable))
+                .map_err(Error::LeakCheck);
+            Self::oneshot_send(tx, result, "run_leak_check response");
+        });
+    }
+
     // Debug features
 
     /// Mark [relay] as active or inactive in the daemon's relay list.
@@ -3,8 +3,8 @@ use std::{path::PathBuf, thread, time::Duration};
 #[cfg(not(windows))]
 use mullvad_daemon::cleanup_old_rpc_socket;
 use mullvad_daemon::{
-    Daemon, DaemonCommandChannel, DaemonConfig, exception_logging, logging, rpc_uniqueness_check,
-    runtime, version,
+    Daemon, DaemonCommandChannel, DaemonConfig, exception_logging, logging, metrics,
+    rpc_uniqueness_check, runtime, version,
 };
 use talpid_types::ErrorExt;
 
@@ -220,6 +220,7 @@ async fn create_daemon(log_dir: Option<PathBuf>) -> Result<Daemon, String> {
             cache_dir,
             rpc_socket_path,
             endpoint: mullvad_api::ApiEndpoint::from_env_vars(),
+            metrics_endpoint: metrics::MetricsEndpoint::from_env_vars(),
         },
         DaemonCommandChannel::new(),
     )
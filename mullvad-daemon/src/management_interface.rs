@@ -1,4 +1,8 @@
-use crate::{DaemonCommand, DaemonCommandSender, account_history, device};
+use crate::{
+    DaemonCommand, DaemonCommandSender,
+    access_policy::{AccessControl, Credentials, Role},
+    account_history, device,
+};
 use futures::{
     StreamExt,
     channel::{mpsc, oneshot},
@@ -20,40 +24,55 @@ use mullvad_types::{
     settings::{DnsOptions, Settings},
     states::{TargetState, TunnelState},
     version,
-    wireguard::{RotationInterval, RotationIntervalError},
+    wireguard::{RotationInterval, RotationIntervalError, TunnelStats},
 };
 use std::collections::BTreeSet;
 use std::{
     path::Path,
     str::FromStr,
     sync::{Arc, Mutex},
-    time::Duration,
+    time::{Duration, Instant},
+};
+use talpid_types::{
+    ErrorExt,
+    net::{AllowedInterface, FirewallException},
 };
-use talpid_types::ErrorExt;
 use tokio::time::timeout;
 use tokio_stream::wrappers::UnboundedReceiverStream;
 
 const RPC_SERVER_SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(1);
+/// Shortest interval that may be requested between tunnel stats updates.
+const MIN_TUNNEL_STATS_INTERVAL: Duration = Duration::from_millis(100);
 
 #[derive(thiserror::Error, Debug)]
 pub enum Error {
     // Unable to start the management interface server
     #[error("Unable to start management interface server")]
     SetupError(#[source] mullvad_management_interface::Error),
+
+    #[error("Unable to load management interface access policy")]
+    AccessPolicy(#[source] crate::access_policy::Error),
 }
 
 pub type AppUpgradeBroadcast = tokio::sync::broadcast::Sender<version::AppUpgradeEvent>;
 
 struct ManagementServiceImpl {
     daemon_tx: DaemonCommandSender,
-    subscriptions: Arc<Mutex<Vec<EventsListenerSender>>>,
+    subscriptions: Arc<Mutex<Vec<EventsSubscription>>>,
     pub app_upgrade_broadcast: AppUpgradeBroadcast,
+    access_control: AccessControl,
 }
 
 pub type ServiceResult<T> = std::result::Result<Response<T>, Status>;
 type EventsListenerReceiver = UnboundedReceiverStream<Result<types::DaemonEvent, Status>>;
 type EventsListenerSender = tokio::sync::mpsc::UnboundedSender<Result<types::DaemonEvent, Status>>;
 
+/// A client that listens for daemon events, and the role it was granted when it subscribed
+struct EventsSubscription {
+    tx: EventsListenerSender,
+    role: Role,
+}
+
 type AppUpgradeEventListenerReceiver =
     Box<dyn futures::Stream<Item = Result<types::AppUpgradeEvent, Status>> + Send + Unpin>;
 
@@ -65,11 +84,13 @@ impl ManagementService for ManagementServiceImpl {
     type GetSplitTunnelProcessesStream = UnboundedReceiverStream<Result<i32, Status>>;
     type EventsListenStream = EventsListenerReceiver;
     type AppUpgradeEventsListenStream = AppUpgradeEventListenerReceiver;
+    type TunnelStatsListenStream = UnboundedReceiverStream<Result<types::TunnelStats, Status>>;
 
     // Control and get the tunnel state
     //
 
-    async fn connect_tunnel(&self, _: Request<()>) -> ServiceResult<bool> {
+    async fn connect_tunnel(&self, request: Request<()>) -> ServiceResult<bool> {
+        self.authorize(&request, Role::Operator)?;
         log::debug!("connect_tunnel");
 
         let (tx, rx) = oneshot::channel();
@@ -78,7 +99,8 @@ impl ManagementService for ManagementServiceImpl {
         Ok(Response::new(connect_issued))
     }
 
-    async fn disconnect_tunnel(&self, _: Request<()>) -> ServiceResult<bool> {
+    async fn disconnect_tunnel(&self, request: Request<()>) -> ServiceResult<bool> {
+        self.authorize(&request, Role::Operator)?;
         log::debug!("disconnect_tunnel");
 
         let (tx, rx) = oneshot::channel();
@@ -87,7 +109,8 @@ impl ManagementService for ManagementServiceImpl {
         Ok(Response::new(disconnect_issued))
     }
 
-    async fn reconnect_tunnel(&self, _: Request<()>) -> ServiceResult<bool> {
+    async fn reconnect_tunnel(&self, request: Request<()>) -> ServiceResult<bool> {
+        self.authorize(&request, Role::Operator)?;
         log::debug!("reconnect_tunnel");
         let (tx, rx) = oneshot::channel();
         self.send_command_to_daemon(DaemonCommand::Reconnect(tx))?;
@@ -95,7 +118,8 @@ impl ManagementService for ManagementServiceImpl {
         Ok(Response::new(reconnect_issued))
     }
 
-    async fn get_tunnel_state(&self, _: Request<()>) -> ServiceResult<types::TunnelState> {
+    async fn get_tunnel_state(&self, request: Request<()>) -> ServiceResult<types::TunnelState> {
+        self.authorize(&request, Role::ReadOnly)?;
         log::debug!("get_tunnel_state");
         let (tx, rx) = oneshot::channel();
         self.send_command_to_daemon(DaemonCommand::GetState(tx))?;
@@ -103,19 +127,83 @@ impl ManagementService for ManagementServiceImpl {
         Ok(Response::new(types::TunnelState::from(state)))
     }
 
+    async fn tunnel_stats_listen(
+        &self,
+        request: Request<types::Duration>,
+    ) -> ServiceResult<Self::TunnelStatsListenStream> {
+        self.authorize(&request, Role::ReadOnly)?;
+        let interval = Duration::try_from(request.into_inner())
+            .map_err(|_| Status::invalid_argument("unexpected negative interval"))?;
+        if interval < MIN_TUNNEL_STATS_INTERVAL {
+            return Err(Status::invalid_argument(format!(
+                "interval must be at least {} ms",
+                MIN_TUNNEL_STATS_INTERVAL.as_millis()
+            )));
+        }
+        log::debug!("tunnel_stats_listen({:?})", interval);
+
+        let daemon_tx = self.daemon_tx.clone();
+        let (tx, rx) = tokio::sync::mpsc::unbounded_channel();
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(interval);
+            let mut previous: Option<(Instant, TunnelStats)> = None;
+            loop {
+                ticker.tick().await;
+
+                let (stats_tx, stats_rx) = oneshot::channel();
+                if daemon_tx
+                    .send(DaemonCommand::GetTunnelStats(stats_tx))
+                    .is_err()
+                {
+                    break;
+                }
+                let sample = stats_rx.await.unwrap_or(None);
+
+                let now = Instant::now();
+                let stats = match sample {
+                    Some(sample) => {
+                        let (elapsed, previous) = match &previous {
+                            Some((time, stats)) => (now.duration_since(*time), Some(stats)),
+                            None => (Duration::ZERO, None),
+                        };
+                        TunnelStats::from_sample(sample, previous, elapsed)
+                    }
+                    None => TunnelStats {
+                        entry: None,
+                        exit: None,
+                    },
+                };
+
+                if tx
+                    .send(Ok(types::TunnelStats::from(stats.clone())))
+                    .is_err()
+                {
+                    // The client has stopped listening
+                    break;
+                }
+                previous = Some((now, stats));
+            }
+        });
+
+        Ok(Response::new(UnboundedReceiverStream::new(rx)))
+    }
+
     // Control the daemon and receive events
     //
 
-    async fn events_listen(&self, _: Request<()>) -> ServiceResult<Self::EventsListenStream> {
+    async fn events_listen(&self, request: Request<()>) -> ServiceResult<Self::EventsListenStream> {
+        // Events that require a more privileged role are not sent to this listener
+        let role = self.authorize(&request, Role::ReadOnly)?;
         let (tx, rx) = tokio::sync::mpsc::unbounded_channel();
 
         let mut subscriptions = self.subscriptions.lock().unwrap();
-        subscriptions.push(tx);
+        subscriptions.push(EventsSubscription { tx, role });
 
         Ok(Response::new(UnboundedReceiverStream::new(rx)))
     }
 
-    async fn prepare_restart(&self, _: Request<()>) -> ServiceResult<()> {
+    async fn prepare_restart(&self, request: Request<()>) -> ServiceResult<()> {
+        self.authorize(&request, Role::Admin)?;
         log::debug!("prepare_restart");
         // Note: The old `PrepareRestart` behavior never shutdown the daemon.
         let shutdown = false;
@@ -124,12 +212,14 @@ impl ManagementService for ManagementServiceImpl {
     }
 
     async fn prepare_restart_v2(&self, shutdown: Request<bool>) -> ServiceResult<()> {
+        self.authorize(&shutdown, Role::Admin)?;
         log::debug!("prepare_restart_v2");
         self.send_command_to_daemon(DaemonCommand::PrepareRestart(shutdown.into_inner()))?;
         Ok(Response::new(()))
     }
 
-    async fn factory_reset(&self, _: Request<()>) -> ServiceResult<()> {
+    async fn factory_reset(&self, request: Request<()>) -> ServiceResult<()> {
+        self.authorize(&request, Role::Admin)?;
         #[cfg(not(target_os = "android"))]
         {
             log::debug!("factory_reset");
@@ -146,7 +236,8 @@ impl ManagementService for ManagementServiceImpl {
         }
     }
 
-    async fn get_current_version(&self, _: Request<()>) -> ServiceResult<String> {
+    async fn get_current_version(&self, request: Request<()>) -> ServiceResult<String> {
+        self.authorize(&request, Role::ReadOnly)?;
         log::debug!("get_current_version");
         let (tx, rx) = oneshot::channel();
         self.send_command_to_daemon(DaemonCommand::GetCurrentVersion(tx))?;
@@ -154,7 +245,8 @@ impl ManagementService for ManagementServiceImpl {
         Ok(Response::new(version))
     }
 
-    async fn get_version_info(&self, _: Request<()>) -> ServiceResult<types::AppVersionInfo> {
+    async fn get_version_info(&self, request: Request<()>) -> ServiceResult<types::AppVersionInfo> {
+        self.authorize(&request, Role::ReadOnly)?;
         log::debug!("get_version_info");
 
         let (tx, rx) = oneshot::channel();
@@ -166,7 +258,8 @@ impl ManagementService for ManagementServiceImpl {
             .map_err(map_daemon_error)
     }
 
-    async fn is_performing_post_upgrade(&self, _: Request<()>) -> ServiceResult<bool> {
+    async fn is_performing_post_upgrade(&self, request: Request<()>) -> ServiceResult<bool> {
+        self.authorize(&request, Role::ReadOnly)?;
         log::debug!("is_performing_post_upgrade");
         let (tx, rx) = oneshot::channel();
         self.send_command_to_daemon(DaemonCommand::IsPerformingPostUpgrade(tx))?;
@@ -176,7 +269,8 @@ impl ManagementService for ManagementServiceImpl {
     // Relays and tunnel constraints
     //
 
-    async fn update_relay_locations(&self, _: Request<()>) -> ServiceResult<()> {
+    async fn update_relay_locations(&self, request: Request<()>) -> ServiceResult<()> {
+        self.authorize(&request, Role::Operator)?;
         log::debug!("update_relay_locations");
         self.send_command_to_daemon(DaemonCommand::UpdateRelayLocations)?;
         Ok(Response::new(()))
@@ -186,6 +280,7 @@ impl ManagementService for ManagementServiceImpl {
         &self,
         request: Request<types::RelaySettings>,
     ) -> ServiceResult<()> {
+        self.authorize(&request, Role::Admin)?;
         log::debug!("set_relay_settings");
         let (tx, rx) = oneshot::channel();
         let constraints_update =
@@ -197,7 +292,8 @@ impl ManagementService for ManagementServiceImpl {
         Ok(Response::new(()))
     }
 
-    async fn get_relay_locations(&self, _: Request<()>) -> ServiceResult<types::RelayList> {
+    async fn get_relay_locations(&self, request: Request<()>) -> ServiceResult<types::RelayList> {
+        self.authorize(&request, Role::ReadOnly)?;
         log::debug!("get_relay_locations");
 
         let (tx, rx) = oneshot::channel();
@@ -207,10 +303,26 @@ impl ManagementService for ManagementServiceImpl {
             .map(|relays| Response::new(types::RelayList::from(relays)))
     }
 
+    async fn get_relay_health(
+        &self,
+        request: Request<()>,
+    ) -> ServiceResult<types::RelayHealthList> {
+        self.authorize(&request, Role::ReadOnly)?;
+        log::debug!("get_relay_health");
+
+        let (tx, rx) = oneshot::channel();
+        self.send_command_to_daemon(DaemonCommand::GetRelayHealth(tx))?;
+        let health = self.wait_for_result(rx).await?;
+        Ok(Response::new(types::RelayHealthList {
+            relays: health.into_iter().map(types::RelayHealth::from).collect(),
+        }))
+    }
+
     async fn set_bridge_settings(
         &self,
         request: Request<types::BridgeSettings>,
     ) -> ServiceResult<()> {
+        self.authorize(&request, Role::Admin)?;
         let settings =
             BridgeSettings::try_from(request.into_inner()).map_err(map_protobuf_type_err)?;
 
@@ -226,6 +338,7 @@ impl ManagementService for ManagementServiceImpl {
         &self,
         request: Request<types::ObfuscationSettings>,
     ) -> ServiceResult<()> {
+        self.authorize(&request, Role::Admin)?;
         let settings =
             ObfuscationSettings::try_from(request.into_inner()).map_err(map_protobuf_type_err)?;
         log::debug!("set_obfuscation_settings({:?})", settings);
@@ -236,6 +349,7 @@ impl ManagementService for ManagementServiceImpl {
     }
 
     async fn set_bridge_state(&self, request: Request<types::BridgeState>) -> ServiceResult<()> {
+        self.authorize(&request, Role::Admin)?;
         let bridge_state =
             BridgeState::try_from(request.into_inner()).map_err(map_protobuf_type_err)?;
 
@@ -249,16 +363,21 @@ impl ManagementService for ManagementServiceImpl {
     // Settings
     //
 
-    async fn get_settings(&self, _: Request<()>) -> ServiceResult<types::Settings> {
+    async fn get_settings(&self, request: Request<()>) -> ServiceResult<types::Settings> {
+        let role = self.authorize(&request, Role::ReadOnly)?;
         log::debug!("get_settings");
         let (tx, rx) = oneshot::channel();
         self.send_command_to_daemon(DaemonCommand::GetSettings(tx))?;
-        self.wait_for_result(rx)
-            .await
-            .map(|settings| Response::new(types::Settings::from(&settings)))
+        let mut settings = self.wait_for_result(rx).await?;
+        // Secrets are only sent to callers that could also set them
+        if role < Role::Admin {
+            settings.redact_secrets();
+        }
+        Ok(Response::new(types::Settings::from(&settings)))
     }
 
-    async fn reset_settings(&self, _: Request<()>) -> ServiceResult<()> {
+    async fn reset_settings(&self, request: Request<()>) -> ServiceResult<()> {
+        self.authorize(&request, Role::Admin)?;
         log::debug!("reset_settings");
         let (tx, rx) = oneshot::channel();
         self.send_command_to_daemon(DaemonCommand::ResetSettings(tx))?;
@@ -267,6 +386,7 @@ impl ManagementService for ManagementServiceImpl {
     }
 
     async fn set_allow_lan(&self, request: Request<bool>) -> ServiceResult<()> {
+        self.authorize(&request, Role::Admin)?;
         let allow_lan = request.into_inner();
         log::debug!("set_allow_lan({})", allow_lan);
         let (tx, rx) = oneshot::channel();
@@ -275,7 +395,60 @@ impl ManagementService for ManagementServiceImpl {
         Ok(Response::new(()))
     }
 
+    async fn add_firewall_exception(
+        &self,
+        request: Request<types::FirewallException>,
+    ) -> ServiceResult<()> {
+        self.authorize(&request, Role::Admin)?;
+        let exception =
+            FirewallException::try_from(request.into_inner()).map_err(map_protobuf_type_err)?;
+        log::debug!("add_firewall_exception({exception})");
+        let (tx, rx) = oneshot::channel();
+        self.send_command_to_daemon(DaemonCommand::AddFirewallException(tx, exception))?;
+        self.wait_for_result(rx).await??;
+        Ok(Response::new(()))
+    }
+
+    async fn remove_firewall_exception(&self, request: Request<u32>) -> ServiceResult<()> {
+        self.authorize(&request, Role::Admin)?;
+        let index = request.into_inner();
+        log::debug!("remove_firewall_exception({index})");
+        let (tx, rx) = oneshot::channel();
+        self.send_command_to_daemon(DaemonCommand::RemoveFirewallException(tx, index as usize))?;
+        self.wait_for_result(rx)
+            .await?
+            .map(Response::new)
+            .map_err(map_daemon_error)
+    }
+
+    async fn add_allowed_interface(
+        &self,
+        request: Request<types::AllowedInterface>,
+    ) -> ServiceResult<()> {
+        self.authorize(&request, Role::Admin)?;
+        let interface =
+            AllowedInterface::try_from(request.into_inner()).map_err(map_protobuf_type_err)?;
+        log::debug!("add_allowed_interface({interface})");
+        let (tx, rx) = oneshot::channel();
+        self.send_command_to_daemon(DaemonCommand::AddAllowedInterface(tx, interface))?;
+        self.wait_for_result(rx).await??;
+        Ok(Response::new(()))
+    }
+
+    async fn remove_allowed_interface(&self, request: Request<String>) -> ServiceResult<()> {
+        self.authorize(&request, Role::Admin)?;
+        let name = request.into_inner();
+        log::debug!("remove_allowed_interface({name})");
+        let (tx, rx) = oneshot::channel();
+        self.send_command_to_daemon(DaemonCommand::RemoveAllowedInterface(tx, name))?;
+        self.wait_for_result(rx)
+            .await?
+            .map(Response::new)
+            .map_err(map_daemon_error)
+    }
+
     async fn set_show_beta_releases(&self, request: Request<bool>) -> ServiceResult<()> {
+        self.authorize(&request, Role::Admin)?;
         let enabled = request.into_inner();
         log::debug!("set_show_beta_releases({})", enabled);
         let (tx, rx) = oneshot::channel();
@@ -286,6 +459,7 @@ impl ManagementService for ManagementServiceImpl {
 
     #[cfg(not(target_os = "android"))]
     async fn set_block_when_disconnected(&self, request: Request<bool>) -> ServiceResult<()> {
+        self.authorize(&request, Role::Admin)?;
         let block_when_disconnected = request.into_inner();
         log::debug!("set_block_when_disconnected({})", block_when_disconnected);
         let (tx, rx) = oneshot::channel();
@@ -299,6 +473,7 @@ impl ManagementService for ManagementServiceImpl {
 
     #[cfg(target_os = "android")]
     async fn set_block_when_disconnected(&self, request: Request<bool>) -> ServiceResult<()> {
+        self.authorize(&request, Role::Admin)?;
         let block_when_disconnected = request.into_inner();
         log::debug!("set_block_when_disconnected({})", block_when_disconnected);
         Err(Status::unimplemented(
@@ -307,6 +482,7 @@ impl ManagementService for ManagementServiceImpl {
     }
 
     async fn set_auto_connect(&self, request: Request<bool>) -> ServiceResult<()> {
+        self.authorize(&request, Role::Admin)?;
         let auto_connect = request.into_inner();
         log::debug!("set_auto_connect({})", auto_connect);
         let (tx, rx) = oneshot::channel();
@@ -315,7 +491,105 @@ impl ManagementService for ManagementServiceImpl {
         Ok(Response::new(()))
     }
 
+    #[cfg(target_os = "linux")]
+    async fn add_auto_connect_rule(
+        &self,
+        request: Request<types::AutoConnectRule>,
+    ) -> ServiceResult<()> {
+        self.authorize(&request, Role::Admin)?;
+        let rule = mullvad_types::auto_connect::AutoConnectRule::try_from(request.into_inner())
+            .map_err(map_protobuf_type_err)?;
+        log::debug!("add_auto_connect_rule({rule})");
+        let (tx, rx) = oneshot::channel();
+        self.send_command_to_daemon(DaemonCommand::AddAutoConnectRule(tx, rule))?;
+        self.wait_for_result(rx).await??;
+        Ok(Response::new(()))
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    async fn add_auto_connect_rule(
+        &self,
+        request: Request<types::AutoConnectRule>,
+    ) -> ServiceResult<()> {
+        self.authorize(&request, Role::Admin)?;
+        Err(Status::unimplemented(
+            "Auto-connect rules are only supported on Linux",
+        ))
+    }
+
+    async fn remove_auto_connect_rule(&self, request: Request<u32>) -> ServiceResult<()> {
+        self.authorize(&request, Role::Admin)?;
+        let index = request.into_inner();
+        log::debug!("remove_auto_connect_rule({index})");
+        let (tx, rx) = oneshot::channel();
+        self.send_command_to_daemon(DaemonCommand::RemoveAutoConnectRule(tx, index as usize))?;
+        self.wait_for_result(rx)
+            .await?
+            .map(Response::new)
+            .map_err(map_daemon_error)
+    }
+
+    async fn list_profiles(
+        &self,
+        request: Request<()>,
+    ) -> ServiceResult<types::SettingsProfileList> {
+        let role = self.authorize(&request, Role::ReadOnly)?;
+        log::debug!("list_profiles");
+        let (tx, rx) = oneshot::channel();
+        self.send_command_to_daemon(DaemonCommand::ListProfiles(tx))?;
+        let profiles = self.wait_for_result(rx).await?;
+        Ok(Response::new(types::SettingsProfileList {
+            profiles: profiles
+                .into_iter()
+                .map(|mut profile| {
+                    if role < Role::Admin {
+                        profile.redact_secrets();
+                    }
+                    types::SettingsProfile::from(profile)
+                })
+                .collect(),
+        }))
+    }
+
+    async fn save_profile(&self, request: Request<String>) -> ServiceResult<()> {
+        self.authorize(&request, Role::Admin)?;
+        let name = request.into_inner();
+        log::debug!("save_profile({name})");
+        if name.is_empty() {
+            return Err(Status::invalid_argument("profile name must not be empty"));
+        }
+        let (tx, rx) = oneshot::channel();
+        self.send_command_to_daemon(DaemonCommand::SaveProfile(tx, name))?;
+        self.wait_for_result(rx).await??;
+        Ok(Response::new(()))
+    }
+
+    async fn activate_profile(&self, request: Request<String>) -> ServiceResult<()> {
+        self.authorize(&request, Role::Admin)?;
+        let name = request.into_inner();
+        log::debug!("activate_profile({name})");
+        let (tx, rx) = oneshot::channel();
+        self.send_command_to_daemon(DaemonCommand::ActivateProfile(tx, name))?;
+        self.wait_for_result(rx)
+            .await?
+            .map(Response::new)
+            .map_err(map_daemon_error)
+    }
+
+    async fn delete_profile(&self, request: Request<String>) -> ServiceResult<()> {
+        self.authorize(&request, Role::Admin)?;
+        let name = request.into_inner();
+        log::debug!("delete_profile({name})");
+        let (tx, rx) = oneshot::channel();
+        self.send_command_to_daemon(DaemonCommand::DeleteProfile(tx, name))?;
+        self.wait_for_result(rx)
+            .await?
+            .map(Response::new)
+            .map_err(map_daemon_error)
+    }
+
     async fn set_openvpn_mssfix(&self, request: Request<u32>) -> ServiceResult<()> {
+        self.authorize(&request, Role::Admin)?;
         let mssfix = request.into_inner();
         let mssfix = if mssfix != 0 {
             Some(mssfix as u16)
@@ -330,6 +604,7 @@ impl ManagementService for ManagementServiceImpl {
     }
 
     async fn set_wireguard_mtu(&self, request: Request<u32>) -> ServiceResult<()> {
+        self.authorize(&request, Role::Admin)?;
         let mtu = request.into_inner();
         let mtu = if mtu != 0 { Some(mtu as u16) } else { None };
         log::debug!("set_wireguard_mtu({:?})", mtu);
@@ -340,6 +615,7 @@ impl ManagementService for ManagementServiceImpl {
     }
 
     async fn set_enable_ipv6(&self, request: Request<bool>) -> ServiceResult<()> {
+        self.authorize(&request, Role::Admin)?;
         let enable_ipv6 = request.into_inner();
         log::debug!("set_enable_ipv6({})", enable_ipv6);
         let (tx, rx) = oneshot::channel();
@@ -352,6 +628,7 @@ impl ManagementService for ManagementServiceImpl {
         &self,
         request: Request<types::QuantumResistantState>,
     ) -> ServiceResult<()> {
+        self.authorize(&request, Role::Admin)?;
         let state = mullvad_types::wireguard::QuantumResistantState::try_from(request.into_inner())
             .map_err(map_protobuf_type_err)?;
 
@@ -362,8 +639,24 @@ impl ManagementService for ManagementServiceImpl {
         Ok(Response::new(()))
     }
 
+    async fn set_wireguard_backend(
+        &self,
+        request: Request<types::WireguardBackend>,
+    ) -> ServiceResult<()> {
+        self.authorize(&request, Role::Admin)?;
+        let backend = talpid_types::net::wireguard::Backend::try_from(request.into_inner())
+            .map_err(map_protobuf_type_err)?;
+
+        log::debug!("set_wireguard_backend({backend})");
+        let (tx, rx) = oneshot::channel();
+        self.send_command_to_daemon(DaemonCommand::SetWireguardBackend(tx, backend))?;
+        self.wait_for_result(rx).await??;
+        Ok(Response::new(()))
+    }
+
     #[cfg(daita)]
     async fn set_enable_daita(&self, request: Request<bool>) -> ServiceResult<()> {
+        self.authorize(&request, Role::Admin)?;
         let daita_enabled = request.into_inner();
         log::debug!("set_enable_daita({daita_enabled})");
         let (tx, rx) = oneshot::channel();
@@ -374,6 +667,7 @@ impl ManagementService for ManagementServiceImpl {
 
     #[cfg(daita)]
     async fn set_daita_direct_only(&self, request: Request<bool>) -> ServiceResult<()> {
+        self.authorize(&request, Role::Admin)?;
         let direct_only_enabled = request.into_inner();
         log::debug!("set_daita_direct_only({direct_only_enabled})");
         let (tx, rx) = oneshot::channel();
@@ -390,6 +684,7 @@ impl ManagementService for ManagementServiceImpl {
         &self,
         request: Request<types::DaitaSettings>,
     ) -> ServiceResult<()> {
+        self.authorize(&request, Role::Admin)?;
         let state = mullvad_types::wireguard::DaitaSettings::from(request.into_inner());
 
         log::debug!("set_daita_settings({state:?})");
@@ -400,21 +695,28 @@ impl ManagementService for ManagementServiceImpl {
     }
 
     #[cfg(not(daita))]
-    async fn set_enable_daita(&self, _: Request<bool>) -> ServiceResult<()> {
+    async fn set_enable_daita(&self, request: Request<bool>) -> ServiceResult<()> {
+        self.authorize(&request, Role::Admin)?;
         Ok(Response::new(()))
     }
 
     #[cfg(not(daita))]
-    async fn set_daita_direct_only(&self, _: Request<bool>) -> ServiceResult<()> {
+    async fn set_daita_direct_only(&self, request: Request<bool>) -> ServiceResult<()> {
+        self.authorize(&request, Role::Admin)?;
         Ok(Response::new(()))
     }
 
     #[cfg(not(daita))]
-    async fn set_daita_settings(&self, _: Request<types::DaitaSettings>) -> ServiceResult<()> {
+    async fn set_daita_settings(
+        &self,
+        request: Request<types::DaitaSettings>,
+    ) -> ServiceResult<()> {
+        self.authorize(&request, Role::Admin)?;
         Ok(Response::new(()))
     }
 
     async fn set_dns_options(&self, request: Request<types::DnsOptions>) -> ServiceResult<()> {
+        self.authorize(&request, Role::Admin)?;
         let options = DnsOptions::try_from(request.into_inner()).map_err(map_protobuf_type_err)?;
         log::debug!("set_dns_options({:?})", options);
 
@@ -428,6 +730,7 @@ impl ManagementService for ManagementServiceImpl {
         &self,
         request: Request<types::RelayOverride>,
     ) -> ServiceResult<()> {
+        self.authorize(&request, Role::Admin)?;
         let relay_override =
             RelayOverride::try_from(request.into_inner()).map_err(map_protobuf_type_err)?;
         log::debug!("set_relay_override");
@@ -437,7 +740,8 @@ impl ManagementService for ManagementServiceImpl {
         Ok(Response::new(()))
     }
 
-    async fn clear_all_relay_overrides(&self, _: Request<()>) -> ServiceResult<()> {
+    async fn clear_all_relay_overrides(&self, request: Request<()>) -> ServiceResult<()> {
+        self.authorize(&request, Role::Admin)?;
         log::debug!("clear_all_relay_overrides");
         let (tx, rx) = oneshot::channel();
         self.send_command_to_daemon(DaemonCommand::ClearAllRelayOverrides(tx))?;
@@ -448,7 +752,8 @@ impl ManagementService for ManagementServiceImpl {
     // Account management
     //
 
-    async fn create_new_account(&self, _: Request<()>) -> ServiceResult<String> {
+    async fn create_new_account(&self, request: Request<()>) -> ServiceResult<String> {
+        self.authorize(&request, Role::Admin)?;
         log::debug!("create_new_account");
         let (tx, rx) = oneshot::channel();
         self.send_command_to_daemon(DaemonCommand::CreateNewAccount(tx))?;
@@ -459,6 +764,7 @@ impl ManagementService for ManagementServiceImpl {
     }
 
     async fn login_account(&self, request: Request<AccountNumber>) -> ServiceResult<()> {
+        self.authorize(&request, Role::Admin)?;
         log::debug!("login_account");
         let account_number = request.into_inner();
         let (tx, rx) = oneshot::channel();
@@ -469,7 +775,8 @@ impl ManagementService for ManagementServiceImpl {
             .map_err(map_daemon_error)
     }
 
-    async fn logout_account(&self, _: Request<()>) -> ServiceResult<()> {
+    async fn logout_account(&self, request: Request<()>) -> ServiceResult<()> {
+        self.authorize(&request, Role::Admin)?;
         log::debug!("logout_account");
         let (tx, rx) = oneshot::channel();
         self.send_command_to_daemon(DaemonCommand::LogoutAccount(tx))?;
@@ -483,6 +790,7 @@ impl ManagementService for ManagementServiceImpl {
         &self,
         request: Request<AccountNumber>,
     ) -> ServiceResult<types::AccountData> {
+        self.authorize(&request, Role::Admin)?;
         log::debug!("get_account_data");
         let account_number = request.into_inner();
         let (tx, rx) = oneshot::channel();
@@ -499,7 +807,11 @@ impl ManagementService for ManagementServiceImpl {
             })
     }
 
-    async fn get_account_history(&self, _: Request<()>) -> ServiceResult<types::AccountHistory> {
+    async fn get_account_history(
+        &self,
+        request: Request<()>,
+    ) -> ServiceResult<types::AccountHistory> {
+        self.authorize(&request, Role::Admin)?;
         log::debug!("get_account_history");
         let (tx, rx) = oneshot::channel();
         self.send_command_to_daemon(DaemonCommand::GetAccountHistory(tx))?;
@@ -508,7 +820,8 @@ impl ManagementService for ManagementServiceImpl {
             .map(|history| Response::new(types::AccountHistory { number: history }))
     }
 
-    async fn clear_account_history(&self, _: Request<()>) -> ServiceResult<()> {
+    async fn clear_account_history(&self, request: Request<()>) -> ServiceResult<()> {
+        self.authorize(&request, Role::Admin)?;
         log::debug!("clear_account_history");
         let (tx, rx) = oneshot::channel();
         self.send_command_to_daemon(DaemonCommand::ClearAccountHistory(tx))?;
@@ -518,7 +831,8 @@ impl ManagementService for ManagementServiceImpl {
             .map_err(map_daemon_error)
     }
 
-    async fn get_www_auth_token(&self, _: Request<()>) -> ServiceResult<String> {
+    async fn get_www_auth_token(&self, request: Request<()>) -> ServiceResult<String> {
+        self.authorize(&request, Role::Admin)?;
         log::debug!("get_www_auth_token");
         let (tx, rx) = oneshot::channel();
         self.send_command_to_daemon(DaemonCommand::GetWwwAuthToken(tx))?;
@@ -536,6 +850,7 @@ impl ManagementService for ManagementServiceImpl {
         &self,
         request: Request<String>,
     ) -> ServiceResult<types::VoucherSubmission> {
+        self.authorize(&request, Role::Admin)?;
         log::debug!("submit_voucher");
         let voucher = request.into_inner();
         let (tx, rx) = oneshot::channel();
@@ -547,7 +862,8 @@ impl ManagementService for ManagementServiceImpl {
     }
 
     // Device management
-    async fn get_device(&self, _: Request<()>) -> ServiceResult<types::DeviceState> {
+    async fn get_device(&self, request: Request<()>) -> ServiceResult<types::DeviceState> {
+        self.authorize(&request, Role::Admin)?;
         log::debug!("get_device");
         let (tx, rx) = oneshot::channel();
         self.send_command_to_daemon(DaemonCommand::GetDevice(tx))?;
@@ -555,7 +871,8 @@ impl ManagementService for ManagementServiceImpl {
         Ok(Response::new(types::DeviceState::from(device)))
     }
 
-    async fn update_device(&self, _: Request<()>) -> ServiceResult<()> {
+    async fn update_device(&self, request: Request<()>) -> ServiceResult<()> {
+        self.authorize(&request, Role::Admin)?;
         log::debug!("update_device");
         let (tx, rx) = oneshot::channel();
         self.send_command_to_daemon(DaemonCommand::UpdateDevice(tx))?;
@@ -569,6 +886,7 @@ impl ManagementService for ManagementServiceImpl {
         &self,
         request: Request<AccountNumber>,
     ) -> ServiceResult<types::DeviceList> {
+        self.authorize(&request, Role::Admin)?;
         log::debug!("list_devices");
         let (tx, rx) = oneshot::channel();
         let token = request.into_inner();
@@ -578,6 +896,7 @@ impl ManagementService for ManagementServiceImpl {
     }
 
     async fn remove_device(&self, request: Request<types::DeviceRemoval>) -> ServiceResult<()> {
+        self.authorize(&request, Role::Admin)?;
         log::debug!("remove_device");
         let (tx, rx) = oneshot::channel();
         let removal = request.into_inner();
@@ -597,6 +916,7 @@ impl ManagementService for ManagementServiceImpl {
         &self,
         request: Request<types::Duration>,
     ) -> ServiceResult<()> {
+        self.authorize(&request, Role::Admin)?;
         let interval: RotationInterval = Duration::try_from(request.into_inner())
             .map_err(|_| Status::invalid_argument("unexpected negative rotation interval"))?
             .try_into()
@@ -614,7 +934,8 @@ impl ManagementService for ManagementServiceImpl {
         Ok(Response::new(()))
     }
 
-    async fn reset_wireguard_rotation_interval(&self, _: Request<()>) -> ServiceResult<()> {
+    async fn reset_wireguard_rotation_interval(&self, request: Request<()>) -> ServiceResult<()> {
+        self.authorize(&request, Role::Admin)?;
         log::debug!("reset_wireguard_rotation_interval");
         let (tx, rx) = oneshot::channel();
         self.send_command_to_daemon(DaemonCommand::SetWireguardRotationInterval(tx, None))?;
@@ -622,7 +943,8 @@ impl ManagementService for ManagementServiceImpl {
         Ok(Response::new(()))
     }
 
-    async fn rotate_wireguard_key(&self, _: Request<()>) -> ServiceResult<()> {
+    async fn rotate_wireguard_key(&self, request: Request<()>) -> ServiceResult<()> {
+        self.authorize(&request, Role::Admin)?;
         log::debug!("rotate_wireguard_key");
         let (tx, rx) = oneshot::channel();
         self.send_command_to_daemon(DaemonCommand::RotateWireguardKey(tx))?;
@@ -632,7 +954,8 @@ impl ManagementService for ManagementServiceImpl {
             .map_err(map_daemon_error)
     }
 
-    async fn get_wireguard_key(&self, _: Request<()>) -> ServiceResult<types::PublicKey> {
+    async fn get_wireguard_key(&self, request: Request<()>) -> ServiceResult<types::PublicKey> {
+        self.authorize(&request, Role::ReadOnly)?;
         log::debug!("get_wireguard_key");
         let (tx, rx) = oneshot::channel();
         self.send_command_to_daemon(DaemonCommand::GetWireguardKey(tx))?;
@@ -647,6 +970,7 @@ impl ManagementService for ManagementServiceImpl {
         &self,
         request: Request<types::AllowedIpsList>,
     ) -> ServiceResult<()> {
+        self.authorize(&request, Role::Admin)?;
         let allowed_ips_str = request.into_inner().values;
         log::debug!("set_wireguard_allowed_ips({:?})", allowed_ips_str);
 
@@ -670,6 +994,7 @@ impl ManagementService for ManagementServiceImpl {
         &self,
         request: Request<types::NewCustomList>,
     ) -> ServiceResult<String> {
+        self.authorize(&request, Role::Admin)?;
         log::debug!("create_custom_list");
         let request = request.into_inner();
         let locations = request
@@ -686,6 +1011,7 @@ impl ManagementService for ManagementServiceImpl {
     }
 
     async fn delete_custom_list(&self, request: Request<String>) -> ServiceResult<()> {
+        self.authorize(&request, Role::Admin)?;
         log::debug!("delete_custom_list");
         let (tx, rx) = oneshot::channel();
         self.send_command_to_daemon(DaemonCommand::DeleteCustomList(
@@ -700,6 +1026,7 @@ impl ManagementService for ManagementServiceImpl {
     }
 
     async fn update_custom_list(&self, request: Request<types::CustomList>) -> ServiceResult<()> {
+        self.authorize(&request, Role::Admin)?;
         log::debug!("update_custom_list");
         let custom_list = mullvad_types::custom_list::CustomList::try_from(request.into_inner())?;
         let (tx, rx) = oneshot::channel();
@@ -710,7 +1037,8 @@ impl ManagementService for ManagementServiceImpl {
             .map_err(map_daemon_error)
     }
 
-    async fn clear_custom_lists(&self, _: Request<()>) -> ServiceResult<()> {
+    async fn clear_custom_lists(&self, request: Request<()>) -> ServiceResult<()> {
+        self.authorize(&request, Role::Admin)?;
         log::debug!("clear_custom_lists");
         let (tx, rx) = oneshot::channel();
         self.send_command_to_daemon(DaemonCommand::ClearCustomLists(tx))?;
@@ -726,6 +1054,7 @@ impl ManagementService for ManagementServiceImpl {
         &self,
         request: Request<types::NewAccessMethodSetting>,
     ) -> ServiceResult<types::Uuid> {
+        self.authorize(&request, Role::Admin)?;
         log::debug!("add_api_access_method");
         let request = request.into_inner();
         let (tx, rx) = oneshot::channel();
@@ -746,6 +1075,7 @@ impl ManagementService for ManagementServiceImpl {
     }
 
     async fn remove_api_access_method(&self, request: Request<types::Uuid>) -> ServiceResult<()> {
+        self.authorize(&request, Role::Admin)?;
         log::debug!("remove_api_access_method");
         let api_access_method = mullvad_types::access_method::Id::try_from(request.into_inner())?;
         let (tx, rx) = oneshot::channel();
@@ -757,6 +1087,7 @@ impl ManagementService for ManagementServiceImpl {
     }
 
     async fn set_api_access_method(&self, request: Request<types::Uuid>) -> ServiceResult<()> {
+        self.authorize(&request, Role::Admin)?;
         log::debug!("set_api_access_method");
         let api_access_method = mullvad_types::access_method::Id::try_from(request.into_inner())?;
         let (tx, rx) = oneshot::channel();
@@ -771,6 +1102,7 @@ impl ManagementService for ManagementServiceImpl {
         &self,
         request: Request<types::AccessMethodSetting>,
     ) -> ServiceResult<()> {
+        self.authorize(&request, Role::Admin)?;
         log::debug!("update_api_access_method");
         let access_method_update =
             mullvad_types::access_method::AccessMethodSetting::try_from(request.into_inner())?;
@@ -785,7 +1117,8 @@ impl ManagementService for ManagementServiceImpl {
             .map_err(map_daemon_error)
     }
 
-    async fn clear_custom_api_access_methods(&self, _: Request<()>) -> ServiceResult<()> {
+    async fn clear_custom_api_access_methods(&self, request: Request<()>) -> ServiceResult<()> {
+        self.authorize(&request, Role::Admin)?;
         log::debug!("clear_custom_api_access_methods");
         let (tx, rx) = oneshot::channel();
         self.send_command_to_daemon(DaemonCommand::ClearCustomApiAccessMethods(tx))?;
@@ -799,14 +1132,20 @@ impl ManagementService for ManagementServiceImpl {
     /// connect to the Mullvad API.
     async fn get_current_api_access_method(
         &self,
-        _: Request<()>,
+        request: Request<()>,
     ) -> ServiceResult<types::AccessMethodSetting> {
+        let role = self.authorize(&request, Role::ReadOnly)?;
         log::debug!("get_current_api_access_method");
         let (tx, rx) = oneshot::channel();
         self.send_command_to_daemon(DaemonCommand::GetCurrentAccessMethod(tx))?;
         self.wait_for_result(rx)
             .await?
-            .map(types::AccessMethodSetting::from)
+            .map(|mut access_method| {
+                if role < Role::Admin {
+                    access_method.redact_secrets();
+                }
+                types::AccessMethodSetting::from(access_method)
+            })
             .map(Response::new)
             .map_err(map_daemon_error)
     }
@@ -815,6 +1154,7 @@ impl ManagementService for ManagementServiceImpl {
         &self,
         config: Request<types::CustomProxy>,
     ) -> ServiceResult<bool> {
+        self.authorize(&config, Role::Admin)?;
         log::debug!("test_custom_api_access_method");
         let (tx, rx) = oneshot::channel();
         let proxy = talpid_types::net::proxy::CustomProxy::try_from(config.into_inner())?;
@@ -829,6 +1169,7 @@ impl ManagementService for ManagementServiceImpl {
         &self,
         request: Request<types::Uuid>,
     ) -> ServiceResult<bool> {
+        self.authorize(&request, Role::Operator)?;
         log::debug!("test_api_access_method_by_id");
         let (tx, rx) = oneshot::channel();
         let api_access_method = mullvad_types::access_method::Id::try_from(request.into_inner())?;
@@ -845,7 +1186,8 @@ impl ManagementService for ManagementServiceImpl {
     // Split tunneling
     //
 
-    async fn split_tunnel_is_enabled(&self, _: Request<()>) -> ServiceResult<bool> {
+    async fn split_tunnel_is_enabled(&self, request: Request<()>) -> ServiceResult<bool> {
+        self.authorize(&request, Role::ReadOnly)?;
         #[cfg(target_os = "linux")]
         {
             log::debug!("split_tunnel_is_enabled");
@@ -862,8 +1204,9 @@ impl ManagementService for ManagementServiceImpl {
 
     async fn get_split_tunnel_processes(
         &self,
-        _: Request<()>,
+        request: Request<()>,
     ) -> ServiceResult<Self::GetSplitTunnelProcessesStream> {
+        self.authorize(&request, Role::ReadOnly)?;
         #[cfg(target_os = "linux")]
         {
             log::debug!("get_split_tunnel_processes");
@@ -892,6 +1235,7 @@ impl ManagementService for ManagementServiceImpl {
 
     #[cfg(target_os = "linux")]
     async fn add_split_tunnel_process(&self, request: Request<i32>) -> ServiceResult<()> {
+        self.authorize(&request, Role::Admin)?;
         let pid = request.into_inner();
         log::debug!("add_split_tunnel_process");
         let (tx, rx) = oneshot::channel();
@@ -902,12 +1246,14 @@ impl ManagementService for ManagementServiceImpl {
         Ok(Response::new(()))
     }
     #[cfg(not(target_os = "linux"))]
-    async fn add_split_tunnel_process(&self, _: Request<i32>) -> ServiceResult<()> {
+    async fn add_split_tunnel_process(&self, request: Request<i32>) -> ServiceResult<()> {
+        self.authorize(&request, Role::Admin)?;
         Ok(Response::new(()))
     }
 
     #[cfg(target_os = "linux")]
     async fn remove_split_tunnel_process(&self, request: Request<i32>) -> ServiceResult<()> {
+        self.authorize(&request, Role::Admin)?;
         let pid = request.into_inner();
         log::debug!("remove_split_tunnel_process");
         let (tx, rx) = oneshot::channel();
@@ -918,11 +1264,13 @@ impl ManagementService for ManagementServiceImpl {
         Ok(Response::new(()))
     }
     #[cfg(not(target_os = "linux"))]
-    async fn remove_split_tunnel_process(&self, _: Request<i32>) -> ServiceResult<()> {
+    async fn remove_split_tunnel_process(&self, request: Request<i32>) -> ServiceResult<()> {
+        self.authorize(&request, Role::Admin)?;
         Ok(Response::new(()))
     }
 
-    async fn clear_split_tunnel_processes(&self, _: Request<()>) -> ServiceResult<()> {
+    async fn clear_split_tunnel_processes(&self, request: Request<()>) -> ServiceResult<()> {
+        self.authorize(&request, Role::Admin)?;
         #[cfg(target_os = "linux")]
         {
             log::debug!("clear_split_tunnel_processes");
@@ -939,8 +1287,8 @@ impl ManagementService for ManagementServiceImpl {
         }
     }
 
-    #[cfg(any(windows, target_os = "android", target_os = "macos"))]
     async fn add_split_tunnel_app(&self, request: Request<String>) -> ServiceResult<()> {
+        self.authorize(&request, Role::Admin)?;
         use mullvad_types::settings::SplitApp;
         log::debug!("add_split_tunnel_app");
         let path = SplitApp::from(request.into_inner());
@@ -952,13 +1300,8 @@ impl ManagementService for ManagementServiceImpl {
             .map(Response::new)
     }
 
-    #[cfg(target_os = "linux")]
-    async fn add_split_tunnel_app(&self, _: Request<String>) -> ServiceResult<()> {
-        Ok(Response::new(()))
-    }
-
-    #[cfg(any(windows, target_os = "android", target_os = "macos"))]
     async fn remove_split_tunnel_app(&self, request: Request<String>) -> ServiceResult<()> {
+        self.authorize(&request, Role::Admin)?;
         use mullvad_types::settings::SplitApp;
         log::debug!("remove_split_tunnel_app");
         let path = SplitApp::from(request.into_inner());
@@ -969,13 +1312,9 @@ impl ManagementService for ManagementServiceImpl {
             .map_err(map_daemon_error)
             .map(Response::new)
     }
-    #[cfg(target_os = "linux")]
-    async fn remove_split_tunnel_app(&self, _: Request<String>) -> ServiceResult<()> {
-        Ok(Response::new(()))
-    }
 
-    #[cfg(any(windows, target_os = "android", target_os = "macos"))]
-    async fn clear_split_tunnel_apps(&self, _: Request<()>) -> ServiceResult<()> {
+    async fn clear_split_tunnel_apps(&self, request: Request<()>) -> ServiceResult<()> {
+        self.authorize(&request, Role::Admin)?;
         log::debug!("clear_split_tunnel_apps");
         let (tx, rx) = oneshot::channel();
         self.send_command_to_daemon(DaemonCommand::ClearSplitTunnelApps(tx))?;
@@ -984,13 +1323,9 @@ impl ManagementService for ManagementServiceImpl {
             .map_err(map_daemon_error)
             .map(Response::new)
     }
-    #[cfg(target_os = "linux")]
-    async fn clear_split_tunnel_apps(&self, _: Request<()>) -> ServiceResult<()> {
-        Ok(Response::new(()))
-    }
 
-    #[cfg(any(windows, target_os = "android", target_os = "macos"))]
     async fn set_split_tunnel_state(&self, request: Request<bool>) -> ServiceResult<()> {
+        self.authorize(&request, Role::Admin)?;
         log::debug!("set_split_tunnel_state");
         let enabled = request.into_inner();
         let (tx, rx) = oneshot::channel();
@@ -1000,16 +1335,13 @@ impl ManagementService for ManagementServiceImpl {
             .map_err(map_daemon_error)
             .map(Response::new)
     }
-    #[cfg(target_os = "linux")]
-    async fn set_split_tunnel_state(&self, _: Request<bool>) -> ServiceResult<()> {
-        Ok(Response::new(()))
-    }
 
     #[cfg(windows)]
     async fn get_excluded_processes(
         &self,
-        _: Request<()>,
+        request: Request<()>,
     ) -> ServiceResult<types::ExcludedProcessList> {
+        self.authorize(&request, Role::ReadOnly)?;
         log::debug!("get_excluded_processes");
         let (tx, rx) = oneshot::channel();
         self.send_command_to_daemon(DaemonCommand::GetSplitTunnelProcesses(tx))?;
@@ -1029,27 +1361,31 @@ impl ManagementService for ManagementServiceImpl {
     #[cfg(not(windows))]
     async fn get_excluded_processes(
         &self,
-        _: Request<()>,
+        request: Request<()>,
     ) -> ServiceResult<types::ExcludedProcessList> {
+        self.authorize(&request, Role::ReadOnly)?;
         Ok(Response::new(types::ExcludedProcessList {
             processes: vec![],
         }))
     }
 
     #[cfg(target_os = "macos")]
-    async fn need_full_disk_permissions(&self, _: Request<()>) -> ServiceResult<bool> {
+    async fn need_full_disk_permissions(&self, request: Request<()>) -> ServiceResult<bool> {
+        self.authorize(&request, Role::ReadOnly)?;
         log::debug!("need_full_disk_permissions");
         let has_access = talpid_core::split_tunnel::has_full_disk_access().await;
         Ok(Response::new(!has_access))
     }
 
     #[cfg(not(target_os = "macos"))]
-    async fn need_full_disk_permissions(&self, _: Request<()>) -> ServiceResult<bool> {
+    async fn need_full_disk_permissions(&self, request: Request<()>) -> ServiceResult<bool> {
+        self.authorize(&request, Role::ReadOnly)?;
         Ok(Response::new(false))
     }
 
     #[cfg(windows)]
-    async fn check_volumes(&self, _: Request<()>) -> ServiceResult<()> {
+    async fn check_volumes(&self, request: Request<()>) -> ServiceResult<()> {
+        self.authorize(&request, Role::Operator)?;
         log::debug!("check_volumes");
         let (tx, rx) = oneshot::channel();
         self.send_command_to_daemon(DaemonCommand::CheckVolumes(tx))?;
@@ -1060,22 +1396,42 @@ impl ManagementService for ManagementServiceImpl {
     }
 
     #[cfg(not(windows))]
-    async fn check_volumes(&self, _: Request<()>) -> ServiceResult<()> {
+    async fn check_volumes(&self, request: Request<()>) -> ServiceResult<()> {
+        self.authorize(&request, Role::Operator)?;
         Ok(Response::new(()))
     }
 
-    async fn apply_json_settings(&self, blob: Request<String>) -> ServiceResult<()> {
+    async fn apply_json_settings(
+        &self,
+        request: Request<types::JsonSettingsPatch>,
+    ) -> ServiceResult<()> {
+        self.authorize(&request, Role::Admin)?;
         log::debug!("apply_json_settings");
+        let types::JsonSettingsPatch {
+            patch,
+            allow_sensitive,
+        } = request.into_inner();
         let (tx, rx) = oneshot::channel();
-        self.send_command_to_daemon(DaemonCommand::ApplyJsonSettings(tx, blob.into_inner()))?;
+        self.send_command_to_daemon(DaemonCommand::ApplyJsonSettings(tx, patch, allow_sensitive))?;
         self.wait_for_result(rx).await??;
         Ok(Response::new(()))
     }
 
-    async fn export_json_settings(&self, _: Request<()>) -> ServiceResult<String> {
+    async fn export_json_settings(
+        &self,
+        request: Request<types::ExportJsonSettingsOptions>,
+    ) -> ServiceResult<String> {
+        let include_sensitive = request.get_ref().include_sensitive;
+        // Secrets are only exported to callers that could also set them
+        let required_role = if include_sensitive {
+            Role::Admin
+        } else {
+            Role::ReadOnly
+        };
+        self.authorize(&request, required_role)?;
         log::debug!("export_json_settings");
         let (tx, rx) = oneshot::channel();
-        self.send_command_to_daemon(DaemonCommand::ExportJsonSettings(tx))?;
+        self.send_command_to_daemon(DaemonCommand::ExportJsonSettings(tx, include_sensitive))?;
         let blob = self.wait_for_result(rx).await??;
         Ok(Response::new(blob))
     }
@@ -1085,6 +1441,7 @@ impl ManagementService for ManagementServiceImpl {
         &self,
         _request: Request<()>,
     ) -> ServiceResult<types::PlayPurchasePaymentToken> {
+        self.authorize(&_request, Role::Admin)?;
         log::debug!("init_play_purchase");
 
         let (tx, rx) = oneshot::channel();
@@ -1103,8 +1460,9 @@ impl ManagementService for ManagementServiceImpl {
     #[cfg(not(target_os = "android"))]
     async fn init_play_purchase(
         &self,
-        _: Request<()>,
+        request: Request<()>,
     ) -> ServiceResult<types::PlayPurchasePaymentToken> {
+        self.authorize(&request, Role::Admin)?;
         log::error!("Called `init_play_purchase` on non-Android platform");
         Ok(Response::new(types::PlayPurchasePaymentToken {
             token: String::default(),
@@ -1116,6 +1474,7 @@ impl ManagementService for ManagementServiceImpl {
         &self,
         request: Request<types::PlayPurchase>,
     ) -> ServiceResult<()> {
+        self.authorize(&request, Role::Admin)?;
         log::debug!("verify_play_purchase");
 
         let (tx, rx) = oneshot::channel();
@@ -1129,15 +1488,20 @@ impl ManagementService for ManagementServiceImpl {
     }
 
     #[cfg(not(target_os = "android"))]
-    async fn verify_play_purchase(&self, _: Request<types::PlayPurchase>) -> ServiceResult<()> {
+    async fn verify_play_purchase(
+        &self,
+        request: Request<types::PlayPurchase>,
+    ) -> ServiceResult<()> {
+        self.authorize(&request, Role::Admin)?;
         log::error!("Called `verify_play_purchase` on non-Android platform");
         Ok(Response::new(()))
     }
 
     async fn get_feature_indicators(
         &self,
-        _: Request<()>,
+        request: Request<()>,
     ) -> ServiceResult<types::FeatureIndicators> {
+        self.authorize(&request, Role::ReadOnly)?;
         log::debug!("get_feature_indicators");
 
         let (tx, rx) = oneshot::channel();
@@ -1151,9 +1515,27 @@ impl ManagementService for ManagementServiceImpl {
         Ok(Response::new(feature_indicators))
     }
 
+    async fn get_connection_history(
+        &self,
+        request: Request<()>,
+    ) -> ServiceResult<types::ConnectionHistory> {
+        self.authorize(&request, Role::ReadOnly)?;
+        log::debug!("get_connection_history");
+        let (tx, rx) = oneshot::channel();
+        self.send_command_to_daemon(DaemonCommand::GetConnectionHistory(tx))?;
+        let history = self.wait_for_result(rx).await?;
+        Ok(Response::new(types::ConnectionHistory {
+            sessions: history
+                .into_iter()
+                .map(types::ConnectionSession::from)
+                .collect(),
+        }))
+    }
+
     // Debug features
 
     async fn disable_relay(&self, relay: Request<String>) -> ServiceResult<()> {
+        self.authorize(&relay, Role::Admin)?;
         log::debug!("disable_relay");
         let (tx, rx) = oneshot::channel();
         let relay = relay.into_inner();
@@ -1163,6 +1545,7 @@ impl ManagementService for ManagementServiceImpl {
     }
 
     async fn enable_relay(&self, relay: Request<String>) -> ServiceResult<()> {
+        self.authorize(&relay, Role::Admin)?;
         log::debug!("enable_relay");
         let (tx, rx) = oneshot::channel();
         let relay = relay.into_inner();
@@ -1171,9 +1554,39 @@ impl ManagementService for ManagementServiceImpl {
         Ok(Response::new(()))
     }
 
+    async fn get_leak_check_history(
+        &self,
+        request: Request<()>,
+    ) -> ServiceResult<types::LeakCheckHistory> {
+        self.authorize(&request, Role::ReadOnly)?;
+        log::debug!("get_leak_check_history");
+        let (tx, rx) = oneshot::channel();
+        self.send_command_to_daemon(DaemonCommand::GetLeakCheckHistory(tx))?;
+        let history = self.wait_for_result(rx).await?;
+        Ok(Response::new(types::LeakCheckHistory {
+            results: history
+                .into_iter()
+                .map(types::LeakCheckResult::from)
+                .collect(),
+        }))
+    }
+
+    async fn run_leak_check(&self, request: Request<()>) -> ServiceResult<types::LeakCheckResult> {
+        self.authorize(&request, Role::Operator)?;
+        log::debug!("run_leak_check");
+        let (tx, rx) = oneshot::channel();
+        self.send_command_to_daemon(DaemonCommand::RunLeakCheck(tx))?;
+        self.wait_for_result(rx)
+            .await?
+            .map(types::LeakCheckResult::from)
+            .map(Response::new)
+            .map_err(map_daemon_error)
+    }
+
     // App upgrade
 
-    async fn app_upgrade(&self, _: Request<()>) -> ServiceResult<()> {
+    async fn app_upgrade(&self, request: Request<()>) -> ServiceResult<()> {
+        self.authorize(&request, Role::Admin)?;
         log::debug!("app_upgrade");
 
         let (tx, rx) = oneshot::channel();
@@ -1186,7 +1599,8 @@ impl ManagementService for ManagementServiceImpl {
         Ok(Response::new(()))
     }
 
-    async fn app_upgrade_abort(&self, _: Request<()>) -> ServiceResult<()> {
+    async fn app_upgrade_abort(&self, request: Request<()>) -> ServiceResult<()> {
+        self.authorize(&request, Role::Admin)?;
         log::debug!("app_upgrade_abort");
 
         let (tx, rx) = oneshot::channel();
@@ -1201,8 +1615,9 @@ impl ManagementService for ManagementServiceImpl {
 
     async fn app_upgrade_events_listen(
         &self,
-        _: Request<()>,
+        request: Request<()>,
     ) -> ServiceResult<Self::AppUpgradeEventsListenStream> {
+        self.authorize(&request, Role::ReadOnly)?;
         log::debug!("app_upgrade_events_listen");
         let rx = self.app_upgrade_broadcast.subscribe();
         let upgrade_event_stream =
@@ -1218,7 +1633,8 @@ impl ManagementService for ManagementServiceImpl {
         ))
     }
 
-    async fn get_app_upgrade_cache_dir(&self, _: Request<()>) -> ServiceResult<String> {
+    async fn get_app_upgrade_cache_dir(&self, request: Request<()>) -> ServiceResult<String> {
+        self.authorize(&request, Role::ReadOnly)?;
         log::debug!("get_app_upgrade_cache_dir");
 
         let (tx, rx) = oneshot::channel();
@@ -1236,6 +1652,7 @@ impl ManagementService for ManagementServiceImpl {
     }
 
     async fn set_enable_recents(&self, request: Request<bool>) -> ServiceResult<()> {
+        self.authorize(&request, Role::Admin)?;
         let enable_recents = request.into_inner();
         log::debug!("set_enable_recents({})", enable_recents);
         let (tx, rx) = oneshot::channel();
@@ -1257,6 +1674,37 @@ impl ManagementServiceImpl {
     async fn wait_for_result<T>(&self, rx: oneshot::Receiver<T>) -> Result<T, Status> {
         rx.await.map_err(|_| Status::internal("sender was dropped"))
     }
+
+    /// Fails unless the caller of `request` has at least the given role.
+    fn authorize<T>(&self, request: &Request<T>, required: Role) -> Result<Role, Status> {
+        let caller = peer_credentials(request);
+        let role = self.access_control.role(caller);
+        if role < required {
+            log::debug!("Denied {caller:?} with role {role}. This call requires {required}");
+            return Err(Status::permission_denied(format!(
+                "This call requires the {required} role"
+            )));
+        }
+        Ok(role)
+    }
+}
+
+/// Returns the credentials of the process that sent `request`.
+#[cfg(unix)]
+fn peer_credentials<T>(request: &Request<T>) -> Option<Credentials> {
+    let credentials = request
+        .extensions()
+        .get::<mullvad_management_interface::UdsConnectInfo>()?
+        .peer_cred?;
+    Some(Credentials {
+        uid: credentials.uid(),
+        gid: credentials.gid(),
+    })
+}
+
+#[cfg(not(unix))]
+fn peer_credentials<T>(_request: &Request<T>) -> Option<Credentials> {
+    None
 }
 
 /// The running management interface serving gRPC requests.
@@ -1276,9 +1724,11 @@ impl ManagementInterfaceServer {
     pub fn start(
         daemon_tx: DaemonCommandSender,
         rpc_socket_path: impl AsRef<Path>,
+        settings_dir: &Path,
         app_upgrade_broadcast: tokio::sync::broadcast::Sender<version::AppUpgradeEvent>,
     ) -> Result<ManagementInterfaceServer, Error> {
-        let subscriptions = Arc::<Mutex<Vec<EventsListenerSender>>>::default();
+        let access_control = AccessControl::load(settings_dir).map_err(Error::AccessPolicy)?;
+        let subscriptions = Arc::<Mutex<Vec<EventsSubscription>>>::default();
 
         // NOTE: It is important that the channel buffer size is kept at 0. When sending a signal
         // to abort the gRPC server, the sender can be awaited to know when the gRPC server has
@@ -1289,6 +1739,7 @@ impl ManagementInterfaceServer {
             daemon_tx,
             subscriptions: subscriptions.clone(),
             app_upgrade_broadcast,
+            access_control,
         };
         let rpc_server_join_handle = mullvad_management_interface::spawn_rpc_server(
             server,
@@ -1343,13 +1794,37 @@ impl ManagementInterfaceServer {
 /// A handle that allows broadcasting messages to all subscribers of the management interface.
 #[derive(Clone)]
 pub struct ManagementInterfaceEventBroadcaster {
-    subscriptions: Arc<Mutex<Vec<EventsListenerSender>>>,
+    subscriptions: Arc<Mutex<Vec<EventsSubscription>>>,
 }
 
 impl ManagementInterfaceEventBroadcaster {
     fn notify(&self, value: types::DaemonEvent) {
+        self.notify_role(value, Role::ReadOnly)
+    }
+
+    /// Send `value` to the subscribers that have at least the `required` role.
+    fn notify_role(&self, value: types::DaemonEvent, required: Role) {
+        let mut subscriptions = self.subscriptions.lock().unwrap();
+        subscriptions.retain(|subscription| {
+            if subscription.role < required {
+                return !subscription.tx.is_closed();
+            }
+            subscription.tx.send(Ok(value.clone())).is_ok()
+        });
+    }
+
+    /// Send `value` to the subscribers that have the admin role, and `redacted_value` to all other
+    /// subscribers.
+    fn notify_redacted(&self, value: types::DaemonEvent, redacted_value: types::DaemonEvent) {
         let mut subscriptions = self.subscriptions.lock().unwrap();
-        subscriptions.retain(|tx| tx.send(Ok(value.clone())).is_ok());
+        subscriptions.retain(|subscription| {
+            let value = if subscription.role < Role::Admin {
+                &redacted_value
+            } else {
+                &value
+            };
+            subscription.tx.send(Ok(value.clone())).is_ok()
+        });
     }
 
     /// Notify that the tunnel state changed.
@@ -1368,11 +1843,17 @@ impl ManagementInterfaceEventBroadcaster {
     /// Sends settings to all `settings` subscribers of the management interface.
     pub(crate) fn notify_settings(&self, settings: Settings) {
         log::debug!("Broadcasting new settings");
-        self.notify(types::DaemonEvent {
+        let mut redacted_settings = settings.clone();
+        redacted_settings.redact_secrets();
+        let settings_event = |settings| types::DaemonEvent {
             event: Some(daemon_event::Event::Settings(types::Settings::from(
-                &settings,
+                settings,
             ))),
-        })
+        };
+        self.notify_redacted(
+            settings_event(&settings),
+            settings_event(&redacted_settings),
+        )
     }
 
     /// Notify that the relay list changed.
@@ -1399,24 +1880,42 @@ impl ManagementInterfaceEventBroadcaster {
     }
 
     /// Notify that device changed (login, logout, or key rotation).
+    ///
+    /// Device events contain the account number, so they are only sent to admins.
     pub(crate) fn notify_device_event(&self, device: mullvad_types::device::DeviceEvent) {
         log::debug!("Broadcasting device event");
-        self.notify(types::DaemonEvent {
-            event: Some(daemon_event::Event::Device(types::DeviceEvent::from(
-                device,
-            ))),
-        })
+        self.notify_role(
+            types::DaemonEvent {
+                event: Some(daemon_event::Event::Device(types::DeviceEvent::from(
+                    device,
+                ))),
+            },
+            Role::Admin,
+        )
     }
 
-    /// Notify that a device was revoked using `RemoveDevice`.
+    /// Notify that a device was revoked using `RemoveDevice`. Only sent to admins.
     pub(crate) fn notify_remove_device_event(
         &self,
         remove_event: mullvad_types::device::RemoveDeviceEvent,
     ) {
         log::debug!("Broadcasting remove device event");
+        self.notify_role(
+            types::DaemonEvent {
+                event: Some(daemon_event::Event::RemoveDevice(
+                    types::RemoveDeviceEvent::from(remove_event),
+                )),
+            },
+            Role::Admin,
+        )
+    }
+
+    /// Notify that the leak checker detected a leak.
+    pub(crate) fn notify_leak_detected(&self, result: mullvad_types::leak_check::LeakCheckResult) {
+        log::debug!("Broadcasting leak detected event");
         self.notify(types::DaemonEvent {
-            event: Some(daemon_event::Event::RemoveDevice(
-                types::RemoveDeviceEvent::from(remove_event),
+            event: Some(daemon_event::Event::LeakDetected(
+                types::LeakCheckResult::from(result),
             )),
         })
     }
@@ -1427,11 +1926,17 @@ impl ManagementInterfaceEventBroadcaster {
         new_access_method: mullvad_types::access_method::AccessMethodSetting,
     ) {
         log::debug!("Broadcasting access method event");
-        self.notify(types::DaemonEvent {
+        let mut redacted_access_method = new_access_method.clone();
+        redacted_access_method.redact_secrets();
+        let access_method_event = |access_method| types::DaemonEvent {
             event: Some(daemon_event::Event::NewAccessMethod(
-                types::AccessMethodSetting::from(new_access_method),
+                types::AccessMethodSetting::from(access_method),
             )),
-        })
+        };
+        self.notify_redacted(
+            access_method_event(new_access_method),
+            access_method_event(redacted_access_method),
+        )
     }
 }
 
@@ -1457,6 +1962,11 @@ fn map_daemon_error(error: crate::Error) -> Status {
             Status::unauthenticated(error.to_string())
         }
         DaemonError::VersionCheckError(error) => map_version_check_error(error),
+        DaemonError::LeakCheck(error) => map_leak_check_error(error),
+        DaemonError::AutoConnectRuleNotFound(_)
+        | DaemonError::FirewallExceptionNotFound(_)
+        | DaemonError::AllowedInterfaceNotFound(_)
+        | DaemonError::ProfileNotFound(_) => Status::not_found(error.to_string()),
         error => Status::unknown(error.to_string()),
     }
 }
@@ -1484,6 +1994,17 @@ fn map_split_tunnel_error(error: talpid_core::split_tunnel::Error) -> Status {
     Status::unknown(error.to_string())
 }
 
+/// Converts a [`crate::leak_checker::Error`] into a tonic status.
+fn map_leak_check_error(error: crate::leak_checker::Error) -> Status {
+    use crate::leak_checker::Error;
+
+    match error {
+        Error::NotConnected | Error::Interrupted => Status::failed_precondition(error.to_string()),
+        Error::Unavailable => Status::unavailable(error.to_string()),
+        Error::CheckFailed(_) => Status::unknown(error.to_string()),
+    }
+}
+
 /// Converts a REST API error into a tonic status.
 fn map_rest_error(error: &RestError) -> Status {
     match error {
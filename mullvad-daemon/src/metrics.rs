@@ -0,0 +1,497 @@
+//! Opt-in endpoint that exposes the state of the daemon in the Prometheus text format.
+//!
+//! The endpoint is enabled by setting `MULLVAD_METRICS_ENDPOINT` to a loopback address, such as
+//! `127.0.0.1:9180`, or to `unix:<path>` to listen on a Unix domain socket. Metrics are served at
+//! `/metrics`.
+
+use crate::{DaemonCommand, DaemonCommandSender};
+use futures::channel::oneshot;
+use http_body_util::Full;
+use hyper::{
+    Method, Request, Response, StatusCode,
+    body::{Bytes, Incoming},
+    header,
+    server::conn::http1,
+    service::service_fn,
+};
+use hyper_util::rt::TokioIo;
+use mullvad_api::rest::{REQUEST_LATENCY_BUCKETS, RequestStats};
+use mullvad_types::states::TunnelState;
+#[cfg(unix)]
+use std::path::PathBuf;
+use std::{
+    collections::BTreeMap,
+    convert::Infallible,
+    fmt::Write,
+    net::SocketAddr,
+    str::FromStr,
+    sync::{Arc, Mutex},
+    time::Instant,
+};
+use talpid_types::{ErrorExt, net::wireguard::TunnelStats, tunnel::ErrorStateCause};
+use tokio::io::{AsyncRead, AsyncWrite};
+
+const METRICS_ENDPOINT_VAR: &str = "MULLVAD_METRICS_ENDPOINT";
+
+const CONTENT_TYPE: &str = "text/plain; version=0.0.4";
+
+const TUNNEL_STATES: [&str; 5] = [
+    "disconnected",
+    "connecting",
+    "connected",
+    "disconnecting",
+    "error",
+];
+
+#[derive(thiserror::Error, Debug)]
+pub enum Error {
+    #[error("Invalid metrics endpoint: {0}")]
+    InvalidEndpoint(String),
+
+    #[error("The metrics endpoint must use a loopback address: {0}")]
+    NotLoopback(SocketAddr),
+
+    #[error("Failed to listen on metrics endpoint")]
+    Bind(#[source] std::io::Error),
+}
+
+/// Where the metrics endpoint listens for scrapes.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MetricsEndpoint {
+    /// A TCP socket on a loopback address.
+    Tcp(SocketAddr),
+    /// A Unix domain socket.
+    #[cfg(unix)]
+    Unix(PathBuf),
+}
+
+impl MetricsEndpoint {
+    /// Read the endpoint from `MULLVAD_METRICS_ENDPOINT`. Returns `None` if the variable is unset
+    /// or invalid.
+    pub fn from_env_vars() -> Option<Self> {
+        let endpoint = std::env::var(METRICS_ENDPOINT_VAR).ok()?;
+        endpoint
+            .parse()
+            .inspect_err(|error: &Error| {
+                log::error!(
+                    "{}",
+                    error.display_chain_with_msg(&format!("Ignoring {METRICS_ENDPOINT_VAR}"))
+                )
+            })
+            .ok()
+    }
+}
+
+impl FromStr for MetricsEndpoint {
+    type Err = Error;
+
+    fn from_str(endpoint: &str) -> Result<Self, Self::Err> {
+        #[cfg(unix)]
+        if let Some(path) = endpoint.strip_prefix("unix:") {
+            return Ok(MetricsEndpoint::Unix(PathBuf::from(path)));
+        }
+        let address: SocketAddr = endpoint
+            .parse()
+            .map_err(|_| Error::InvalidEndpoint(endpoint.to_owned()))?;
+        if !address.ip().is_loopback() {
+            return Err(Error::NotLoopback(address));
+        }
+        Ok(MetricsEndpoint::Tcp(address))
+    }
+}
+
+/// Counters that are updated by the daemon and rendered on every scrape.
+#[derive(Clone)]
+pub(crate) struct Metrics(Arc<Mutex<MetricsState>>);
+
+struct MetricsState {
+    tunnel_state: &'static str,
+    tunnel_state_since: Instant,
+    connection_attempts: u64,
+    reconnects: u64,
+    error_states: BTreeMap<&'static str, u64>,
+    relay_selections: u64,
+    relay_selection_failures: u64,
+}
+
+impl Metrics {
+    pub fn new() -> Self {
+        Metrics(Arc::new(Mutex::new(MetricsState {
+            tunnel_state: "disconnected",
+            tunnel_state_since: Instant::now(),
+            connection_attempts: 0,
+            reconnects: 0,
+            error_states: BTreeMap::new(),
+            relay_selections: 0,
+            relay_selection_failures: 0,
+        })))
+    }
+
+    /// Update the metrics when the tunnel goes from the `previous` state to the `new` state.
+    pub fn on_tunnel_state_transition(&self, previous: &TunnelState, new: &TunnelState) {
+        let mut state = self.0.lock().unwrap();
+
+        let label = tunnel_state_label(new);
+        if label != state.tunnel_state {
+            state.tunnel_state = label;
+            state.tunnel_state_since = Instant::now();
+        }
+
+        match new {
+            TunnelState::Connecting { .. } => {
+                state.connection_attempts += 1;
+                if !previous.is_disconnected() {
+                    state.reconnects += 1;
+                }
+            }
+            TunnelState::Error(error_state) => {
+                *state
+                    .error_states
+                    .entry(error_cause_label(error_state.cause()))
+                    .or_default() += 1;
+            }
+            _ => (),
+        }
+    }
+
+    /// Count an attempt to select a relay for a new tunnel.
+    pub fn on_relay_selection(&self, success: bool) {
+        let mut state = self.0.lock().unwrap();
+        state.relay_selections += 1;
+        if !success {
+            state.relay_selection_failures += 1;
+        }
+    }
+
+    fn render(&self, request_stats: &RequestStats, tunnel_stats: Option<&TunnelStats>) -> String {
+        let mut out = String::new();
+        {
+            let state = self.0.lock().unwrap();
+
+            header(
+                &mut out,
+                "mullvad_tunnel_state",
+                "gauge",
+                "Current tunnel state",
+            );
+            for label in TUNNEL_STATES {
+                let value = u8::from(label == state.tunnel_state);
+                let _ = writeln!(out, "mullvad_tunnel_state{{state=\"{label}\"}} {value}");
+            }
+
+            header(
+                &mut out,
+                "mullvad_tunnel_state_duration_seconds",
+                "gauge",
+                "Time spent in the current tunnel state",
+            );
+            let _ = writeln!(
+                out,
+                "mullvad_tunnel_state_duration_seconds {}",
+                state.tunnel_state_since.elapsed().as_secs_f64()
+            );
+
+            header(
+                &mut out,
+                "mullvad_tunnel_connection_attempts_total",
+                "counter",
+                "Number of times the tunnel started connecting",
+            );
+            let _ = writeln!(
+                out,
+                "mullvad_tunnel_connection_attempts_total {}",
+                state.connection_attempts
+            );
+
+            header(
+                &mut out,
+                "mullvad_tunnel_reconnects_total",
+                "counter",
+                "Number of connection attempts that were not started from the disconnected state",
+            );
+            let _ = writeln!(out, "mullvad_tunnel_reconnects_total {}", state.reconnects);
+
+            header(
+                &mut out,
+                "mullvad_tunnel_error_states_total",
+                "counter",
+                "Number of times the error state was entered, by cause",
+            );
+            for (cause, count) in &state.error_states {
+                let _ = writeln!(
+                    out,
+                    "mullvad_tunnel_error_states_total{{cause=\"{cause}\"}} {count}"
+                );
+            }
+
+            header(
+                &mut out,
+                "mullvad_relay_selections_total",
+                "counter",
+                "Number of attempts to select a relay, by result",
+            );
+            let _ = writeln!(
+                out,
+                "mullvad_relay_selections_total{{result=\"success\"}} {}",
+                state.relay_selections - state.relay_selection_failures
+            );
+            let _ = writeln!(
+                out,
+                "mullvad_relay_selections_total{{result=\"failure\"}} {}",
+                state.relay_selection_failures
+            );
+        }
+
+        let api = request_stats.snapshot();
+        header(
+            &mut out,
+            "mullvad_api_request_failures_total",
+            "counter",
+            "Number of API requests that failed",
+        );
+        let _ = writeln!(out, "mullvad_api_request_failures_total {}", api.failures);
+
+        header(
+            &mut out,
+            "mullvad_api_request_duration_seconds",
+            "histogram",
+            "Duration of completed API requests",
+        );
+        for (bound, count) in REQUEST_LATENCY_BUCKETS.iter().zip(api.latency_buckets) {
+            let _ = writeln!(
+                out,
+                "mullvad_api_request_duration_seconds_bucket{{le=\"{}\"}} {count}",
+                bound.as_secs_f64()
+            );
+        }
+        let _ = writeln!(
+            out,
+            "mullvad_api_request_duration_seconds_bucket{{le=\"+Inf\"}} {}",
+            api.requests
+        );
+        let _ = writeln!(
+            out,
+            "mullvad_api_request_duration_seconds_sum {}",
+            api.latency_sum.as_secs_f64()
+        );
+        let _ = writeln!(
+            out,
+            "mullvad_api_request_duration_seconds_count {}",
+            api.requests
+        );
+
+        header(
+            &mut out,
+            "mullvad_tunnel_bytes_total",
+            "counter",
+            "Bytes transferred through the current tunnel, by peer and direction",
+        );
+        if let Some(stats) = tunnel_stats {
+            for (peer, stats) in [("entry", &stats.entry), ("exit", &stats.exit)] {
+                let Some(stats) = stats else {
+                    continue;
+                };
+                let _ = writeln!(
+                    out,
+                    "mullvad_tunnel_bytes_total{{peer=\"{peer}\",direction=\"tx\"}} {}",
+                    stats.tx_bytes
+                );
+                let _ = writeln!(
+                    out,
+                    "mullvad_tunnel_bytes_total{{peer=\"{peer}\",direction=\"rx\"}} {}",
+                    stats.rx_bytes
+                );
+            }
+        }
+
+        out
+    }
+}
+
+fn header(out: &mut String, name: &str, kind: &str, help: &str) {
+    let _ = writeln!(out, "# HELP {name} {help}.");
+    let _ = writeln!(out, "# TYPE {name} {kind}");
+}
+
+fn tunnel_state_label(state: &TunnelState) -> &'static str {
+    match state {
+        TunnelState::Disconnected { .. } => "disconnected",
+        TunnelState::Connecting { .. } => "connecting",
+        TunnelState::Connected { .. } => "connected",
+        TunnelState::Disconnecting(..) => "disconnecting",
+        TunnelState::Error(..) => "error",
+    }
+}
+
+fn error_cause_label(cause: &ErrorStateCause) -> &'static str {
+    match cause {
+        ErrorStateCause::AuthFailed(_) => "auth_failed",
+        ErrorStateCause::Ipv6Unavailable => "ipv6_unavailable",
+        ErrorStateCause::SetFirewallPolicyError(_) => "set_firewall_policy_error",
+        ErrorStateCause::SetDnsError => "set_dns_error",
+        #[cfg(target_os = "android")]
+        ErrorStateCause::InvalidDnsServers(_) => "invalid_dns_servers",
+        #[cfg(target_os = "windows")]
+        ErrorStateCause::CreateTunnelDevice { .. } => "create_tunnel_device",
+        ErrorStateCause::StartTunnelError => "start_tunnel_error",
+        ErrorStateCause::TunnelParameterError(_) => "tunnel_parameter_error",
+        ErrorStateCause::IsOffline => "is_offline",
+        #[cfg(target_os = "android")]
+        ErrorStateCause::NotPrepared => "not_prepared",
+        #[cfg(target_os = "android")]
+        ErrorStateCause::OtherAlwaysOnApp { .. } => "other_always_on_app",
+        #[cfg(target_os = "android")]
+        ErrorStateCause::OtherLegacyAlwaysOnVpn => "other_legacy_always_on_vpn",
+        #[cfg(any(target_os = "windows", target_os = "macos", target_os = "android"))]
+        ErrorStateCause::SplitTunnelError => "split_tunnel_error",
+        #[cfg(target_os = "macos")]
+        ErrorStateCause::NeedFullDiskPermissions => "need_full_disk_permissions",
+    }
+}
+
+/// Everything needed to answer a scrape.
+#[derive(Clone)]
+struct Scraper {
+    metrics: Metrics,
+    request_stats: RequestStats,
+    daemon_tx: DaemonCommandSender,
+}
+
+impl Scraper {
+    async fn handle(&self, request: Request<Incoming>) -> Response<Full<Bytes>> {
+        if request.uri().path() != "/metrics" {
+            return status_response(StatusCode::NOT_FOUND);
+        }
+        if request.method() != Method::GET {
+            return status_response(StatusCode::METHOD_NOT_ALLOWED);
+        }
+
+        let tunnel_stats = self.tunnel_stats().await;
+        let body = self
+            .metrics
+            .render(&self.request_stats, tunnel_stats.as_ref());
+        Response::builder()
+            .header(header::CONTENT_TYPE, CONTENT_TYPE)
+            .body(Full::new(Bytes::from(body)))
+            .expect("response is valid")
+    }
+
+    async fn tunnel_stats(&self) -> Option<TunnelStats> {
+        let (tx, rx) = oneshot::channel();
+        self.daemon_tx
+            .send(DaemonCommand::GetTunnelStats(tx))
+            .ok()?;
+        rx.await.ok().flatten()
+    }
+}
+
+fn status_response(status: StatusCode) -> Response<Full<Bytes>> {
+    let mut response = Response::new(Full::default());
+    *response.status_mut() = status;
+    response
+}
+
+/// Start serving metrics on `endpoint`.
+pub(crate) async fn spawn_server(
+    endpoint: MetricsEndpoint,
+    metrics: Metrics,
+    request_stats: RequestStats,
+    daemon_tx: DaemonCommandSender,
+) -> Result<(), Error> {
+    let scraper = Scraper {
+        metrics,
+        request_stats,
+        daemon_tx,
+    };
+
+    match endpoint {
+        MetricsEndpoint::Tcp(address) => {
+            let listener = tokio::net::TcpListener::bind(address)
+                .await
+                .map_err(Error::Bind)?;
+            log::info!("Serving metrics on {address}");
+            tokio::spawn(async move {
+                loop {
+                    match listener.accept().await {
+                        Ok((stream, _)) => serve_connection(stream, scraper.clone()),
+                        Err(error) => log::error!(
+                            "{}",
+                            error.display_chain_with_msg("Failed to accept metrics connection")
+                        ),
+                    }
+                }
+            });
+        }
+        #[cfg(unix)]
+        MetricsEndpoint::Unix(path) => {
+            use std::os::unix::fs::PermissionsExt;
+
+            // Remove the socket left behind by a previous instance, if any
+            let _ = std::fs::remove_file(&path);
+            let listener = tokio::net::UnixListener::bind(&path).map_err(Error::Bind)?;
+            // Metrics reveal when the device connects and how much traffic it sends, so only
+            // root may read them
+            std::fs::set_permissions(&path, PermissionsExt::from_mode(0o600))
+                .map_err(Error::Bind)?;
+            log::info!("Serving metrics on {}", path.display());
+            tokio::spawn(async move {
+                loop {
+                    match listener.accept().await {
+                        Ok((stream, _)) => serve_connection(stream, scraper.clone()),
+                        Err(error) => log::error!(
+                            "{}",
+                            error.display_chain_with_msg("Failed to accept metrics connection")
+                        ),
+                    }
+                }
+            });
+        }
+    }
+    Ok(())
+}
+
+fn serve_connection<S>(stream: S, scraper: Scraper)
+where
+    S: AsyncRead + AsyncWrite + Unpin + Send + 'static,
+{
+    tokio::spawn(async move {
+        let service = service_fn(move |request| {
+            let scraper = scraper.clone();
+            async move { Ok::<_, Infallible>(scraper.handle(request).await) }
+        });
+        if let Err(error) = http1::Builder::new()
+            .serve_connection(TokioIo::new(stream), service)
+            .await
+        {
+            log::debug!("Metrics connection failed: {error}");
+        }
+    });
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_parse_endpoint() {
+        assert_eq!(
+            "127.0.0.1:9180".parse::<MetricsEndpoint>().unwrap(),
+            MetricsEndpoint::Tcp("127.0.0.1:9180".parse().unwrap())
+        );
+        assert!(matches!(
+            "0.0.0.0:9180".parse::<MetricsEndpoint>(),
+            Err(Error::NotLoopback(_))
+        ));
+        assert!(matches!(
+            "localhost".parse::<MetricsEndpoint>(),
+            Err(Error::InvalidEndpoint(_))
+        ));
+        #[cfg(unix)]
+        assert_eq!(
+            "unix:/run/mullvad-metrics"
+                .parse::<MetricsEndpoint>()
+                .unwrap(),
+            MetricsEndpoint::Unix(PathBuf::from("/run/mullvad-metrics"))
+        );
+    }
+}
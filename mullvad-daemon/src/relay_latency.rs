@@ -0,0 +1,296 @@
+//! Measures the round-trip time to WireGuard relays, so that the relay selector can pick the relay
+//! with the lowest latency when the selection strategy is
+//! [`SelectionStrategy::LowestLatency`](mullvad_types::relay_constraints::SelectionStrategy).
+//!
+//! While disconnected, relays are pinged directly. Pings are blocked by the firewall in lockdown
+//! mode, so nothing is measured then.
+//!
+//! While connected to a single WireGuard relay, relays are pinged through the tunnel, and thus via
+//! the current relay. To keep these measurements comparable to direct ones, the overhead of the
+//! tunnel is subtracted from them. The overhead is the round-trip time to the relay inside the
+//! tunnel minus the round-trip time to the relay that was measured before connecting. If the
+//! latter is unknown, no overhead is subtracted. Since the detour via the current relay is never
+//! shorter than the direct path, these estimates err on the side of being too high.
+
+use futures::StreamExt;
+use mullvad_relay_selector::RelaySelector;
+use mullvad_types::relay_list::Relay;
+use std::{net::IpAddr, time::Duration};
+use surge_ping::{Client, Config, ICMP, PingIdentifier, PingSequence, SurgeError};
+use talpid_types::{
+    ErrorExt,
+    net::{IpVersion, TunnelType},
+    tunnel::TunnelStateTransition,
+};
+use tokio::{
+    sync::mpsc,
+    task::JoinHandle,
+    time::{MissedTickBehavior, interval},
+};
+
+/// How long to wait for a reply to a single ping.
+const PING_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Maximum number of relays to ping at the same time.
+const MAX_CONCURRENT_PINGS: usize = 16;
+
+/// How often to look for relays whose latency needs to be measured again.
+const PROBE_INTERVAL: Duration = Duration::from_secs(10 * 60);
+
+/// An actor that measures the latency to relays that match the current relay constraints, and
+/// reports them to the [`RelaySelector`].
+pub struct LatencyProber {
+    task_event_tx: mpsc::UnboundedSender<TaskEvent>,
+}
+
+/// [LatencyProber] internal task state.
+struct Task {
+    events_rx: mpsc::UnboundedReceiver<TaskEvent>,
+    relay_selector: RelaySelector,
+    /// How relays can be pinged in the current tunnel state, if at all.
+    probe_mode: Option<ProbeMode>,
+    /// Task that is currently pinging relays, if any.
+    current_probe: Option<JoinHandle<()>>,
+}
+
+/// How pings reach the relays.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum ProbeMode {
+    /// Pings are sent directly to the relays.
+    Direct,
+    /// Pings are sent through a tunnel to the relay at `relay_address`.
+    Tunnel { relay_address: IpAddr },
+}
+
+enum TaskEvent {
+    NewTunnelState(TunnelStateTransition),
+    Probe,
+}
+
+impl LatencyProber {
+    pub fn new(relay_selector: RelaySelector) -> Self {
+        let (task_event_tx, events_rx) = mpsc::unbounded_channel();
+
+        let task = Task {
+            events_rx,
+            relay_selector,
+            probe_mode: None,
+            current_probe: None,
+        };
+
+        tokio::task::spawn(task.run());
+
+        LatencyProber { task_event_tx }
+    }
+
+    /// Call when we transition to a new tunnel state.
+    pub fn on_tunnel_state_transition(&self, tunnel_state: TunnelStateTransition) {
+        self.send(TaskEvent::NewTunnelState(tunnel_state))
+    }
+
+    /// Measure the latency to relays that have not been measured recently, if the tunnel state
+    /// allows it. Call this when the set of relays that may be selected could have changed.
+    pub fn probe(&self) {
+        self.send(TaskEvent::Probe)
+    }
+
+    /// Send a [TaskEvent] to the running [Task];
+    fn send(&self, event: TaskEvent) {
+        if self.task_event_tx.send(event).is_err() {
+            panic!("LatencyProber unexpectedly closed");
+        }
+    }
+}
+
+impl Task {
+    async fn run(mut self) {
+        let mut probe_interval = interval(PROBE_INTERVAL);
+        probe_interval.set_missed_tick_behavior(MissedTickBehavior::Delay);
+
+        loop {
+            tokio::select! {
+                event = self.events_rx.recv() => match event {
+                    Some(TaskEvent::NewTunnelState(tunnel_state)) => {
+                        self.on_new_tunnel_state(tunnel_state)
+                    }
+                    Some(TaskEvent::Probe) => self.start_probe(),
+                    None => break, // All LatencyProber handles dropped.
+                },
+                _ = probe_interval.tick() => self.start_probe(),
+            }
+        }
+
+        self.stop_probe();
+    }
+
+    fn on_new_tunnel_state(&mut self, tunnel_state: TunnelStateTransition) {
+        let probe_mode = match tunnel_state {
+            // Pings are blocked by the firewall in lockdown mode
+            #[cfg(not(target_os = "android"))]
+            TunnelStateTransition::Disconnected { locked_down } => {
+                (!locked_down).then_some(ProbeMode::Direct)
+            }
+            #[cfg(target_os = "android")]
+            TunnelStateTransition::Disconnected {} => Some(ProbeMode::Direct),
+            // With multihop, pings would pass through two relays, and the overhead could not be
+            // estimated from the direct round-trip time to the entry relay alone
+            TunnelStateTransition::Connected(endpoint)
+                if endpoint.tunnel_type == TunnelType::Wireguard
+                    && endpoint.entry_endpoint.is_none() =>
+            {
+                Some(ProbeMode::Tunnel {
+                    relay_address: endpoint.endpoint.address.ip(),
+                })
+            }
+            _ => None,
+        };
+
+        if probe_mode != self.probe_mode {
+            self.stop_probe();
+        }
+        self.probe_mode = probe_mode;
+        self.start_probe();
+    }
+
+    fn start_probe(&mut self) {
+        let Some(probe_mode) = self.probe_mode else {
+            return;
+        };
+        let probe_running = self
+            .current_probe
+            .as_ref()
+            .is_some_and(|probe| !probe.is_finished());
+        if probe_running {
+            return;
+        }
+
+        let mut relays = self.relay_selector.latency_probe_candidates();
+        if let ProbeMode::Tunnel { relay_address } = probe_mode {
+            // Traffic to the current relay is routed outside the tunnel
+            relays.retain(|(relay, _)| {
+                IpAddr::from(relay.ipv4_addr_in) != relay_address
+                    && relay.ipv6_addr_in.map(IpAddr::from) != Some(relay_address)
+            });
+        }
+        if relays.is_empty() {
+            return;
+        }
+        let probe = probe_relays(self.relay_selector.clone(), probe_mode, relays);
+        self.current_probe = Some(tokio::task::spawn(probe));
+    }
+
+    fn stop_probe(&mut self) {
+        if let Some(probe) = self.current_probe.take() {
+            probe.abort();
+        }
+    }
+}
+
+/// Ping each of `relays` once, and report the round-trip times to `relay_selector`.
+async fn probe_relays(
+    relay_selector: RelaySelector,
+    probe_mode: ProbeMode,
+    relays: Vec<(Relay, IpAddr)>,
+) {
+    let mut clients = Clients::default();
+
+    let tunnel_overhead = match probe_mode {
+        ProbeMode::Direct => Duration::ZERO,
+        ProbeMode::Tunnel { relay_address } => {
+            let ip_version = match relays[0].1 {
+                IpAddr::V4(_) => IpVersion::V4,
+                IpAddr::V6(_) => IpVersion::V6,
+            };
+            let gateway = relay_selector.wireguard_gateway(ip_version);
+            let Some(client) = clients.get(gateway) else {
+                return;
+            };
+            let tunnel_rtt = match ping(&client, gateway).await {
+                Ok(rtt) => rtt,
+                Err(error) => {
+                    log::debug!(
+                        "Failed to measure the round-trip time through the tunnel: {error}"
+                    );
+                    return;
+                }
+            };
+            let direct_rtt = relay_selector
+                .relay_latency_by_address(relay_address)
+                .unwrap_or(tunnel_rtt);
+            tunnel_rtt.saturating_sub(direct_rtt)
+        }
+    };
+
+    log::debug!("Measuring latency to {} relays", relays.len());
+
+    let mut pings = vec![];
+    for (relay, address) in relays {
+        let Some(client) = clients.get(address) else {
+            continue;
+        };
+        pings.push(async move {
+            let rtt = ping(&client, address).await;
+            (relay, rtt)
+        });
+    }
+
+    let results: Vec<_> = futures::stream::iter(pings)
+        .buffer_unordered(MAX_CONCURRENT_PINGS)
+        .collect()
+        .await;
+
+    let mut num_measured = 0;
+    for (relay, rtt) in results {
+        match rtt {
+            Ok(rtt) => {
+                relay_selector
+                    .set_relay_latency(&relay.hostname, rtt.saturating_sub(tunnel_overhead));
+                num_measured += 1;
+            }
+            Err(error) => {
+                log::trace!("Failed to measure latency to {}: {error}", relay.hostname);
+            }
+        }
+    }
+
+    log::debug!("Measured latency to {num_measured} relays");
+}
+
+/// ICMP clients for each IP version, opened when first needed.
+#[derive(Default)]
+struct Clients {
+    v4: Option<Client>,
+    v6: Option<Client>,
+}
+
+impl Clients {
+    /// Returns a client that can ping `address`, or `None` if the socket could not be opened.
+    fn get(&mut self, address: IpAddr) -> Option<Client> {
+        let (client, kind) = match address {
+            IpAddr::V4(_) => (&mut self.v4, ICMP::V4),
+            IpAddr::V6(_) => (&mut self.v6, ICMP::V6),
+        };
+        if client.is_none() {
+            match Client::new(&Config::builder().kind(kind).build()) {
+                Ok(new_client) => *client = Some(new_client),
+                Err(error) => {
+                    log::error!(
+                        "{}",
+                        error.display_chain_with_msg(
+                            "Failed to open socket for measuring relay latency"
+                        )
+                    );
+                    return None;
+                }
+            }
+        }
+        client.clone()
+    }
+}
+
+async fn ping(client: &Client, address: IpAddr) -> Result<Duration, SurgeError> {
+    let mut pinger = client.pinger(address, PingIdentifier(rand::random())).await;
+    pinger.timeout(PING_TIMEOUT);
+    let (_packet, rtt) = pinger.ping(PingSequence(0), &[0; 8]).await?;
+    Ok(rtt)
+}
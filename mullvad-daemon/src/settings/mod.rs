@@ -4,6 +4,7 @@ use mullvad_types::{
     relay_constraints::{RelayConstraints, RelaySettings, WireguardConstraints},
     settings::{DnsState, Settings},
 };
+use policy::SettingsPolicy;
 use std::{
     fmt::{self, Display},
     ops::Deref,
@@ -17,6 +18,7 @@ use tokio::{
 };
 
 pub mod patch;
+pub mod policy;
 
 const SETTINGS_FILE: &str = "settings.json";
 
@@ -42,6 +44,9 @@ pub enum Error {
 
     #[error("Failed to parse IP network from string: {0}")]
     ParseIp(String),
+
+    #[error("Settings policy error")]
+    Policy(#[source] policy::Error),
 }
 
 /// Converts an [Error] to a management interface status
@@ -60,10 +65,24 @@ impl From<Error> for mullvad_management_interface::Status {
                 let custom_list_err = *err.downcast::<CustomListError>().unwrap();
                 handle_custom_list_error(custom_list_err)
             }
+            Error::UpdateFailed(err)
+                if err
+                    .downcast_ref::<talpid_types::net::dns::InvalidDomain>()
+                    .is_some()
+                    || err
+                        .downcast_ref::<talpid_types::net::InvalidException>()
+                        .is_some() =>
+            {
+                Status::new(Code::InvalidArgument, err.to_string())
+            }
+            Error::Policy(error @ policy::Error::Locked(_)) => {
+                Status::new(Code::PermissionDenied, error.to_string())
+            }
             Error::SerializeError(..)
             | Error::ParseError(..)
             | Error::UpdateFailed(..)
-            | Error::ParseIp(..) => Status::new(Code::Internal, error.to_string()),
+            | Error::ParseIp(..)
+            | Error::Policy(..) => Status::new(Code::Internal, error.to_string()),
         }
     }
 }
@@ -96,6 +115,7 @@ fn handle_custom_list_error(
 pub struct SettingsPersister {
     settings: Settings,
     path: PathBuf,
+    policy: SettingsPolicy,
     #[allow(clippy::type_complexity)]
     on_change_listeners: Vec<Box<dyn Fn(&Settings) + Send + Sync>>,
 }
@@ -104,17 +124,42 @@ pub type MadeChanges = bool;
 
 impl SettingsPersister {
     /// Loads user settings from file. If it fails, it returns the defaults, and overwrites the old
-    /// settings.
-    pub async fn load(settings_dir: &Path) -> Self {
+    /// settings. Settings overridden or locked by `policy` are applied to the loaded settings.
+    pub async fn load(settings_dir: &Path, policy: SettingsPolicy) -> Self {
         let path = settings_dir.join(SETTINGS_FILE);
         let LoadSettingsResult {
-            settings,
-            should_save,
+            mut settings,
+            mut should_save,
         } = Self::load_inner(|| Self::load_from_file(&path)).await;
 
+        let enforced_settings = policy.apply(&settings).or_else(|error| {
+            // The policy may conflict with the current settings, e.g. by selecting another enum
+            // variant. The policy is known to apply to the default settings.
+            log::error!(
+                "{}",
+                error.display_chain_with_msg(
+                    "Failed to apply settings policy to current settings. Using defaults."
+                )
+            );
+            policy.apply(&Self::default_settings())
+        });
+        match enforced_settings {
+            Ok(enforced_settings) => {
+                should_save |= enforced_settings != settings;
+                settings = enforced_settings;
+            }
+            Err(error) => {
+                log::error!(
+                    "{}",
+                    error.display_chain_with_msg("Failed to apply settings policy")
+                );
+            }
+        }
+
         let mut persister = SettingsPersister {
             settings,
             path,
+            policy,
             on_change_listeners: vec![],
         };
 
@@ -236,9 +281,12 @@ impl SettingsPersister {
         Ok(())
     }
 
-    /// Resets default settings
+    /// Resets default settings, except for settings enforced by the settings policy
     pub async fn reset(&mut self) -> Result<(), Error> {
-        self.settings = Self::default_settings();
+        self.settings = self
+            .policy
+            .apply(&Self::default_settings())
+            .map_err(Error::Policy)?;
         let path = self.path.clone();
         self.save()
             .or_else(|e| async move {
@@ -285,8 +333,9 @@ impl SettingsPersister {
     ///
     /// # On failure
     ///
-    /// If the settings could not be written to disk, all changes are rolled
-    /// back, and an error is returned.
+    /// If the settings could not be written to disk, or a setting locked by
+    /// the settings policy was changed, all changes are rolled back, and an
+    /// error is returned.
     ///
     /// # Note
     ///
@@ -310,12 +359,16 @@ impl SettingsPersister {
     ///
     /// # On failure
     ///
-    /// `try_update` may fail in two scenarios
+    /// `try_update` may fail in three scenarios
     ///
     /// ## The settings could not be written to disk
     ///
     /// In this case, all changes are rolled back and an error is returned.
     ///
+    /// ## A setting locked by the settings policy was changed
+    ///
+    /// All changes are rejected, and [`Error::Policy`] is returned.
+    ///
     /// ## `update_fn` failed
     ///
     /// If `update_fn` were to fail the error will be propagated through the
@@ -358,6 +411,10 @@ impl SettingsPersister {
             return Ok(false);
         }
 
+        self.policy
+            .check_locked(&new_settings)
+            .map_err(Error::Policy)?;
+
         Self::save_inner(&self.path, &new_settings).await?;
         self.settings = new_settings;
 
@@ -1,22 +1,25 @@
 //! This module provides functionality for updating settings using a JSON string, i.e. applying a
 //! patch. It is intended to be relatively safe, preventing editing of "dangerous" settings such as
-//! custom DNS.
+//! custom DNS unless the caller explicitly allows it.
 //!
-//! Patching the settings is a three-step procedure:
+//! Patching the settings is a four-step procedure:
 //! 1. Validating the input. Only a subset of settings is allowed to be edited using this method.
-//!    Attempting to edit prohibited or invalid settings results in an error.
+//!    Attempting to edit prohibited or invalid settings results in an error. Sensitive settings,
+//!    which may contain secrets or weaken security, are only accepted if explicitly allowed.
 //! 2. Merging the changes. When the patch has been accepted, it can be applied to the existing
 //!    settings. How they're merged depends on the actual setting. See [MergeStrategy].
-//! 3. Deserialize the resulting JSON back to a [Settings] instance, and, if valid, replace the
-//!    existing settings.
+//! 3. Deserialize the resulting JSON back to a [Settings] instance.
+//! 4. Check that every patched setting exists on this platform, and, if so, replace the existing
+//!    settings.
 //!
-//! Permitted settings and merge strategies are defined in the [PERMITTED_SUBKEYS] constant.
+//! Permitted settings and merge strategies are defined in the [PERMITTED_SUBKEYS] constant. The
+//! same constant decides which settings are exported.
 //!
 //! This implementation must be kept in sync with the
 //! [spec](../../../docs/settings-patch-format.md).
 
 use super::SettingsPersister;
-use mullvad_types::settings::Settings;
+use mullvad_types::{access_method, custom_list::CustomList, settings::Settings};
 
 #[derive(thiserror::Error, Debug)]
 pub enum Error {
@@ -26,6 +29,12 @@ pub enum Error {
     /// Unknown or prohibited key
     #[error("Invalid or prohibited key: {0}")]
     UnknownOrProhibitedKey(String),
+    /// Sensitive key that was not explicitly allowed
+    #[error("Key requires sensitive settings to be allowed: {0}")]
+    SensitiveKey(String),
+    /// Key that does not exist on this platform
+    #[error("Setting is not supported on this platform: {0}")]
+    UnsupportedKey(String),
     /// Failed to parse patch json
     #[error("Failed to parse settings patch")]
     ParsePatch(#[source] serde_json::Error),
@@ -41,6 +50,9 @@ pub enum Error {
     /// Recursion limit reached
     #[error("Maximum JSON object depth reached")]
     RecursionLimit,
+    /// Invalid custom list
+    #[error("Invalid custom list")]
+    CustomList(#[source] mullvad_types::custom_list::Error),
     /// Settings error
     #[error("Settings error")]
     Settings(#[source] super::Error),
@@ -54,9 +66,12 @@ impl From<Error> for mullvad_management_interface::Status {
         match error {
             Error::InvalidOrMissingValue(_)
             | Error::UnknownOrProhibitedKey(_)
+            | Error::UnsupportedKey(_)
             | Error::ParsePatch(_)
             | Error::DeserializePatched(_)
-            | Error::RecursionLimit => Status::invalid_argument(error.to_string()),
+            | Error::RecursionLimit
+            | Error::CustomList(_) => Status::invalid_argument(error.to_string()),
+            Error::SensitiveKey(_) => Status::permission_denied(error.to_string()),
             Error::Settings(error) => Status::from(error),
             Error::SerializeSettings(error) | Error::SerializeValue(error) => {
                 Status::internal(error.to_string())
@@ -79,6 +94,9 @@ const DEFAULT_MERGE_STRATEGY: MergeStrategy = MergeStrategy::Replace;
 struct PermittedKey {
     key_type: PermittedKeyValue,
     merge_strategy: MergeStrategy,
+    /// Whether the key may contain secrets or weaken security. Such keys are only imported and
+    /// exported if explicitly allowed.
+    sensitive: bool,
 }
 
 impl PermittedKey {
@@ -86,6 +104,7 @@ impl PermittedKey {
         Self {
             key_type: PermittedKeyValue::Object(keys),
             merge_strategy: DEFAULT_MERGE_STRATEGY,
+            sensitive: false,
         }
     }
 
@@ -93,6 +112,7 @@ impl PermittedKey {
         Self {
             key_type: PermittedKeyValue::Array(key),
             merge_strategy: DEFAULT_MERGE_STRATEGY,
+            sensitive: false,
         }
     }
 
@@ -100,6 +120,7 @@ impl PermittedKey {
         Self {
             key_type: PermittedKeyValue::Any,
             merge_strategy: DEFAULT_MERGE_STRATEGY,
+            sensitive: false,
         }
     }
 
@@ -107,6 +128,11 @@ impl PermittedKey {
         self.merge_strategy = merge_strategy;
         self
     }
+
+    const fn sensitive(mut self) -> Self {
+        self.sensitive = true;
+        self
+    }
 }
 
 enum PermittedKeyValue {
@@ -118,41 +144,108 @@ enum PermittedKeyValue {
     Any,
 }
 
-const PERMITTED_SUBKEYS: &PermittedKey = &PermittedKey::object(&[(
-    "relay_overrides",
-    PermittedKey::array(&PermittedKey::object(&[
-        ("hostname", PermittedKey::any()),
-        ("ipv4_addr_in", PermittedKey::any()),
-        ("ipv6_addr_in", PermittedKey::any()),
-    ]))
-    .merge_strategy(MergeStrategy::Custom(merge_relay_overrides)),
-)]);
+const PERMITTED_SUBKEYS: &PermittedKey = &PermittedKey::object(&[
+    (
+        "relay_overrides",
+        PermittedKey::array(&PermittedKey::object(&[
+            ("hostname", PermittedKey::any()),
+            ("ipv4_addr_in", PermittedKey::any()),
+            ("ipv6_addr_in", PermittedKey::any()),
+        ]))
+        .merge_strategy(MergeStrategy::Custom(merge_relay_overrides)),
+    ),
+    (
+        "custom_lists",
+        PermittedKey::object(&[(
+            "custom_lists",
+            PermittedKey::array(&PermittedKey::object(&[
+                ("name", PermittedKey::any()),
+                ("locations", PermittedKey::any()),
+            ]))
+            .merge_strategy(MergeStrategy::Custom(merge_custom_lists)),
+        )]),
+    ),
+    (
+        "obfuscation_settings",
+        PermittedKey::object(&[
+            ("selected_obfuscation", PermittedKey::any()),
+            ("udp2tcp", PermittedKey::any()),
+            ("shadowsocks", PermittedKey::any()),
+        ]),
+    ),
+    ("allow_lan", PermittedKey::any()),
+    // Disabling lockdown mode weakens security
+    ("block_when_disconnected", PermittedKey::any().sensitive()),
+    (
+        "tunnel_options",
+        PermittedKey::object(&[
+            (
+                "wireguard",
+                PermittedKey::object(&[
+                    ("mtu", PermittedKey::any()),
+                    ("quantum_resistant", PermittedKey::any()),
+                    ("daita", PermittedKey::any()),
+                ]),
+            ),
+            (
+                "dns_options",
+                PermittedKey::object(&[
+                    ("default_options", PermittedKey::any()),
+                    ("state", PermittedKey::any().sensitive()),
+                    ("custom_options", PermittedKey::any().sensitive()),
+                ]),
+            ),
+        ]),
+    ),
+    (
+        "api_access_methods",
+        PermittedKey::object(&[(
+            "custom",
+            PermittedKey::array(&PermittedKey::object(&[
+                ("name", PermittedKey::any()),
+                ("enabled", PermittedKey::any()),
+                ("access_method", PermittedKey::any()),
+            ]))
+            .merge_strategy(MergeStrategy::Custom(merge_access_methods)),
+        )])
+        .sensitive(),
+    ),
+]);
 /// Prohibit stack overflow via excessive recursion. It might be possible to forgo this when
 /// tail-call optimization can be enforced?
 const RECURSE_LIMIT: usize = 15;
 
-/// Export a patch containing all currently supported settings.
-pub fn export_settings(settings: &Settings) -> Result<String, Error> {
-    let patch = export_settings_inner(settings)?;
+/// Export a patch containing all currently supported settings. Sensitive settings are only
+/// included if `include_sensitive` is set.
+pub fn export_settings(settings: &Settings, include_sensitive: bool) -> Result<String, Error> {
+    let patch = export_settings_inner(settings, include_sensitive)?;
     serde_json::to_string_pretty(&patch).map_err(Error::SerializeValue)
 }
 
-fn export_settings_inner(settings: &Settings) -> Result<serde_json::Value, Error> {
-    let mut out = serde_json::Map::new();
-    let mut overrides = vec![];
+fn export_settings_inner(
+    settings: &Settings,
+    include_sensitive: bool,
+) -> Result<serde_json::Value, Error> {
+    let settings_value = serde_json::to_value(settings).map_err(Error::SerializeSettings)?;
+    let serde_json::Value::Object(mut out) =
+        export_value(PERMITTED_SUBKEYS, &settings_value, include_sensitive, 0)?
+    else {
+        return Err(Error::InvalidOrMissingValue("settings must be an object"));
+    };
 
-    for relay_override in &settings.relay_overrides {
-        let mut relay_override =
-            serde_json::to_value(relay_override).map_err(Error::SerializeValue)?;
-        if let Some(relay_overrides) = relay_override.as_object_mut() {
-            // prune empty override entries
-            relay_overrides.retain(|_k, v| !v.is_null());
-            let has_overrides = relay_overrides.iter().any(|(key, _)| key != "hostname");
-            if !has_overrides {
-                continue;
+    let mut overrides = vec![];
+    if let Some(serde_json::Value::Array(relay_overrides)) = out.remove("relay_overrides") {
+        for mut relay_override in relay_overrides {
+            if let Some(relay_overrides) = relay_override.as_object_mut() {
+                // prune empty override entries
+                relay_overrides.retain(|_k, v| !v.is_null());
+                let has_overrides = relay_overrides.iter().any(|(key, _)| key != "hostname");
+                if !has_overrides {
+                    continue;
+                }
             }
+            overrides.push(relay_override);
         }
-        overrides.push(relay_override);
     }
 
     if !overrides.is_empty() {
@@ -165,13 +258,52 @@ fn export_settings_inner(settings: &Settings) -> Result<serde_json::Value, Error
     Ok(serde_json::Value::Object(out))
 }
 
+/// Return the parts of `value` that are permitted by `permitted_key`.
+fn export_value(
+    permitted_key: &'static PermittedKey,
+    value: &serde_json::Value,
+    include_sensitive: bool,
+    recurse_level: usize,
+) -> Result<serde_json::Value, Error> {
+    if recurse_level >= RECURSE_LIMIT {
+        return Err(Error::RecursionLimit);
+    }
+
+    match (&permitted_key.key_type, value) {
+        (PermittedKeyValue::Object(subkeys), serde_json::Value::Object(map)) => {
+            let mut out = serde_json::Map::new();
+            for (k, subkey) in subkeys.iter() {
+                if subkey.sensitive && !include_sensitive {
+                    continue;
+                }
+                // Settings that do not exist on this platform are left out
+                let Some(sub_value) = map.get(*k) else {
+                    continue;
+                };
+                let sub_value =
+                    export_value(subkey, sub_value, include_sensitive, recurse_level + 1)?;
+                out.insert((*k).to_owned(), sub_value);
+            }
+            Ok(serde_json::Value::Object(out))
+        }
+        (PermittedKeyValue::Array(subkey), serde_json::Value::Array(values)) => values
+            .iter()
+            .map(|v| export_value(subkey, v, include_sensitive, recurse_level + 1))
+            .collect::<Result<_, _>>()
+            .map(serde_json::Value::Array),
+        _ => Ok(value.clone()),
+    }
+}
+
 /// Update the settings with the supplied patch. Only settings specified in `PERMITTED_SUBKEYS` can
-/// be updated. All other changes are rejected
+/// be updated, and sensitive settings only if `allow_sensitive` is set. All other changes are
+/// rejected
 pub async fn merge_validate_patch(
     settings: &mut SettingsPersister,
     json_patch: &str,
+    allow_sensitive: bool,
 ) -> Result<(), Error> {
-    let new_settings = merge_validate_patch_inner(settings, json_patch)?;
+    let new_settings = merge_validate_patch_inner(settings, json_patch, allow_sensitive)?;
 
     settings
         .update(move |settings| *settings = new_settings)
@@ -181,18 +313,28 @@ pub async fn merge_validate_patch(
     Ok(())
 }
 
-fn merge_validate_patch_inner(settings: &Settings, json_patch: &str) -> Result<Settings, Error> {
+fn merge_validate_patch_inner(
+    settings: &Settings,
+    json_patch: &str,
+    allow_sensitive: bool,
+) -> Result<Settings, Error> {
     let mut settings_value: serde_json::Value =
         serde_json::to_value(settings).map_err(Error::SerializeSettings)?;
     let patch_value: serde_json::Value =
         serde_json::from_str(json_patch).map_err(Error::ParsePatch)?;
 
-    validate_patch_value(PERMITTED_SUBKEYS, &patch_value, 0)?;
+    validate_patch_value(PERMITTED_SUBKEYS, &patch_value, allow_sensitive, 0)?;
     merge_patch_to_value(PERMITTED_SUBKEYS, &mut settings_value, &patch_value, 0)?;
 
     let new_settings: Settings =
         serde_json::from_value(settings_value).map_err(Error::DeserializePatched)?;
 
+    // Unknown fields are ignored when deserializing, so settings that do not exist on this
+    // platform would be silently dropped
+    let new_settings_value =
+        serde_json::to_value(&new_settings).map_err(Error::SerializeSettings)?;
+    validate_patch_supported(PERMITTED_SUBKEYS, &patch_value, &new_settings_value, 0)?;
+
     Ok(new_settings)
 }
 
@@ -259,6 +401,82 @@ fn merge_relay_overrides(
     Ok(serde_json::Value::Array(new_array))
 }
 
+/// Update custom lists by name. Lists with names that do not exist are appended with a new ID, if
+/// the name is valid.
+fn merge_custom_lists(
+    current_settings: &serde_json::Value,
+    patch: &serde_json::Value,
+) -> Result<serde_json::Value, Error> {
+    merge_named_entries(current_settings, patch, |name| {
+        let list = CustomList::new(name.to_owned()).map_err(Error::CustomList)?;
+        serde_json::to_value(list.id()).map_err(Error::SerializeValue)
+    })
+}
+
+/// Update custom API access methods by name. Access methods with names that do not exist are
+/// appended with a new ID.
+fn merge_access_methods(
+    current_settings: &serde_json::Value,
+    patch: &serde_json::Value,
+) -> Result<serde_json::Value, Error> {
+    merge_named_entries(current_settings, patch, |_name| {
+        serde_json::to_value(access_method::Id::new()).map_err(Error::SerializeValue)
+    })
+}
+
+/// Replace or append keys to existing entries in the array with a matching name. Entries for names
+/// that do not exist are appended, with an ID returned by `new_id` for the name. IDs are never
+/// taken from the patch, since they differ between devices.
+fn merge_named_entries(
+    current_settings: &serde_json::Value,
+    patch: &serde_json::Value,
+    new_id: impl Fn(&str) -> Result<serde_json::Value, Error>,
+) -> Result<serde_json::Value, Error> {
+    let patch_array = patch
+        .as_array()
+        .ok_or(Error::InvalidOrMissingValue("named entries must be array"))?;
+    let mut new_array = match current_settings {
+        serde_json::Value::Null => vec![],
+        serde_json::Value::Array(current_array) => current_array.clone(),
+        _ => {
+            return Err(Error::InvalidOrMissingValue(
+                "existing entries should be an array",
+            ));
+        }
+    };
+
+    for patch_entry in patch_array {
+        let patch_obj = patch_entry
+            .as_object()
+            .ok_or(Error::InvalidOrMissingValue("named entry"))?;
+        let patch_name = patch_obj
+            .get("name")
+            .and_then(|name| name.as_str())
+            .ok_or(Error::InvalidOrMissingValue("name"))?;
+
+        let existing_obj = new_array.iter_mut().find_map(|value| {
+            value
+                .as_object_mut()
+                .filter(|obj| obj.get("name").and_then(|name| name.as_str()) == Some(patch_name))
+        });
+
+        match existing_obj {
+            Some(existing_obj) => {
+                for (k, v) in patch_obj {
+                    existing_obj.insert(k.to_owned(), v.to_owned());
+                }
+            }
+            None => {
+                let mut new_obj = patch_obj.clone();
+                new_obj.insert("id".to_owned(), new_id(patch_name)?);
+                new_array.push(serde_json::Value::Object(new_obj));
+            }
+        }
+    }
+
+    Ok(serde_json::Value::Array(new_array))
+}
+
 fn merge_patch_to_value(
     permitted_key: &'static PermittedKey,
     current_value: &mut serde_json::Value,
@@ -311,6 +529,7 @@ fn merge_patch_to_value(
 fn validate_patch_value(
     permitted_key: &'static PermittedKey,
     json_value: &serde_json::Value,
+    allow_sensitive: bool,
     recurse_level: usize,
 ) -> Result<(), Error> {
     if recurse_level >= RECURSE_LIMIT {
@@ -330,7 +549,10 @@ fn validate_patch_value(
                 else {
                     return Err(Error::UnknownOrProhibitedKey(k.to_owned()));
                 };
-                validate_patch_value(subkey, v, recurse_level + 1)?;
+                if subkey.sensitive && !allow_sensitive {
+                    return Err(Error::SensitiveKey(k.to_owned()));
+                }
+                validate_patch_value(subkey, v, allow_sensitive, recurse_level + 1)?;
             }
             Ok(())
         }
@@ -339,7 +561,7 @@ fn validate_patch_value(
                 .as_array()
                 .ok_or(Error::InvalidOrMissingValue("expected JSON array in patch"))?;
             for v in values {
-                validate_patch_value(subkey, v, recurse_level + 1)?;
+                validate_patch_value(subkey, v, allow_sensitive, recurse_level + 1)?;
             }
             Ok(())
         }
@@ -347,6 +569,37 @@ fn validate_patch_value(
     }
 }
 
+/// Check that every object key in a validated patch exists in the patched settings. Keys for
+/// settings that only exist on other platforms are rejected.
+fn validate_patch_supported(
+    permitted_key: &'static PermittedKey,
+    patch_value: &serde_json::Value,
+    settings_value: &serde_json::Value,
+    recurse_level: usize,
+) -> Result<(), Error> {
+    if recurse_level >= RECURSE_LIMIT {
+        return Err(Error::RecursionLimit);
+    }
+
+    let (PermittedKeyValue::Object(subkeys), Some(patch), Some(settings)) = (
+        &permitted_key.key_type,
+        patch_value.as_object(),
+        settings_value.as_object(),
+    ) else {
+        return Ok(());
+    };
+
+    for (k, sub_patch) in patch {
+        let Some(sub_settings) = settings.get(k) else {
+            return Err(Error::UnsupportedKey(k.to_owned()));
+        };
+        if let Some((_, subkey)) = subkeys.iter().find(|(permitted_key, _)| k == permitted_key) {
+            validate_patch_supported(subkey, sub_patch, sub_settings, recurse_level + 1)?;
+        }
+    }
+    Ok(())
+}
+
 #[test]
 fn test_permitted_value() {
     const PERMITTED_SUBKEYS: &PermittedKey = &PermittedKey::object(&[(
@@ -357,7 +610,7 @@ fn test_permitted_value() {
     let patch = r#"{"key": [ {"a": "test" } ] }"#;
     let patch: serde_json::Value = serde_json::from_str(patch).unwrap();
 
-    validate_patch_value(PERMITTED_SUBKEYS, &patch, 0).unwrap();
+    validate_patch_value(PERMITTED_SUBKEYS, &patch, false, 0).unwrap();
 }
 
 #[test]
@@ -370,12 +623,12 @@ fn test_prohibited_value() {
     let patch = r#"{"keyx": [] }"#;
     let patch: serde_json::Value = serde_json::from_str(patch).unwrap();
 
-    validate_patch_value(PERMITTED_SUBKEYS, &patch, 0).unwrap_err();
+    validate_patch_value(PERMITTED_SUBKEYS, &patch, false, 0).unwrap_err();
 
     let patch = r#"{"key": { "b": 1 } }"#;
     let patch: serde_json::Value = serde_json::from_str(patch).unwrap();
 
-    validate_patch_value(PERMITTED_SUBKEYS, &patch, 0).unwrap_err();
+    validate_patch_value(PERMITTED_SUBKEYS, &patch, false, 0).unwrap_err();
 }
 
 #[test]
@@ -441,7 +694,7 @@ fn test_overflow() {
     let patch: serde_json::Value = serde_json::from_str(patch).unwrap();
 
     assert!(matches!(
-        validate_patch_value(PERMITTED_SUBKEYS, &patch, 0),
+        validate_patch_value(PERMITTED_SUBKEYS, &patch, false, 0),
         Err(Error::RecursionLimit)
     ));
 }
@@ -453,7 +706,7 @@ fn test_valid_patch_files() {
         include_str!("../../../docs/patch-examples/override-relay-ips.json");
 
     let prev_settings = Settings::default();
-    let _ = merge_validate_patch_inner(&prev_settings, OVERRIDE_PATCH)
+    let _ = merge_validate_patch_inner(&prev_settings, OVERRIDE_PATCH, false)
         .expect("failed to apply relay overrides");
 }
 
@@ -468,12 +721,29 @@ fn test_patch_export() {
     relay_override.ipv6_addr_in = Some("::1".parse().unwrap());
     settings.relay_overrides.push(relay_override);
 
-    let exported = export_settings_inner(&settings).expect("patch export failed");
+    let exported = export_settings_inner(&settings, false).expect("patch export failed");
 
     let expected = r#"{ "relay_overrides": [ { "hostname": "test", "ipv4_addr_in": "1.2.3.4", "ipv6_addr_in": "::1" } ] }"#;
-    let expected: serde_json::Value = serde_json::from_str(expected).unwrap();
+    let mut expected: serde_json::Value = serde_json::from_str(expected).unwrap();
+    // All other settings are exported with their default values
+    let serde_json::Value::Object(defaults) =
+        export_settings_inner(&Settings::default(), false).unwrap()
+    else {
+        panic!("exported settings must be an object");
+    };
+    for (key, value) in defaults {
+        expected[key] = value;
+    }
 
     assert_eq!(exported, expected);
+    // Sensitive settings are left out
+    assert!(exported.get("api_access_methods").is_none());
+    assert!(exported.get("block_when_disconnected").is_none());
+    assert!(
+        exported["tunnel_options"]["dns_options"]
+            .get("state")
+            .is_none()
+    );
 }
 
 #[test]
@@ -492,7 +762,7 @@ fn test_patch_relay_override() {
     //
     let patch = r#"{ "relay_overrides": [ { "invalid": 0 } ] }"#;
     let patch: serde_json::Value = serde_json::from_str(patch).unwrap();
-    validate_patch_value(PERMITTED_SUBKEYS, &patch, 0).unwrap_err();
+    validate_patch_value(PERMITTED_SUBKEYS, &patch, false, 0).unwrap_err();
 
     // If there are no overrides, append new override
     //
@@ -504,7 +774,7 @@ fn test_patch_relay_override() {
     let patch: serde_json::Value = serde_json::from_str(patch).unwrap();
     let expected: serde_json::Value = serde_json::from_str(expected).unwrap();
 
-    validate_patch_value(PERMITTED_SUBKEYS, &patch, 0).unwrap();
+    validate_patch_value(PERMITTED_SUBKEYS, &patch, false, 0).unwrap();
     merge_patch_to_value(PERMITTED_SUBKEYS, &mut current, &patch, 0).unwrap();
 
     assert_eq!(current, expected);
@@ -519,7 +789,7 @@ fn test_patch_relay_override() {
     let patch: serde_json::Value = serde_json::from_str(patch).unwrap();
     let expected: serde_json::Value = serde_json::from_str(expected).unwrap();
 
-    validate_patch_value(PERMITTED_SUBKEYS, &patch, 0).unwrap();
+    validate_patch_value(PERMITTED_SUBKEYS, &patch, false, 0).unwrap();
     merge_patch_to_value(PERMITTED_SUBKEYS, &mut current, &patch, 0).unwrap();
 
     assert_eq!(current, expected);
@@ -534,7 +804,7 @@ fn test_patch_relay_override() {
     let patch: serde_json::Value = serde_json::from_str(patch).unwrap();
     let expected: serde_json::Value = serde_json::from_str(expected).unwrap();
 
-    validate_patch_value(PERMITTED_SUBKEYS, &patch, 0).unwrap();
+    validate_patch_value(PERMITTED_SUBKEYS, &patch, false, 0).unwrap();
     merge_patch_to_value(PERMITTED_SUBKEYS, &mut current, &patch, 0).unwrap();
 
     assert_eq!(current, expected);
@@ -550,8 +820,62 @@ fn test_patch_relay_override() {
     let patch: serde_json::Value = serde_json::from_str(patch).unwrap();
     let expected: serde_json::Value = serde_json::from_str(expected).unwrap();
 
-    validate_patch_value(PERMITTED_SUBKEYS, &patch, 0).unwrap();
+    validate_patch_value(PERMITTED_SUBKEYS, &patch, false, 0).unwrap();
     merge_patch_to_value(PERMITTED_SUBKEYS, &mut current, &patch, 0).unwrap();
 
     assert_eq!(current, expected);
 }
+
+#[test]
+fn test_sensitive_key() {
+    let patch = r#"{ "tunnel_options": { "dns_options": { "state": "custom" } } }"#;
+
+    assert!(matches!(
+        merge_validate_patch_inner(&Settings::default(), patch, false),
+        Err(Error::SensitiveKey(_))
+    ));
+    let settings = merge_validate_patch_inner(&Settings::default(), patch, true).unwrap();
+    assert_eq!(
+        settings.tunnel_options.dns_options.state,
+        mullvad_types::settings::DnsState::Custom
+    );
+
+    let exported = export_settings_inner(&settings, false).unwrap();
+    assert!(
+        exported["tunnel_options"]["dns_options"]
+            .get("state")
+            .is_none()
+    );
+    let exported = export_settings_inner(&settings, true).unwrap();
+    assert_eq!(exported["tunnel_options"]["dns_options"]["state"], "custom");
+}
+
+#[test]
+fn test_patch_custom_lists() {
+    let mut settings = Settings::default();
+    let existing = CustomList::new("existing".to_owned()).unwrap();
+    let existing_id = existing.id();
+    settings.custom_lists.custom_lists.push(existing);
+
+    let patch = r#"{ "custom_lists": { "custom_lists": [
+        { "name": "existing", "locations": [ { "country": "se" } ] },
+        { "name": "new", "locations": [] }
+    ] } }"#;
+    let settings = merge_validate_patch_inner(&settings, patch, false).unwrap();
+
+    let lists = &settings.custom_lists.custom_lists;
+    assert_eq!(lists.len(), 2);
+    assert_eq!(lists[0].id(), existing_id);
+    assert_eq!(lists[0].locations.len(), 1);
+    assert_eq!(lists[1].name, "new");
+    assert_ne!(lists[1].id(), existing_id);
+
+    // The name of new lists must be valid
+    let patch = r#"{ "custom_lists": { "custom_lists": [
+        { "name": "a name that is far too long for a custom list", "locations": [] }
+    ] } }"#;
+    assert!(matches!(
+        merge_validate_patch_inner(&settings, patch, false),
+        Err(Error::CustomList(_))
+    ));
+}
@@ -0,0 +1,400 @@
+//! Settings enforced by the administrator of the device.
+//!
+//! The policy is read from [`POLICY_FILENAME`] in the settings directory, which is only writable
+//! by root, when the daemon starts. It uses the same keys and value formats as the settings file:
+//!
+//! ```toml
+//! # Applied when the daemon starts. Users may change these settings afterwards.
+//! [override]
+//! allow_lan = true
+//!
+//! # Applied when the daemon starts. Changing these settings is rejected.
+//! [locked]
+//! block_when_disconnected = true
+//!
+//! [locked.obfuscation_settings]
+//! selected_obfuscation = "udp2_tcp"
+//!
+//! # Where app updates are fetched from. See [`UpdatePolicy`].
+//! [update]
+//! metadata_source = "https://updates.example.com/mullvad/linux.json"
+//! trusted_keys = ["<hex-encoded ed25519 key>"]
+//! signature_threshold = 1
+//! mirror_url = "https://updates.example.com/mullvad"
+//! ```
+//!
+//! Only the settings in [`POLICY_KEYS`] may be set by the policy. Tables are merged key by key,
+//! so that only the values present in the policy are overridden or locked.
+
+use mullvad_types::settings::Settings;
+use mullvad_update::{format::key::VerifyingKey, source::CustomMetadataSource};
+use serde::Deserialize;
+use std::{io, path::Path};
+
+/// Name of the file in the settings directory that contains the settings policy.
+pub const POLICY_FILENAME: &str = "policy.toml";
+
+/// Settings that may be set by the policy, including all of their subkeys.
+const POLICY_KEYS: &[&str] = &[
+    "relay_settings",
+    "block_when_disconnected",
+    "allow_lan",
+    "obfuscation_settings",
+    "tunnel_options.dns_options",
+];
+
+type Map = serde_json::Map<String, serde_json::Value>;
+
+#[derive(thiserror::Error, Debug)]
+pub enum Error {
+    #[error("Failed to read settings policy")]
+    Read(#[source] io::Error),
+
+    #[error("Failed to parse settings policy")]
+    Parse(#[source] toml::de::Error),
+
+    #[error("Setting cannot be set by the settings policy: {0}")]
+    ProhibitedKey(String),
+
+    #[error("Setting is not supported on this platform: {0}")]
+    UnsupportedKey(String),
+
+    #[error("Failed to apply settings policy")]
+    Apply(#[source] serde_json::Error),
+
+    #[error("Setting is locked by the settings policy: {0}")]
+    Locked(String),
+
+    #[error("Invalid update source in settings policy: {0}")]
+    InvalidUpdateSource(String),
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct PolicyFile {
+    #[serde(default, rename = "override")]
+    overrides: Map,
+    #[serde(default)]
+    locked: Map,
+    update: Option<UpdatePolicyFile>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct UpdatePolicyFile {
+    metadata_source: Option<String>,
+    #[serde(default)]
+    trusted_keys: Vec<VerifyingKey>,
+    #[serde(default = "default_signature_threshold")]
+    signature_threshold: usize,
+    mirror_url: Option<String>,
+}
+
+fn default_signature_threshold() -> usize {
+    1
+}
+
+/// Where app updates are fetched from, for sites that host their own copy of the version metadata
+/// and installers.
+#[derive(Debug, Clone, Default)]
+pub struct UpdatePolicy {
+    /// Version metadata source to use instead of the Mullvad API.
+    pub metadata_source: Option<CustomMetadataSource>,
+    /// Base URL of a mirror. Installer URLs that begin with it are preferred.
+    pub mirror_url: Option<String>,
+}
+
+impl TryFrom<UpdatePolicyFile> for UpdatePolicy {
+    type Error = Error;
+
+    fn try_from(policy: UpdatePolicyFile) -> Result<Self, Error> {
+        let metadata_source = match policy.metadata_source {
+            Some(location) => {
+                let source = location.parse().and_then(|location| {
+                    CustomMetadataSource::new(
+                        location,
+                        policy.trusted_keys,
+                        policy.signature_threshold,
+                    )
+                });
+                Some(source.map_err(|error| Error::InvalidUpdateSource(format!("{error:#}")))?)
+            }
+            None if !policy.trusted_keys.is_empty() => {
+                return Err(Error::InvalidUpdateSource(
+                    "trusted_keys requires metadata_source".to_owned(),
+                ));
+            }
+            None => None,
+        };
+
+        let is_http_url = |url: &str| url.starts_with("https://") || url.starts_with("http://");
+        if let Some(mirror_url) = policy.mirror_url.as_deref().filter(|url| !is_http_url(url)) {
+            return Err(Error::InvalidUpdateSource(format!(
+                "mirror_url must be an HTTP(S) URL: {mirror_url}"
+            )));
+        }
+
+        Ok(UpdatePolicy {
+            metadata_source,
+            mirror_url: policy.mirror_url,
+        })
+    }
+}
+
+/// Settings that are overridden or locked by the administrator.
+#[derive(Debug, Default)]
+pub struct SettingsPolicy {
+    overrides: Map,
+    /// Locked values, in the form that they are serialized to in the settings.
+    locked: Map,
+    update: UpdatePolicy,
+}
+
+impl SettingsPolicy {
+    /// Load the settings policy from `settings_dir`. If there is no policy file, no settings are
+    /// enforced.
+    pub fn load(settings_dir: &Path) -> Result<Self, Error> {
+        let path = settings_dir.join(POLICY_FILENAME);
+        let policy = match std::fs::read_to_string(&path) {
+            Ok(policy) => policy,
+            Err(error) if error.kind() == io::ErrorKind::NotFound => {
+                return Ok(Self::default());
+            }
+            Err(error) => return Err(Error::Read(error)),
+        };
+        let policy = Self::parse(&policy)?;
+        log::info!("Loaded settings policy from {}", path.display());
+        Ok(policy)
+    }
+
+    fn parse(policy: &str) -> Result<Self, Error> {
+        let PolicyFile {
+            overrides,
+            locked,
+            update,
+        } = toml::from_str(policy).map_err(Error::Parse)?;
+        validate_keys(&overrides, "")?;
+        validate_keys(&locked, "")?;
+
+        let update = update
+            .map(UpdatePolicy::try_from)
+            .transpose()?
+            .unwrap_or_default();
+
+        let mut policy = SettingsPolicy {
+            overrides,
+            locked,
+            update,
+        };
+
+        // Values are compared with the serialized settings, so store them in the same form. This
+        // also rejects settings that are dropped when deserializing on this platform.
+        let enforced = policy.apply(&Settings::default())?;
+        let enforced = serde_json::to_value(&enforced).map_err(Error::Apply)?;
+        project(&policy.overrides, &enforced, "")?;
+        policy.locked = project(&policy.locked, &enforced, "")?;
+
+        Ok(policy)
+    }
+
+    /// Return `settings` with all overridden and locked settings applied.
+    pub fn apply(&self, settings: &Settings) -> Result<Settings, Error> {
+        if self.overrides.is_empty() && self.locked.is_empty() {
+            return Ok(settings.clone());
+        }
+        let mut value = serde_json::to_value(settings).map_err(Error::Apply)?;
+        merge(&mut value, &self.overrides);
+        merge(&mut value, &self.locked);
+        serde_json::from_value(value).map_err(Error::Apply)
+    }
+
+    /// Return where app updates should be fetched from.
+    pub fn update_policy(&self) -> &UpdatePolicy {
+        &self.update
+    }
+
+    /// Fail if the value of a locked setting in `settings` differs from the policy.
+    pub fn check_locked(&self, settings: &Settings) -> Result<(), Error> {
+        if self.locked.is_empty() {
+            return Ok(());
+        }
+        let value = serde_json::to_value(settings).map_err(Error::Apply)?;
+        match find_difference(&self.locked, &value, "") {
+            Some(key) => Err(Error::Locked(key)),
+            None => Ok(()),
+        }
+    }
+}
+
+fn join_path(path: &str, key: &str) -> String {
+    if path.is_empty() {
+        key.to_owned()
+    } else {
+        format!("{path}.{key}")
+    }
+}
+
+/// Reject keys that are neither in [`POLICY_KEYS`] nor a parent of a key in it.
+fn validate_keys(table: &Map, path: &str) -> Result<(), Error> {
+    for (key, value) in table {
+        let key_path = join_path(path, key);
+        if POLICY_KEYS.contains(&key_path.as_str()) {
+            continue;
+        }
+        let is_parent = POLICY_KEYS
+            .iter()
+            .any(|permitted| permitted.starts_with(&format!("{key_path}.")));
+        match value {
+            serde_json::Value::Object(table) if is_parent => validate_keys(table, &key_path)?,
+            _ => return Err(Error::ProhibitedKey(key_path)),
+        }
+    }
+    Ok(())
+}
+
+/// Replace or add the values in `table` to `value`, merging tables key by key.
+fn merge(value: &mut serde_json::Value, table: &Map) {
+    let serde_json::Value::Object(current) = value else {
+        *value = serde_json::Value::Object(table.clone());
+        return;
+    };
+    for (key, new_value) in table {
+        match new_value {
+            serde_json::Value::Object(table) => {
+                merge(current.entry(key).or_insert(serde_json::Value::Null), table);
+            }
+            _ => {
+                current.insert(key.to_owned(), new_value.clone());
+            }
+        }
+    }
+}
+
+/// Return the values in `settings` at the keys in `table`.
+fn project(table: &Map, settings: &serde_json::Value, path: &str) -> Result<Map, Error> {
+    let mut out = Map::new();
+    for (key, value) in table {
+        let key_path = join_path(path, key);
+        let settings_value = settings
+            .get(key)
+            .ok_or_else(|| Error::UnsupportedKey(key_path.clone()))?;
+        let projected = match (value, settings_value) {
+            (serde_json::Value::Object(table), serde_json::Value::Object(_)) => {
+                serde_json::Value::Object(project(table, settings_value, &key_path)?)
+            }
+            _ => settings_value.clone(),
+        };
+        out.insert(key.to_owned(), projected);
+    }
+    Ok(out)
+}
+
+fn find_difference(table: &Map, settings: &serde_json::Value, path: &str) -> Option<String> {
+    table.iter().find_map(|(key, value)| {
+        let key_path = join_path(path, key);
+        match (value, settings.get(key)) {
+            (
+                serde_json::Value::Object(table),
+                Some(settings_value @ serde_json::Value::Object(_)),
+            ) => find_difference(table, settings_value, &key_path),
+            (value, settings_value) if settings_value == Some(value) => None,
+            _ => Some(key_path),
+        }
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use mullvad_types::relay_constraints::SelectedObfuscation;
+
+    const POLICY: &str = r#"
+        [override.obfuscation_settings]
+        selected_obfuscation = "off"
+
+        [locked]
+        allow_lan = true
+
+        [locked.tunnel_options.dns_options.default_options]
+        block_ads = true
+    "#;
+
+    #[test]
+    fn test_apply_policy() {
+        let policy = SettingsPolicy::parse(POLICY).unwrap();
+        let mut settings = policy.apply(&Settings::default()).unwrap();
+
+        assert_eq!(
+            settings.obfuscation_settings.selected_obfuscation,
+            SelectedObfuscation::Off
+        );
+        assert!(settings.allow_lan);
+        assert!(
+            settings
+                .tunnel_options
+                .dns_options
+                .default_options
+                .block_ads
+        );
+        policy.check_locked(&settings).unwrap();
+
+        // Overridden and unlocked settings may be changed
+        settings.obfuscation_settings.selected_obfuscation = SelectedObfuscation::Auto;
+        let dns_options = &mut settings.tunnel_options.dns_options.default_options;
+        dns_options.block_malware = true;
+        policy.check_locked(&settings).unwrap();
+
+        let dns_options = &mut settings.tunnel_options.dns_options.default_options;
+        dns_options.block_ads = false;
+        assert!(matches!(
+            policy.check_locked(&settings),
+            Err(Error::Locked(key)) if key == "tunnel_options.dns_options.default_options.block_ads"
+        ));
+    }
+
+    #[test]
+    fn test_prohibited_key() {
+        let policy = "[locked.tunnel_options]\nenable_ipv6 = true";
+        assert!(matches!(
+            SettingsPolicy::parse(policy),
+            Err(Error::ProhibitedKey(key)) if key == "tunnel_options.enable_ipv6"
+        ));
+    }
+
+    #[test]
+    fn test_update_policy() {
+        const KEY: &str = "bb4ef63ffdcc6bd5a19c30cd23b9de03099407a04463418f17ae338b98aa09d4";
+
+        let policy = SettingsPolicy::parse(&format!(
+            r#"
+            [update]
+            metadata_source = "https://updates.example.com/mullvad/linux.json"
+            trusted_keys = ["{KEY}"]
+            mirror_url = "https://updates.example.com/mullvad"
+            "#
+        ))
+        .unwrap();
+        let update = policy.update_policy();
+        let source = update.metadata_source.as_ref().unwrap();
+        assert_eq!(source.threshold, 1);
+        assert_eq!(source.trusted_keys.first().to_string(), KEY);
+        assert_eq!(
+            update.mirror_url.as_deref(),
+            Some("https://updates.example.com/mullvad")
+        );
+
+        // A threshold that cannot be reached must be rejected
+        let policy = format!(
+            r#"
+            [update]
+            metadata_source = "https://updates.example.com/mullvad/linux.json"
+            trusted_keys = ["{KEY}"]
+            signature_threshold = 2
+            "#
+        );
+        assert!(matches!(
+            SettingsPolicy::parse(&policy),
+            Err(Error::InvalidUpdateSource(_))
+        ));
+    }
+}
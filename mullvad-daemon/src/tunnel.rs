@@ -18,7 +18,10 @@ use talpid_types::net::{TunnelParameters, obfuscation::ObfuscatorConfig, wiregua
 
 use talpid_types::{ErrorExt, net::IpAvailability, tunnel::ParameterGenerationError};
 
-use crate::device::{AccountManagerHandle, Error as DeviceError, PrivateAccountAndDevice};
+use crate::{
+    device::{AccountManagerHandle, Error as DeviceError, PrivateAccountAndDevice},
+    metrics::Metrics,
+};
 
 #[derive(thiserror::Error, Debug)]
 pub enum Error {
@@ -42,6 +45,7 @@ struct InnerParametersGenerator {
     relay_selector: RelaySelector,
     tunnel_options: TunnelOptions,
     account_manager: AccountManagerHandle,
+    metrics: Metrics,
 
     last_generated_relays: Option<LastSelectedRelays>,
 }
@@ -52,12 +56,14 @@ impl ParametersGenerator {
         account_manager: AccountManagerHandle,
         relay_selector: RelaySelector,
         tunnel_options: TunnelOptions,
+        metrics: Metrics,
     ) -> Self {
         Self(Arc::new(Mutex::new(InnerParametersGenerator {
             tunnel_options,
             relay_selector,
 
             account_manager,
+            metrics,
 
             last_generated_relays: None,
         })))
@@ -143,10 +149,16 @@ impl InnerParametersGenerator {
         retry_attempt: u32,
         ip_availability: IpAvailability,
     ) -> Result<TunnelParameters, Error> {
+        if retry_attempt > 0 {
+            self.record_failed_relays();
+        }
+
         let data = self.device().await?;
         let selected_relay = self
             .relay_selector
-            .get_relay(retry_attempt as usize, ip_availability)?;
+            .get_relay(retry_attempt as usize, ip_availability);
+        self.metrics.on_relay_selection(selected_relay.is_ok());
+        let selected_relay = selected_relay?;
 
         match selected_relay {
             #[cfg(not(target_os = "android"))]
@@ -211,6 +223,23 @@ impl InnerParametersGenerator {
         }
     }
 
+    /// Tell the relay selector that connecting using the last generated relays failed, so that
+    /// they are less likely to be selected for the next attempt.
+    fn record_failed_relays(&self) {
+        let failed_relays: Vec<&Relay> = match &self.last_generated_relays {
+            Some(LastSelectedRelays::WireGuard {
+                wg_entry, wg_exit, ..
+            }) => wg_entry.iter().chain(Some(wg_exit)).collect(),
+            #[cfg(not(target_os = "android"))]
+            Some(LastSelectedRelays::OpenVpn { relay, .. }) => vec![relay],
+            None => vec![],
+        };
+        for relay in failed_relays {
+            log::debug!("Recording connection failure for {}", relay.hostname);
+            self.relay_selector.record_relay_failure(&relay.hostname);
+        }
+    }
+
     #[cfg(not(target_os = "android"))]
     fn create_openvpn_tunnel_parameters(
         &self,
@@ -259,6 +288,7 @@ impl InnerParametersGenerator {
                 .into_talpid_tunnel_options(),
             generic_options: self.tunnel_options.generic.clone(),
             obfuscation: obfuscator_config,
+            fallback_peers: vec![],
         }
         .into()
     }
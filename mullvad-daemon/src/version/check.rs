@@ -7,7 +7,9 @@ use mullvad_api::{
     availability::ApiAvailability, rest::MullvadRestHandle, version::AppVersionProxy,
 };
 
-use mullvad_update::version::VersionInfo;
+#[cfg(in_app_upgrade)]
+use mullvad_update::metadata_version::MetadataVersionStore;
+use mullvad_update::{source::CustomMetadataSource, version::VersionInfo};
 use mullvad_version::Version;
 use serde::{Deserialize, Serialize};
 use std::{
@@ -26,6 +28,15 @@ use tokio::{fs::File, io::AsyncReadExt};
 use super::Error;
 
 const VERSION_INFO_FILENAME: &str = "version-info.json";
+/// File containing the highest accepted metadata version. This is kept separate from the version
+/// cache, since the cache is discarded when the app is upgraded.
+#[cfg(in_app_upgrade)]
+const METADATA_VERSION_FILENAME: &str = "metadata-version";
+/// Directory containing the version cache and metadata version for each custom metadata source.
+/// Metadata versions from different sources are unrelated, so a version accepted from one source
+/// must not cause newer metadata from another source to be rejected, or the other way around.
+#[cfg(in_app_upgrade)]
+const METADATA_SOURCES_DIRNAME: &str = "metadata-sources";
 
 static APP_VERSION: LazyLock<Version> =
     LazyLock::new(|| Version::from_str(mullvad_version::VERSION).unwrap());
@@ -67,19 +78,52 @@ struct VersionUpdaterInner {
     last_app_version_info: Option<(VersionCache, SystemTime)>,
     /// Oneshot channels for responding to [VersionUpdaterCommand::GetVersionInfo].
     get_version_info_responders: Vec<oneshot::Sender<VersionCache>>,
+    /// The highest metadata version that has ever been accepted.
+    #[cfg(in_app_upgrade)]
+    highest_metadata_version: usize,
 }
 
 impl VersionUpdater {
+    #[cfg_attr(not(in_app_upgrade), allow(unused_variables))]
     pub(super) async fn spawn(
         mut api_handle: MullvadRestHandle,
         availability_handle: ApiAvailability,
         cache_dir: PathBuf,
         update_sender: mpsc::UnboundedSender<VersionCache>,
         refresh_rx: mpsc::UnboundedReceiver<()>,
+        metadata_source: Option<CustomMetadataSource>,
+        package_format: Option<mullvad_update::format::PackageFormat>,
     ) {
+        #[cfg(in_app_upgrade)]
+        let cache_dir = match &metadata_source {
+            Some(source) => {
+                let source_dir = cache_dir.join(METADATA_SOURCES_DIRNAME).join(source.id());
+                if let Err(error) = tokio::fs::create_dir_all(&source_dir).await {
+                    log::error!(
+                        "{}",
+                        error.display_chain_with_msg(
+                            "Failed to create cache directory for metadata source"
+                        )
+                    );
+                }
+                source_dir
+            }
+            None => cache_dir,
+        };
+
         // load the last known AppVersionInfo from cache
         let last_app_version_info = load_cache(&cache_dir).await;
 
+        #[cfg(in_app_upgrade)]
+        let metadata_version_store =
+            MetadataVersionStore::new(cache_dir.join(METADATA_VERSION_FILENAME));
+        #[cfg(in_app_upgrade)]
+        let highest_metadata_version = metadata_version_store
+            .load()
+            .await
+            .inspect_err(|error| log::error!("Failed to load metadata version: {error:#}"))
+            .unwrap_or(mullvad_update::version::MIN_VERIFY_METADATA_VERSION);
+
         api_handle.factory = api_handle.factory.default_timeout(DOWNLOAD_TIMEOUT);
         let version_proxy = AppVersionProxy::new(api_handle);
         let cache_path = cache_dir.join(VERSION_INFO_FILENAME);
@@ -89,17 +133,25 @@ impl VersionUpdater {
             VersionUpdaterInner {
                 last_app_version_info,
                 get_version_info_responders: vec![],
+                #[cfg(in_app_upgrade)]
+                highest_metadata_version,
             }
             .run(
                 refresh_rx,
                 UpdateContext {
                     cache_path,
+                    #[cfg(in_app_upgrade)]
+                    metadata_version_store,
                     update_sender,
                 },
                 ApiContext {
                     api_handle: availability_handle,
                     version_proxy,
                     platform_version,
+                    #[cfg(in_app_upgrade)]
+                    metadata_source,
+                    #[cfg(in_app_upgrade)]
+                    package_format,
                 },
             ),
         );
@@ -121,6 +173,7 @@ impl VersionUpdaterInner {
             // important to prevent downgrade attacks.
             .map(|(info, _)| info.metadata_version)
             .unwrap_or(mullvad_update::version::MIN_VERIFY_METADATA_VERSION)
+            .max(self.highest_metadata_version)
     }
 
     #[cfg(not(in_app_upgrade))]
@@ -138,12 +191,23 @@ impl VersionUpdaterInner {
     ) {
         #[cfg(in_app_upgrade)]
         if let Some((current_cache, _)) = self.last_app_version_info.as_ref() {
-            if current_cache.metadata_version == new_version_info.metadata_version {
+            // Version info that did not come from the version metadata is never ignored
+            if current_cache.metadata_version == new_version_info.metadata_version
+                && new_version_info.metadata_version
+                    != mullvad_update::version::MIN_VERIFY_METADATA_VERSION
+            {
                 log::trace!("Ignoring version info with same metadata version");
                 new_version_info = current_cache.clone();
             }
         }
 
+        #[cfg(in_app_upgrade)]
+        {
+            self.highest_metadata_version = self
+                .highest_metadata_version
+                .max(new_version_info.metadata_version);
+        }
+
         if let Err(err) = update(new_version_info.clone()).await {
             log::error!("Failed to save version cache to disk: {}", err);
         }
@@ -290,20 +354,33 @@ impl VersionUpdaterInner {
 
 struct UpdateContext {
     cache_path: PathBuf,
+    #[cfg(in_app_upgrade)]
+    metadata_version_store: MetadataVersionStore,
     update_sender: mpsc::UnboundedSender<VersionCache>,
 }
 
 impl UpdateContext {
     /// Write [VersionUpdaterInner::last_app_version_info], if any, to the cache file
-    /// ([VERSION_INFO_FILENAME]). Also, notify `self.update_sender`
+    /// ([VERSION_INFO_FILENAME]), and persist its metadata version ([METADATA_VERSION_FILENAME]).
+    /// Also, notify `self.update_sender`
     fn update(
         &self,
         last_app_version: VersionCache,
     ) -> impl Future<Output = Result<(), Error>> + use<> {
         let _ = self.update_sender.send(last_app_version.clone());
         let cache_path = self.cache_path.clone();
+        #[cfg(in_app_upgrade)]
+        let metadata_version_store = self.metadata_version_store.clone();
 
         async move {
+            #[cfg(in_app_upgrade)]
+            if let Err(error) = metadata_version_store
+                .store(last_app_version.metadata_version)
+                .await
+            {
+                log::error!("Failed to save metadata version: {error:#}");
+            }
+
             log::debug!("Writing version check cache to {}", cache_path.display());
             let buf = serde_json::to_vec_pretty(&last_app_version).map_err(Error::Serialize)?;
             tokio::fs::write(cache_path, buf)
@@ -318,6 +395,12 @@ struct ApiContext {
     api_handle: ApiAvailability,
     version_proxy: AppVersionProxy,
     platform_version: String,
+    /// Source of version metadata configured by the settings policy, used instead of the API
+    #[cfg(in_app_upgrade)]
+    metadata_source: Option<CustomMetadataSource>,
+    /// Format of the package that the app was installed from, if any
+    #[cfg(in_app_upgrade)]
+    package_format: Option<mullvad_update::format::PackageFormat>,
 }
 
 /// Immediately query the API for the latest [AppVersionInfo].
@@ -373,7 +456,7 @@ fn do_version_check_in_background(
 fn version_check_inner(
     api: &ApiContext,
     min_metadata_version: usize,
-) -> impl Future<Output = Result<VersionCache, Error>> + use<> {
+) -> BoxFuture<'static, Result<VersionCache, Error>> {
     use mullvad_api::version::{AppVersionResponse, AppVersionResponse2};
 
     let v1_endpoint = api.version_proxy.version_check(
@@ -391,13 +474,27 @@ fn version_check_inner(
             mullvad_update::format::Architecture::Arm64
         }
     };
+    let package_format = api.package_format;
+
+    if let Some(source) = api.metadata_source.clone() {
+        let params = mullvad_update::version::VersionParameters {
+            architecture,
+            package_format,
+            rollout: mullvad_update::version::IGNORE,
+            lowest_metadata_version: min_metadata_version,
+        };
+        return Box::pin(custom_version_check(v1_endpoint, source, params));
+    }
+
     let v2_endpoint = api.version_proxy.version_check_2(
         PLATFORM,
         architecture,
+        package_format,
         mullvad_update::version::IGNORE,
         min_metadata_version,
     );
-    async move {
+    Box::pin(async move {
+        #[cfg(not(target_os = "linux"))]
         let (
             AppVersionResponse {
                 supported: current_version_supported,
@@ -409,12 +506,91 @@ fn version_check_inner(
             },
         ) = tokio::try_join!(v1_endpoint, v2_endpoint).map_err(Error::Download)?;
 
+        // Linux packages may be missing from the version metadata, and the app may not have been
+        // installed from a package at all. Fall back on the old endpoint in that case, without
+        // offering in-app upgrades.
+        #[cfg(target_os = "linux")]
+        let (
+            AppVersionResponse {
+                supported: current_version_supported,
+                ..
+            },
+            AppVersionResponse2 {
+                version_info,
+                metadata_version,
+            },
+        ) = {
+            let (v1_response, v2_response) = tokio::join!(v1_endpoint, v2_endpoint);
+            let v1_response = v1_response.map_err(Error::Download)?;
+            let v2_response = match v2_response {
+                Ok(v2_response) => v2_response,
+                Err(error) => {
+                    log::warn!(
+                        "{}",
+                        error.display_chain_with_msg(
+                            "Failed to get version metadata, in-app upgrades are unavailable"
+                        )
+                    );
+                    AppVersionResponse2 {
+                        version_info: version_info_from_v1(&v1_response)?,
+                        metadata_version: mullvad_update::version::MIN_VERIFY_METADATA_VERSION,
+                    }
+                }
+            };
+            (v1_response, v2_response)
+        };
+
         Ok(VersionCache {
             current_version_supported,
             version_info,
             metadata_version,
         })
-    }
+    })
+}
+
+/// Check for updates using version metadata from the source in the settings policy.
+///
+/// Sites that host their own metadata may be unable to reach the API, so the installed version is
+/// assumed to be supported unless the old version endpoint says otherwise.
+#[cfg(in_app_upgrade)]
+async fn custom_version_check(
+    v1_endpoint: impl Future<
+        Output = Result<mullvad_api::version::AppVersionResponse, mullvad_api::rest::Error>,
+    >,
+    source: CustomMetadataSource,
+    params: mullvad_update::version::VersionParameters,
+) -> Result<VersionCache, Error> {
+    let fetch_error = |error| {
+        Error::Download(mullvad_api::rest::Error::FetchVersions(
+            std::sync::Arc::new(error),
+        ))
+    };
+
+    let (v1_response, signed_response) = tokio::join!(
+        v1_endpoint,
+        source.get_versions(params.lowest_metadata_version)
+    );
+    let current_version_supported = match v1_response {
+        Ok(response) => response.supported,
+        Err(error) => {
+            log::debug!(
+                "{}",
+                error.display_chain_with_msg("Failed to check if the current version is supported")
+            );
+            true
+        }
+    };
+
+    let signed_response = signed_response.map_err(fetch_error)?;
+    let metadata_version = signed_response.signed.metadata_version;
+    let version_info =
+        VersionInfo::try_from_response(&params, signed_response.signed).map_err(fetch_error)?;
+
+    Ok(VersionCache {
+        current_version_supported,
+        version_info,
+        metadata_version,
+    })
 }
 
 #[cfg(not(in_app_upgrade))]
@@ -430,40 +606,48 @@ fn version_check_inner(
     );
     async move {
         let response = v1_endpoint.await.map_err(Error::Download)?;
-        let latest_stable = response.latest_stable
-            .and_then(|version| version.parse().ok())
-            // Suggested stable must actually be stable
-            .filter(|version: &mullvad_version::Version| version.pre_stable.is_none())
-            .ok_or_else(|| Error::MissingStable)?;
-        let latest_beta = response.latest_beta
-            .and_then(|version| version.parse().ok())
-            // Suggested beta must actually be non-stable
-            .filter(|version: &mullvad_version::Version| version.pre_stable.is_some());
-
         Ok(VersionCache {
             current_version_supported: response.supported,
-            // Note: We're pretending that this is complete information,
-            // but on Android and Linux, most of the information is missing
-            version_info: VersionInfo {
-                stable: mullvad_update::version::Version {
-                    version: latest_stable,
-                    changelog: "".to_owned(),
-                    urls: vec![],
-                    sha256: [0u8; 32],
-                    size: 0,
-                },
-                beta: latest_beta.map(|version| mullvad_update::version::Version {
-                    version,
-                    changelog: "".to_owned(),
-                    urls: vec![],
-                    sha256: [0u8; 32],
-                    size: 0,
-                }),
-            },
+            version_info: version_info_from_v1(&response)?,
         })
     }
 }
 
+/// Convert a response from the old version endpoint to [VersionInfo]
+#[cfg(any(not(in_app_upgrade), target_os = "linux"))]
+fn version_info_from_v1(
+    response: &mullvad_api::version::AppVersionResponse,
+) -> Result<VersionInfo, Error> {
+    let latest_stable = response.latest_stable.as_ref()
+        .and_then(|version| version.parse().ok())
+        // Suggested stable must actually be stable
+        .filter(|version: &mullvad_version::Version| version.pre_stable.is_none())
+        .ok_or_else(|| Error::MissingStable)?;
+    let latest_beta = response.latest_beta.as_ref()
+        .and_then(|version| version.parse().ok())
+        // Suggested beta must actually be non-stable
+        .filter(|version: &mullvad_version::Version| version.pre_stable.is_some());
+
+    // Note: We're pretending that this is complete information,
+    // but on Android and Linux, most of the information is missing
+    Ok(VersionInfo {
+        stable: mullvad_update::version::Version {
+            version: latest_stable,
+            changelog: "".to_owned(),
+            urls: vec![],
+            sha256: [0u8; 32],
+            size: 0,
+        },
+        beta: latest_beta.map(|version| mullvad_update::version::Version {
+            version,
+            changelog: "".to_owned(),
+            urls: vec![],
+            sha256: [0u8; 32],
+            size: 0,
+        }),
+    })
+}
+
 /// Read the app version cache from the provided directory.
 ///
 /// Returns the [AppVersionInfo] along with the modification time of the cache file,
@@ -4,9 +4,13 @@ use mullvad_types::version::{AppUpgradeDownloadProgress, AppUpgradeError, AppUpg
 use mullvad_update::app::{
     AppDownloader, AppDownloaderParameters, DownloadError, DownloadedInstaller, bin_path,
 };
+#[cfg(target_os = "linux")]
+use mullvad_update::{app::VerifiedInstaller, package::PackageInstaller};
 use rand::seq::SliceRandom;
 use std::io;
 use std::path::PathBuf;
+#[cfg(target_os = "linux")]
+use std::sync::Arc;
 use std::time::{Duration, Instant};
 use talpid_types::ErrorExt;
 use tokio::fs;
@@ -31,6 +35,10 @@ pub enum Error {
 
     #[error("Could not select URL for app update")]
     NoUrlFound,
+
+    #[cfg(target_os = "linux")]
+    #[error("The app was not installed from a .deb or .rpm package")]
+    UnknownPackageFormat,
 }
 
 pub type Result<T> = std::result::Result<T, Error>;
@@ -67,8 +75,14 @@ impl std::future::Future for DownloaderHandle {
     }
 }
 
+/// Spawn a task that downloads `version`.
+///
+/// On Linux, the package is installed using `package_installer`, which is `None` if the app was
+/// not installed from a package.
 pub fn spawn_downloader<D>(
     version: mullvad_update::version::Version,
+    mirror_url: Option<String>,
+    #[cfg(target_os = "linux")] package_installer: Option<Arc<dyn PackageInstaller>>,
     event_tx: broadcast::Sender<AppUpgradeEvent>,
 ) -> DownloaderHandle
 where
@@ -76,7 +90,13 @@ where
     D: From<AppDownloaderParameters<ProgressUpdater>>,
 {
     DownloaderHandle {
-        task: tokio::spawn(start::<D>(version, event_tx.clone())),
+        task: tokio::spawn(start::<D>(
+            version,
+            mirror_url,
+            #[cfg(target_os = "linux")]
+            package_installer,
+            event_tx.clone(),
+        )),
         dropped_tx: Some(event_tx),
     }
 }
@@ -84,13 +104,15 @@ where
 /// Begin or resume download of `version`
 async fn start<D>(
     version: mullvad_update::version::Version,
+    mirror_url: Option<String>,
+    #[cfg(target_os = "linux")] package_installer: Option<Arc<dyn PackageInstaller>>,
     event_tx: broadcast::Sender<AppUpgradeEvent>,
 ) -> Result<PathBuf>
 where
     D: AppDownloader + Send + 'static,
     D: From<AppDownloaderParameters<ProgressUpdater>>,
 {
-    let url = select_cdn_url(&version.urls)
+    let url = select_cdn_url(&version.urls, mirror_url.as_deref())
         .ok_or(Error::NoUrlFound)?
         .to_owned();
 
@@ -104,7 +126,17 @@ where
             let _ = event_tx.send(AppUpgradeEvent::Error(AppUpgradeError::GeneralError));
         })?
     };
-    let bin_path = bin_path(&version.version, &download_dir);
+    #[cfg(target_os = "linux")]
+    let package_installer = package_installer.ok_or_else(|| {
+        let _ = event_tx.send(AppUpgradeEvent::Error(AppUpgradeError::GeneralError));
+        Error::UnknownPackageFormat
+    })?;
+    let bin_path = bin_path(
+        &version.version,
+        &download_dir,
+        #[cfg(target_os = "linux")]
+        package_installer.package_format(),
+    );
 
     let params = AppDownloaderParameters {
         app_version: version.version,
@@ -113,6 +145,8 @@ where
         app_progress: ProgressUpdater::new(server_from_url(&url), event_tx.clone()),
         app_sha256: version.sha256,
         cache_dir: download_dir,
+        #[cfg(target_os = "linux")]
+        package_installer,
     };
     let downloader = D::from(params);
 
@@ -121,13 +155,26 @@ where
         let _ = event_tx.send(AppUpgradeEvent::Error(AppUpgradeError::DownloadFailed));
     })?;
     let _ = event_tx.send(AppUpgradeEvent::VerifyingInstaller);
-    installer.verify().await.inspect_err(|_| {
+    #[cfg_attr(not(target_os = "linux"), allow(unused_variables))]
+    let verified_installer = installer.verify().await.inspect_err(|_| {
         let _ = event_tx.send(AppUpgradeEvent::Error(AppUpgradeError::VerificationFailed));
     })?;
     let _ = event_tx.send(AppUpgradeEvent::VerifiedInstaller);
 
-    // Note that we cannot call `downloader.install()` here, as it must be done by the user process.
-    // Instead, the GUI is responsible for launching the installer.
+    // Note that on Windows and macOS, we cannot call `downloader.install()` here, as it must be done
+    // by the user process. Instead, the GUI is responsible for launching the installer.
+    // On Linux, the package is installed by the daemon using the package installer hook.
+    #[cfg(target_os = "linux")]
+    {
+        log::info!("Installing {}", bin_path.display());
+        verified_installer.install().await.inspect_err(|error| {
+            log::error!(
+                "{}",
+                error.display_chain_with_msg("Failed to install package")
+            );
+            let _ = event_tx.send(AppUpgradeEvent::Error(AppUpgradeError::GeneralError));
+        })?;
+    }
 
     Ok(bin_path)
 }
@@ -228,8 +275,19 @@ fn estimate_time_left(
 }
 
 /// Select a mirror to download from
-/// Currently, the selection is random
-fn select_cdn_url(urls: &[String]) -> Option<&str> {
+/// URLs that begin with `mirror_url` are preferred. Otherwise, the selection is random
+fn select_cdn_url<'a>(urls: &'a [String], mirror_url: Option<&str>) -> Option<&'a str> {
+    if let Some(mirror_url) = mirror_url {
+        let mirror_url = mirror_url.trim_end_matches('/');
+        let preferred = urls.iter().find(|url| {
+            url.strip_prefix(mirror_url)
+                .is_some_and(|path| path.starts_with('/'))
+        });
+        if let Some(url) = preferred {
+            return Some(url.as_str());
+        }
+        log::warn!("No installer URL begins with {mirror_url}, using the default URLs");
+    }
     urls.choose(&mut rand::thread_rng()).map(String::as_str)
 }
 
@@ -40,6 +40,19 @@ pub enum Error {
     UpdateAborted,
 }
 
+/// Return the format of the package that the app was installed from. Upgrades are only available
+/// if this is known.
+#[cfg(all(in_app_upgrade, target_os = "linux"))]
+async fn installed_package_format() -> Option<mullvad_update::format::PackageFormat> {
+    // This queries the package manager, which blocks
+    tokio::task::spawn_blocking(mullvad_update::package::installed_package_format)
+        .await
+        .unwrap_or_else(|error| {
+            log::error!("Failed to determine package format: {error}");
+            None
+        })
+}
+
 /// Contains the date of the git commit this was built from
 pub const COMMIT_DATE: &str = include_str!(concat!(env!("OUT_DIR"), "/git-commit-date.txt"));
 
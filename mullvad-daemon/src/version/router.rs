@@ -14,6 +14,7 @@ use talpid_types::ErrorExt;
 
 use crate::DaemonEventSender;
 use crate::management_interface::AppUpgradeBroadcast;
+use crate::settings::policy::UpdatePolicy;
 
 #[cfg(in_app_upgrade)]
 use super::downloader::ProgressUpdater;
@@ -117,6 +118,12 @@ struct VersionRouter<S = DaemonEventSender<AppVersionInfo>, D = DefaultDownloade
     app_upgrade_broadcast: AppUpgradeBroadcast,
     #[cfg(in_app_upgrade)]
     cache_dir: PathBuf,
+    /// Base URL of a mirror to prefer when downloading installers
+    #[cfg(in_app_upgrade)]
+    mirror_url: Option<String>,
+    /// Hook used to install packages, or `None` if the app was not installed from a package
+    #[cfg(all(in_app_upgrade, target_os = "linux"))]
+    package_installer: Option<std::sync::Arc<dyn mullvad_update::package::PackageInstaller>>,
     /// Type used to spawn the downloader task, replaced when testing
     _phantom: std::marker::PhantomData<D>,
 }
@@ -211,6 +218,7 @@ pub(crate) fn spawn_version_router(
     version_event_sender: DaemonEventSender<AppVersionInfo>,
     beta_program: bool,
     app_upgrade_broadcast: AppUpgradeBroadcast,
+    update_policy: UpdatePolicy,
 ) -> VersionRouterHandle {
     let (tx, rx) = mpsc::unbounded();
 
@@ -226,12 +234,19 @@ pub(crate) fn spawn_version_router(
             )
         });
 
+        #[cfg(all(in_app_upgrade, target_os = "linux"))]
+        let package_format = super::installed_package_format().await;
+        #[cfg(not(all(in_app_upgrade, target_os = "linux")))]
+        let package_format = None;
+
         VersionUpdater::spawn(
             api_handle,
             availability_handle,
             cache_dir.clone(),
             new_version_tx,
             refresh_version_check_rx,
+            update_policy.metadata_source,
+            package_format,
         )
         .await;
 
@@ -246,6 +261,10 @@ pub(crate) fn spawn_version_router(
             app_upgrade_broadcast,
             #[cfg(in_app_upgrade)]
             cache_dir,
+            #[cfg(in_app_upgrade)]
+            mirror_url: update_policy.mirror_url,
+            #[cfg(all(in_app_upgrade, target_os = "linux"))]
+            package_installer: package_format.map(mullvad_update::package::default_installer),
             refresh_version_check_tx,
             _phantom: std::marker::PhantomData::<DefaultDownloader>,
         }
@@ -446,6 +465,9 @@ where
 
                 let downloader_handle = spawn_downloader::<D>(
                     upgrading_to_version.clone(),
+                    self.mirror_url.clone(),
+                    #[cfg(target_os = "linux")]
+                    self.package_installer.clone(),
                     self.app_upgrade_broadcast.clone(),
                 );
 
@@ -747,6 +769,11 @@ mod test {
                 app_upgrade_broadcast,
                 refresh_version_check_tx,
                 cache_dir: PathBuf::new(),
+                mirror_url: None,
+                #[cfg(target_os = "linux")]
+                package_installer: Some(std::sync::Arc::new(
+                    mullvad_update::package::DpkgInstaller,
+                )),
                 _phantom: std::marker::PhantomData::<D>,
             },
             VersionRouterChannels {
@@ -1103,6 +1130,27 @@ mod test {
         );
     }
 
+    /// Test that the upgrade fails if the app was not installed from a package
+    #[cfg(target_os = "linux")]
+    #[tokio::test]
+    async fn test_upgrade_without_package() {
+        let (mut version_router, _channels) = make_version_router::<SuccessfulAppDownloader>();
+        version_router.package_installer = None;
+        version_router.on_new_version(get_new_stable_version_cache());
+
+        let mut app_upgrade_listener = version_router.app_upgrade_broadcast.subscribe();
+        version_router.update_application();
+        assert!(matches!(version_router.state, State::Downloading { .. }));
+
+        assert_eq!(version_router.run_step().await, ControlFlow::Continue(()));
+        assert_eq!(
+            app_upgrade_listener.try_recv().unwrap(),
+            AppUpgradeEvent::Error(mullvad_types::version::AppUpgradeError::GeneralError)
+        );
+        assert_eq!(app_upgrade_listener.try_recv(), Err(TryRecvError::Empty));
+        assert!(matches!(version_router.state, State::HasVersion { .. }));
+    }
+
     #[tokio::test]
     async fn test_failed_verification() {
         let (mut version_router, _channels) = make_version_router::<FailingAppVerifier>();
@@ -14,7 +14,7 @@ use std::{
 };
 
 #[cfg(target_os = "linux")]
-use talpid_types::cgroup::{SPLIT_TUNNEL_CGROUP_NAME, find_net_cls_mount};
+use talpid_types::cgroup::find_split_tunnel_cgroup;
 
 #[cfg(target_os = "linux")]
 const PROGRAM_NAME: &str = "mullvad-exclude";
@@ -40,11 +40,11 @@ enum Error {
     #[error("An argument contains interior nul bytes")]
     ArgumentNul(#[source] NulError),
 
-    #[error("Failed to find net_cls controller")]
-    FindNetClsController(#[source] io::Error),
+    #[error("Failed to find the split tunneling cgroup")]
+    FindCGroup(#[source] io::Error),
 
-    #[error("No net_cls controller")]
-    NoNetClsController,
+    #[error("No net_cls controller or cgroup v2 hierarchy")]
+    NoCGroup,
 }
 
 fn main() {
@@ -85,13 +85,11 @@ fn run() -> Result<Infallible, Error> {
         .collect::<Result<Vec<CString>, NulError>>()
         .map_err(Error::ArgumentNul)?;
 
-    let cgroup_dir = find_net_cls_mount()
-        .map_err(Error::FindNetClsController)?
-        .ok_or(Error::NoNetClsController)?;
+    let cgroup = find_split_tunnel_cgroup()
+        .map_err(Error::FindCGroup)?
+        .ok_or(Error::NoCGroup)?;
 
-    let procs_path = cgroup_dir
-        .join(SPLIT_TUNNEL_CGROUP_NAME)
-        .join("cgroup.procs");
+    let procs_path = cgroup.exclusion_path().join("cgroup.procs");
 
     let file = fs::OpenOptions::new()
         .write(true)
@@ -183,6 +183,7 @@ fn spawn_daemon(
         cache_dir,
         android_context,
         endpoint,
+        metrics_endpoint: None,
     };
 
     let running_daemon =
@@ -25,6 +25,28 @@ pub enum LeakInfo {
     AmIMullvad { ip: IpAddr },
 }
 
+impl LeakInfo {
+    /// The interface that traffic leaked on, if known.
+    pub fn interface(&self) -> Option<&Interface> {
+        match self {
+            LeakInfo::NodeReachableOnInterface { interface, .. } => Some(interface),
+            #[cfg(feature = "am-i-mullvad")]
+            LeakInfo::AmIMullvad { .. } => None,
+        }
+    }
+
+    /// Addresses that could be reached outside of the tunnel.
+    pub fn reachable_nodes(&self) -> Vec<IpAddr> {
+        match self {
+            LeakInfo::NodeReachableOnInterface {
+                reachable_nodes, ..
+            } => reachable_nodes.clone(),
+            #[cfg(feature = "am-i-mullvad")]
+            LeakInfo::AmIMullvad { ip } => vec![*ip],
+        }
+    }
+}
+
 #[derive(Clone)]
 pub enum Interface {
     Name(String),
@@ -42,6 +64,21 @@ impl From<String> for Interface {
     }
 }
 
+impl fmt::Display for Interface {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Name(name) => write!(f, "{name}"),
+
+            #[cfg(target_os = "windows")]
+            // SAFETY: u64 is valid for all bit patterns, so reading the union as a u64 is safe.
+            Self::Luid(luid) => write!(f, "LUID {}", unsafe { luid.Value }),
+
+            #[cfg(target_os = "macos")]
+            Self::Index(index) => write!(f, "index {index}"),
+        }
+    }
+}
+
 impl fmt::Debug for Interface {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
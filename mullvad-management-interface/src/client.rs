@@ -7,8 +7,11 @@ use futures::{Stream, StreamExt};
 use mullvad_types::wireguard::DaitaSettings;
 use mullvad_types::{
     access_method::AccessMethodSetting,
+    auto_connect::AutoConnectRule,
     device::{DeviceEvent, RemoveDeviceEvent},
-    relay_list::RelayList,
+    leak_check::LeakCheckResult,
+    profile::SettingsProfile,
+    relay_list::{RelayHealth, RelayList},
     settings::Settings,
     states::TunnelState,
     version::AppVersionInfo,
@@ -18,6 +21,7 @@ use mullvad_types::{
 use mullvad_types::{
     access_method::{self, AccessMethod},
     account::{AccountData, AccountNumber, VoucherSubmission},
+    connection_history::ConnectionSession,
     custom_list::{CustomList, Id},
     device::{Device, DeviceId, DeviceState},
     features::FeatureIndicators,
@@ -25,10 +29,12 @@ use mullvad_types::{
         AllowedIps, BridgeSettings, BridgeState, ObfuscationSettings, RelayOverride, RelaySettings,
     },
     settings::DnsOptions,
-    wireguard::{PublicKey, QuantumResistantState, RotationInterval},
+    wireguard::{PublicKey, QuantumResistantState, RotationInterval, TunnelStats},
 };
 #[cfg(not(target_os = "android"))]
-use std::{path::Path, str::FromStr};
+use std::{path::Path, str::FromStr, time::Duration};
+#[cfg(not(target_os = "android"))]
+use talpid_types::net::{AllowedInterface, FirewallException};
 #[cfg(target_os = "windows")]
 use talpid_types::split_tunnel::ExcludedProcess;
 #[cfg(not(target_os = "android"))]
@@ -51,6 +57,7 @@ pub enum DaemonEvent {
     Device(DeviceEvent),
     RemoveDevice(RemoveDeviceEvent),
     NewAccessMethod(AccessMethodSetting),
+    LeakDetected(LeakCheckResult),
 }
 
 impl TryFrom<types::daemon_event::Event> for DaemonEvent {
@@ -81,6 +88,9 @@ impl TryFrom<types::daemon_event::Event> for DaemonEvent {
                     .map(DaemonEvent::NewAccessMethod)
                     .map_err(Error::InvalidResponse)
             }
+            types::daemon_event::Event::LeakDetected(result) => LeakCheckResult::try_from(result)
+                .map(DaemonEvent::LeakDetected)
+                .map_err(Error::InvalidResponse),
         }
     }
 }
@@ -124,6 +134,17 @@ impl MullvadProxyClient {
         }))
     }
 
+    /// Listen for traffic statistics for the active WireGuard tunnel, sent once per `interval`.
+    pub async fn tunnel_stats_listen<'a>(
+        &mut self,
+        interval: Duration,
+    ) -> Result<impl Stream<Item = Result<TunnelStats>> + 'a> {
+        let interval = types::Duration::try_from(interval).map_err(|_| Error::DurationTooLarge)?;
+        let listener = self.0.tunnel_stats_listen(interval).await?.into_inner();
+
+        Ok(listener.map(|item| TunnelStats::try_from(item?).map_err(Error::InvalidResponse)))
+    }
+
     /// DEPRECATED: Prefer to use `prepare_restart_v2`.
     pub async fn prepare_restart(&mut self) -> Result<()> {
         self.0.prepare_restart(()).await?;
@@ -159,6 +180,17 @@ impl MullvadProxyClient {
         mullvad_types::relay_list::RelayList::try_from(list).map_err(Error::InvalidResponse)
     }
 
+    pub async fn get_relay_health(&mut self) -> Result<Vec<RelayHealth>> {
+        self.0
+            .get_relay_health(())
+            .await?
+            .into_inner()
+            .relays
+            .into_iter()
+            .map(|health| RelayHealth::try_from(health).map_err(Error::InvalidResponse))
+            .collect()
+    }
+
     pub async fn get_api_access_methods(&mut self) -> Result<Vec<AccessMethodSetting>> {
         let access_method_settings = self
             .0
@@ -260,6 +292,33 @@ impl MullvadProxyClient {
         Ok(())
     }
 
+    pub async fn add_firewall_exception(&mut self, exception: FirewallException) -> Result<()> {
+        self.0
+            .add_firewall_exception(types::FirewallException::from(exception))
+            .await?;
+        Ok(())
+    }
+
+    /// Remove the exception at `index` in [`Settings::firewall_exceptions`].
+    pub async fn remove_firewall_exception(&mut self, index: u32) -> Result<()> {
+        self.0.remove_firewall_exception(index).await?;
+        Ok(())
+    }
+
+    /// Add an interface to [`Settings::allowed_interfaces`], replacing any interface with the
+    /// same name.
+    pub async fn add_allowed_interface(&mut self, interface: AllowedInterface) -> Result<()> {
+        self.0
+            .add_allowed_interface(types::AllowedInterface::from(interface))
+            .await?;
+        Ok(())
+    }
+
+    pub async fn remove_allowed_interface(&mut self, name: String) -> Result<()> {
+        self.0.remove_allowed_interface(name).await?;
+        Ok(())
+    }
+
     pub async fn set_show_beta_releases(&mut self, state: bool) -> Result<()> {
         self.0.set_show_beta_releases(state).await?;
         Ok(())
@@ -275,6 +334,46 @@ impl MullvadProxyClient {
         Ok(())
     }
 
+    pub async fn add_auto_connect_rule(&mut self, rule: AutoConnectRule) -> Result<()> {
+        self.0
+            .add_auto_connect_rule(types::AutoConnectRule::from(rule))
+            .await?;
+        Ok(())
+    }
+
+    /// Remove the auto-connect rule at `index` in [`Settings::auto_connect_rules`].
+    pub async fn remove_auto_connect_rule(&mut self, index: u32) -> Result<()> {
+        self.0.remove_auto_connect_rule(index).await?;
+        Ok(())
+    }
+
+    pub async fn list_profiles(&mut self) -> Result<Vec<SettingsProfile>> {
+        self.0
+            .list_profiles(())
+            .await?
+            .into_inner()
+            .profiles
+            .into_iter()
+            .map(|profile| SettingsProfile::try_from(profile).map_err(Error::InvalidResponse))
+            .collect()
+    }
+
+    /// Save the current settings as the profile `name`, replacing any existing profile.
+    pub async fn save_profile(&mut self, name: String) -> Result<()> {
+        self.0.save_profile(name).await?;
+        Ok(())
+    }
+
+    pub async fn activate_profile(&mut self, name: String) -> Result<()> {
+        self.0.activate_profile(name).await?;
+        Ok(())
+    }
+
+    pub async fn delete_profile(&mut self, name: String) -> Result<()> {
+        self.0.delete_profile(name).await?;
+        Ok(())
+    }
+
     pub async fn set_openvpn_mssfix(&mut self, mssfix: Option<u16>) -> Result<()> {
         self.0
             .set_openvpn_mssfix(mssfix.map(u32::from).unwrap_or(0))
@@ -303,6 +402,15 @@ impl MullvadProxyClient {
         Ok(())
     }
 
+    pub async fn set_wireguard_backend(
+        &mut self,
+        backend: talpid_types::net::wireguard::Backend,
+    ) -> Result<()> {
+        let backend = types::WireguardBackend::from(backend);
+        self.0.set_wireguard_backend(backend).await?;
+        Ok(())
+    }
+
     #[cfg(daita)]
     pub async fn set_enable_daita(&mut self, value: bool) -> Result<()> {
         self.0.set_enable_daita(value).await?;
@@ -604,13 +712,21 @@ impl MullvadProxyClient {
 
     // check_volumes
 
-    pub async fn apply_json_settings(&mut self, blob: String) -> Result<()> {
-        self.0.apply_json_settings(blob).await?;
+    pub async fn apply_json_settings(&mut self, blob: String, allow_sensitive: bool) -> Result<()> {
+        self.0
+            .apply_json_settings(types::JsonSettingsPatch {
+                patch: blob,
+                allow_sensitive,
+            })
+            .await?;
         Ok(())
     }
 
-    pub async fn export_json_settings(&mut self) -> Result<String> {
-        let blob = self.0.export_json_settings(()).await?;
+    pub async fn export_json_settings(&mut self, include_sensitive: bool) -> Result<String> {
+        let blob = self
+            .0
+            .export_json_settings(types::ExportJsonSettingsOptions { include_sensitive })
+            .await?;
         Ok(blob.into_inner())
     }
 
@@ -620,6 +736,17 @@ impl MullvadProxyClient {
         ))
     }
 
+    pub async fn get_connection_history(&mut self) -> Result<Vec<ConnectionSession>> {
+        self.0
+            .get_connection_history(())
+            .await?
+            .into_inner()
+            .sessions
+            .into_iter()
+            .map(|session| ConnectionSession::try_from(session).map_err(Error::InvalidResponse))
+            .collect()
+    }
+
     // Debug features
     pub async fn disable_relay(&mut self, relay: String) -> Result<()> {
         self.0.disable_relay(relay).await?;
@@ -631,6 +758,22 @@ impl MullvadProxyClient {
         Ok(())
     }
 
+    pub async fn get_leak_check_history(&mut self) -> Result<Vec<LeakCheckResult>> {
+        self.0
+            .get_leak_check_history(())
+            .await?
+            .into_inner()
+            .results
+            .into_iter()
+            .map(|result| LeakCheckResult::try_from(result).map_err(Error::InvalidResponse))
+            .collect()
+    }
+
+    pub async fn run_leak_check(&mut self) -> Result<LeakCheckResult> {
+        let result = self.0.run_leak_check(()).await?.into_inner();
+        LeakCheckResult::try_from(result).map_err(Error::InvalidResponse)
+    }
+
     pub async fn set_wireguard_allowed_ips(&mut self, allowed_ips: AllowedIps) -> Result<()> {
         self.0
             .set_wireguard_allowed_ips(types::AllowedIpsList {
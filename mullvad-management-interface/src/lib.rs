@@ -1,22 +1,30 @@
 pub mod client;
 pub mod types;
 
+#[cfg(not(target_os = "android"))]
 use parity_tokio_ipc::Endpoint as IpcEndpoint;
 #[cfg(unix)]
 use std::{env, fs, os::unix::fs::PermissionsExt};
+use std::{future::Future, io};
+#[cfg(windows)]
 use std::{
-    future::Future,
-    io,
     pin::Pin,
     task::{Context, Poll},
 };
+#[cfg(windows)]
 use tokio::io::{AsyncRead, AsyncWrite, ReadBuf};
+use tonic::transport::Server;
+#[cfg(windows)]
+use tonic::transport::server::Connected;
 #[cfg(not(target_os = "android"))]
 use tonic::transport::{Endpoint, Uri};
-use tonic::transport::{Server, server::Connected};
 #[cfg(not(target_os = "android"))]
 use tower::service_fn;
 
+/// Information about the peer of a management interface connection. This includes the
+/// credentials of the peer process.
+#[cfg(unix)]
+pub use tonic::transport::server::UdsConnectInfo;
 pub use tonic::{Code, Request, Response, Status, async_trait, transport::Channel};
 
 pub type ManagementServiceClient =
@@ -145,38 +153,56 @@ pub use client::MullvadProxyClient;
 
 pub type ServerJoinHandle = tokio::task::JoinHandle<()>;
 
+/// Serve `service` on a Unix domain socket or named pipe at `rpc_socket_path`.
+///
+/// On Unix, requests carry a [`UdsConnectInfo`] extension with the credentials of the caller.
 pub fn spawn_rpc_server<T: ManagementService, F: Future<Output = ()> + Send + 'static>(
     service: T,
     abort_rx: F,
     rpc_socket_path: impl AsRef<std::path::Path>,
 ) -> std::result::Result<ServerJoinHandle, Error> {
-    use futures::stream::TryStreamExt;
-    use parity_tokio_ipc::SecurityAttributes;
-
-    let mut endpoint = IpcEndpoint::new(rpc_socket_path.as_ref().to_string_lossy().to_string());
-    endpoint.set_security_attributes(
-        SecurityAttributes::allow_everyone_create()
-            .map_err(Error::SecurityAttributes)?
-            .set_mode(0o766)
-            .map_err(Error::SecurityAttributes)?,
-    );
-    let incoming = endpoint.incoming().map_err(Error::StartServerError)?;
-
     #[cfg(unix)]
-    if let Some(group_name) = &*MULLVAD_MANAGEMENT_SOCKET_GROUP {
-        let group = nix::unistd::Group::from_name(group_name)
-            .map_err(Error::ObtainGidError)?
-            .ok_or(Error::NoGidError)?;
-        nix::unistd::chown(rpc_socket_path.as_ref(), None, Some(group.gid))
-            .map_err(Error::SetGidError)?;
-        fs::set_permissions(rpc_socket_path, PermissionsExt::from_mode(0o760))
+    let incoming = {
+        let listener = tokio::net::UnixListener::bind(rpc_socket_path.as_ref())
+            .map_err(Error::StartServerError)?;
+        fs::set_permissions(rpc_socket_path.as_ref(), PermissionsExt::from_mode(0o766))
             .map_err(Error::PermissionsError)?;
-    }
+
+        if let Some(group_name) = &*MULLVAD_MANAGEMENT_SOCKET_GROUP {
+            let group = nix::unistd::Group::from_name(group_name)
+                .map_err(Error::ObtainGidError)?
+                .ok_or(Error::NoGidError)?;
+            nix::unistd::chown(rpc_socket_path.as_ref(), None, Some(group.gid))
+                .map_err(Error::SetGidError)?;
+            fs::set_permissions(rpc_socket_path, PermissionsExt::from_mode(0o760))
+                .map_err(Error::PermissionsError)?;
+        }
+
+        tokio_stream::wrappers::UnixListenerStream::new(listener)
+    };
+
+    #[cfg(windows)]
+    let incoming = {
+        use futures::stream::TryStreamExt;
+        use parity_tokio_ipc::SecurityAttributes;
+
+        let mut endpoint = IpcEndpoint::new(rpc_socket_path.as_ref().to_string_lossy().to_string());
+        endpoint.set_security_attributes(
+            SecurityAttributes::allow_everyone_create()
+                .map_err(Error::SecurityAttributes)?
+                .set_mode(0o766)
+                .map_err(Error::SecurityAttributes)?,
+        );
+        endpoint
+            .incoming()
+            .map_err(Error::StartServerError)?
+            .map_ok(StreamBox)
+    };
 
     Ok(tokio::spawn(async move {
         if let Err(execution_error) = Server::builder()
             .add_service(ManagementServiceServer::new(service))
-            .serve_with_incoming_shutdown(incoming.map_ok(StreamBox), abort_rx)
+            .serve_with_incoming_shutdown(incoming, abort_rx)
             .await
             .map_err(Error::GrpcTransportError)
         {
@@ -186,8 +212,10 @@ pub fn spawn_rpc_server<T: ManagementService, F: Future<Output = ()> + Send + 's
     }))
 }
 
+#[cfg(windows)]
 #[derive(Debug)]
 struct StreamBox<T: AsyncRead + AsyncWrite>(pub T);
+#[cfg(windows)]
 impl<T: AsyncRead + AsyncWrite> Connected for StreamBox<T> {
     type ConnectInfo = Option<()>;
 
@@ -195,6 +223,7 @@ impl<T: AsyncRead + AsyncWrite> Connected for StreamBox<T> {
         None
     }
 }
+#[cfg(windows)]
 impl<T: AsyncRead + AsyncWrite + Unpin> AsyncRead for StreamBox<T> {
     fn poll_read(
         mut self: Pin<&mut Self>,
@@ -204,6 +233,7 @@ impl<T: AsyncRead + AsyncWrite + Unpin> AsyncRead for StreamBox<T> {
         Pin::new(&mut self.0).poll_read(cx, buf)
     }
 }
+#[cfg(windows)]
 impl<T: AsyncRead + AsyncWrite + Unpin> AsyncWrite for StreamBox<T> {
     fn poll_write(
         mut self: Pin<&mut Self>,
@@ -0,0 +1,57 @@
+use crate::types::{FromProtobufTypeError, proto};
+use mullvad_types::auto_connect::{AutoConnectAction, AutoConnectRule, NetworkMatcher};
+
+impl From<AutoConnectRule> for proto::AutoConnectRule {
+    fn from(rule: AutoConnectRule) -> Self {
+        use proto::auto_connect_rule::{Action, Network};
+
+        let network = match rule.network {
+            NetworkMatcher::Ssid(ssid) => Network::Ssid(ssid),
+            NetworkMatcher::GatewayMac(mac) => Network::GatewayMac(mac),
+            NetworkMatcher::Interface(interface) => Network::Interface(interface),
+        };
+        let action = match rule.action {
+            AutoConnectAction::Connect => Action::Connect,
+            AutoConnectAction::Disconnect => Action::Disconnect,
+            AutoConnectAction::Block => Action::Block,
+        };
+
+        proto::AutoConnectRule {
+            network: Some(network),
+            action: i32::from(action),
+        }
+    }
+}
+
+impl TryFrom<proto::AutoConnectRule> for AutoConnectRule {
+    type Error = FromProtobufTypeError;
+
+    fn try_from(rule: proto::AutoConnectRule) -> Result<Self, Self::Error> {
+        use proto::auto_connect_rule::{Action, Network};
+
+        let network = match rule.network {
+            Some(Network::Ssid(ssid)) => NetworkMatcher::Ssid(ssid),
+            Some(Network::GatewayMac(mac)) => NetworkMatcher::gateway_mac(&mac).ok_or(
+                FromProtobufTypeError::InvalidArgument("invalid gateway MAC address"),
+            )?,
+            Some(Network::Interface(interface)) => NetworkMatcher::Interface(interface),
+            None => {
+                return Err(FromProtobufTypeError::InvalidArgument(
+                    "missing auto-connect rule network",
+                ));
+            }
+        };
+        let action = match Action::try_from(rule.action) {
+            Ok(Action::Connect) => AutoConnectAction::Connect,
+            Ok(Action::Disconnect) => AutoConnectAction::Disconnect,
+            Ok(Action::Block) => AutoConnectAction::Block,
+            Err(_) => {
+                return Err(FromProtobufTypeError::InvalidArgument(
+                    "invalid auto-connect rule action",
+                ));
+            }
+        };
+
+        Ok(AutoConnectRule { network, action })
+    }
+}
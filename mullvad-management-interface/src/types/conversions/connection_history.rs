@@ -0,0 +1,91 @@
+use crate::types::{self, FromProtobufTypeError, proto};
+use chrono::{DateTime, Utc};
+use mullvad_types::{
+    connection_history::{ConnectionSession, SessionEndReason},
+    features::FeatureIndicators,
+    location::GeoIpLocation,
+};
+use proto::connection_session::EndReason;
+
+impl From<ConnectionSession> for proto::ConnectionSession {
+    fn from(session: ConnectionSession) -> Self {
+        let to_timestamp = |time: DateTime<Utc>| types::Timestamp {
+            seconds: time.timestamp(),
+            nanos: time.timestamp_subsec_nanos() as i32,
+        };
+
+        proto::ConnectionSession {
+            start: Some(to_timestamp(session.start)),
+            end: session.end.map(to_timestamp),
+            location: session.location.map(proto::GeoIpLocation::from),
+            obfuscation: session.obfuscation.map(|obfuscation| {
+                i32::from(proto::obfuscation_endpoint::ObfuscationType::from(
+                    obfuscation,
+                ))
+            }),
+            feature_indicators: Some(proto::FeatureIndicators::from(session.feature_indicators)),
+            rx_bytes: session.rx_bytes,
+            tx_bytes: session.tx_bytes,
+            reconnects: session.reconnects,
+            end_reason: session.end_reason.map(|reason| match reason {
+                SessionEndReason::Disconnected => {
+                    EndReason::Disconnected(proto::connection_session::Disconnected {})
+                }
+                SessionEndReason::Error(error_state) => {
+                    EndReason::Error(proto::ErrorState::from(error_state))
+                }
+                SessionEndReason::Interrupted => {
+                    EndReason::Interrupted(proto::connection_session::Interrupted {})
+                }
+            }),
+        }
+    }
+}
+
+impl TryFrom<proto::ConnectionSession> for ConnectionSession {
+    type Error = FromProtobufTypeError;
+
+    fn try_from(session: proto::ConnectionSession) -> Result<Self, FromProtobufTypeError> {
+        let from_timestamp = |time: types::Timestamp| {
+            DateTime::from_timestamp(time.seconds, time.nanos as u32)
+                .ok_or(FromProtobufTypeError::InvalidArgument("invalid timestamp"))
+        };
+
+        let start = session
+            .start
+            .ok_or(FromProtobufTypeError::InvalidArgument("missing start time"))?;
+        let obfuscation = session
+            .obfuscation
+            .map(|obfuscation| {
+                proto::obfuscation_endpoint::ObfuscationType::try_from(obfuscation)
+                    .map(talpid_types::net::ObfuscationType::from)
+                    .map_err(|_| FromProtobufTypeError::InvalidArgument("invalid obfuscation type"))
+            })
+            .transpose()?;
+        let end_reason = session
+            .end_reason
+            .map(|reason| match reason {
+                EndReason::Disconnected(_) => Ok(SessionEndReason::Disconnected),
+                EndReason::Error(error_state) => Ok(SessionEndReason::Error(
+                    talpid_types::tunnel::ErrorState::try_from(error_state)?,
+                )),
+                EndReason::Interrupted(_) => Ok(SessionEndReason::Interrupted),
+            })
+            .transpose()?;
+
+        Ok(ConnectionSession {
+            start: from_timestamp(start)?,
+            end: session.end.map(from_timestamp).transpose()?,
+            location: session.location.map(GeoIpLocation::try_from).transpose()?,
+            obfuscation,
+            feature_indicators: session
+                .feature_indicators
+                .map(FeatureIndicators::from)
+                .unwrap_or_default(),
+            rx_bytes: session.rx_bytes,
+            tx_bytes: session.tx_bytes,
+            reconnects: session.reconnects,
+            end_reason,
+        })
+    }
+}
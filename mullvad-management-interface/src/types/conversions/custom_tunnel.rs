@@ -155,3 +155,52 @@ impl From<mullvad_types::ConnectionConfig> for proto::ConnectionConfig {
         }
     }
 }
+
+impl TryFrom<proto::FallbackPeer> for mullvad_types::FallbackPeer {
+    type Error = FromProtobufTypeError;
+
+    fn try_from(peer: proto::FallbackPeer) -> Result<Self, Self::Error> {
+        let port = u16::try_from(peer.port)
+            .map_err(|_| FromProtobufTypeError::InvalidArgument("invalid peer port"))?;
+        let psk = peer
+            .psk
+            .map(|psk| {
+                wireguard::PresharedKey::try_from(psk.as_slice())
+                    .map_err(|_| FromProtobufTypeError::InvalidArgument("invalid psk"))
+            })
+            .transpose()?;
+        let allowed_ips = peer
+            .allowed_ips
+            .iter()
+            .map(|address| {
+                address
+                    .parse()
+                    .map_err(|_| FromProtobufTypeError::InvalidArgument("invalid address"))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(mullvad_types::FallbackPeer {
+            host: peer.host,
+            port,
+            public_key: bytes_to_pubkey(&peer.public_key)?,
+            psk,
+            allowed_ips,
+        })
+    }
+}
+
+impl From<mullvad_types::FallbackPeer> for proto::FallbackPeer {
+    fn from(peer: mullvad_types::FallbackPeer) -> Self {
+        Self {
+            host: peer.host,
+            port: u32::from(peer.port),
+            public_key: peer.public_key.as_bytes().to_vec(),
+            psk: peer.psk.map(|psk| psk.as_bytes().to_vec()),
+            allowed_ips: peer
+                .allowed_ips
+                .iter()
+                .map(|address| address.to_string())
+                .collect(),
+        }
+    }
+}
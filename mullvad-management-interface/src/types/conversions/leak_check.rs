@@ -0,0 +1,66 @@
+use crate::types;
+use chrono::DateTime;
+use mullvad_types::leak_check::{LeakCheckResult, LeakInfo};
+
+use super::{FromProtobufTypeError, arg_from_str};
+
+impl From<LeakInfo> for types::LeakInfo {
+    fn from(info: LeakInfo) -> Self {
+        types::LeakInfo {
+            interface: info.interface,
+            reachable_nodes: info
+                .reachable_nodes
+                .iter()
+                .map(ToString::to_string)
+                .collect(),
+        }
+    }
+}
+
+impl TryFrom<types::LeakInfo> for LeakInfo {
+    type Error = FromProtobufTypeError;
+
+    fn try_from(info: types::LeakInfo) -> Result<Self, FromProtobufTypeError> {
+        let reachable_nodes = info
+            .reachable_nodes
+            .iter()
+            .map(|node| arg_from_str(node, "invalid reachable node address"))
+            .collect::<Result<_, _>>()?;
+
+        Ok(LeakInfo {
+            interface: info.interface,
+            reachable_nodes,
+        })
+    }
+}
+
+impl From<LeakCheckResult> for types::LeakCheckResult {
+    fn from(result: LeakCheckResult) -> Self {
+        types::LeakCheckResult {
+            timestamp: Some(types::Timestamp {
+                seconds: result.timestamp.timestamp(),
+                nanos: result.timestamp.timestamp_subsec_nanos() as i32,
+            }),
+            relay: result.relay.to_string(),
+            leak: result.leak.map(types::LeakInfo::from),
+        }
+    }
+}
+
+impl TryFrom<types::LeakCheckResult> for LeakCheckResult {
+    type Error = FromProtobufTypeError;
+
+    fn try_from(result: types::LeakCheckResult) -> Result<Self, FromProtobufTypeError> {
+        let timestamp = result
+            .timestamp
+            .ok_or(FromProtobufTypeError::InvalidArgument("missing timestamp"))?;
+        let timestamp = DateTime::from_timestamp(timestamp.seconds, timestamp.nanos as u32)
+            .ok_or(FromProtobufTypeError::InvalidArgument("invalid timestamp"))?;
+
+        Ok(LeakCheckResult {
+            timestamp,
+            relay: arg_from_str(&result.relay, "invalid relay address")?,
+            leak: result.leak.map(LeakInfo::try_from).transpose()?,
+        })
+    }
+}
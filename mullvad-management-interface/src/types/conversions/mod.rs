@@ -2,12 +2,16 @@ use std::str::FromStr;
 
 mod access_method;
 mod account;
+mod auto_connect;
+mod connection_history;
 mod custom_list;
 mod custom_tunnel;
 mod device;
 mod features;
+mod leak_check;
 mod location;
 mod net;
+mod profile;
 pub mod relay_constraints;
 mod relay_list;
 mod settings;
@@ -49,9 +49,14 @@ impl From<talpid_types::net::TunnelEndpoint> for proto::TunnelEndpoint {
                 address: entry.address.to_string(),
                 protocol: i32::from(proto::TransportProtocol::from(entry.protocol)),
             }),
-            tunnel_metadata: endpoint
-                .tunnel_interface
-                .map(|tunnel_interface| proto::TunnelMetadata { tunnel_interface }),
+            tunnel_metadata: endpoint.tunnel_interface.map(|tunnel_interface| {
+                proto::TunnelMetadata {
+                    tunnel_interface,
+                    wireguard_backend: endpoint
+                        .wireguard_backend
+                        .map(proto::WireguardBackend::from),
+                }
+            }),
             #[cfg(daita)]
             daita: endpoint.daita,
             #[cfg(not(daita))]
@@ -149,7 +154,13 @@ impl TryFrom<proto::TunnelEndpoint> for talpid_types::net::TunnelEndpoint {
                 .transpose()?,
             tunnel_interface: endpoint
                 .tunnel_metadata
-                .map(|tunnel_metadata| tunnel_metadata.tunnel_interface),
+                .as_ref()
+                .map(|tunnel_metadata| tunnel_metadata.tunnel_interface.clone()),
+            wireguard_backend: endpoint
+                .tunnel_metadata
+                .and_then(|tunnel_metadata| tunnel_metadata.wireguard_backend)
+                .map(talpid_types::net::wireguard::Backend::try_from)
+                .transpose()?,
             #[cfg(daita)]
             daita: endpoint.daita,
         })
@@ -192,6 +203,93 @@ impl From<proto::IpVersion> for talpid_types::net::IpVersion {
     }
 }
 
+impl From<talpid_types::net::ObfuscationType> for proto::obfuscation_endpoint::ObfuscationType {
+    fn from(obfuscation_type: talpid_types::net::ObfuscationType) -> Self {
+        use talpid_types::net::ObfuscationType;
+        match obfuscation_type {
+            ObfuscationType::Udp2Tcp => Self::Udp2tcp,
+            ObfuscationType::Shadowsocks => Self::Shadowsocks,
+            ObfuscationType::Quic => Self::Quic,
+        }
+    }
+}
+
+impl From<proto::obfuscation_endpoint::ObfuscationType> for talpid_types::net::ObfuscationType {
+    fn from(obfuscation_type: proto::obfuscation_endpoint::ObfuscationType) -> Self {
+        use proto::obfuscation_endpoint::ObfuscationType;
+        match obfuscation_type {
+            ObfuscationType::Udp2tcp => Self::Udp2Tcp,
+            ObfuscationType::Shadowsocks => Self::Shadowsocks,
+            ObfuscationType::Quic => Self::Quic,
+        }
+    }
+}
+
+impl From<talpid_types::net::FirewallException> for proto::FirewallException {
+    fn from(exception: talpid_types::net::FirewallException) -> Self {
+        proto::FirewallException {
+            network: exception.network.to_string(),
+            protocol: exception
+                .protocol
+                .map(|protocol| i32::from(proto::TransportProtocol::from(protocol))),
+            port: exception.port.map(u32::from),
+            interface: exception.interface,
+        }
+    }
+}
+
+impl TryFrom<proto::FirewallException> for talpid_types::net::FirewallException {
+    type Error = FromProtobufTypeError;
+
+    fn try_from(exception: proto::FirewallException) -> Result<Self, Self::Error> {
+        Ok(talpid_types::net::FirewallException {
+            network: exception
+                .network
+                .parse()
+                .map_err(|_| FromProtobufTypeError::InvalidArgument("invalid network"))?,
+            protocol: exception
+                .protocol
+                .map(try_transport_protocol_from_i32)
+                .transpose()?,
+            port: exception
+                .port
+                .map(u16::try_from)
+                .transpose()
+                .map_err(|_| FromProtobufTypeError::InvalidArgument("invalid port"))?,
+            interface: exception.interface,
+        })
+    }
+}
+
+impl From<talpid_types::net::AllowedInterface> for proto::AllowedInterface {
+    fn from(interface: talpid_types::net::AllowedInterface) -> Self {
+        proto::AllowedInterface {
+            name: interface.name,
+            networks: interface
+                .networks
+                .iter()
+                .map(|network| network.to_string())
+                .collect(),
+        }
+    }
+}
+
+impl TryFrom<proto::AllowedInterface> for talpid_types::net::AllowedInterface {
+    type Error = FromProtobufTypeError;
+
+    fn try_from(interface: proto::AllowedInterface) -> Result<Self, Self::Error> {
+        Ok(talpid_types::net::AllowedInterface {
+            name: interface.name,
+            networks: interface
+                .networks
+                .iter()
+                .map(|network| network.parse())
+                .collect::<Result<Vec<_>, _>>()
+                .map_err(|_| FromProtobufTypeError::InvalidArgument("invalid network"))?,
+        })
+    }
+}
+
 pub fn try_tunnel_type_from_i32(
     tunnel_type: i32,
 ) -> Result<talpid_types::net::TunnelType, FromProtobufTypeError> {
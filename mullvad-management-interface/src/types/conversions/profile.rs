@@ -0,0 +1,51 @@
+use crate::types::{FromProtobufTypeError, proto};
+use mullvad_types::{
+    profile::SettingsProfile,
+    relay_constraints::{ObfuscationSettings, RelaySettings},
+    settings::TunnelOptions,
+};
+
+impl From<SettingsProfile> for proto::SettingsProfile {
+    fn from(profile: SettingsProfile) -> Self {
+        proto::SettingsProfile {
+            name: profile.name,
+            relay_settings: Some(proto::RelaySettings::from(profile.relay_settings)),
+            obfuscation_settings: Some(proto::ObfuscationSettings::from(
+                profile.obfuscation_settings,
+            )),
+            tunnel_options: Some(proto::TunnelOptions::from(&profile.tunnel_options)),
+        }
+    }
+}
+
+impl TryFrom<proto::SettingsProfile> for SettingsProfile {
+    type Error = FromProtobufTypeError;
+
+    fn try_from(profile: proto::SettingsProfile) -> Result<Self, Self::Error> {
+        let relay_settings =
+            profile
+                .relay_settings
+                .ok_or(FromProtobufTypeError::InvalidArgument(
+                    "missing relay settings",
+                ))?;
+        let obfuscation_settings =
+            profile
+                .obfuscation_settings
+                .ok_or(FromProtobufTypeError::InvalidArgument(
+                    "missing obfuscation settings",
+                ))?;
+        let tunnel_options =
+            profile
+                .tunnel_options
+                .ok_or(FromProtobufTypeError::InvalidArgument(
+                    "missing tunnel options",
+                ))?;
+
+        Ok(SettingsProfile {
+            name: profile.name,
+            relay_settings: RelaySettings::try_from(relay_settings)?,
+            obfuscation_settings: ObfuscationSettings::try_from(obfuscation_settings)?,
+            tunnel_options: TunnelOptions::try_from(tunnel_options)?,
+        })
+    }
+}
@@ -8,7 +8,7 @@ use mullvad_types::{
     },
 };
 use std::str::FromStr;
-use talpid_types::net::proxy::CustomProxy;
+use talpid_types::net::{proxy::CustomProxy, wireguard};
 
 impl TryFrom<&proto::WireguardConstraints>
     for mullvad_types::relay_constraints::WireguardConstraints
@@ -94,10 +94,34 @@ impl TryFrom<proto::RelaySettings> for mullvad_types::relay_constraints::RelaySe
                         "missing relay connection config",
                     ))?;
                 let config = mullvad_types::ConnectionConfig::try_from(config)?;
+                let psk = settings
+                    .wireguard_psk
+                    .map(|psk| {
+                        wireguard::PresharedKey::try_from(psk.as_slice())
+                            .map_err(|_| FromProtobufTypeError::InvalidArgument("invalid psk"))
+                    })
+                    .transpose()?;
+                let dns_servers = settings
+                    .dns_servers
+                    .iter()
+                    .map(|server| {
+                        server.parse().map_err(|_| {
+                            FromProtobufTypeError::InvalidArgument("invalid DNS server")
+                        })
+                    })
+                    .collect::<Result<Vec<_>, _>>()?;
+                let fallback_peers = settings
+                    .fallback_peers
+                    .into_iter()
+                    .map(mullvad_types::FallbackPeer::try_from)
+                    .collect::<Result<Vec<_>, _>>()?;
                 Ok(mullvad_constraints::RelaySettings::CustomTunnelEndpoint(
                     CustomTunnelEndpoint {
                         host: settings.host,
                         config,
+                        psk,
+                        dns_servers,
+                        fallback_peers,
                     },
                 ))
             }
@@ -110,6 +134,12 @@ impl TryFrom<proto::RelaySettings> for mullvad_types::relay_constraints::RelaySe
                 let providers = try_providers_constraint_from_proto(&settings.providers)?;
                 let ownership = try_ownership_constraint_from_i32(settings.ownership)?;
                 let tunnel_protocol = try_tunnel_type_from_i32(settings.tunnel_type)?;
+                let selection_strategy =
+                    proto::SelectionStrategy::try_from(settings.selection_strategy)
+                        .map(mullvad_constraints::SelectionStrategy::from)
+                        .map_err(|_| {
+                            FromProtobufTypeError::InvalidArgument("invalid selection strategy")
+                        })?;
 
                 let openvpn_constraints =
                     mullvad_constraints::OpenVpnConstraints::try_from(
@@ -131,6 +161,7 @@ impl TryFrom<proto::RelaySettings> for mullvad_types::relay_constraints::RelaySe
                         tunnel_protocol,
                         wireguard_constraints,
                         openvpn_constraints,
+                        selection_strategy,
                     },
                 ))
             }
@@ -241,6 +272,17 @@ impl From<mullvad_types::relay_constraints::RelaySettings> for proto::RelaySetti
                 relay_settings::Endpoint::Custom(proto::CustomRelaySettings {
                     host: endpoint.host,
                     config: Some(proto::ConnectionConfig::from(endpoint.config)),
+                    wireguard_psk: endpoint.psk.map(|psk| psk.as_bytes().to_vec()),
+                    dns_servers: endpoint
+                        .dns_servers
+                        .iter()
+                        .map(|server| server.to_string())
+                        .collect(),
+                    fallback_peers: endpoint
+                        .fallback_peers
+                        .into_iter()
+                        .map(proto::FallbackPeer::from)
+                        .collect(),
                 })
             }
             MullvadRelaySettings::Normal(constraints) => {
@@ -252,6 +294,9 @@ impl From<mullvad_types::relay_constraints::RelaySettings> for proto::RelaySetti
                     providers: convert_providers_constraint(&constraints.providers),
                     ownership: convert_ownership_constraint(&constraints.ownership) as i32,
                     tunnel_type: constraints.tunnel_protocol as i32,
+                    selection_strategy: i32::from(proto::SelectionStrategy::from(
+                        constraints.selection_strategy,
+                    )),
 
                     wireguard_constraints: Some(proto::WireguardConstraints {
                         port: constraints
@@ -621,6 +666,26 @@ pub fn ownership_constraint_from_proto(
     }
 }
 
+impl From<mullvad_types::relay_constraints::SelectionStrategy> for proto::SelectionStrategy {
+    fn from(strategy: mullvad_types::relay_constraints::SelectionStrategy) -> Self {
+        use mullvad_types::relay_constraints::SelectionStrategy;
+        match strategy {
+            SelectionStrategy::Random => proto::SelectionStrategy::Random,
+            SelectionStrategy::LowestLatency => proto::SelectionStrategy::LowestLatency,
+        }
+    }
+}
+
+impl From<proto::SelectionStrategy> for mullvad_types::relay_constraints::SelectionStrategy {
+    fn from(strategy: proto::SelectionStrategy) -> Self {
+        use mullvad_types::relay_constraints::SelectionStrategy;
+        match strategy {
+            proto::SelectionStrategy::Random => SelectionStrategy::Random,
+            proto::SelectionStrategy::LowestLatency => SelectionStrategy::LowestLatency,
+        }
+    }
+}
+
 fn convert_providers_constraint(
     providers: &Constraint<mullvad_types::relay_constraints::Providers>,
 ) -> Vec<String> {
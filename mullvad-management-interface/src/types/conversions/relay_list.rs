@@ -459,3 +459,41 @@ impl TryFrom<proto::PortRange> for RangeInclusive<u16> {
         Ok(first..=last)
     }
 }
+
+impl From<mullvad_types::relay_list::RelayHealth> for proto::RelayHealth {
+    fn from(health: mullvad_types::relay_list::RelayHealth) -> Self {
+        proto::RelayHealth {
+            hostname: health.hostname,
+            failures: health.failures,
+            last_failure: Some(crate::types::Timestamp {
+                seconds: health.last_failure.timestamp(),
+                nanos: health.last_failure.timestamp_subsec_nanos() as i32,
+            }),
+            weight_factor: health.weight_factor,
+        }
+    }
+}
+
+impl TryFrom<proto::RelayHealth> for mullvad_types::relay_list::RelayHealth {
+    type Error = FromProtobufTypeError;
+
+    fn try_from(health: proto::RelayHealth) -> Result<Self, Self::Error> {
+        let last_failure = health
+            .last_failure
+            .ok_or(FromProtobufTypeError::InvalidArgument(
+                "missing last failure",
+            ))?;
+        let last_failure =
+            chrono::DateTime::from_timestamp(last_failure.seconds, last_failure.nanos as u32)
+                .ok_or(FromProtobufTypeError::InvalidArgument(
+                    "invalid last failure",
+                ))?;
+
+        Ok(mullvad_types::relay_list::RelayHealth {
+            hostname: health.hostname,
+            failures: health.failures,
+            last_failure,
+            weight_factor: health.weight_factor,
+        })
+    }
+}
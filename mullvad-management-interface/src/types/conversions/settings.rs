@@ -3,7 +3,6 @@ use mullvad_types::settings::CURRENT_SETTINGS_VERSION;
 use talpid_types::ErrorExt;
 impl From<&mullvad_types::settings::Settings> for proto::Settings {
     fn from(settings: &mullvad_types::settings::Settings) -> Self {
-        #[cfg(any(windows, target_os = "android", target_os = "macos"))]
         let split_tunnel = {
             let apps = settings
                 .split_tunnel
@@ -23,9 +22,6 @@ impl From<&mullvad_types::settings::Settings> for proto::Settings {
                 apps,
             })
         };
-        #[cfg(target_os = "linux")]
-        let split_tunnel = None;
-
         Self {
             relay_settings: Some(proto::RelaySettings::from(settings.get_relay_settings())),
             bridge_settings: Some(proto::BridgeSettings::from(
@@ -57,6 +53,24 @@ impl From<&mullvad_types::settings::Settings> for proto::Settings {
                 .map(proto::RelayOverride::from)
                 .collect(),
             recents: settings.recents.clone().map(proto::Recents::from),
+            auto_connect_rules: settings
+                .auto_connect_rules
+                .iter()
+                .cloned()
+                .map(proto::AutoConnectRule::from)
+                .collect(),
+            firewall_exceptions: settings
+                .firewall_exceptions
+                .iter()
+                .cloned()
+                .map(proto::FirewallException::from)
+                .collect(),
+            allowed_interfaces: settings
+                .allowed_interfaces
+                .iter()
+                .cloned()
+                .map(proto::AllowedInterface::from)
+                .collect(),
         }
     }
 }
@@ -85,11 +99,98 @@ impl From<&mullvad_types::settings::DnsOptions> for proto::DnsOptions {
                     .iter()
                     .map(|addr| addr.to_string())
                     .collect(),
+                encrypted_servers: options
+                    .custom_options
+                    .encrypted_servers
+                    .iter()
+                    .map(proto::EncryptedDnsServer::from)
+                    .collect(),
             }),
+            domain_rules: options
+                .domain_rules
+                .iter()
+                .map(proto::DomainDnsRule::from)
+                .collect(),
+        }
+    }
+}
+
+impl From<&mullvad_types::settings::DomainDnsRule> for proto::DomainDnsRule {
+    fn from(rule: &mullvad_types::settings::DomainDnsRule) -> Self {
+        proto::DomainDnsRule {
+            domain: rule.domain.clone(),
+            servers: rule.servers.iter().map(|addr| addr.to_string()).collect(),
+        }
+    }
+}
+
+impl TryFrom<proto::DomainDnsRule> for mullvad_types::settings::DomainDnsRule {
+    type Error = FromProtobufTypeError;
+
+    fn try_from(rule: proto::DomainDnsRule) -> Result<Self, Self::Error> {
+        let servers = rule
+            .servers
+            .into_iter()
+            .map(|addr| {
+                addr.parse()
+                    .map_err(|_| FromProtobufTypeError::InvalidArgument("invalid IP address"))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        mullvad_types::settings::DomainDnsRule::new(&rule.domain, servers)
+            .map_err(|_| FromProtobufTypeError::InvalidArgument("invalid domain for DNS rule"))
+    }
+}
+
+impl From<&mullvad_types::settings::EncryptedDnsServer> for proto::EncryptedDnsServer {
+    fn from(server: &mullvad_types::settings::EncryptedDnsServer) -> Self {
+        use mullvad_types::settings::EncryptedDnsProtocol;
+        use proto::encrypted_dns_server::Protocol;
+
+        let protocol = match server.protocol {
+            EncryptedDnsProtocol::Https => Protocol::Https,
+            EncryptedDnsProtocol::Tls => Protocol::Tls,
+        };
+        proto::EncryptedDnsServer {
+            protocol: i32::from(protocol),
+            address: server.address.to_string(),
+            port: u32::from(server.port),
+            hostname: server.hostname.clone(),
         }
     }
 }
 
+impl TryFrom<proto::EncryptedDnsServer> for mullvad_types::settings::EncryptedDnsServer {
+    type Error = FromProtobufTypeError;
+
+    fn try_from(server: proto::EncryptedDnsServer) -> Result<Self, Self::Error> {
+        use mullvad_types::settings::EncryptedDnsProtocol;
+        use proto::encrypted_dns_server::Protocol;
+
+        let protocol = match Protocol::try_from(server.protocol) {
+            Ok(Protocol::Https) => EncryptedDnsProtocol::Https,
+            Ok(Protocol::Tls) => EncryptedDnsProtocol::Tls,
+            Err(_) => {
+                return Err(FromProtobufTypeError::InvalidArgument(
+                    "invalid encrypted DNS protocol",
+                ));
+            }
+        };
+        let address = server
+            .address
+            .parse()
+            .map_err(|_| FromProtobufTypeError::InvalidArgument("invalid IP address"))?;
+        let port = u16::try_from(server.port)
+            .map_err(|_| FromProtobufTypeError::InvalidArgument("invalid port"))?;
+
+        Ok(mullvad_types::settings::EncryptedDnsServer {
+            protocol,
+            address,
+            port,
+            hostname: server.hostname,
+        })
+    }
+}
+
 impl From<&mullvad_types::settings::TunnelOptions> for proto::TunnelOptions {
     fn from(options: &mullvad_types::settings::TunnelOptions) -> Self {
         Self {
@@ -107,6 +208,7 @@ impl From<&mullvad_types::settings::TunnelOptions> for proto::TunnelOptions {
                 daita: Some(proto::DaitaSettings::from(options.wireguard.daita.clone())),
                 #[cfg(not(daita))]
                 daita: None,
+                backend: Some(proto::WireguardBackend::from(options.wireguard.backend)),
             }),
             generic: Some(proto::tunnel_options::GenericOptions {
                 enable_ipv6: options.generic.enable_ipv6,
@@ -162,7 +264,6 @@ impl TryFrom<proto::Settings> for mullvad_types::settings::Settings {
                 .ok_or(FromProtobufTypeError::InvalidArgument(
                     "missing api access methods settings",
                 ))?;
-        #[cfg(any(windows, target_os = "android", target_os = "macos"))]
         let split_tunnel = settings
             .split_tunnel
             .ok_or(FromProtobufTypeError::InvalidArgument(
@@ -181,6 +282,21 @@ impl TryFrom<proto::Settings> for mullvad_types::settings::Settings {
             #[cfg(not(target_os = "android"))]
             block_when_disconnected: settings.block_when_disconnected,
             auto_connect: settings.auto_connect,
+            auto_connect_rules: settings
+                .auto_connect_rules
+                .into_iter()
+                .map(mullvad_types::auto_connect::AutoConnectRule::try_from)
+                .collect::<Result<Vec<_>, _>>()?,
+            firewall_exceptions: settings
+                .firewall_exceptions
+                .into_iter()
+                .map(talpid_types::net::FirewallException::try_from)
+                .collect::<Result<Vec<_>, _>>()?,
+            allowed_interfaces: settings
+                .allowed_interfaces
+                .into_iter()
+                .map(talpid_types::net::AllowedInterface::try_from)
+                .collect::<Result<Vec<_>, _>>()?,
             tunnel_options: mullvad_types::settings::TunnelOptions::try_from(tunnel_options)?,
             relay_overrides: settings
                 .relay_overrides
@@ -188,7 +304,6 @@ impl TryFrom<proto::Settings> for mullvad_types::settings::Settings {
                 .map(mullvad_types::relay_constraints::RelayOverride::try_from)
                 .collect::<Result<Vec<_>, _>>()?,
             show_beta_releases: settings.show_beta_releases,
-            #[cfg(any(windows, target_os = "android", target_os = "macos"))]
             split_tunnel: mullvad_types::settings::SplitTunnelSettings::from(split_tunnel),
             obfuscation_settings: mullvad_types::relay_constraints::ObfuscationSettings::try_from(
                 obfuscation_settings,
@@ -203,6 +318,8 @@ impl TryFrom<proto::Settings> for mullvad_types::settings::Settings {
                 api_access_methods_settings,
             )?,
             recents: Some(vec![]),
+            // NOTE: Profiles are not part of the settings message. Use `ListProfiles` instead.
+            profiles: vec![],
         })
     }
 }
@@ -224,7 +341,6 @@ pub fn try_bridge_state_from_i32(
     }
 }
 
-#[cfg(any(windows, target_os = "android", target_os = "macos"))]
 impl From<proto::SplitTunnelSettings> for mullvad_types::settings::SplitTunnelSettings {
     fn from(value: proto::SplitTunnelSettings) -> Self {
         use mullvad_types::settings::{SplitApp, SplitTunnelSettings};
@@ -295,6 +411,11 @@ impl TryFrom<proto::TunnelOptions> for mullvad_types::settings::TunnelOptions {
                     .ok_or(FromProtobufTypeError::InvalidArgument(
                         "missing daita settings",
                     ))?,
+                backend: wireguard_options
+                    .backend
+                    .map(talpid_types::net::wireguard::Backend::try_from)
+                    .transpose()?
+                    .unwrap_or_default(),
             },
             generic: net::GenericTunnelOptions {
                 enable_ipv6: generic_options.enable_ipv6,
@@ -357,7 +478,17 @@ impl TryFrom<proto::DnsOptions> for mullvad_types::settings::DnsOptions {
                         })
                     })
                     .collect::<Result<Vec<_>, _>>()?,
+                encrypted_servers: custom_options
+                    .encrypted_servers
+                    .into_iter()
+                    .map(mullvad_types::settings::EncryptedDnsServer::try_from)
+                    .collect::<Result<Vec<_>, _>>()?,
             },
+            domain_rules: options
+                .domain_rules
+                .into_iter()
+                .map(mullvad_types::settings::DomainDnsRule::try_from)
+                .collect::<Result<Vec<_>, _>>()?,
         })
     }
 }
@@ -73,6 +73,37 @@ impl TryFrom<proto::QuantumResistantState> for mullvad_types::wireguard::Quantum
     }
 }
 
+impl From<talpid_types::net::wireguard::Backend> for proto::WireguardBackend {
+    fn from(backend: talpid_types::net::wireguard::Backend) -> Self {
+        use talpid_types::net::wireguard::Backend;
+        let backend = match backend {
+            Backend::Auto => proto::wireguard_backend::Backend::Auto,
+            Backend::Kernel => proto::wireguard_backend::Backend::Kernel,
+            Backend::WireguardGo => proto::wireguard_backend::Backend::WireguardGo,
+            Backend::Boringtun => proto::wireguard_backend::Backend::Boringtun,
+        };
+        proto::WireguardBackend {
+            backend: i32::from(backend),
+        }
+    }
+}
+
+impl TryFrom<proto::WireguardBackend> for talpid_types::net::wireguard::Backend {
+    type Error = FromProtobufTypeError;
+
+    fn try_from(backend: proto::WireguardBackend) -> Result<Self, Self::Error> {
+        match proto::wireguard_backend::Backend::try_from(backend.backend) {
+            Ok(proto::wireguard_backend::Backend::Auto) => Ok(Self::Auto),
+            Ok(proto::wireguard_backend::Backend::Kernel) => Ok(Self::Kernel),
+            Ok(proto::wireguard_backend::Backend::WireguardGo) => Ok(Self::WireguardGo),
+            Ok(proto::wireguard_backend::Backend::Boringtun) => Ok(Self::Boringtun),
+            Err(_) => Err(FromProtobufTypeError::InvalidArgument(
+                "invalid WireGuard backend",
+            )),
+        }
+    }
+}
+
 #[cfg(daita)]
 impl From<mullvad_types::wireguard::DaitaSettings> for proto::DaitaSettings {
     fn from(settings: mullvad_types::wireguard::DaitaSettings) -> Self {
@@ -92,3 +123,68 @@ impl From<proto::DaitaSettings> for mullvad_types::wireguard::DaitaSettings {
         }
     }
 }
+
+impl From<mullvad_types::wireguard::PeerStats> for proto::PeerStats {
+    fn from(stats: mullvad_types::wireguard::PeerStats) -> Self {
+        proto::PeerStats {
+            public_key: stats.public_key.as_bytes().to_vec(),
+            tx_bytes: stats.tx_bytes,
+            rx_bytes: stats.rx_bytes,
+            tx_rate: stats.tx_rate,
+            rx_rate: stats.rx_rate,
+            last_handshake: stats.last_handshake.map(|time| Timestamp {
+                seconds: time.timestamp(),
+                nanos: time.timestamp_subsec_nanos() as i32,
+            }),
+        }
+    }
+}
+
+impl TryFrom<proto::PeerStats> for mullvad_types::wireguard::PeerStats {
+    type Error = FromProtobufTypeError;
+
+    fn try_from(stats: proto::PeerStats) -> Result<Self, Self::Error> {
+        let last_handshake = stats
+            .last_handshake
+            .map(|time| {
+                DateTime::from_timestamp(time.seconds, time.nanos as u32)
+                    .ok_or(FromProtobufTypeError::InvalidArgument("invalid timestamp"))
+            })
+            .transpose()?;
+
+        Ok(mullvad_types::wireguard::PeerStats {
+            public_key: super::bytes_to_pubkey(&stats.public_key)?,
+            tx_bytes: stats.tx_bytes,
+            rx_bytes: stats.rx_bytes,
+            tx_rate: stats.tx_rate,
+            rx_rate: stats.rx_rate,
+            last_handshake,
+        })
+    }
+}
+
+impl From<mullvad_types::wireguard::TunnelStats> for proto::TunnelStats {
+    fn from(stats: mullvad_types::wireguard::TunnelStats) -> Self {
+        proto::TunnelStats {
+            entry: stats.entry.map(proto::PeerStats::from),
+            exit: stats.exit.map(proto::PeerStats::from),
+        }
+    }
+}
+
+impl TryFrom<proto::TunnelStats> for mullvad_types::wireguard::TunnelStats {
+    type Error = FromProtobufTypeError;
+
+    fn try_from(stats: proto::TunnelStats) -> Result<Self, Self::Error> {
+        Ok(mullvad_types::wireguard::TunnelStats {
+            entry: stats
+                .entry
+                .map(mullvad_types::wireguard::PeerStats::try_from)
+                .transpose()?,
+            exit: stats
+                .exit
+                .map(mullvad_types::wireguard::PeerStats::try_from)
+                .transpose()?,
+        })
+    }
+}
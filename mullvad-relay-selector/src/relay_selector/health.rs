@@ -0,0 +1,103 @@
+//! Memory of relays that recently failed to connect, so that reconnect attempts are less likely to
+//! land on the same broken relay.
+//!
+//! Each failure is forgotten gradually: its contribution to the penalty of a relay is halved every
+//! [`FAILURE_HALF_LIFE`], and it is dropped completely after [`FAILURE_MEMORY`].
+
+use chrono::{DateTime, Utc};
+use mullvad_types::relay_list::{Relay, RelayHealth};
+use std::{collections::HashMap, time::Duration};
+
+/// Time it takes for the penalty of a single failure to be halved.
+pub const FAILURE_HALF_LIFE: Duration = Duration::from_secs(5 * 60);
+
+/// Failures older than this are forgotten.
+pub const FAILURE_MEMORY: Duration = Duration::from_secs(30 * 60);
+
+/// The weight of a relay is multiplied by this factor for each recent failure.
+const FAILURE_PENALTY: f64 = 0.1;
+
+/// Relay weights are multiplied by this before being penalized, to retain precision.
+const WEIGHT_SCALE: u64 = 1000;
+
+/// Recent connection failures, keyed by hostname.
+#[derive(Debug, Default, Clone)]
+pub struct RelayFailures {
+    failures: HashMap<String, Vec<DateTime<Utc>>>,
+}
+
+impl RelayFailures {
+    /// Remember that connecting to `hostname` just failed.
+    pub fn insert(&mut self, hostname: &str) {
+        self.failures
+            .entry(hostname.to_owned())
+            .or_default()
+            .push(Utc::now());
+    }
+
+    /// Returns the weight of `relay` after penalizing recent failures. The weight is scaled up by
+    /// [`WEIGHT_SCALE`] so that relays with small weights can be penalized too. Relays with a
+    /// non-zero weight keep a weight of at least 1, so that they can still be picked if nothing
+    /// else works.
+    pub fn weight(&self, relay: &Relay) -> u64 {
+        if relay.weight == 0 {
+            return 0;
+        }
+        let factor = self.weight_factor(&relay.hostname);
+        let weight = relay.weight.saturating_mul(WEIGHT_SCALE);
+        ((weight as f64 * factor) as u64).max(1)
+    }
+
+    /// Returns the factor, between 0.0 and 1.0, that the weight of the relay with the given
+    /// hostname is multiplied by due to recent failures.
+    pub fn weight_factor(&self, hostname: &str) -> f64 {
+        self.weight_factor_at(hostname, Utc::now())
+    }
+
+    /// Forget failures that no longer affect the weight of any relay.
+    pub fn prune(&mut self) {
+        let now = Utc::now();
+        self.failures.retain(|_hostname, failures| {
+            failures.retain(|failure| age(*failure, now) < FAILURE_MEMORY);
+            !failures.is_empty()
+        });
+    }
+
+    /// Returns the remembered failures for each relay.
+    pub fn health(&self) -> Vec<RelayHealth> {
+        let now = Utc::now();
+        self.failures
+            .iter()
+            .filter_map(|(hostname, failures)| {
+                let recent = failures
+                    .iter()
+                    .filter(|failure| age(**failure, now) < FAILURE_MEMORY);
+                Some(RelayHealth {
+                    hostname: hostname.clone(),
+                    failures: u32::try_from(recent.clone().count()).unwrap_or(u32::MAX),
+                    last_failure: recent.max().copied()?,
+                    weight_factor: self.weight_factor_at(hostname, now),
+                })
+            })
+            .collect()
+    }
+
+    fn weight_factor_at(&self, hostname: &str, now: DateTime<Utc>) -> f64 {
+        let Some(failures) = self.failures.get(hostname) else {
+            return 1.0;
+        };
+        let penalty: f64 = failures
+            .iter()
+            .map(|failure| age(*failure, now))
+            .filter(|age| *age < FAILURE_MEMORY)
+            .map(|age| 0.5f64.powf(age.as_secs_f64() / FAILURE_HALF_LIFE.as_secs_f64()))
+            .sum();
+        FAILURE_PENALTY.powf(penalty)
+    }
+}
+
+/// Time since `failure`. Failures in the future, due to the clock being changed, are treated as
+/// having just happened.
+fn age(failure: DateTime<Utc>, now: DateTime<Utc>) -> Duration {
+    (now - failure).to_std().unwrap_or_default()
+}
@@ -8,7 +8,7 @@ use std::{
 use mullvad_types::{
     constraints::Constraint,
     endpoint::MullvadWireguardEndpoint,
-    relay_constraints::{ShadowsocksSettings, Udp2TcpObfuscationSettings},
+    relay_constraints::{SelectionStrategy, ShadowsocksSettings, Udp2TcpObfuscationSettings},
     relay_list::Relay,
 };
 use rand::{
@@ -18,6 +18,7 @@ use rand::{
 };
 use talpid_types::net::{IpVersion, obfuscation::ObfuscatorConfig};
 
+use super::{health::RelayFailures, latency::RelayLatencies};
 use crate::SelectedObfuscator;
 
 /// Port ranges available for WireGuard relays that have extra IPs for Shadowsocks.
@@ -34,15 +35,72 @@ pub enum Error {
 pub fn pick_random_relay_excluding<'a>(
     relays: &'a [Relay],
     exclude: &'_ Relay,
+    failures: &RelayFailures,
 ) -> Option<&'a Relay> {
     let filtered_relays = relays.iter().filter(|&a| a != exclude);
-    pick_random_relay_weighted(filtered_relays, |relay: &Relay| relay.weight)
+    pick_random_relay_weighted(filtered_relays, |relay: &Relay| failures.weight(relay))
 }
 
 /// Picks a relay using [pick_random_relay_weighted], using the `weight` member of each relay
-/// as the weight function.
-pub fn pick_random_relay(relays: &[Relay]) -> Option<&Relay> {
-    pick_random_relay_weighted(relays.iter(), |relay| relay.weight)
+/// as the weight function. Relays that recently failed to connect are down-weighted.
+pub fn pick_random_relay<'a>(relays: &'a [Relay], failures: &RelayFailures) -> Option<&'a Relay> {
+    pick_random_relay_weighted(relays.iter(), |relay| failures.weight(relay))
+}
+
+/// Picks a relay from `relays` according to `strategy`.
+pub fn pick_relay<'a>(
+    relays: &'a [Relay],
+    strategy: SelectionStrategy,
+    latencies: &RelayLatencies,
+    failures: &RelayFailures,
+) -> Option<&'a Relay> {
+    match strategy {
+        SelectionStrategy::Random => pick_random_relay(relays, failures),
+        SelectionStrategy::LowestLatency => {
+            pick_lowest_latency_relay(relays.iter(), latencies, failures)
+                .or_else(|| pick_random_relay(relays, failures))
+        }
+    }
+}
+
+/// Picks a relay from `relays` according to `strategy`, but don't pick `exclude`.
+pub fn pick_relay_excluding<'a>(
+    relays: &'a [Relay],
+    exclude: &'_ Relay,
+    strategy: SelectionStrategy,
+    latencies: &RelayLatencies,
+    failures: &RelayFailures,
+) -> Option<&'a Relay> {
+    match strategy {
+        SelectionStrategy::Random => pick_random_relay_excluding(relays, exclude, failures),
+        SelectionStrategy::LowestLatency => {
+            let filtered_relays = relays.iter().filter(|&relay| relay != exclude);
+            pick_lowest_latency_relay(filtered_relays, latencies, failures)
+                .or_else(|| pick_random_relay_excluding(relays, exclude, failures))
+        }
+    }
+}
+
+/// Picks the relay with the lowest measured latency. Returns `None` if the latency of none of the
+/// relays has been measured recently.
+///
+/// The latency of relays that recently failed to connect is inflated by the same factor that
+/// their weight is reduced by, so that a broken relay is not picked again right away.
+fn pick_lowest_latency_relay<'a>(
+    relays: impl Iterator<Item = &'a Relay>,
+    latencies: &RelayLatencies,
+    failures: &RelayFailures,
+) -> Option<&'a Relay> {
+    relays
+        .filter_map(|relay| {
+            let rtt = latencies.get(&relay.hostname)?;
+            // The factor approaches 0 for relays with many recent failures, so dividing a
+            // `Duration` by it could overflow
+            let factor = failures.weight_factor(&relay.hostname);
+            Some((relay, rtt.as_secs_f64() / factor))
+        })
+        .min_by(|(_, a), (_, b)| a.total_cmp(b))
+        .map(|(relay, _rtt)| relay)
 }
 
 /// Pick a random relay from the given slice. Will return `None` if the given slice is empty.
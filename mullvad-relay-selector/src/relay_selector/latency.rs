@@ -0,0 +1,62 @@
+//! Round-trip times to relays, used to pick relays with
+//! [`SelectionStrategy::LowestLatency`](mullvad_types::relay_constraints::SelectionStrategy).
+//!
+//! The relay selector never measures anything itself. Measurements are reported by whoever probes
+//! the relays, and are forgotten once they are older than [`MAX_MEASUREMENT_AGE`].
+
+use std::{
+    collections::HashMap,
+    time::{Duration, Instant},
+};
+
+/// Measurements older than this are not used when selecting relays, and the relays should be
+/// probed again.
+pub const MAX_MEASUREMENT_AGE: Duration = Duration::from_secs(30 * 60);
+
+#[derive(Debug, Clone, Copy)]
+struct Measurement {
+    rtt: Duration,
+    measured_at: Instant,
+}
+
+impl Measurement {
+    fn is_fresh(&self) -> bool {
+        self.measured_at.elapsed() < MAX_MEASUREMENT_AGE
+    }
+}
+
+/// Cache of the most recently measured round-trip time to each relay, keyed by hostname.
+#[derive(Debug, Default, Clone)]
+pub struct RelayLatencies {
+    measurements: HashMap<String, Measurement>,
+}
+
+impl RelayLatencies {
+    /// Remember that the round-trip time to `hostname` was `rtt`.
+    pub fn insert(&mut self, hostname: &str, rtt: Duration) {
+        let measurement = Measurement {
+            rtt,
+            measured_at: Instant::now(),
+        };
+        self.measurements.insert(hostname.to_owned(), measurement);
+    }
+
+    /// Returns the round-trip time to `hostname`, unless it has not been measured recently.
+    pub fn get(&self, hostname: &str) -> Option<Duration> {
+        self.measurements
+            .get(hostname)
+            .filter(|measurement| measurement.is_fresh())
+            .map(|measurement| measurement.rtt)
+    }
+
+    /// Returns whether `hostname` should be probed, i.e. whether it lacks a recent measurement.
+    pub fn needs_probe(&self, hostname: &str) -> bool {
+        self.get(hostname).is_none()
+    }
+
+    /// Forget measurements that are too old to be used.
+    pub fn prune(&mut self) {
+        self.measurements
+            .retain(|_hostname, measurement| measurement.is_fresh());
+    }
+}
@@ -1,13 +1,17 @@
 //! The implementation of the relay selector.
 
 pub mod detailer;
+mod health;
 mod helpers;
+mod latency;
 pub mod matcher;
 mod parsed_relays;
 pub mod query;
 pub mod relays;
 
 use detailer::resolve_ip_version;
+use health::RelayFailures;
+use latency::RelayLatencies;
 use matcher::{filter_matching_bridges, filter_matching_relay_list};
 use parsed_relays::ParsedRelays;
 use relays::{Multihop, Singlehop, WireguardConfig};
@@ -32,16 +36,17 @@ use mullvad_types::{
     relay_constraints::{
         BridgeSettings, BridgeState, InternalBridgeConstraints, ObfuscationSettings,
         OpenVpnConstraints, RelayConstraints, RelayOverride, RelaySettings, ResolvedBridgeSettings,
-        WireguardConstraints,
+        SelectionStrategy, WireguardConstraints,
     },
-    relay_list::{Relay, RelayEndpointData, RelayList},
+    relay_list::{Relay, RelayEndpointData, RelayHealth, RelayList},
     settings::Settings,
     wireguard::QuantumResistantState,
 };
 use std::{
+    net::IpAddr,
     path::Path,
     sync::{Arc, LazyLock, Mutex},
-    time::SystemTime,
+    time::{Duration, SystemTime},
 };
 use talpid_types::{
     ErrorExt,
@@ -108,6 +113,8 @@ pub static OPENVPN_RETRY_ORDER: LazyLock<Vec<RelayQuery>> = LazyLock::new(|| {
 pub struct RelaySelector {
     config: Arc<Mutex<SelectorConfig>>,
     parsed_relays: Arc<Mutex<ParsedRelays>>,
+    latencies: Arc<Mutex<RelayLatencies>>,
+    failures: Arc<Mutex<RelayFailures>>,
 }
 
 #[derive(Clone)]
@@ -390,14 +397,16 @@ impl<'a> TryFrom<NormalSelectorConfig<'a>> for RelayQuery {
             *value.bridge_state,
             value.bridge_settings.clone(),
         );
-        RelayQuery::new(
+        let mut query = RelayQuery::new(
             value.user_preferences.location.clone(),
             value.user_preferences.providers.clone(),
             value.user_preferences.ownership,
             value.user_preferences.tunnel_protocol,
             wireguard_constraints,
             openvpn_constraints,
-        )
+        )?;
+        query.set_selection_strategy(value.user_preferences.selection_strategy);
+        Ok(query)
     }
 }
 
@@ -428,6 +437,8 @@ impl RelaySelector {
         RelaySelector {
             config: Arc::new(Mutex::new(config)),
             parsed_relays: Arc::new(Mutex::new(unsynchronized_parsed_relays)),
+            latencies: Arc::new(Mutex::new(RelayLatencies::default())),
+            failures: Arc::new(Mutex::new(RelayFailures::default())),
         }
     }
 
@@ -439,6 +450,8 @@ impl RelaySelector {
                 &config.relay_overrides,
             ))),
             config: Arc::new(Mutex::new(config)),
+            latencies: Arc::new(Mutex::new(RelayLatencies::default())),
+            failures: Arc::new(Mutex::new(RelayFailures::default())),
         }
     }
 
@@ -473,6 +486,101 @@ impl RelaySelector {
         self.parsed_relays.lock().unwrap().last_updated()
     }
 
+    /// Remember the measured round-trip time to the relay with the given hostname. This is used
+    /// to pick relays when the selection strategy is [`SelectionStrategy::LowestLatency`].
+    pub fn set_relay_latency(&self, hostname: &str, rtt: Duration) {
+        self.latencies.lock().unwrap().insert(hostname, rtt);
+    }
+
+    /// Returns the recently measured round-trip time to the relay with the given public address,
+    /// if any.
+    pub fn relay_latency_by_address(&self, address: IpAddr) -> Option<Duration> {
+        let parsed_relays = self.parsed_relays.lock().unwrap();
+        let relay = parsed_relays.relays().find(|relay| {
+            IpAddr::from(relay.ipv4_addr_in) == address
+                || relay.ipv6_addr_in.map(IpAddr::from) == Some(address)
+        })?;
+        self.latencies.lock().unwrap().get(&relay.hostname)
+    }
+
+    /// Returns the address of the WireGuard relays inside the tunnel.
+    pub fn wireguard_gateway(&self, ip_version: IpVersion) -> IpAddr {
+        let parsed_relays = self.parsed_relays.lock().unwrap();
+        let endpoint_data = &parsed_relays.parsed_list().wireguard;
+        match ip_version {
+            IpVersion::V4 => IpAddr::from(endpoint_data.ipv4_gateway),
+            IpVersion::V6 => IpAddr::from(endpoint_data.ipv6_gateway),
+        }
+    }
+
+    /// Remember that connecting to the relay with the given hostname failed. The relay will be
+    /// less likely to be picked until the failure has been forgotten.
+    pub fn record_relay_failure(&self, hostname: &str) {
+        let mut failures = self.failures.lock().unwrap();
+        failures.prune();
+        failures.insert(hostname);
+    }
+
+    /// Returns the relays that recently failed to connect, and how that affects their weight.
+    pub fn relay_health(&self) -> Vec<RelayHealth> {
+        self.failures.lock().unwrap().health()
+    }
+
+    /// Returns the WireGuard relays that may be picked using the current settings, and whose
+    /// latency has not been measured recently. For multihop, these are the entry relays, since
+    /// the entry relay is the one that is reached directly. This includes entry relays that may
+    /// be picked automatically when DAITA is used.
+    ///
+    /// Each relay is returned along with the address to measure the latency to, which is its IPv6
+    /// address if IPv6 is required by the constraints, and its IPv4 address otherwise.
+    ///
+    /// Nothing is returned unless the selection strategy is
+    /// [`SelectionStrategy::LowestLatency`], since latencies are not used otherwise.
+    pub fn latency_probe_candidates(&self) -> Vec<(Relay, IpAddr)> {
+        let config_guard = self.config.lock().unwrap();
+        let SpecializedSelectorConfig::Normal(normal_config) =
+            SpecializedSelectorConfig::from(&*config_guard)
+        else {
+            return vec![];
+        };
+        let Ok(mut query) = RelayQuery::try_from(normal_config.clone()) else {
+            return vec![];
+        };
+        if query.selection_strategy() != SelectionStrategy::LowestLatency
+            || query.tunnel_protocol() != TunnelType::Wireguard
+        {
+            return vec![];
+        }
+        // An entry relay picked automatically for DAITA may be in any location
+        let entry_location = if query.using_daita() && query.use_multihop_if_necessary() {
+            Some(Constraint::Any)
+        } else if !query.singlehop() {
+            Some(query.wireguard_constraints().entry_location.clone())
+        } else {
+            None
+        };
+        if entry_location.is_some_and(|location| query.set_location(location).is_err()) {
+            return vec![];
+        }
+
+        let ip_version = resolve_ip_version(query.wireguard_constraints().ip_version);
+
+        let relay_list = self.parsed_relays.lock().unwrap().parsed_list().clone();
+        let mut latencies = self.latencies.lock().unwrap();
+        latencies.prune();
+        filter_matching_relay_list(&query, &relay_list, normal_config.custom_lists)
+            .into_iter()
+            .filter(|relay| latencies.needs_probe(&relay.hostname))
+            .filter_map(|relay| {
+                let address = match ip_version {
+                    IpVersion::V4 => IpAddr::from(relay.ipv4_addr_in),
+                    IpVersion::V6 => IpAddr::from(relay.ipv6_addr_in?),
+                };
+                Some((relay, address))
+            })
+            .collect()
+    }
+
     /// Returns a non-custom bridge based on the relay and bridge constraints, ignoring the bridge
     /// state.
     pub fn get_bridge_forced(&self) -> Option<Shadowsocks> {
@@ -522,7 +630,15 @@ impl RelaySelector {
             }
             SpecializedSelectorConfig::Normal(normal_config) => {
                 let relay_list = &self.parsed_relays.lock().unwrap().parsed_list().clone();
-                Self::get_relay_inner(&query, relay_list, normal_config.custom_lists)
+                let latencies = &self.latencies.lock().unwrap().clone();
+                let failures = &self.failures.lock().unwrap().clone();
+                Self::get_relay_inner(
+                    &query,
+                    relay_list,
+                    normal_config.custom_lists,
+                    latencies,
+                    failures,
+                )
             }
         }
     }
@@ -580,6 +696,8 @@ impl RelaySelector {
             }
             SpecializedSelectorConfig::Normal(normal_config) => {
                 let relay_list = self.parsed_relays.lock().unwrap().parsed_list().clone();
+                let latencies = self.latencies.lock().unwrap().clone();
+                let failures = self.failures.lock().unwrap().clone();
                 // Merge user preferences with the relay selector's default preferences.
                 let query = Self::pick_and_merge_query(
                     retry_attempt,
@@ -588,7 +706,13 @@ impl RelaySelector {
                     &normal_config,
                     &relay_list,
                 )?;
-                Self::get_relay_inner(&query, &relay_list, normal_config.custom_lists)
+                Self::get_relay_inner(
+                    &query,
+                    &relay_list,
+                    normal_config.custom_lists,
+                    &latencies,
+                    &failures,
+                )
             }
         }
     }
@@ -617,10 +741,12 @@ impl RelaySelector {
         let mut user_query = RelayQuery::try_from(user_config.clone())?;
         apply_ip_availability(runtime_ip_availability, &mut user_query)?;
         log::trace!("Merging user preferences {user_query:?} with default retry strategy");
+        // Latencies and failures do not affect whether a query yields a relay
+        let (latencies, failures) = (RelayLatencies::default(), RelayFailures::default());
         retry_order
             .iter()
             .filter_map(|query| query.clone().intersection(user_query.clone()))
-            .filter(|query| Self::get_relay_inner(query, parsed_relays, user_config.custom_lists).is_ok())
+            .filter(|query| Self::get_relay_inner(query, parsed_relays, user_config.custom_lists, &latencies, &failures).is_ok())
             .cycle() // If the above filters remove all relays, cycle will also return an empty iterator
             .nth(retry_attempt)
             .ok_or(Error::NoRelay)
@@ -646,12 +772,20 @@ impl RelaySelector {
         query: &RelayQuery,
         parsed_relays: &RelayList,
         custom_lists: &CustomListsSettings,
+        latencies: &RelayLatencies,
+        failures: &RelayFailures,
     ) -> Result<GetRelay, Error> {
         match query.tunnel_protocol() {
-            TunnelType::Wireguard => {
-                Self::get_wireguard_relay_inner(query, custom_lists, parsed_relays)
+            TunnelType::Wireguard => Self::get_wireguard_relay_inner(
+                query,
+                custom_lists,
+                parsed_relays,
+                latencies,
+                failures,
+            ),
+            TunnelType::OpenVpn => {
+                Self::get_openvpn_relay(query, custom_lists, parsed_relays, failures)
             }
-            TunnelType::OpenVpn => Self::get_openvpn_relay(query, custom_lists, parsed_relays),
         }
     }
 
@@ -660,6 +794,8 @@ impl RelaySelector {
         query: &RelayQuery,
         parsed_relays: &RelayList,
         custom_lists: &CustomListsSettings,
+        latencies: &RelayLatencies,
+        failures: &RelayFailures,
     ) -> Result<GetRelay, Error> {
         // FIXME: A bit of defensive programming - calling `get_wireguard_relay_inner` with a query
         // that doesn't specify Wireguard as the desired tunnel type is not valid and will
@@ -667,7 +803,7 @@ impl RelaySelector {
         // nicer to lift this invariant to be checked by the type system instead.
         let mut query = query.clone();
         query.set_tunnel_protocol(TunnelType::Wireguard)?;
-        Self::get_wireguard_relay_inner(&query, custom_lists, parsed_relays)
+        Self::get_wireguard_relay_inner(&query, custom_lists, parsed_relays, latencies, failures)
     }
 
     /// Derive a valid relay configuration from `query`.
@@ -687,9 +823,17 @@ impl RelaySelector {
         query: &RelayQuery,
         custom_lists: &CustomListsSettings,
         parsed_relays: &RelayList,
+        latencies: &RelayLatencies,
+        failures: &RelayFailures,
     ) -> Result<GetRelay, Error> {
         assert_eq!(query.tunnel_protocol(), TunnelType::Wireguard);
-        let inner = Self::get_wireguard_relay_config(query, custom_lists, parsed_relays)?;
+        let inner = Self::get_wireguard_relay_config(
+            query,
+            custom_lists,
+            parsed_relays,
+            latencies,
+            failures,
+        )?;
         let endpoint = Self::get_wireguard_endpoint(query, parsed_relays, &inner)?;
         let obfuscator =
             Self::get_wireguard_obfuscator(query, inner.clone(), &endpoint, parsed_relays)?;
@@ -711,9 +855,17 @@ impl RelaySelector {
         query: &RelayQuery,
         custom_lists: &CustomListsSettings,
         parsed_relays: &RelayList,
+        latencies: &RelayLatencies,
+        failures: &RelayFailures,
     ) -> Result<WireguardConfig, Error> {
         let inner = if query.singlehop() {
-            match Self::get_wireguard_singlehop_config(query, custom_lists, parsed_relays) {
+            match Self::get_wireguard_singlehop_config(
+                query,
+                custom_lists,
+                parsed_relays,
+                latencies,
+                failures,
+            ) {
                 Some(exit) => WireguardConfig::from(exit),
                 None => {
                     // If we found no matching relays because DAITA was enabled, and
@@ -725,6 +877,8 @@ impl RelaySelector {
                             query,
                             custom_lists,
                             parsed_relays,
+                            latencies,
+                            failures,
                         )?;
                         WireguardConfig::from(multihop)
                     } else {
@@ -738,9 +892,21 @@ impl RelaySelector {
             // entry relay with smarting routing enabled, even if multihop is turned on
             // Also implied: Multihop is enabled.
             let multihop = if query.using_daita() && query.use_multihop_if_necessary() {
-                Self::get_wireguard_auto_multihop_config(query, custom_lists, parsed_relays)?
+                Self::get_wireguard_auto_multihop_config(
+                    query,
+                    custom_lists,
+                    parsed_relays,
+                    latencies,
+                    failures,
+                )?
             } else {
-                Self::get_wireguard_multihop_config(query, custom_lists, parsed_relays)?
+                Self::get_wireguard_multihop_config(
+                    query,
+                    custom_lists,
+                    parsed_relays,
+                    latencies,
+                    failures,
+                )?
             };
             WireguardConfig::from(multihop)
         };
@@ -757,9 +923,11 @@ impl RelaySelector {
         query: &RelayQuery,
         custom_lists: &CustomListsSettings,
         parsed_relays: &RelayList,
+        latencies: &RelayLatencies,
+        failures: &RelayFailures,
     ) -> Option<Singlehop> {
         let candidates = filter_matching_relay_list(query, parsed_relays, custom_lists);
-        helpers::pick_random_relay(&candidates)
+        helpers::pick_relay(&candidates, query.selection_strategy(), latencies, failures)
             .cloned()
             .map(Singlehop::new)
     }
@@ -773,6 +941,8 @@ impl RelaySelector {
         query: &RelayQuery,
         custom_lists: &CustomListsSettings,
         parsed_relays: &RelayList,
+        latencies: &RelayLatencies,
+        failures: &RelayFailures,
     ) -> Result<Multihop, Error> {
         let mut exit_relay_query = query.clone();
 
@@ -783,7 +953,7 @@ impl RelaySelector {
 
         let exit_candidates =
             filter_matching_relay_list(&exit_relay_query, parsed_relays, custom_lists);
-        let exit = helpers::pick_random_relay(&exit_candidates).ok_or(Error::NoRelay)?;
+        let exit = helpers::pick_random_relay(&exit_candidates, failures).ok_or(Error::NoRelay)?;
 
         // generate a list of potential entry relays, disregarding any location constraint
         let mut entry_query = query.clone();
@@ -807,8 +977,15 @@ impl RelaySelector {
             .take_while(|relay| relay.distance <= smallest_distance)
             .map(|relay_with_distance| relay_with_distance.relay)
             .collect_vec();
-        let entry =
-            helpers::pick_random_relay_excluding(&entry_candidates, exit).ok_or(Error::NoRelay)?;
+        // As for regular multihop, the selection strategy only applies to the entry relay
+        let entry = helpers::pick_relay_excluding(
+            &entry_candidates,
+            exit,
+            query.selection_strategy(),
+            latencies,
+            failures,
+        )
+        .ok_or(Error::NoRelay)?;
 
         Ok(Multihop::new(entry.clone(), exit.clone()))
     }
@@ -824,6 +1001,8 @@ impl RelaySelector {
         query: &RelayQuery,
         custom_lists: &CustomListsSettings,
         parsed_relays: &RelayList,
+        latencies: &RelayLatencies,
+        failures: &RelayFailures,
     ) -> Result<Multihop, Error> {
         // Here, we modify the original query just a bit.
         // The actual query for an entry relay is identical as for an exit relay, with the
@@ -847,19 +1026,24 @@ impl RelaySelector {
             filter_matching_relay_list(&entry_relay_query, parsed_relays, custom_lists);
 
         // We avoid picking the same relay for entry and exit by choosing one and excluding it when
-        // choosing the other.
+        // choosing the other. The selection strategy only applies to the entry relay, since that
+        // is the relay that is reached directly.
+        let strategy = query.selection_strategy();
         let (exit, entry) = match (exit_candidates.as_slice(), entry_candidates.as_slice()) {
             // In the case where there is only one entry to choose from, we have to pick it before
             // the exit
             (exits, [entry]) if exits.contains(entry) => {
-                helpers::pick_random_relay_excluding(exits, entry).map(|exit| (exit, entry))
+                helpers::pick_random_relay_excluding(exits, entry, failures)
+                    .map(|exit| (exit, entry))
             }
             // Vice versa for the case of only one exit
             ([exit], entries) if entries.contains(exit) => {
-                helpers::pick_random_relay_excluding(entries, exit).map(|entry| (exit, entry))
+                helpers::pick_relay_excluding(entries, exit, strategy, latencies, failures)
+                    .map(|entry| (exit, entry))
             }
-            (exits, entries) => helpers::pick_random_relay(exits).and_then(|exit| {
-                helpers::pick_random_relay_excluding(entries, exit).map(|entry| (exit, entry))
+            (exits, entries) => helpers::pick_random_relay(exits, failures).and_then(|exit| {
+                helpers::pick_relay_excluding(entries, exit, strategy, latencies, failures)
+                    .map(|entry| (exit, entry))
             }),
         }
         .ok_or(Error::NoRelay)?;
@@ -944,10 +1128,11 @@ impl RelaySelector {
         query: &RelayQuery,
         custom_lists: &CustomListsSettings,
         parsed_relays: &RelayList,
+        failures: &RelayFailures,
     ) -> Result<GetRelay, Error> {
         assert_eq!(query.tunnel_protocol(), TunnelType::OpenVpn);
-        let exit =
-            Self::choose_openvpn_relay(query, custom_lists, parsed_relays).ok_or(Error::NoRelay)?;
+        let exit = Self::choose_openvpn_relay(query, custom_lists, parsed_relays, failures)
+            .ok_or(Error::NoRelay)?;
         let endpoint = Self::get_openvpn_endpoint(query, &exit, parsed_relays)?;
         let bridge = Self::get_openvpn_bridge(
             query,
@@ -1073,7 +1258,8 @@ impl RelaySelector {
         let bridge_data = &relay_list.bridge;
         let bridge = match location {
             Some(location) => Self::get_proximate_bridge(bridges, location),
-            None => helpers::pick_random_relay(&bridges)
+            // Failures are only tracked for relays, not for bridges
+            None => helpers::pick_random_relay_weighted(bridges.iter(), |bridge| bridge.weight)
                 .cloned()
                 .ok_or(Error::NoRelay),
         }?;
@@ -1147,11 +1333,12 @@ impl RelaySelector {
         query: &RelayQuery,
         custom_lists: &CustomListsSettings,
         parsed_relays: &RelayList,
+        failures: &RelayFailures,
     ) -> Option<Relay> {
         // Filter among all valid relays
         let candidates = filter_matching_relay_list(query, parsed_relays, custom_lists);
         // Pick one of the valid relays.
-        helpers::pick_random_relay(&candidates).cloned()
+        helpers::pick_random_relay(&candidates, failures).cloned()
     }
 }
 
@@ -35,7 +35,7 @@ use mullvad_types::{
     relay_constraints::{
         BridgeConstraints, BridgeSettings, BridgeState, BridgeType, LocationConstraint,
         ObfuscationSettings, OpenVpnConstraints, Ownership, Providers, RelayConstraints,
-        RelaySettings, SelectedObfuscation, ShadowsocksSettings, TransportPort,
+        RelaySettings, SelectedObfuscation, SelectionStrategy, ShadowsocksSettings, TransportPort,
         Udp2TcpObfuscationSettings, WireguardConstraints, allowed_ip::AllowedIps,
     },
     wireguard::QuantumResistantState,
@@ -81,6 +81,7 @@ pub struct RelayQuery {
     tunnel_protocol: TunnelType,
     wireguard_constraints: WireguardRelayQuery,
     openvpn_constraints: OpenVpnRelayQuery,
+    selection_strategy: SelectionStrategy,
 }
 
 impl RelayQuery {
@@ -100,6 +101,7 @@ impl RelayQuery {
             tunnel_protocol,
             wireguard_constraints,
             openvpn_constraints,
+            selection_strategy: SelectionStrategy::default(),
         };
         query.validate()?;
         Ok(query)
@@ -181,6 +183,14 @@ impl RelayQuery {
         self.set_if_valid(|query| query.wireguard_constraints = wireguard_constraints)
     }
 
+    pub fn selection_strategy(&self) -> SelectionStrategy {
+        self.selection_strategy
+    }
+
+    pub fn set_selection_strategy(&mut self, selection_strategy: SelectionStrategy) {
+        self.selection_strategy = selection_strategy;
+    }
+
     fn set_if_valid(&mut self, set_fn: impl FnOnce(&mut Self)) -> Result<(), Error> {
         let mut new = self.clone();
         (set_fn)(&mut new);
@@ -221,6 +231,7 @@ impl RelayQuery {
             tunnel_protocol: self.tunnel_protocol,
             wireguard_constraints: self.wireguard_constraints.into_constraints(),
             openvpn_constraints: self.openvpn_constraints.into_constraints(),
+            selection_strategy: self.selection_strategy,
         };
 
         (constraints, bridge_state, bridge_settings, obfuscation)
@@ -249,6 +260,7 @@ impl Default for RelayQuery {
             tunnel_protocol: TunnelType::default(),
             wireguard_constraints: WireguardRelayQuery::new(),
             openvpn_constraints: OpenVpnRelayQuery::new(),
+            selection_strategy: SelectionStrategy::Random,
         }
     }
 }
@@ -533,7 +545,7 @@ pub mod builder {
 
     // Re-exports
     pub use mullvad_types::relay_constraints::{
-        GeographicLocationConstraint, Ownership, Providers,
+        GeographicLocationConstraint, Ownership, Providers, SelectionStrategy,
     };
     pub use talpid_types::net::{IpVersion, TransportProtocol};
 
@@ -575,6 +587,12 @@ pub mod builder {
             self
         }
 
+        /// Configure how to choose among the matching relays.
+        pub const fn selection_strategy(mut self, selection_strategy: SelectionStrategy) -> Self {
+            self.query.selection_strategy = selection_strategy;
+            self
+        }
+
         /// Assemble the final [`RelayQuery`] that has been configured
         /// through `self`.
         pub fn build(mut self) -> RelayQuery {
@@ -1079,6 +1097,30 @@ mod test {
         }
     }
 
+    /// A selection strategy that is not random takes precedence over random selection when queries
+    /// are merged, so that the retry order does not override the strategy of the user.
+    #[test]
+    fn test_selection_strategy_intersection() {
+        use mullvad_types::relay_constraints::SelectionStrategy::{LowestLatency, Random};
+
+        assert_eq!(Random.intersection(Random), Some(Random));
+        assert_eq!(Random.intersection(LowestLatency), Some(LowestLatency));
+        assert_eq!(LowestLatency.intersection(Random), Some(LowestLatency));
+        assert_eq!(
+            LowestLatency.intersection(LowestLatency),
+            Some(LowestLatency)
+        );
+
+        let user_query = RelayQueryBuilder::wireguard()
+            .selection_strategy(LowestLatency)
+            .build();
+        let merged = RelayQueryBuilder::wireguard()
+            .build()
+            .intersection(user_query)
+            .unwrap();
+        assert_eq!(merged.selection_strategy(), LowestLatency);
+    }
+
     /// Test whether the default relay query is valid
     #[test]
     fn test_relay_query_default_valid() {
@@ -4,6 +4,7 @@ use std::{
     collections::HashSet,
     net::{IpAddr, Ipv4Addr, Ipv6Addr},
     sync::LazyLock,
+    time::Duration,
 };
 use talpid_types::net::{
     Endpoint, IpVersion,
@@ -16,7 +17,9 @@ use talpid_types::net::{
 use mullvad_relay_selector::{
     Error, GetRelay, OPENVPN_RETRY_ORDER, RelaySelector, SelectedObfuscator, SelectorConfig,
     WIREGUARD_RETRY_ORDER, WireguardConfig,
-    query::{BridgeQuery, ObfuscationQuery, OpenVpnRelayQuery, builder::RelayQueryBuilder},
+    query::{
+        BridgeQuery, ObfuscationQuery, OpenVpnRelayQuery, RelayQuery, builder::RelayQueryBuilder,
+    },
 };
 use mullvad_types::{
     constraints::Constraint,
@@ -24,7 +27,7 @@ use mullvad_types::{
     location::Location,
     relay_constraints::{
         BridgeConstraints, BridgeState, GeographicLocationConstraint, Ownership, Providers,
-        RelayConstraints, RelayOverride, RelaySettings, TransportPort,
+        RelayConstraints, RelayOverride, RelaySettings, SelectionStrategy, TransportPort,
     },
     relay_list::{
         BridgeEndpointData, OpenVpnEndpoint, OpenVpnEndpointData, Quic, Relay, RelayEndpointData,
@@ -1727,3 +1730,191 @@ fn test_runtime_ipv4_unavailable() {
         ),
     }
 }
+
+/// Relays that recently failed to connect should be much less likely to be picked again, but
+/// still be remembered by [`RelaySelector::relay_health`].
+#[test]
+fn test_recently_failed_relays_are_deprioritized() {
+    let relay_selector = default_relay_selector();
+    for _ in 0..3 {
+        relay_selector.record_relay_failure("se9-wireguard");
+        relay_selector.record_relay_failure("se10-wireguard");
+    }
+
+    let mut health = relay_selector.relay_health();
+    health.sort_by(|a, b| a.hostname.cmp(&b.hostname));
+    assert_eq!(health.len(), 2);
+    assert_eq!(health[0].hostname, "se10-wireguard");
+    assert_eq!(health[0].failures, 3);
+    assert!(health[0].weight_factor < 0.01);
+
+    let query = RelayQueryBuilder::wireguard().build();
+    let healthy_picks = (0..100)
+        .map(|_| unwrap_relay(relay_selector.get_relay_by_query(query.clone()).unwrap()))
+        .filter(|relay| relay.hostname == "se11-wireguard")
+        .count();
+    assert!(
+        healthy_picks > 90,
+        "Expected the relay without failures to be picked almost every time, but it was picked {healthy_picks} times"
+    );
+}
+
+/// The lowest latency strategy should always pick the matching relay with the lowest measured
+/// latency, both for explicit queries and when the query is merged with the retry order.
+#[test]
+fn test_lowest_latency_strategy() {
+    let relay_selector = default_relay_selector();
+    relay_selector.set_relay_latency("se9-wireguard", Duration::from_millis(50));
+    relay_selector.set_relay_latency("se10-wireguard", Duration::from_millis(10));
+    relay_selector.set_relay_latency("se11-wireguard", Duration::from_millis(30));
+
+    let query = RelayQueryBuilder::wireguard()
+        .selection_strategy(SelectionStrategy::LowestLatency)
+        .build();
+    for _ in 0..100 {
+        let relay = unwrap_relay(relay_selector.get_relay_by_query(query.clone()).unwrap());
+        assert_eq!(relay.hostname, "se10-wireguard");
+    }
+
+    // The strategy of the user must survive the intersection with the retry order
+    let (user_constraints, ..) = query.into_settings();
+    let config = SelectorConfig {
+        relay_settings: user_constraints.into(),
+        ..SelectorConfig::default()
+    };
+    let relay_selector = RelaySelector::from_list(config, RELAYS.clone());
+    relay_selector.set_relay_latency("se9-wireguard", Duration::from_millis(50));
+    relay_selector.set_relay_latency("se10-wireguard", Duration::from_millis(10));
+    for _ in 0..100 {
+        let relay = unwrap_relay(
+            relay_selector
+                .get_relay(0, talpid_types::net::IpAvailability::Ipv4)
+                .unwrap(),
+        );
+        assert_eq!(relay.hostname, "se10-wireguard");
+    }
+}
+
+/// Relays without a latency measurement should not be picked by the lowest latency strategy,
+/// unless no relay has been measured at all.
+#[test]
+fn test_lowest_latency_strategy_missing_latency() {
+    let relay_selector = default_relay_selector();
+    let query = RelayQueryBuilder::wireguard()
+        .selection_strategy(SelectionStrategy::LowestLatency)
+        .build();
+
+    // Without any measurements, the relays are picked at random
+    let picked: HashSet<_> = (0..100)
+        .map(|_| unwrap_relay(relay_selector.get_relay_by_query(query.clone()).unwrap()).hostname)
+        .collect();
+    assert!(
+        picked.len() > 1,
+        "Expected random relays to be picked, but only {picked:?} were"
+    );
+
+    relay_selector.set_relay_latency("se11-wireguard", Duration::from_millis(300));
+    for _ in 0..100 {
+        let relay = unwrap_relay(relay_selector.get_relay_by_query(query.clone()).unwrap());
+        assert_eq!(relay.hostname, "se11-wireguard");
+    }
+}
+
+/// With multihop, the lowest latency strategy applies to the entry relay, which must still differ
+/// from the exit relay.
+#[test]
+fn test_lowest_latency_strategy_multihop() {
+    let relay_selector = default_relay_selector();
+    relay_selector.set_relay_latency("se9-wireguard", Duration::from_millis(40));
+    relay_selector.set_relay_latency("se10-wireguard", Duration::from_millis(1));
+    relay_selector.set_relay_latency("se11-wireguard", Duration::from_millis(20));
+
+    let query = RelayQueryBuilder::wireguard()
+        .location(NON_DAITA_RELAY_LOCATION.clone())
+        .multihop()
+        .selection_strategy(SelectionStrategy::LowestLatency)
+        .build();
+    for _ in 0..100 {
+        let result = relay_selector.get_relay_by_query(query.clone()).unwrap();
+        assert_eq!(unwrap_relay(result.clone()).hostname, "se10-wireguard");
+        assert_eq!(unwrap_entry_relay(result).hostname, "se11-wireguard");
+    }
+
+    // The same applies to the entry relay that is picked automatically for DAITA
+    let query = RelayQueryBuilder::wireguard()
+        .daita()
+        .daita_use_multihop_if_necessary(true)
+        .multihop()
+        .selection_strategy(SelectionStrategy::LowestLatency)
+        .build();
+    for _ in 0..100 {
+        let result = relay_selector.get_relay_by_query(query.clone()).unwrap();
+        let exit = unwrap_relay(result.clone());
+        let entry = unwrap_entry_relay(result);
+        let expected_entry = if exit.hostname == "se11-wireguard" {
+            "se9-wireguard"
+        } else {
+            "se11-wireguard"
+        };
+        assert_eq!(entry.hostname, expected_entry);
+    }
+}
+
+/// Relays with many recent failures must not break the lowest latency strategy, even if their
+/// weight factor underflows to zero.
+#[test]
+fn test_lowest_latency_strategy_with_failures() {
+    let relay_selector = default_relay_selector();
+    relay_selector.set_relay_latency("se10-wireguard", Duration::from_millis(10));
+    relay_selector.set_relay_latency("se11-wireguard", Duration::from_millis(30));
+    for _ in 0..1000 {
+        relay_selector.record_relay_failure("se10-wireguard");
+    }
+
+    let query = RelayQueryBuilder::wireguard()
+        .selection_strategy(SelectionStrategy::LowestLatency)
+        .build();
+    let relay = unwrap_relay(relay_selector.get_relay_by_query(query).unwrap());
+    assert_eq!(relay.hostname, "se11-wireguard");
+}
+
+/// Latencies should be measured to the IPv6 address of relays if IPv6 is required, and to the
+/// IPv4 address otherwise.
+#[test]
+fn test_latency_probe_candidates_ip_version() {
+    let config_with_query = |query: RelayQuery| {
+        let (user_constraints, ..) = query.into_settings();
+        SelectorConfig {
+            relay_settings: user_constraints.into(),
+            ..SelectorConfig::default()
+        }
+    };
+
+    let query = RelayQueryBuilder::wireguard()
+        .selection_strategy(SelectionStrategy::LowestLatency)
+        .build();
+    let relay_selector = RelaySelector::from_list(config_with_query(query), RELAYS.clone());
+    let candidates = relay_selector.latency_probe_candidates();
+    assert!(!candidates.is_empty());
+    for (relay, address) in &candidates {
+        assert_eq!(*address, IpAddr::from(relay.ipv4_addr_in));
+    }
+
+    let query = RelayQueryBuilder::wireguard()
+        .ip_version(IpVersion::V6)
+        .selection_strategy(SelectionStrategy::LowestLatency)
+        .build();
+    let relay_selector = RelaySelector::from_list(config_with_query(query), RELAYS.clone());
+    let candidates = relay_selector.latency_probe_candidates();
+    assert!(!candidates.is_empty());
+    for (relay, address) in &candidates {
+        assert_eq!(Some(*address), relay.ipv6_addr_in.map(IpAddr::from));
+    }
+
+    // Measured relays are not probed again
+    relay_selector.set_relay_latency(&candidates[0].0.hostname, Duration::from_millis(10));
+    assert_eq!(
+        relay_selector.latency_probe_candidates().len(),
+        candidates.len() - 1
+    );
+}
@@ -29,6 +29,13 @@ impl Settings {
         }
     }
 
+    /// Remove the credentials of all custom proxies.
+    pub fn redact_secrets(&mut self) {
+        for method in &mut self.custom {
+            method.redact_secrets();
+        }
+    }
+
     /// Append an [`AccessMethod`] to the end of `api_access_methods`.
     pub fn append(&mut self, api_access_method: AccessMethodSetting) {
         self.custom.push(api_access_method)
@@ -279,6 +286,13 @@ impl AccessMethodSetting {
     pub fn disable(&mut self) {
         self.enabled = false;
     }
+
+    /// Remove the credentials of the proxy, if it is a custom proxy.
+    pub fn redact_secrets(&mut self) {
+        if let AccessMethod::Custom(proxy) = &mut self.access_method {
+            proxy.redact_secrets();
+        }
+    }
 }
 
 /// Built-In access method datastructure.
@@ -0,0 +1,93 @@
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// A rule that decides what the daemon should do when the host joins a matching network.
+///
+/// Rules are evaluated in order, and the first rule that matches the current network wins. If no
+/// rule matches, the regular auto-connect setting applies.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AutoConnectRule {
+    pub network: NetworkMatcher,
+    pub action: AutoConnectAction,
+}
+
+impl fmt::Display for AutoConnectRule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} -> {}", self.network, self.action)
+    }
+}
+
+/// Identifies a network that the host may be connected to.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum NetworkMatcher {
+    /// Matches a Wi-Fi network by its SSID.
+    Ssid(String),
+    /// Matches a network by the MAC address of its default gateway. This is stored in lowercase,
+    /// colon-separated form.
+    GatewayMac(String),
+    /// Matches the network interface that is used to reach the internet.
+    Interface(String),
+}
+
+impl NetworkMatcher {
+    /// Create a matcher for the gateway with the given MAC address. Returns `None` unless `mac`
+    /// consists of six hexadecimal octets separated by `:` or `-`.
+    pub fn gateway_mac(mac: &str) -> Option<Self> {
+        let octets: Vec<_> = mac.split([':', '-']).collect();
+        let valid = octets.len() == 6
+            && octets
+                .iter()
+                .all(|octet| octet.len() == 2 && u8::from_str_radix(octet, 16).is_ok());
+        valid.then(|| NetworkMatcher::GatewayMac(octets.join(":").to_lowercase()))
+    }
+}
+
+impl fmt::Display for NetworkMatcher {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            NetworkMatcher::Ssid(ssid) => write!(f, "SSID \"{ssid}\""),
+            NetworkMatcher::GatewayMac(mac) => write!(f, "gateway MAC {mac}"),
+            NetworkMatcher::Interface(interface) => write!(f, "interface {interface}"),
+        }
+    }
+}
+
+/// What to do when joining a network that matches an [`AutoConnectRule`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+#[cfg_attr(feature = "clap", derive(clap::ValueEnum))]
+pub enum AutoConnectAction {
+    /// Connect the tunnel.
+    Connect,
+    /// Disconnect the tunnel.
+    Disconnect,
+    /// Disconnect the tunnel and block all traffic, regardless of the lockdown mode setting.
+    Block,
+}
+
+impl fmt::Display for AutoConnectAction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AutoConnectAction::Connect => f.write_str("connect"),
+            AutoConnectAction::Disconnect => f.write_str("disconnect"),
+            AutoConnectAction::Block => f.write_str("block"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::NetworkMatcher;
+
+    #[test]
+    fn test_gateway_mac() {
+        assert_eq!(
+            NetworkMatcher::gateway_mac("AA-bb-CC-dd-00-11"),
+            Some(NetworkMatcher::GatewayMac("aa:bb:cc:dd:00:11".to_owned()))
+        );
+        assert_eq!(NetworkMatcher::gateway_mac("aa:bb:cc:dd:00"), None);
+        assert_eq!(NetworkMatcher::gateway_mac("aa:bb:cc:dd:00:1"), None);
+        assert_eq!(NetworkMatcher::gateway_mac("aa:bb:cc:dd:00:zz"), None);
+    }
+}
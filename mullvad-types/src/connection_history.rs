@@ -0,0 +1,49 @@
+use crate::{features::FeatureIndicators, location::GeoIpLocation};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use talpid_types::{net::ObfuscationType, tunnel::ErrorState};
+
+/// Summary of a tunnel session. A session starts when the daemon begins connecting, and lasts
+/// until it disconnects or enters the error state. Reconnects in between are part of the same
+/// session.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ConnectionSession {
+    /// When the daemon started connecting.
+    pub start: DateTime<Utc>,
+    /// When the session ended, or `None` if it is ongoing. If the daemon stopped unexpectedly,
+    /// this is when the session was last saved.
+    pub end: Option<DateTime<Utc>>,
+    /// Location and hostname of the most recent relay.
+    pub location: Option<GeoIpLocation>,
+    /// Obfuscation used by the most recent tunnel, if any.
+    pub obfuscation: Option<ObfuscationType>,
+    /// Feature indicators of the most recent tunnel.
+    pub feature_indicators: FeatureIndicators,
+    /// Bytes received through the tunnel. The traffic counters are sampled periodically and when
+    /// a tunnel goes down.
+    pub rx_bytes: u64,
+    /// Bytes sent through the tunnel.
+    pub tx_bytes: u64,
+    /// Number of times a tunnel that had come up was replaced during the session. Failed attempts
+    /// to bring up a tunnel are not counted.
+    pub reconnects: u32,
+    /// Why the session ended, or `None` if it is ongoing.
+    pub end_reason: Option<SessionEndReason>,
+}
+
+impl ConnectionSession {
+    pub fn is_ongoing(&self) -> bool {
+        self.end_reason.is_none()
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SessionEndReason {
+    /// The tunnel was disconnected.
+    Disconnected,
+    /// The daemon entered the error state.
+    Error(ErrorState),
+    /// The daemon stopped before the session ended.
+    Interrupted,
+}
@@ -1,4 +1,5 @@
 use crate::settings::TunnelOptions;
+use ipnetwork::IpNetwork;
 use serde::{Deserialize, Serialize};
 use std::{
     fmt, io,
@@ -19,11 +20,41 @@ pub enum Error {
 pub struct CustomTunnelEndpoint {
     pub host: String,
     pub config: ConnectionConfig,
+    /// Preshared key of the WireGuard peer.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub psk: Option<wireguard::PresharedKey>,
+    /// DNS servers to use in the tunnel, unless custom DNS servers are set.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub dns_servers: Vec<IpAddr>,
+    /// WireGuard peers to switch to, in order, if the current peer stops responding.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub fallback_peers: Vec<FallbackPeer>,
 }
 
 impl CustomTunnelEndpoint {
     pub fn new(host: String, config: ConnectionConfig) -> Self {
-        Self { host, config }
+        Self {
+            host,
+            config,
+            psk: None,
+            dns_servers: vec![],
+            fallback_peers: vec![],
+        }
+    }
+
+    /// Remove the private key, preshared keys and OpenVPN password. The private key is replaced
+    /// by an all-zero key.
+    pub fn redact_secrets(&mut self) {
+        match &mut self.config {
+            ConnectionConfig::OpenVpn(config) => config.password.clear(),
+            ConnectionConfig::Wireguard(config) => {
+                config.tunnel.private_key = wireguard::PrivateKey::from([0; 32]);
+            }
+        }
+        self.psk = None;
+        for peer in &mut self.fallback_peers {
+            peer.psk = None;
+        }
     }
 
     pub fn endpoint(&self) -> Endpoint {
@@ -52,17 +83,30 @@ impl CustomTunnelEndpoint {
                 fwmark: crate::TUNNEL_FWMARK,
             }
             .into(),
-            ConnectionConfig::Wireguard(connection) => {
+            ConnectionConfig::Wireguard(mut connection) => {
+                connection.peer.psk = self.psk.clone();
                 let mut options = tunnel_options.wireguard.into_talpid_tunnel_options();
                 if options.quantum_resistant {
                     options.quantum_resistant = false;
                     log::info!("Ignoring quantum resistant option for custom tunnel");
                 }
+                let fallback_peers = self
+                    .fallback_peers
+                    .iter()
+                    .filter_map(|peer| {
+                        peer.to_peer_config()
+                            .inspect_err(|error| {
+                                log::warn!("Ignoring fallback peer {}: {error}", peer.host);
+                            })
+                            .ok()
+                    })
+                    .collect();
                 wireguard::TunnelParameters {
                     connection,
                     options,
                     generic_options: tunnel_options.generic,
                     obfuscation: None,
+                    fallback_peers,
                 }
                 .into()
             }
@@ -81,17 +125,49 @@ impl fmt::Display for CustomTunnelEndpoint {
                 config.endpoint.address.port(),
                 config.endpoint.protocol
             ),
-            ConnectionConfig::Wireguard(connection) => write!(
-                f,
-                "WireGuard relay - {}:{} with public key {}",
-                self.host,
-                connection.peer.endpoint.port(),
-                connection.peer.public_key
-            ),
+            ConnectionConfig::Wireguard(connection) => {
+                write!(
+                    f,
+                    "WireGuard relay - {}:{} with public key {}",
+                    self.host,
+                    connection.peer.endpoint.port(),
+                    connection.peer.public_key
+                )?;
+                for peer in &self.fallback_peers {
+                    write!(f, ", falling back to {}:{}", peer.host, peer.port)?;
+                }
+                Ok(())
+            }
         }
     }
 }
 
+/// A WireGuard peer of a custom tunnel endpoint that is used if the current peer stops
+/// responding. It uses the tunnel configuration of the endpoint.
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub struct FallbackPeer {
+    pub host: String,
+    pub port: u16,
+    pub public_key: wireguard::PublicKey,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub psk: Option<wireguard::PresharedKey>,
+    pub allowed_ips: Vec<IpNetwork>,
+}
+
+impl FallbackPeer {
+    fn to_peer_config(&self) -> Result<wireguard::PeerConfig, Error> {
+        let ip = resolve_to_ip(&self.host)?;
+        Ok(wireguard::PeerConfig {
+            public_key: self.public_key.clone(),
+            allowed_ips: self.allowed_ips.clone(),
+            endpoint: SocketAddr::new(ip, self.port),
+            psk: self.psk.clone(),
+            #[cfg(daita)]
+            constant_packet_size: false,
+        })
+    }
+}
+
 /// Does a DNS lookup if the host isn't an IP.
 /// Returns the first IPv4 address if one exists, otherwise the first IPv6 address.
 /// Rust only provides means to resolve a socket addr, not just a host, for some reason. So
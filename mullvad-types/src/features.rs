@@ -132,10 +132,7 @@ pub fn compute_feature_indicators(
     endpoint: &TunnelEndpoint,
     server_ip_override: bool,
 ) -> FeatureIndicators {
-    #[cfg(any(windows, target_os = "android", target_os = "macos"))]
     let split_tunneling = settings.split_tunnel.enable_exclusions;
-    #[cfg(not(any(windows, target_os = "android", target_os = "macos")))]
-    let split_tunneling = false;
 
     #[cfg(not(target_os = "android"))]
     let lockdown_mode = settings.block_when_disconnected;
@@ -254,6 +251,7 @@ mod tests {
             obfuscation: Default::default(),
             entry_endpoint: Default::default(),
             tunnel_interface: Default::default(),
+            wireguard_backend: Default::default(),
             daita: Default::default(),
         };
 
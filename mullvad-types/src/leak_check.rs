@@ -0,0 +1,29 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::net::IpAddr;
+
+/// The outcome of a single run of the leak checker.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LeakCheckResult {
+    /// When the check completed.
+    pub timestamp: DateTime<Utc>,
+    /// The relay that the leak checker tried to reach outside of the tunnel.
+    pub relay: IpAddr,
+    /// Details about the leak, or `None` if no traffic escaped the tunnel.
+    pub leak: Option<LeakInfo>,
+}
+
+impl LeakCheckResult {
+    pub fn is_leak(&self) -> bool {
+        self.leak.is_some()
+    }
+}
+
+/// Details about how traffic leaked outside of the tunnel.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LeakInfo {
+    /// The physical interface that traffic leaked on, if known.
+    pub interface: Option<String>,
+    /// Network nodes that could be reached, bypassing the firewall.
+    pub reachable_nodes: Vec<IpAddr>,
+}
@@ -1,17 +1,22 @@
 pub mod access_method;
 pub mod account;
 pub mod auth_failed;
+pub mod auto_connect;
+pub mod connection_history;
 pub mod constraints;
 pub mod custom_list;
 pub mod device;
 pub mod endpoint;
 pub mod features;
+pub mod leak_check;
 pub mod location;
+pub mod profile;
 pub mod relay_constraints;
 pub mod relay_list;
 pub mod settings;
 pub mod states;
 pub mod version;
+pub mod wg_quick;
 pub mod wireguard;
 
 mod custom_tunnel;
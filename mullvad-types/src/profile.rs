@@ -0,0 +1,125 @@
+use crate::{
+    relay_constraints::{ObfuscationSettings, RelaySettings},
+    settings::{Settings, TunnelOptions},
+};
+use serde::{Deserialize, Serialize};
+
+/// A named set of settings that are applied together, such as relay constraints, obfuscation,
+/// DNS and tunnel options.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SettingsProfile {
+    pub name: String,
+    pub relay_settings: RelaySettings,
+    pub obfuscation_settings: ObfuscationSettings,
+    pub tunnel_options: TunnelOptions,
+}
+
+impl SettingsProfile {
+    /// Create a profile from the current values of `settings`.
+    pub fn from_settings(name: String, settings: &Settings) -> Self {
+        SettingsProfile {
+            name,
+            relay_settings: settings.relay_settings.clone(),
+            obfuscation_settings: settings.obfuscation_settings.clone(),
+            tunnel_options: settings.tunnel_options.clone(),
+        }
+    }
+
+    /// Replace the settings in `settings` that are part of the profile.
+    pub fn apply_to(&self, settings: &mut Settings) {
+        settings.set_relay_settings(self.relay_settings.clone());
+        settings.obfuscation_settings = self.obfuscation_settings.clone();
+        settings.tunnel_options = self.tunnel_options.clone();
+    }
+
+    /// Returns whether `settings` are equal to the settings in the profile.
+    pub fn is_active(&self, settings: &Settings) -> bool {
+        settings.relay_settings == self.relay_settings
+            && settings.obfuscation_settings == self.obfuscation_settings
+            && settings.tunnel_options == self.tunnel_options
+    }
+
+    /// Remove secrets, such as the private key of a custom relay, from the profile.
+    pub fn redact_secrets(&mut self) {
+        if let RelaySettings::CustomTunnelEndpoint(endpoint) = &mut self.relay_settings {
+            endpoint.redact_secrets();
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::relay_constraints::SelectedObfuscation;
+
+    #[test]
+    fn test_apply_profile() {
+        let mut settings = Settings::default();
+        settings.obfuscation_settings.selected_obfuscation = SelectedObfuscation::Shadowsocks;
+        settings.tunnel_options.wireguard.mtu = Some(1280);
+        let profile = SettingsProfile::from_settings("censored".to_owned(), &settings);
+
+        let mut settings = Settings {
+            allow_lan: true,
+            ..Settings::default()
+        };
+        assert!(!profile.is_active(&settings));
+
+        profile.apply_to(&mut settings);
+        assert!(profile.is_active(&settings));
+        assert_eq!(settings.tunnel_options.wireguard.mtu, Some(1280));
+        // Settings outside of the profile are left alone
+        assert!(settings.allow_lan);
+    }
+
+    #[test]
+    fn test_redact_secrets() {
+        use talpid_types::net::wireguard;
+
+        let config = crate::wg_quick::WgQuickConfig::parse(
+            r#"
+            [Interface]
+            PrivateKey = mPzI8CrXCXnNC1xTvOn5qh5jWvLm2B0/3D4lDMcfomk=
+            Address = 10.8.0.2/24
+            DNS = 10.8.0.1
+
+            [Peer]
+            PublicKey = 6oeNfINvBoTsnF/4EAfhUbl8tQjH6Tt/A6qz38yNZUw=
+            PresharedKey = bt8SOIcawWq5vHX3ZfqxJfJ64Rfz7QI5x9QoG/vX6XE=
+            AllowedIPs = 0.0.0.0/0
+            Endpoint = 192.0.2.1:51820
+        "#,
+        )
+        .unwrap();
+        let mut settings = Settings::default();
+        settings.set_relay_settings(RelaySettings::CustomTunnelEndpoint(
+            config.into_custom_tunnel_endpoint(None, None).unwrap(),
+        ));
+        settings.profiles.push(SettingsProfile::from_settings(
+            "custom".to_owned(),
+            &settings,
+        ));
+
+        settings.redact_secrets();
+
+        let relay_settings = std::iter::once(&settings.relay_settings).chain(
+            settings
+                .profiles
+                .iter()
+                .map(|profile| &profile.relay_settings),
+        );
+        for relay_settings in relay_settings {
+            let RelaySettings::CustomTunnelEndpoint(endpoint) = relay_settings else {
+                panic!("Expected a custom relay");
+            };
+            let crate::ConnectionConfig::Wireguard(config) = &endpoint.config else {
+                panic!("Expected a WireGuard relay");
+            };
+            assert_eq!(
+                config.tunnel.private_key,
+                wireguard::PrivateKey::from([0; 32])
+            );
+            assert!(endpoint.psk.is_none());
+        }
+    }
+}
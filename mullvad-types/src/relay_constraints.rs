@@ -126,6 +126,7 @@ pub struct RelayConstraints {
     pub tunnel_protocol: TunnelType,
     pub wireguard_constraints: WireguardConstraints,
     pub openvpn_constraints: OpenVpnConstraints,
+    pub selection_strategy: SelectionStrategy,
 }
 
 pub struct RelayConstraintsFormatter<'a> {
@@ -157,7 +158,51 @@ impl fmt::Display for RelayConstraintsFormatter<'_> {
                 })
         )?;
         writeln!(f, "Provider(s): {}", self.constraints.providers)?;
-        write!(f, "Ownership: {}", self.constraints.ownership)
+        writeln!(f, "Ownership: {}", self.constraints.ownership)?;
+        write!(
+            f,
+            "Selection strategy: {}",
+            self.constraints.selection_strategy
+        )
+    }
+}
+
+/// Determines how a relay is chosen among the relays that match all other constraints.
+#[derive(Debug, Default, Copy, Clone, Eq, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+#[cfg_attr(feature = "clap", derive(clap::ValueEnum))]
+pub enum SelectionStrategy {
+    /// Pick a random relay, where relays with more capacity are more likely to be picked.
+    #[default]
+    Random,
+    /// Pick the relay with the lowest measured latency. Relays are picked at random until
+    /// latencies have been measured.
+    #[cfg_attr(feature = "clap", clap(name = "fastest"))]
+    LowestLatency,
+}
+
+impl Intersection for SelectionStrategy {
+    fn intersection(self, other: Self) -> Option<Self>
+    where
+        Self: PartialEq,
+        Self: Sized,
+    {
+        // Random selection is the absence of a preference
+        match (self, other) {
+            (left, SelectionStrategy::Random) => Some(left),
+            (SelectionStrategy::Random, right) => Some(right),
+            (left, right) if left == right => Some(left),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for SelectionStrategy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SelectionStrategy::Random => f.write_str("random"),
+            SelectionStrategy::LowestLatency => f.write_str("lowest latency"),
+        }
     }
 }
 
@@ -1,4 +1,5 @@
 use crate::location::{CityCode, CountryCode, Location};
+use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use std::{
     collections::HashSet,
@@ -254,6 +255,19 @@ impl std::hash::Hash for Relay {
     }
 }
 
+/// Recent connection failures of a single relay, as remembered by the relay selector.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct RelayHealth {
+    pub hostname: String,
+    /// Number of failures that are still remembered.
+    pub failures: u32,
+    /// When connecting to the relay most recently failed.
+    pub last_failure: DateTime<Utc>,
+    /// Factor that the weight of the relay is currently multiplied by. Decays back to 1.0 as the
+    /// failures are forgotten.
+    pub weight_factor: f64,
+}
+
 /// Specifies the type of a relay or relay-specific endpoint data.
 #[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
 #[serde(rename_all = "snake_case")]
@@ -1,6 +1,8 @@
 use serde::{Deserialize, Serialize};
 use std::net::IpAddr;
 
+pub use talpid_types::net::dns::{DomainDnsRule, EncryptedDnsProtocol, EncryptedDnsServer};
+
 #[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize, Hash)]
 #[serde(rename_all = "snake_case")]
 pub enum DnsState {
@@ -16,6 +18,10 @@ pub struct DnsOptions {
     pub state: DnsState,
     pub default_options: DefaultDnsOptions,
     pub custom_options: CustomDnsOptions,
+    /// Per-domain DNS rules. Queries for a domain that matches a rule are sent to the servers of
+    /// the rule, and all other queries to the servers selected by `state`. Only supported on Linux.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub domain_rules: Vec<DomainDnsRule>,
 }
 
 /// Default DNS config
@@ -34,6 +40,10 @@ pub struct DefaultDnsOptions {
 #[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize, Hash)]
 pub struct CustomDnsOptions {
     pub addresses: Vec<IpAddr>,
+    /// DNS-over-HTTPS and DNS-over-TLS servers. If any are set, they are used instead of
+    /// `addresses`. Only supported on Linux.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub encrypted_servers: Vec<EncryptedDnsServer>,
 }
 
 impl DefaultDnsOptions {
@@ -1,7 +1,9 @@
 use crate::{
     access_method,
+    auto_connect::AutoConnectRule,
     constraints::Constraint,
     custom_list::CustomListsSettings,
+    profile::SettingsProfile,
     relay_constraints::{
         BridgeSettings, BridgeState, GeographicLocationConstraint, LocationConstraint,
         ObfuscationSettings, RelayConstraints, RelayOverride, RelaySettings,
@@ -10,9 +12,8 @@ use crate::{
     wireguard,
 };
 use serde::{Deserialize, Deserializer, Serialize, Serializer};
-#[cfg(any(windows, target_os = "android", target_os = "macos"))]
 use std::collections::HashSet;
-use talpid_types::net::{GenericTunnelOptions, openvpn};
+use talpid_types::net::{AllowedInterface, FirewallException, GenericTunnelOptions, openvpn};
 
 mod dns;
 
@@ -83,12 +84,21 @@ pub struct Settings {
     pub api_access_methods: access_method::Settings,
     /// If the daemon should allow communication with private (LAN) networks.
     pub allow_lan: bool,
+    /// User-defined exceptions that allow traffic to specific networks outside the tunnel. Only
+    /// supported on Linux.
+    pub firewall_exceptions: Vec<FirewallException>,
+    /// Interfaces of other VPNs or overlay networks, such as Tailscale or Docker bridges, that are
+    /// neither blocked nor routed through the tunnel. Only supported on Linux.
+    pub allowed_interfaces: Vec<AllowedInterface>,
     /// Extra level of kill switch. When this setting is on, the disconnected state will block
     /// the firewall to not allow any traffic in or out.
     #[cfg(not(target_os = "android"))]
     pub block_when_disconnected: bool,
     /// If the daemon should connect the VPN tunnel directly on start or not.
     pub auto_connect: bool,
+    /// Rules that connect or disconnect the tunnel when joining specific networks. These take
+    /// precedence over `auto_connect`.
+    pub auto_connect_rules: Vec<AutoConnectRule>,
     /// Options that should be applied to tunnels of a specific type regardless of where the relays
     /// might be located.
     pub tunnel_options: TunnelOptions,
@@ -97,12 +107,13 @@ pub struct Settings {
     /// Whether to notify users of beta updates.
     pub show_beta_releases: bool,
     /// Split tunneling settings
-    #[cfg(any(windows, target_os = "android", target_os = "macos"))]
     pub split_tunnel: SplitTunnelSettings,
     /// Specifies settings schema version
     pub settings_version: SettingsVersion,
     /// Stores the user's recently connected locations. If None recents have been disabled by the user.
     pub recents: Option<Vec<Recent>>,
+    /// Named sets of settings that the user can switch between.
+    pub profiles: Vec<SettingsProfile>,
 }
 
 #[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
@@ -153,7 +164,6 @@ impl TryFrom<&RelaySettings> for Recent {
     }
 }
 
-#[cfg(any(windows, target_os = "android", target_os = "macos"))]
 #[derive(Debug, Clone, Default, Deserialize, Serialize, PartialEq)]
 pub struct SplitTunnelSettings {
     /// Toggles split tunneling on or off
@@ -163,7 +173,7 @@ pub struct SplitTunnelSettings {
 }
 
 /// An application whose traffic should be excluded from any active tunnel.
-#[cfg(any(windows, target_os = "macos"))]
+#[cfg(any(windows, target_os = "linux", target_os = "macos"))]
 #[derive(Debug, Clone, Eq, Hash, PartialEq, Serialize, Deserialize)]
 pub struct SplitApp(std::path::PathBuf);
 
@@ -172,7 +182,7 @@ pub struct SplitApp(std::path::PathBuf);
 #[derive(Debug, Clone, Eq, Hash, PartialEq, Serialize, Deserialize)]
 pub struct SplitApp(String);
 
-#[cfg(any(windows, target_os = "macos"))]
+#[cfg(any(windows, target_os = "linux", target_os = "macos"))]
 impl SplitApp {
     /// Convert the underlying path to a [`String`].
     /// This function will fail if the underlying path string is not valid UTF-8. See
@@ -208,14 +218,14 @@ impl SplitApp {
     }
 }
 
-#[cfg(any(windows, target_os = "macos"))]
+#[cfg(any(windows, target_os = "linux", target_os = "macos"))]
 impl From<String> for SplitApp {
     fn from(value: String) -> Self {
         SplitApp::from(std::path::PathBuf::from(value))
     }
 }
 
-#[cfg(any(windows, target_os = "macos"))]
+#[cfg(any(windows, target_os = "linux", target_os = "macos"))]
 impl From<std::path::PathBuf> for SplitApp {
     fn from(value: std::path::PathBuf) -> Self {
         SplitApp(value)
@@ -253,16 +263,19 @@ impl Default for Settings {
             custom_lists: CustomListsSettings::default(),
             api_access_methods: access_method::Settings::default(),
             allow_lan: false,
+            firewall_exceptions: vec![],
+            allowed_interfaces: vec![],
             #[cfg(not(target_os = "android"))]
             block_when_disconnected: false,
             auto_connect: false,
+            auto_connect_rules: vec![],
             tunnel_options: TunnelOptions::default(),
             relay_overrides: vec![],
             show_beta_releases: false,
-            #[cfg(any(windows, target_os = "android", target_os = "macos"))]
             split_tunnel: SplitTunnelSettings::default(),
             settings_version: CURRENT_SETTINGS_VERSION,
             recents: Some(vec![]),
+            profiles: vec![],
         }
     }
 }
@@ -276,6 +289,53 @@ impl Settings {
         self.relay_settings.clone()
     }
 
+    /// Return the firewall exceptions that can be applied. Exceptions are validated when they are
+    /// added, but may have been read from an invalid settings file.
+    pub fn valid_firewall_exceptions(&self) -> Vec<FirewallException> {
+        self.firewall_exceptions
+            .iter()
+            .filter(|exception| match exception.validate() {
+                Ok(()) => true,
+                Err(error) => {
+                    log::warn!("Ignoring firewall exception \"{exception}\": {error}");
+                    false
+                }
+            })
+            .cloned()
+            .collect()
+    }
+
+    /// Return the allowed interfaces that can be applied. Interfaces are validated when they are
+    /// added, but may have been read from an invalid settings file.
+    pub fn valid_allowed_interfaces(&self) -> Vec<AllowedInterface> {
+        self.allowed_interfaces
+            .iter()
+            .filter(|interface| match interface.validate() {
+                Ok(()) => true,
+                Err(error) => {
+                    log::warn!("Ignoring allowed interface \"{interface}\": {error}");
+                    false
+                }
+            })
+            .cloned()
+            .collect()
+    }
+
+    /// Remove secrets, such as private keys and proxy passwords, from the settings. This is used to
+    /// show the settings to users that are not allowed to change them.
+    pub fn redact_secrets(&mut self) {
+        if let RelaySettings::CustomTunnelEndpoint(endpoint) = &mut self.relay_settings {
+            endpoint.redact_secrets();
+        }
+        for profile in &mut self.profiles {
+            profile.redact_secrets();
+        }
+        if let Some(proxy) = &mut self.bridge_settings.custom {
+            proxy.redact_secrets();
+        }
+        self.api_access_methods.redact_secrets();
+    }
+
     pub fn set_relay_settings(&mut self, new_settings: RelaySettings) {
         if self.relay_settings != new_settings {
             if !new_settings.supports_bridge() && BridgeState::On == self.bridge_state {
@@ -349,7 +409,10 @@ pub struct TunnelOptions {
     pub dns_options: DnsOptions,
 }
 
-pub use dns::{CustomDnsOptions, DefaultDnsOptions, DnsOptions, DnsState};
+pub use dns::{
+    CustomDnsOptions, DefaultDnsOptions, DnsOptions, DnsState, DomainDnsRule, EncryptedDnsProtocol,
+    EncryptedDnsServer,
+};
 
 impl Default for TunnelOptions {
     fn default() -> Self {
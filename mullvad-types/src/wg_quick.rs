@@ -0,0 +1,312 @@
+//! Import of WireGuard configuration files in the format used by `wg-quick`.
+//!
+//! Only the keys that describe the tunnel are used. Keys that only affect how `wg-quick` sets up
+//! the interface, such as `PostUp`, `Table` or `MTU`, are ignored, since the tunnel is managed by
+//! the daemon.
+//!
+//! `wg-quick` routes traffic to all peers at once, based on their allowed IPs. Here, only the
+//! first peer is used, and any other peers are fallback peers that are switched to if the current
+//! peer stops responding.
+
+use crate::{ConnectionConfig, CustomTunnelEndpoint, FallbackPeer};
+use ipnetwork::IpNetwork;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+use talpid_types::net::wireguard::{self, PresharedKey, PrivateKey, PublicKey};
+
+#[derive(thiserror::Error, Debug)]
+pub enum Error {
+    #[error("Invalid line {0}")]
+    InvalidLine(usize),
+
+    #[error("Unknown section on line {0}")]
+    UnknownSection(usize),
+
+    #[error("Key outside of a section on line {0}")]
+    KeyOutsideSection(usize),
+
+    #[error("Invalid value for {key} on line {line}")]
+    InvalidValue { key: String, line: usize },
+
+    #[error("Missing [{0}] section")]
+    MissingSection(&'static str),
+
+    #[error("Missing key: {0}")]
+    MissingKey(&'static str),
+
+    #[error("No IPv4 gateway. It must be given when there is no IPv4 DNS server")]
+    MissingGateway,
+}
+
+/// The contents of a `wg-quick` configuration file.
+#[derive(Debug, Clone, PartialEq)]
+pub struct WgQuickConfig {
+    pub private_key: PrivateKey,
+    /// Addresses of the tunnel interface.
+    pub addresses: Vec<IpAddr>,
+    /// DNS servers. Search domains are ignored.
+    pub dns_servers: Vec<IpAddr>,
+    /// Peers in the order that they appear in the file.
+    pub peers: Vec<WgQuickPeer>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct WgQuickPeer {
+    pub public_key: PublicKey,
+    pub psk: Option<PresharedKey>,
+    pub allowed_ips: Vec<IpNetwork>,
+    /// Hostname or IP of the peer.
+    pub host: String,
+    pub port: u16,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Section {
+    Interface,
+    Peer,
+}
+
+impl WgQuickConfig {
+    pub fn parse(config: &str) -> Result<Self, Error> {
+        let mut section = None;
+
+        let mut private_key = None;
+        let mut addresses = vec![];
+        let mut dns_servers = vec![];
+        let mut peers = vec![];
+        let mut peer = PeerBuilder::default();
+
+        for (index, line) in config.lines().enumerate() {
+            let line_number = index + 1;
+            let line = line.split('#').next().unwrap_or_default().trim();
+            if line.is_empty() {
+                continue;
+            }
+
+            if let Some(name) = line.strip_prefix('[').and_then(|s| s.strip_suffix(']')) {
+                if section == Some(Section::Peer) {
+                    peers.push(std::mem::take(&mut peer).build()?);
+                }
+                section = match name.trim().to_lowercase().as_str() {
+                    "interface" => Some(Section::Interface),
+                    "peer" => Some(Section::Peer),
+                    _ => return Err(Error::UnknownSection(line_number)),
+                };
+                continue;
+            }
+
+            let (key, value) = line
+                .split_once('=')
+                .ok_or(Error::InvalidLine(line_number))?;
+            let key = key.trim();
+            let value = value.trim();
+            let invalid_value = || Error::InvalidValue {
+                key: key.to_owned(),
+                line: line_number,
+            };
+
+            let section = section.ok_or(Error::KeyOutsideSection(line_number))?;
+            match (section, key.to_lowercase().as_str()) {
+                (Section::Interface, "privatekey") => {
+                    private_key =
+                        Some(PrivateKey::from_base64(value).map_err(|_| invalid_value())?);
+                }
+                (Section::Interface, "address") => {
+                    for address in split_list(value) {
+                        let address: IpNetwork = address.parse().map_err(|_| invalid_value())?;
+                        addresses.push(address.ip());
+                    }
+                }
+                (Section::Interface, "dns") => {
+                    dns_servers.extend(split_list(value).filter_map(|server| server.parse().ok()));
+                }
+                (Section::Peer, "publickey") => {
+                    peer.public_key =
+                        Some(PublicKey::from_base64(value).map_err(|_| invalid_value())?);
+                }
+                (Section::Peer, "presharedkey") => {
+                    peer.psk = Some(PresharedKey::from_base64(value).map_err(|_| invalid_value())?);
+                }
+                (Section::Peer, "allowedips") => {
+                    for network in split_list(value) {
+                        peer.allowed_ips
+                            .push(network.parse().map_err(|_| invalid_value())?);
+                    }
+                }
+                (Section::Peer, "endpoint") => {
+                    peer.endpoint = Some(parse_endpoint(value).ok_or_else(invalid_value)?);
+                }
+                _ => log::debug!("Ignoring key {key} on line {line_number}"),
+            }
+        }
+
+        if section == Some(Section::Peer) {
+            peers.push(peer.build()?);
+        }
+        if peers.is_empty() {
+            return Err(Error::MissingSection("Peer"));
+        }
+
+        Ok(WgQuickConfig {
+            private_key: private_key.ok_or(Error::MissingKey("PrivateKey"))?,
+            addresses,
+            dns_servers,
+            peers,
+        })
+    }
+
+    /// Create a custom tunnel endpoint from the configuration. If no gateways are given, the first
+    /// DNS servers of each IP version are used, which is where self-hosted servers usually run
+    /// their resolver.
+    pub fn into_custom_tunnel_endpoint(
+        self,
+        ipv4_gateway: Option<Ipv4Addr>,
+        ipv6_gateway: Option<Ipv6Addr>,
+    ) -> Result<CustomTunnelEndpoint, Error> {
+        if self.addresses.is_empty() {
+            return Err(Error::MissingKey("Address"));
+        }
+        let ipv4_gateway = ipv4_gateway
+            .or_else(|| {
+                self.dns_servers.iter().find_map(|server| match server {
+                    IpAddr::V4(server) => Some(*server),
+                    IpAddr::V6(_) => None,
+                })
+            })
+            .ok_or(Error::MissingGateway)?;
+        let ipv6_gateway = ipv6_gateway.or_else(|| {
+            self.dns_servers.iter().find_map(|server| match server {
+                IpAddr::V6(server) => Some(*server),
+                IpAddr::V4(_) => None,
+            })
+        });
+
+        let mut peers = self.peers.into_iter();
+        let peer = peers.next().ok_or(Error::MissingSection("Peer"))?;
+        let config = wireguard::ConnectionConfig {
+            tunnel: wireguard::TunnelConfig {
+                private_key: self.private_key,
+                addresses: self.addresses,
+            },
+            peer: wireguard::PeerConfig {
+                public_key: peer.public_key,
+                allowed_ips: peer.allowed_ips,
+                // The host is resolved when connecting
+                endpoint: SocketAddr::new(IpAddr::V4(Ipv4Addr::UNSPECIFIED), peer.port),
+                psk: None,
+                #[cfg(daita)]
+                constant_packet_size: false,
+            },
+            exit_peer: None,
+            ipv4_gateway,
+            ipv6_gateway,
+            #[cfg(target_os = "linux")]
+            fwmark: Some(crate::TUNNEL_FWMARK),
+        };
+
+        Ok(CustomTunnelEndpoint {
+            host: peer.host,
+            config: ConnectionConfig::Wireguard(config),
+            psk: peer.psk,
+            dns_servers: self.dns_servers,
+            fallback_peers: peers
+                .map(|peer| FallbackPeer {
+                    host: peer.host,
+                    port: peer.port,
+                    public_key: peer.public_key,
+                    psk: peer.psk,
+                    allowed_ips: peer.allowed_ips,
+                })
+                .collect(),
+        })
+    }
+}
+
+/// Keys of a `[Peer]` section that have been read so far.
+#[derive(Default)]
+struct PeerBuilder {
+    public_key: Option<PublicKey>,
+    psk: Option<PresharedKey>,
+    allowed_ips: Vec<IpNetwork>,
+    endpoint: Option<(String, u16)>,
+}
+
+impl PeerBuilder {
+    fn build(self) -> Result<WgQuickPeer, Error> {
+        let (host, port) = self.endpoint.ok_or(Error::MissingKey("Endpoint"))?;
+        Ok(WgQuickPeer {
+            public_key: self.public_key.ok_or(Error::MissingKey("PublicKey"))?,
+            psk: self.psk,
+            allowed_ips: self.allowed_ips,
+            host,
+            port,
+        })
+    }
+}
+
+fn split_list(value: &str) -> impl Iterator<Item = &str> {
+    value
+        .split(',')
+        .map(str::trim)
+        .filter(|item| !item.is_empty())
+}
+
+/// Parse `host:port`, where IPv6 hosts are enclosed in brackets.
+fn parse_endpoint(endpoint: &str) -> Option<(String, u16)> {
+    let (host, port) = endpoint.rsplit_once(':')?;
+    let host = match host.strip_prefix('[') {
+        Some(host) => host.strip_suffix(']')?,
+        None if host.contains(':') => return None,
+        None => host,
+    };
+    if host.is_empty() {
+        return None;
+    }
+    Some((host.to_owned(), port.parse().ok()?))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const CONFIG: &str = r#"
+        [Interface]
+        PrivateKey = mPzI8CrXCXnNC1xTvOn5qh5jWvLm2B0/3D4lDMcfomk=
+        Address = 10.8.0.2/24, fd00::2/64
+        DNS = 10.8.0.1, fd00::1, home.arpa
+        MTU = 1420 # Ignored
+
+        [Peer]
+        PublicKey = 6oeNfINvBoTsnF/4EAfhUbl8tQjH6Tt/A6qz38yNZUw=
+        PresharedKey = bt8SOIcawWq5vHX3ZfqxJfJ64Rfz7QI5x9QoG/vX6XE=
+        AllowedIPs = 0.0.0.0/0,::/0
+        Endpoint = [2001:db8::1]:51820
+        PersistentKeepalive = 25
+
+        [Peer]
+        PublicKey = 6oeNfINvBoTsnF/4EAfhUbl8tQjH6Tt/A6qz38yNZUw=
+        AllowedIPs = 0.0.0.0/0
+        Endpoint = backup.example.com:51820
+    "#;
+
+    #[test]
+    fn test_parse_wg_quick_config() {
+        let config = WgQuickConfig::parse(CONFIG).unwrap();
+        assert_eq!(config.addresses.len(), 2);
+        assert_eq!(config.dns_servers.len(), 2);
+        assert_eq!(config.peers.len(), 2);
+        assert_eq!(config.peers[0].allowed_ips.len(), 2);
+        assert_eq!(config.peers[0].host, "2001:db8::1");
+        assert_eq!(config.peers[0].port, 51820);
+        assert!(config.peers[0].psk.is_some());
+
+        let endpoint = config.into_custom_tunnel_endpoint(None, None).unwrap();
+        assert!(endpoint.psk.is_some());
+        assert_eq!(endpoint.fallback_peers.len(), 1);
+        assert_eq!(endpoint.fallback_peers[0].host, "backup.example.com");
+        let ConnectionConfig::Wireguard(connection) = endpoint.config else {
+            panic!("expected WireGuard config");
+        };
+        assert_eq!(connection.ipv4_gateway, Ipv4Addr::new(10, 8, 0, 1));
+        assert_eq!(connection.ipv6_gateway, Some("fd00::1".parse().unwrap()));
+    }
+}
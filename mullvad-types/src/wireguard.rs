@@ -251,6 +251,8 @@ pub struct TunnelOptions {
     pub daita: DaitaSettings,
     /// Interval used for automatic key rotation
     pub rotation_interval: Option<RotationInterval>,
+    /// WireGuard implementation to use
+    pub backend: wireguard::Backend,
 }
 
 #[allow(clippy::derivable_impls)]
@@ -262,6 +264,7 @@ impl Default for TunnelOptions {
             #[cfg(daita)]
             daita: DaitaSettings::default(),
             rotation_interval: None,
+            backend: wireguard::Backend::Auto,
         }
     }
 }
@@ -273,6 +276,7 @@ impl TunnelOptions {
             quantum_resistant: self.quantum_resistant.enabled(),
             #[cfg(daita)]
             daita: self.daita.enabled,
+            backend: self.backend,
         }
     }
 }
@@ -291,3 +295,104 @@ pub struct AssociatedAddresses {
     pub ipv4_address: ipnetwork::Ipv4Network,
     pub ipv6_address: ipnetwork::Ipv6Network,
 }
+
+/// Traffic statistics and throughput for a single WireGuard peer.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct PeerStats {
+    pub public_key: wireguard::PublicKey,
+    /// Total number of bytes sent to the peer.
+    pub tx_bytes: u64,
+    /// Total number of bytes received from the peer.
+    pub rx_bytes: u64,
+    /// Bytes sent per second since the previous sample.
+    pub tx_rate: f64,
+    /// Bytes received per second since the previous sample.
+    pub rx_rate: f64,
+    /// Time of the most recent successful handshake with the peer, if any.
+    pub last_handshake: Option<DateTime<Utc>>,
+}
+
+impl PeerStats {
+    /// Create stats from a new sample. Throughput is computed relative to `previous` if it
+    /// belongs to the same peer, and is zero otherwise.
+    pub fn from_sample(
+        sample: wireguard::PeerStats,
+        previous: Option<&PeerStats>,
+        elapsed: Duration,
+    ) -> Self {
+        let (tx_rate, rx_rate) = match previous {
+            Some(previous) if previous.public_key == sample.public_key && !elapsed.is_zero() => {
+                let secs = elapsed.as_secs_f64();
+                (
+                    sample.tx_bytes.saturating_sub(previous.tx_bytes) as f64 / secs,
+                    sample.rx_bytes.saturating_sub(previous.rx_bytes) as f64 / secs,
+                )
+            }
+            _ => (0.0, 0.0),
+        };
+        PeerStats {
+            public_key: sample.public_key,
+            tx_bytes: sample.tx_bytes,
+            rx_bytes: sample.rx_bytes,
+            tx_rate,
+            rx_rate,
+            last_handshake: sample.last_handshake.map(DateTime::<Utc>::from),
+        }
+    }
+}
+
+/// Traffic statistics for the peers of the active WireGuard tunnel.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct TunnelStats {
+    /// Entry peer. Only set when multihop is used.
+    pub entry: Option<PeerStats>,
+    pub exit: Option<PeerStats>,
+}
+
+impl TunnelStats {
+    /// Create stats from a new sample, computing throughput relative to the `previous` stats.
+    pub fn from_sample(
+        sample: wireguard::TunnelStats,
+        previous: Option<&TunnelStats>,
+        elapsed: Duration,
+    ) -> Self {
+        TunnelStats {
+            entry: sample.entry.map(|entry| {
+                let previous = previous.and_then(|previous| previous.entry.as_ref());
+                PeerStats::from_sample(entry, previous, elapsed)
+            }),
+            exit: sample.exit.map(|exit| {
+                let previous = previous.and_then(|previous| previous.exit.as_ref());
+                PeerStats::from_sample(exit, previous, elapsed)
+            }),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn sample(tx_bytes: u64, rx_bytes: u64) -> wireguard::PeerStats {
+        wireguard::PeerStats {
+            public_key: wireguard::PublicKey::from([1u8; 32]),
+            tx_bytes,
+            rx_bytes,
+            last_handshake: None,
+        }
+    }
+
+    #[test]
+    fn test_peer_stats_rates() {
+        let first = PeerStats::from_sample(sample(1000, 2000), None, Duration::from_secs(2));
+        assert_eq!((first.tx_rate, first.rx_rate), (0.0, 0.0));
+
+        let second =
+            PeerStats::from_sample(sample(3000, 6000), Some(&first), Duration::from_secs(2));
+        assert_eq!((second.tx_rate, second.rx_rate), (1000.0, 2000.0));
+
+        // Counters that are reset, e.g. after a reconnect, should not produce negative rates
+        let third = PeerStats::from_sample(sample(0, 0), Some(&second), Duration::from_secs(1));
+        assert_eq!((third.tx_rate, third.rx_rate), (0.0, 0.0));
+    }
+}
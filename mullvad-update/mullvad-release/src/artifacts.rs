@@ -44,9 +44,19 @@ pub async fn generate_installer_details(
         urls,
         size: file_size.try_into().context("Invalid file size")?,
         sha256: hex::encode(checksum),
+        package: package_format(artifact),
     })
 }
 
+/// Return the Linux package format of `artifact`, based on its file extension
+fn package_format(artifact: &Path) -> Option<format::PackageFormat> {
+    match artifact.extension()?.to_str()? {
+        "deb" => Some(format::PackageFormat::Deb),
+        "rpm" => Some(format::PackageFormat::Rpm),
+        _ => None,
+    }
+}
+
 fn derive_urls(
     base_urls: &[String],
     version: &mullvad_version::Version,
@@ -5,7 +5,7 @@
 
 use anyhow::{Context, bail};
 use clap::Parser;
-use std::str::FromStr;
+use std::{path::PathBuf, str::FromStr};
 
 use config::Config;
 use io_util::create_dir_and_write;
@@ -17,6 +17,7 @@ mod artifacts;
 mod config;
 mod github;
 mod io_util;
+mod mirror;
 mod platform;
 
 /// Metadata expiry to use when not specified (months from now)
@@ -99,11 +100,34 @@ pub enum Opt {
         assume_yes: bool,
     },
 
-    /// Verify that payloads are signed by a given ed25519 pubkey
+    /// Add a signature to the metadata in `signed/`, without otherwise modifying it
+    /// A secret ed25519 key will be read from stdin
+    Cosign {
+        /// Platforms to add signatures for. All if none are specified
+        platforms: Vec<Platform>,
+    },
+
+    /// Verify that payloads are signed by enough of the trusted ed25519 pubkeys
     Verify {
         /// Platforms to remove releases for. All if none are specified
         platforms: Vec<Platform>,
     },
+
+    /// Download the installers in `signed/` and write metadata for a self-hosted mirror
+    /// A secret ed25519 key, trusted by the clients that use the mirror, will be read from stdin
+    Mirror {
+        /// Platforms to mirror. All if none are specified
+        platforms: Vec<Platform>,
+        /// URL that `out_dir` will be served from
+        #[arg(long)]
+        base_url: String,
+        /// Directory to write the mirror to
+        #[arg(long, default_value = "mirror")]
+        out_dir: PathBuf,
+        /// Versions to mirror. All if none are specified
+        #[arg(long = "version")]
+        versions: Vec<mullvad_version::Version>,
+    },
 }
 
 #[tokio::main]
@@ -158,6 +182,20 @@ async fn main() -> anyhow::Result<()> {
             }
             Ok(())
         }
+        Opt::Cosign { platforms } => {
+            let key_str = io_util::wait_for_input("Enter ed25519 secret: ")
+                .await
+                .context("Failed to read secret from stdin")?;
+            let secret = key::SecretKey::from_str(&key_str).context("Invalid secret")?;
+
+            for platform in all_platforms_if_empty(platforms) {
+                platform
+                    .cosign(secret.clone())
+                    .await
+                    .context("Failed to add signature")?;
+            }
+            Ok(())
+        }
         Opt::ListReleases { platforms } => {
             for platform in all_platforms_if_empty(platforms) {
                 platform.list_releases().await?;
@@ -209,6 +247,25 @@ async fn main() -> anyhow::Result<()> {
             }
             Ok(())
         }
+        Opt::Mirror {
+            platforms,
+            base_url,
+            out_dir,
+            versions,
+        } => {
+            let key_str = io_util::wait_for_input("Enter ed25519 secret: ")
+                .await
+                .context("Failed to read secret from stdin")?;
+            let secret = key::SecretKey::from_str(&key_str).context("Invalid secret")?;
+
+            for platform in all_platforms_if_empty(platforms) {
+                platform
+                    .mirror(secret.clone(), &out_dir, &base_url, &versions)
+                    .await
+                    .with_context(|| format!("Failed to mirror {platform}"))?;
+            }
+            Ok(())
+        }
     }
 }
 
@@ -0,0 +1,112 @@
+//! Create a mirror of signed version metadata and its installers.
+//!
+//! A mirror lets a site without access to the Mullvad CDN serve app updates itself. The mirror
+//! directory has the same layout as the CDN, i.e. `<version>/<filename>`, along with a metadata
+//! file for each platform. The metadata is identical to the verified upstream metadata, except
+//! that the installer URLs on the mirror are listed first, and that it is signed by a key owned by
+//! the site. Clients must be configured to trust that key.
+
+use anyhow::{Context, bail};
+use mullvad_update::{
+    fetch::{self, ProgressUpdater},
+    format, hash,
+};
+use std::path::Path;
+use tokio::{fs, io::BufReader};
+
+/// Download `installer` to `out_dir`, unless it has been mirrored already, and add its URL on the
+/// mirror to `installer.urls`.
+pub async fn mirror_installer(
+    installer: &mut format::Installer,
+    version: &mullvad_version::Version,
+    out_dir: &Path,
+    base_url: &str,
+) -> anyhow::Result<()> {
+    let filename = installer
+        .urls
+        .iter()
+        .find_map(|url| url.rsplit_once('/').map(|(_, filename)| filename))
+        .filter(|filename| !filename.is_empty())
+        .context("Installer has no URLs")?
+        .to_owned();
+    let path = out_dir.join(version.to_string()).join(&filename);
+
+    if verify_installer(installer, &path).await.is_err() {
+        download_installer(installer, &path).await?;
+        verify_installer(installer, &path)
+            .await
+            .with_context(|| format!("Failed to verify {}", path.display()))?;
+    } else {
+        println!("{} has already been mirrored", path.display());
+    }
+
+    let base_url = base_url.strip_suffix('/').unwrap_or(base_url);
+    let mirror_url = format!("{base_url}/{version}/{filename}");
+    if !installer.urls.contains(&mirror_url) {
+        installer.urls.insert(0, mirror_url);
+    }
+    Ok(())
+}
+
+/// Download `installer` to `path` from the first URL that succeeds
+async fn download_installer(installer: &format::Installer, path: &Path) -> anyhow::Result<()> {
+    let parent_dir = path.parent().context("Missing parent directory")?;
+    fs::create_dir_all(parent_dir)
+        .await
+        .context("Failed to create directories")?;
+
+    for url in &installer.urls {
+        // Discard any partial or corrupt download
+        let _ = fs::remove_file(path).await;
+
+        println!("Downloading {url} to {}", path.display());
+        match fetch::get_to_file(
+            path,
+            url,
+            &mut PrintProgress::default(),
+            fetch::SizeHint::Exact(installer.size),
+        )
+        .await
+        {
+            Ok(()) => return Ok(()),
+            Err(error) => eprintln!("Failed to download {url}: {error:#}"),
+        }
+    }
+    bail!("Failed to download {}", path.display())
+}
+
+/// Fail unless the file at `path` matches the size and checksum of `installer`
+async fn verify_installer(installer: &format::Installer, path: &Path) -> anyhow::Result<()> {
+    let file = fs::File::open(path).await?;
+    let size = file.metadata().await?.len();
+    if size != installer.size as u64 {
+        bail!("File size mismatch");
+    }
+    let checksum = hash::checksum(BufReader::new(file)).await?;
+    if hex::encode(checksum) != installer.sha256.to_ascii_lowercase() {
+        bail!("Checksum mismatch");
+    }
+    Ok(())
+}
+
+/// Print download progress in increments of 10%
+#[derive(Default)]
+struct PrintProgress {
+    last_printed: Option<u32>,
+}
+
+impl ProgressUpdater for PrintProgress {
+    fn set_progress(&mut self, fraction_complete: f32) {
+        let percent = (fraction_complete * 10.) as u32 * 10;
+        if self.last_printed != Some(percent) {
+            println!("{percent}%");
+            self.last_printed = Some(percent);
+        }
+    }
+
+    fn clear_progress(&mut self) {
+        self.last_printed = None;
+    }
+
+    fn set_url(&mut self, _url: &str) {}
+}
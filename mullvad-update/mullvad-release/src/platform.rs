@@ -16,6 +16,7 @@ use tokio::{fs, io};
 use crate::{
     artifacts,
     io_util::{create_dir_and_write, wait_for_confirm},
+    mirror,
 };
 
 #[derive(Clone, Copy)]
@@ -81,8 +82,14 @@ impl Platform {
                 ],
             },
             Platform::Linux => Artifacts {
-                x86_artifacts: vec![],
-                arm64_artifacts: vec![],
+                x86_artifacts: vec![
+                    artifacts_dir.join(format!("MullvadVPN-{version}_amd64.deb")),
+                    artifacts_dir.join(format!("MullvadVPN-{version}_x86_64.rpm")),
+                ],
+                arm64_artifacts: vec![
+                    artifacts_dir.join(format!("MullvadVPN-{version}_arm64.deb")),
+                    artifacts_dir.join(format!("MullvadVPN-{version}_aarch64.rpm")),
+                ],
             },
             Platform::Macos => Artifacts {
                 x86_artifacts: vec![artifacts_dir.join(format!("MullvadVPN-{version}.pkg"))],
@@ -200,6 +207,32 @@ impl Platform {
         Ok(())
     }
 
+    /// Add a signature by `secret` to the file at `self.signed_path()`.
+    /// The signed data itself is left unchanged, so that it may be signed by multiple keys.
+    pub async fn cosign(&self, secret: key::SecretKey) -> anyhow::Result<()> {
+        let signed_path = self.signed_path();
+
+        println!("Adding signature to {}...", signed_path.display());
+
+        let data = fs::read(&signed_path).await?;
+        let mut response = format::SignedResponse::deserialize_insecure(&data)?;
+
+        response.add_signature(secret)?;
+        println!(
+            "{} now has {} signatures",
+            signed_path.display(),
+            response.signatures.len()
+        );
+
+        let signed_bytes = serde_json::to_string_pretty(&response)
+            .context("Failed to serialize signed version")?;
+        create_dir_and_write(&signed_path, signed_bytes)
+            .await
+            .context("Failed to write signed data")?;
+
+        Ok(())
+    }
+
     /// Verify the integrity of the platform in `signed/`
     pub async fn verify(&self) -> anyhow::Result<()> {
         let signed_path = self.signed_path();
@@ -210,7 +243,66 @@ impl Platform {
             &bytes,
             mullvad_update::version::MIN_VERIFY_METADATA_VERSION,
         )
-        .context("Failed to verify metadata for {platform}: {error}")?;
+        .with_context(|| {
+            format!(
+                "Failed to verify metadata for {self}. Published metadata must be signed by \
+                 multiple trusted keys, so add signatures using `cosign`"
+            )
+        })?;
+
+        Ok(())
+    }
+
+    /// Mirror the metadata in `signed/` and its installers to `out_dir`, which is served from
+    /// `base_url`. Only `versions` are included, or all releases if it is empty.
+    ///
+    /// The mirrored metadata keeps the metadata version and expiry of the original, but is signed
+    /// by `secret` instead.
+    pub async fn mirror(
+        &self,
+        secret: key::SecretKey,
+        out_dir: &Path,
+        base_url: &str,
+        versions: &[mullvad_version::Version],
+    ) -> anyhow::Result<()> {
+        let signed_path = self.signed_path();
+        println!(
+            "Mirroring {} to {}...",
+            signed_path.display(),
+            out_dir.display()
+        );
+
+        // Only mirror metadata that is signed by the trusted keys
+        let bytes = fs::read(&signed_path)
+            .await
+            .with_context(|| format!("Failed to read {}", signed_path.display()))?;
+        let mut response = format::SignedResponse::deserialize_and_verify(
+            &bytes,
+            mullvad_update::version::MIN_VERIFY_METADATA_VERSION,
+        )
+        .with_context(|| format!("Failed to verify {}", signed_path.display()))?;
+
+        if !versions.is_empty() {
+            response
+                .signed
+                .releases
+                .retain(|release| versions.contains(&release.version));
+        }
+
+        for release in &mut response.signed.releases {
+            for installer in &mut release.installers {
+                mirror::mirror_installer(installer, &release.version, out_dir, base_url).await?;
+            }
+        }
+
+        let signed_response = format::SignedResponse::sign(secret, response.signed)?;
+        let signed_bytes = serde_json::to_string_pretty(&signed_response)
+            .context("Failed to serialize signed version")?;
+        let mirror_path = out_dir.join(self.local_filename());
+        create_dir_and_write(&mirror_path, signed_bytes)
+            .await
+            .context("Failed to write signed data")?;
+        println!("Wrote signed response to {}", mirror_path.display());
 
         Ok(())
     }
@@ -94,7 +94,7 @@ impl From<MetaRepositoryPlatform> for HttpVersionInfoProvider {
 
 impl HttpVersionInfoProvider {
     /// Maximum size of the GET response, in bytes
-    const SIZE_LIMIT: usize = 1024 * 1024;
+    pub(crate) const SIZE_LIMIT: usize = 1024 * 1024;
 
     /// Retrieve version metadata for the given platform using reasonable defaults.
     ///
@@ -133,6 +133,7 @@ impl HttpVersionInfoProvider {
         self.get_versions_inner(|raw_json| {
             format::SignedResponse::deserialize_and_verify_at_time(
                 verifying_keys,
+                1,
                 raw_json,
                 chrono::DateTime::UNIX_EPOCH,
                 lowest_metadata_version,
@@ -161,7 +162,7 @@ impl HttpVersionInfoProvider {
     /// `url` - URL to fetch
     /// `pinned_certificate` - Optional pinned certificate for TLS verification
     /// `resolve` - Optional host to resolve (to the IP) without DNS
-    async fn get(
+    pub(crate) async fn get(
         url: &str,
         pinned_certificate: Option<reqwest::Certificate>,
         resolve: Option<(&'static str, IpAddr)>,
@@ -275,6 +276,7 @@ mod test {
         let cached_data = fs::read(temp_dump).await.expect("expected dumped info");
         let cached_info = SignedResponse::deserialize_and_verify_at_time(
             &verifying_keys,
+            1,
             &cached_data,
             chrono::DateTime::UNIX_EPOCH,
             0,
@@ -1,17 +1,22 @@
-#![cfg(any(target_os = "macos", target_os = "windows"))]
+#![cfg(any(target_os = "macos", target_os = "windows", target_os = "linux"))]
 
 //! This module implements the flow of downloading and verifying the app.
 
+#[cfg(target_os = "linux")]
+use std::sync::Arc;
 use std::{
-    ffi::OsString,
     future::Future,
     path::{Path, PathBuf},
     time::Duration,
 };
 
+#[cfg(any(target_os = "macos", target_os = "windows"))]
 use anyhow::{Context, bail};
 use tokio::{process::Command, time::timeout};
 
+#[cfg(target_os = "linux")]
+use crate::{format::PackageFormat, package::PackageInstaller};
+
 use crate::{
     fetch::{self, ProgressUpdater},
     format::SignedResponse,
@@ -44,6 +49,9 @@ pub struct AppDownloaderParameters<AppProgress> {
     /// Directory to store the installer in.
     /// Ensure that this has proper permissions set.
     pub cache_dir: PathBuf,
+    /// Hook used to install the downloaded package
+    #[cfg(target_os = "linux")]
+    pub package_installer: Arc<dyn PackageInstaller>,
 }
 
 /// See the [module-level documentation](self).
@@ -76,7 +84,13 @@ pub trait AppCache: Send {
 }
 
 /// How long to wait for the installer to exit before returning
+#[cfg(any(target_os = "macos", target_os = "windows"))]
 const INSTALLER_STARTUP_TIMEOUT: Duration = Duration::from_millis(500);
+/// How long to wait for the package manager to exit before returning. The install command only
+/// exits once the package manager has finished, so failures that happen before this are reported.
+/// If the package is installed, the daemon is restarted before this returns.
+#[cfg(target_os = "linux")]
+const INSTALLER_STARTUP_TIMEOUT: Duration = Duration::from_secs(10 * 60);
 
 /// Download the app and signature, and verify the installer's signature
 pub async fn download_install_and_upgrade(
@@ -121,11 +135,18 @@ pub struct InstallerFile<const VERIFIED: bool> {
     pub app_version: mullvad_version::Version,
     pub app_size: usize,
     pub app_sha256: [u8; 32],
+    #[cfg(target_os = "linux")]
+    package_installer: Arc<dyn PackageInstaller>,
 }
 
 impl<AppProgress: ProgressUpdater> AppDownloader for HttpAppDownloader<AppProgress> {
     async fn download_executable(mut self) -> Result<impl DownloadedInstaller, DownloadError> {
-        let bin_path = bin_path(&self.params.app_version, &self.params.cache_dir);
+        let bin_path = bin_path(
+            &self.params.app_version,
+            &self.params.cache_dir,
+            #[cfg(target_os = "linux")]
+            self.params.package_installer.package_format(),
+        );
         fetch::get_to_file(
             &bin_path,
             &self.params.app_url,
@@ -140,6 +161,8 @@ impl<AppProgress: ProgressUpdater> AppDownloader for HttpAppDownloader<AppProgre
             app_version: self.params.app_version,
             app_size: self.params.app_size,
             app_sha256: self.params.app_sha256,
+            #[cfg(target_os = "linux")]
+            package_installer: self.params.package_installer,
         })
     }
 }
@@ -156,6 +179,8 @@ impl DownloadedInstaller for InstallerFile<false> {
                 app_version: self.app_version,
                 app_size: self.app_size,
                 app_sha256: self.app_sha256,
+                #[cfg(target_os = "linux")]
+                package_installer: self.package_installer,
             }),
             // Verification failed
             Err(err) => {
@@ -173,12 +198,11 @@ impl DownloadedInstaller for InstallerFile<false> {
 
 impl VerifiedInstaller for InstallerFile<true> {
     async fn install(self) -> Result<(), DownloadError> {
-        let launch_path = &self.launch_path();
-
         // Launch process
-        let mut cmd = Command::new(launch_path);
-        cmd.args(self.launch_args());
-        let mut child = cmd.spawn().map_err(DownloadError::Launch)?;
+        let mut child = self
+            .launch_command()
+            .spawn()
+            .map_err(DownloadError::Launch)?;
 
         // Wait to see if the installer fails
         match timeout(INSTALLER_STARTUP_TIMEOUT, child.wait()).await {
@@ -194,16 +218,24 @@ impl VerifiedInstaller for InstallerFile<true> {
     }
 }
 
-pub fn bin_path(app_version: &mullvad_version::Version, cache_dir: &Path) -> PathBuf {
+pub fn bin_path(
+    app_version: &mullvad_version::Version,
+    cache_dir: &Path,
+    #[cfg(target_os = "linux")] package_format: PackageFormat,
+) -> PathBuf {
     #[cfg(windows)]
     let bin_filename = format!("mullvad-{app_version}.exe");
 
     #[cfg(target_os = "macos")]
     let bin_filename = format!("mullvad-{app_version}.pkg");
 
+    #[cfg(target_os = "linux")]
+    let bin_filename = format!("mullvad-{app_version}.{}", package_format.extension());
+
     cache_dir.join(bin_filename)
 }
 
+#[cfg(any(target_os = "macos", target_os = "windows"))]
 impl InstallerFile<false> {
     /// Create an unverified [InstallerFile] from a cache_dir and some metadata.
     pub fn try_from_installer(
@@ -232,29 +264,22 @@ impl InstallerFile<false> {
 }
 
 impl InstallerFile<true> {
-    fn launch_path(&self) -> PathBuf {
+    fn launch_command(&self) -> Command {
         #[cfg(target_os = "windows")]
         {
-            self.path.clone()
+            Command::new(&self.path)
         }
 
         #[cfg(target_os = "macos")]
         {
-            use std::path::Path;
-
-            Path::new("/usr/bin/open").to_owned()
-        }
-    }
-
-    fn launch_args(&self) -> Vec<OsString> {
-        #[cfg(target_os = "windows")]
-        {
-            vec![]
+            let mut cmd = Command::new("/usr/bin/open");
+            cmd.arg(&self.path);
+            cmd
         }
 
-        #[cfg(target_os = "macos")]
+        #[cfg(target_os = "linux")]
         {
-            vec![self.path.clone().into_os_string()]
+            self.package_installer.install_command(&self.path)
         }
     }
 }
@@ -0,0 +1,86 @@
+//! This module implements persistent storage of the highest accepted metadata version.
+//!
+//! Version metadata with a lower `metadata_version` than the highest one previously accepted must
+//! be rejected, even if it has not expired yet. Otherwise, an attacker could replay old, validly
+//! signed metadata to downgrade clients.
+
+use anyhow::Context;
+use std::{io, path::PathBuf};
+use tokio::fs;
+
+use crate::version::MIN_VERIFY_METADATA_VERSION;
+
+/// Highest `metadata_version` that has been accepted, stored in a file
+#[derive(Clone)]
+pub struct MetadataVersionStore {
+    path: PathBuf,
+}
+
+impl MetadataVersionStore {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self { path: path.into() }
+    }
+
+    /// Return the highest accepted metadata version, or [MIN_VERIFY_METADATA_VERSION] if none has
+    /// been stored yet.
+    pub async fn load(&self) -> anyhow::Result<usize> {
+        let content = match fs::read_to_string(&self.path).await {
+            Ok(content) => content,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => {
+                return Ok(MIN_VERIFY_METADATA_VERSION);
+            }
+            Err(err) => {
+                return Err(err).context("Failed to read metadata version");
+            }
+        };
+        let version: usize = content
+            .trim()
+            .parse()
+            .context("Invalid stored metadata version")?;
+        Ok(version.max(MIN_VERIFY_METADATA_VERSION))
+    }
+
+    /// Store `metadata_version` if it is higher than the currently stored version.
+    /// Returns the highest accepted metadata version.
+    pub async fn store(&self, metadata_version: usize) -> anyhow::Result<usize> {
+        let current = self.load().await?;
+        if metadata_version <= current {
+            return Ok(current);
+        }
+
+        // Write to a temporary file first, so that the stored version is never lost
+        let temp_path = self.path.with_extension("tmp");
+        fs::write(&temp_path, metadata_version.to_string())
+            .await
+            .context("Failed to write metadata version")?;
+        fs::rename(&temp_path, &self.path)
+            .await
+            .context("Failed to replace metadata version")?;
+
+        Ok(metadata_version)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    /// Test that the stored metadata version never decreases
+    #[tokio::test]
+    async fn test_metadata_version_store() {
+        let dir = async_tempfile::TempDir::new().await.unwrap();
+        let store = MetadataVersionStore::new(dir.join("metadata-version"));
+
+        assert_eq!(store.load().await.unwrap(), MIN_VERIFY_METADATA_VERSION);
+
+        assert_eq!(store.store(5).await.unwrap(), 5);
+        assert_eq!(store.load().await.unwrap(), 5);
+
+        // Lower versions must be ignored
+        assert_eq!(store.store(3).await.unwrap(), 5);
+        assert_eq!(store.load().await.unwrap(), 5);
+
+        assert_eq!(store.store(6).await.unwrap(), 6);
+        assert_eq!(store.load().await.unwrap(), 6);
+    }
+}
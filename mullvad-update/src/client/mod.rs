@@ -2,5 +2,8 @@ pub mod api;
 pub mod app;
 pub mod fetch;
 pub mod local;
+pub mod metadata_version;
+pub mod package;
+pub mod source;
 pub mod verify;
 pub mod version_provider;
@@ -0,0 +1,102 @@
+#![cfg(target_os = "linux")]
+
+//! This module implements installation of Linux packages (.deb and .rpm).
+//!
+//! The package manager is started in a transient systemd unit. Installing the package restarts the
+//! daemon, which would otherwise take the package manager down with it.
+//!
+//! `systemd-run` waits for the unit to finish and exits with an error if the package manager
+//! failed, so that failures can be reported. Stopping the daemon only stops `systemd-run`, not the
+//! unit, so the restart of the daemon does not wait for the upgrade or interrupt it.
+
+use std::{path::Path, sync::Arc};
+use tokio::process::Command;
+
+use crate::format::PackageFormat;
+
+/// Name of the installed app package
+const PACKAGE_NAME: &str = "mullvad-vpn";
+
+/// Name of the transient systemd unit that installs the package
+const INSTALLER_UNIT: &str = "mullvad-vpn-upgrade";
+
+/// Hook used to install a verified package
+pub trait PackageInstaller: Send + Sync + 'static {
+    /// Package format that this hook can install
+    fn package_format(&self) -> PackageFormat;
+
+    /// Return a command that installs the package at `path`
+    fn install_command(&self, path: &Path) -> Command;
+}
+
+/// Install .deb packages using dpkg
+pub struct DpkgInstaller;
+
+impl PackageInstaller for DpkgInstaller {
+    fn package_format(&self) -> PackageFormat {
+        PackageFormat::Deb
+    }
+
+    fn install_command(&self, path: &Path) -> Command {
+        let mut cmd = systemd_run();
+        cmd.args(["dpkg", "--install"]).arg(path);
+        cmd
+    }
+}
+
+/// Install .rpm packages using rpm
+pub struct RpmInstaller;
+
+impl PackageInstaller for RpmInstaller {
+    fn package_format(&self) -> PackageFormat {
+        PackageFormat::Rpm
+    }
+
+    fn install_command(&self, path: &Path) -> Command {
+        let mut cmd = systemd_run();
+        cmd.args(["rpm", "--upgrade"]).arg(path);
+        cmd
+    }
+}
+
+/// Return the default [PackageInstaller] for `package_format`
+pub fn default_installer(package_format: PackageFormat) -> Arc<dyn PackageInstaller> {
+    match package_format {
+        PackageFormat::Deb => Arc::new(DpkgInstaller),
+        PackageFormat::Rpm => Arc::new(RpmInstaller),
+    }
+}
+
+/// Return the format of the package that the app was installed from, or `None` if it was not
+/// installed using dpkg or rpm.
+pub fn installed_package_format() -> Option<PackageFormat> {
+    let dpkg_status = std::process::Command::new("dpkg-query")
+        .args(["--show", "--showformat=${db:Status-Status}", PACKAGE_NAME])
+        .output();
+    if dpkg_status.is_ok_and(|output| output.status.success() && output.stdout == b"installed") {
+        return Some(PackageFormat::Deb);
+    }
+
+    let rpm_status = std::process::Command::new("rpm")
+        .args(["--query", PACKAGE_NAME])
+        .output();
+    if rpm_status.is_ok_and(|output| output.status.success()) {
+        return Some(PackageFormat::Rpm);
+    }
+
+    None
+}
+
+fn systemd_run() -> Command {
+    let mut cmd = Command::new("systemd-run");
+    cmd.args([
+        "--wait",
+        "--quiet",
+        "--collect",
+        "--unit",
+        INSTALLER_UNIT,
+        "--description",
+        "Mullvad VPN upgrade",
+    ]);
+    cmd
+}
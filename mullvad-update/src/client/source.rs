@@ -0,0 +1,203 @@
+//! This module implements fetching of version metadata from a source other than the default
+//! metadata repository, such as a mirror hosted by the administrator of the device.
+//!
+//! Metadata from a custom source is verified using keys that are trusted for that source only,
+//! instead of the keys in `trusted-metadata-signing-pubkeys`.
+
+use anyhow::{Context, bail};
+use sha2::{Digest, Sha256};
+use std::{path::PathBuf, str::FromStr};
+use tokio::fs;
+use vec1::Vec1;
+
+use crate::format::{self, key::VerifyingKey};
+
+use super::api::HttpVersionInfoProvider;
+
+/// Location of version metadata
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MetadataLocation {
+    /// Fetch the metadata using a GET request
+    Url(String),
+    /// Read the metadata from a local file
+    File(PathBuf),
+}
+
+impl FromStr for MetadataLocation {
+    type Err = anyhow::Error;
+
+    /// Parse an `http(s)://` URL or an absolute file path
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s.starts_with("https://") || s.starts_with("http://") {
+            return Ok(Self::Url(s.to_owned()));
+        }
+        let path = PathBuf::from(s);
+        if !path.is_absolute() {
+            bail!("Metadata location must be a URL or an absolute path: {s}");
+        }
+        Ok(Self::File(path))
+    }
+}
+
+/// Version metadata source with its own set of trusted keys
+#[derive(Debug, Clone)]
+pub struct CustomMetadataSource {
+    pub location: MetadataLocation,
+    /// Keys that may sign metadata from this source
+    pub trusted_keys: Vec1<VerifyingKey>,
+    /// Number of signatures from `trusted_keys` that are required
+    pub threshold: usize,
+}
+
+impl CustomMetadataSource {
+    /// Construct a source that requires signatures from at least `threshold` of `trusted_keys`
+    pub fn new(
+        location: MetadataLocation,
+        trusted_keys: Vec<VerifyingKey>,
+        threshold: usize,
+    ) -> anyhow::Result<Self> {
+        let trusted_keys =
+            Vec1::try_from_vec(trusted_keys).context("At least one trusted key is required")?;
+        if threshold == 0 || threshold > trusted_keys.len() {
+            bail!(
+                "Signature threshold must be between 1 and {}",
+                trusted_keys.len()
+            );
+        }
+        Ok(Self {
+            location,
+            trusted_keys,
+            threshold,
+        })
+    }
+
+    /// Return an identifier of this source, derived from its location and trusted keys.
+    ///
+    /// Metadata versions from different sources are unrelated, so state such as the highest
+    /// accepted metadata version must be kept separately for each source.
+    pub fn id(&self) -> String {
+        let mut hasher = Sha256::new();
+        match &self.location {
+            MetadataLocation::Url(url) => {
+                hasher.update(b"url:");
+                hasher.update(url.as_bytes());
+            }
+            MetadataLocation::File(path) => {
+                hasher.update(b"file:");
+                hasher.update(path.as_os_str().as_encoded_bytes());
+            }
+        }
+        let mut keys: Vec<_> = self
+            .trusted_keys
+            .iter()
+            .map(|key| key.0.to_bytes())
+            .collect();
+        keys.sort();
+        keys.dedup();
+        for key in keys {
+            hasher.update(key);
+        }
+        hex::encode(hasher.finalize())
+    }
+
+    /// Retrieve and verify version metadata from this source
+    pub async fn get_versions(
+        &self,
+        lowest_metadata_version: usize,
+    ) -> anyhow::Result<format::SignedResponse> {
+        let raw_json = match &self.location {
+            MetadataLocation::Url(url) => HttpVersionInfoProvider::get(url, None, None).await?,
+            MetadataLocation::File(path) => read_with_limit(path).await?,
+        };
+        format::SignedResponse::deserialize_and_verify_with_keys(
+            &self.trusted_keys,
+            self.threshold,
+            &raw_json,
+            lowest_metadata_version,
+        )
+    }
+}
+
+async fn read_with_limit(path: &std::path::Path) -> anyhow::Result<Vec<u8>> {
+    let size = fs::metadata(path)
+        .await
+        .with_context(|| format!("Failed to read metadata file {}", path.display()))?
+        .len();
+    if size > HttpVersionInfoProvider::SIZE_LIMIT as u64 {
+        bail!(
+            "Version info exceeded limit: {} bytes",
+            HttpVersionInfoProvider::SIZE_LIMIT
+        );
+    }
+    fs::read(path)
+        .await
+        .with_context(|| format!("Failed to read metadata file {}", path.display()))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_parse_metadata_location() {
+        assert_eq!(
+            "https://updates.example.com/linux.json"
+                .parse::<MetadataLocation>()
+                .unwrap(),
+            MetadataLocation::Url("https://updates.example.com/linux.json".to_owned())
+        );
+        assert!("relative/linux.json".parse::<MetadataLocation>().is_err());
+
+        #[cfg(unix)]
+        assert_eq!(
+            "/srv/mullvad/linux.json"
+                .parse::<MetadataLocation>()
+                .unwrap(),
+            MetadataLocation::File(PathBuf::from("/srv/mullvad/linux.json"))
+        );
+    }
+
+    /// Test that sources are identified by their location and set of keys
+    #[test]
+    fn test_source_id() {
+        let key = VerifyingKey::from_hex(include_str!("../../test-pubkey")).unwrap();
+        let other_key = crate::format::key::SecretKey::generate().pubkey();
+        let source = |location: &str, keys: Vec<VerifyingKey>| {
+            CustomMetadataSource::new(location.parse().unwrap(), keys, 1).unwrap()
+        };
+        let url = "https://updates.example.com/linux.json";
+
+        let id = source(url, vec![key.clone(), other_key.clone()]).id();
+        assert_eq!(id, source(url, vec![other_key.clone(), key.clone()]).id());
+        assert_ne!(id, source(url, vec![key.clone()]).id());
+        assert_ne!(
+            id,
+            source(
+                "https://mirror.example.com/linux.json",
+                vec![key, other_key]
+            )
+            .id()
+        );
+    }
+
+    /// Test that metadata from a file is verified using the keys of the source
+    #[tokio::test]
+    async fn test_file_source() {
+        let dir = async_tempfile::TempDir::new().await.unwrap();
+        let path = dir.join("metadata.json");
+        fs::write(&path, include_bytes!("../../test-version-response.json"))
+            .await
+            .unwrap();
+
+        let test_key = VerifyingKey::from_hex(include_str!("../../test-pubkey")).unwrap();
+        let source = CustomMetadataSource {
+            location: MetadataLocation::File(path),
+            trusted_keys: vec1::vec1![test_key],
+            threshold: 1,
+        };
+
+        // The signature is valid, but the test response has expired
+        let err = source.get_versions(0).await.unwrap_err();
+        assert!(format!("{err:#}").contains("Version metadata has expired"));
+    }
+}
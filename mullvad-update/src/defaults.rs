@@ -19,6 +19,15 @@ pub static PINNED_CERTIFICATE: LazyLock<reqwest::Certificate> = LazyLock::new(||
 pub static TRUSTED_METADATA_SIGNING_PUBKEYS: LazyLock<Vec1<VerifyingKey>> =
     LazyLock::new(|| parse_keys(include_str!("../trusted-metadata-signing-pubkeys")));
 
+/// Number of distinct keys in [TRUSTED_METADATA_SIGNING_PUBKEYS] that must have signed version
+/// metadata for it to be accepted. This must be at least 1 and at most the number of trusted keys.
+///
+/// Requiring more than one signature means that a single compromised key cannot be used to sign
+/// malicious metadata. Clients reject metadata with fewer signatures than this, so published
+/// metadata must be co-signed using `mullvad-release cosign`. Use `mullvad-release verify` to
+/// check that it is before publishing it.
+pub const METADATA_SIGNATURE_THRESHOLD: usize = 2;
+
 fn parse_keys(keys: &str) -> Vec1<VerifyingKey> {
     let mut v = vec![];
     for key in keys.split('\n') {
@@ -48,5 +57,8 @@ fn test_parse_keys() {
     assert_eq!(format!("{}", keys[1]), key2);
 
     // Test that actual keys are validly parsed
-    let _prod = &*TRUSTED_METADATA_SIGNING_PUBKEYS;
+    let prod = &*TRUSTED_METADATA_SIGNING_PUBKEYS;
+
+    // Test that the threshold can be met by the actual keys, and that a single key is not enough
+    assert!((2..=prod.len()).contains(&METADATA_SIGNATURE_THRESHOLD));
 }
@@ -1,6 +1,7 @@
 //! Deserializer and verifier of version metadata
 
 use anyhow::Context;
+use std::collections::HashSet;
 use vec1::Vec1;
 
 use super::Response;
@@ -11,13 +12,15 @@ impl SignedResponse {
     /// Deserialize some bytes to JSON, and verify them, including signature and expiry.
     /// If successful, the deserialized data is returned.
     ///
-    /// This uses the keys in `trusted-metadata-signing-pubkeys`
+    /// This uses the keys in `trusted-metadata-signing-pubkeys`, and requires signatures from at
+    /// least `METADATA_SIGNATURE_THRESHOLD` of them.
     pub fn deserialize_and_verify(
         bytes: &[u8],
         min_metadata_version: usize,
     ) -> Result<Self, anyhow::Error> {
         Self::deserialize_and_verify_with_keys(
             &crate::defaults::TRUSTED_METADATA_SIGNING_PUBKEYS,
+            crate::defaults::METADATA_SIGNATURE_THRESHOLD,
             bytes,
             min_metadata_version,
         )
@@ -26,13 +29,21 @@ impl SignedResponse {
     /// Deserialize some bytes to JSON, and verify them, including signature and expiry.
     /// If successful, the deserialized data is returned.
     ///
-    /// This is typically only used for testing. Prefer [deserialize_and_verify].
-    pub(crate) fn deserialize_and_verify_with_keys(
+    /// Unlike [deserialize_and_verify], this uses the given `keys` and `threshold`. Prefer
+    /// [deserialize_and_verify] unless the metadata comes from a custom source.
+    pub fn deserialize_and_verify_with_keys(
         keys: &Vec1<VerifyingKey>,
+        threshold: usize,
         bytes: &[u8],
         min_metadata_version: usize,
     ) -> Result<Self, anyhow::Error> {
-        Self::deserialize_and_verify_at_time(keys, bytes, chrono::Utc::now(), min_metadata_version)
+        Self::deserialize_and_verify_at_time(
+            keys,
+            threshold,
+            bytes,
+            chrono::Utc::now(),
+            min_metadata_version,
+        )
     }
 
     /// This method is used mostly for testing, and skips all verification.
@@ -54,12 +65,13 @@ impl SignedResponse {
     /// This is typically only used for testing. Prefer [deserialize_and_verify].
     pub(crate) fn deserialize_and_verify_at_time(
         keys: &Vec1<VerifyingKey>,
+        threshold: usize,
         bytes: &[u8],
         current_time: chrono::DateTime<chrono::Utc>,
         min_metadata_version: usize,
     ) -> Result<Self, anyhow::Error> {
-        // Deserialize and verify signature
-        let partial_data = deserialize_and_verify(keys, bytes)?;
+        // Deserialize and verify signatures
+        let partial_data = deserialize_and_verify(keys, threshold, bytes)?;
 
         // Deserialize the canonical JSON to structured representation
         let signed_response: Response = serde_json::from_value(partial_data.signed)
@@ -89,39 +101,61 @@ impl SignedResponse {
     }
 }
 
-/// Deserialize arbitrary JSON object with a signature attached.
-/// WARNING: This only verifies the signature, not expiration.
+/// Deserialize arbitrary JSON object with signatures attached.
+/// WARNING: This only verifies the signatures, not expiration.
 ///
-/// On success, this returns verified data and signature
+/// The data is only accepted if it has valid signatures from at least `threshold` distinct keys
+/// in `keys`. Signatures by unknown keys and invalid signatures do not count towards the
+/// threshold.
+///
+/// On success, this returns verified data and signatures
 pub(super) fn deserialize_and_verify(
     keys: &Vec1<VerifyingKey>,
+    threshold: usize,
     bytes: &[u8],
 ) -> anyhow::Result<PartialSignedResponse> {
+    if threshold == 0 || threshold > keys.len() {
+        anyhow::bail!(
+            "Invalid signature threshold: {threshold} of {} trusted keys",
+            keys.len()
+        );
+    }
+
     let partial_data: PartialSignedResponse =
         serde_json::from_slice(bytes).context("Invalid version JSON")?;
 
     let valid_keys: Vec<_> = keys.into_iter().map(|k| k.0).collect();
 
-    // Check if one of the keys matches
-    let Some((key, sig)) = partial_data.signatures.iter().find_map(|sig| match sig {
-        // Check if ed25519 key matches
-        ResponseSignature::Ed25519 { keyid, sig } if valid_keys.contains(&keyid.0) => {
-            Some((keyid, sig))
-        }
-        // Ignore all non-matching key
-        _ => None,
-    }) else {
-        anyhow::bail!("Unrecognized key");
-    };
-
     // Serialize to canonical json format
     let canon_data = json_canon::to_vec(&partial_data.signed)
         .context("Failed to serialize to canonical JSON")?;
 
-    // Check if the data is signed by our key
-    key.0
-        .verify_strict(&canon_data, &sig.0)
-        .context("Signature verification failed")?;
+    // Collect the distinct trusted keys that have signed the data
+    let signed_by: HashSet<_> = partial_data
+        .signatures
+        .iter()
+        .filter_map(|sig| match sig {
+            // Check if ed25519 key matches, and if it signed the data
+            ResponseSignature::Ed25519 { keyid, sig } if valid_keys.contains(&keyid.0) => {
+                match keyid.0.verify_strict(&canon_data, &sig.0) {
+                    Ok(()) => Some(keyid.0.to_bytes()),
+                    Err(error) => {
+                        log::warn!("Ignoring invalid signature by {keyid}: {error}");
+                        None
+                    }
+                }
+            }
+            // Ignore all non-matching keys
+            _ => None,
+        })
+        .collect();
+
+    if signed_by.len() < threshold {
+        anyhow::bail!(
+            "Signature verification failed: signed by {} trusted keys, {threshold} required",
+            signed_by.len()
+        );
+    }
 
     Ok(PartialSignedResponse {
         signatures: partial_data.signatures,
@@ -148,6 +182,7 @@ mod test {
 
         SignedResponse::deserialize_and_verify_at_time(
             &vec1![VerifyingKey(verifying_key)],
+            1,
             include_bytes!("../../test-version-response.json"),
             // It's 1970 again
             chrono::DateTime::UNIX_EPOCH,
@@ -159,6 +194,7 @@ mod test {
         // Reject expired data
         SignedResponse::deserialize_and_verify_at_time(
             &vec1![VerifyingKey(verifying_key)],
+            1,
             include_bytes!("../../test-version-response.json"),
             // In the year 3000
             chrono::DateTime::from_str("3000-01-01T00:00:00Z").unwrap(),
@@ -170,6 +206,7 @@ mod test {
         // Reject expired version number
         SignedResponse::deserialize_and_verify_at_time(
             &vec1![VerifyingKey(verifying_key)],
+            1,
             include_bytes!("../../test-version-response.json"),
             chrono::DateTime::UNIX_EPOCH,
             usize::MAX,
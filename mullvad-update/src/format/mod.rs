@@ -8,8 +8,8 @@
 //! expires.
 //!
 //! For the deserializer to succeed in deserializing a file, it must verify that the canonicalized
-//! form of `signed` is in fact signed by key/signature in `signature`. It also reads the `expires`
-//! and rejects the file if it has expired.
+//! form of `signed` is in fact signed by a threshold number of distinct trusted keys in
+//! `signature`. It also reads the `expires` and rejects the file if it has expired.
 
 use std::fmt::Display;
 
@@ -111,6 +111,9 @@ pub struct Installer {
     pub size: usize,
     /// Hash of the installer, hexadecimal string
     pub sha256: String,
+    /// Package format of the installer. This is only set for Linux packages
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub package: Option<PackageFormat>,
 }
 
 /// Installer architecture
@@ -132,6 +135,32 @@ impl Display for Architecture {
     }
 }
 
+/// Linux package format
+#[derive(Debug, Clone, Copy, Deserialize, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum PackageFormat {
+    /// Debian package, installed using dpkg
+    Deb,
+    /// RPM package, installed using rpm
+    Rpm,
+}
+
+impl PackageFormat {
+    /// File extension used for packages of this format
+    pub fn extension(&self) -> &'static str {
+        match self {
+            PackageFormat::Deb => "deb",
+            PackageFormat::Rpm => "rpm",
+        }
+    }
+}
+
+impl Display for PackageFormat {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.extension())
+    }
+}
+
 /// JSON response signature
 #[derive(Debug, Deserialize, Serialize)]
 #[cfg_attr(test, derive(PartialEq))]
@@ -38,6 +38,29 @@ impl SignedResponse {
             signed: response,
         })
     }
+
+    /// Add a signature by `key` to already signed data. This is used when the metadata must be
+    /// signed by more than one key.
+    pub fn add_signature(&mut self, key: key::SecretKey) -> anyhow::Result<()> {
+        // Refuse to sign expired data
+        if self.signed.metadata_expiry < chrono::Utc::now() {
+            anyhow::bail!("Signing failed since the data has expired");
+        }
+
+        let pubkey = key.pubkey();
+        if self
+            .signatures
+            .iter()
+            .any(|sig| matches!(sig, ResponseSignature::Ed25519 { keyid, .. } if keyid == &pubkey))
+        {
+            anyhow::bail!("The data has already been signed by {pubkey}");
+        }
+
+        let partial_signed = sign(&key, &self.signed)?;
+        self.signatures.extend(partial_signed.signatures);
+
+        Ok(())
+    }
 }
 
 /// Serialize JSON to bytes, with a signature attached, signed using `key`
@@ -96,16 +119,16 @@ mod test {
 
         let bytes = serde_json::to_vec(&partial)?;
 
-        deserialize_and_verify(&vec1![pubkey.clone()], &bytes)?;
+        deserialize_and_verify(&vec1![pubkey.clone()], 1, &bytes)?;
 
         // Verify that an irrelevant key is ignored
         let invalid_key = key::SecretKey::generate();
         let invalid_pubkey = invalid_key.pubkey();
 
-        deserialize_and_verify(&vec1![pubkey.clone(), invalid_pubkey.clone()], &bytes)?;
+        deserialize_and_verify(&vec1![pubkey.clone(), invalid_pubkey.clone()], 1, &bytes)?;
 
         // Wrong public key only fails
-        deserialize_and_verify(&vec1![invalid_pubkey], &bytes).unwrap_err();
+        deserialize_and_verify(&vec1![invalid_pubkey], 1, &bytes).unwrap_err();
 
         Ok(())
     }
@@ -134,20 +157,62 @@ mod test {
         let bytes = serde_json::to_vec(&partial)?;
 
         // Accept either (or both) keys
-        deserialize_and_verify(&vec1![pubkey.clone(), pubkey2.clone()], &bytes)?;
-        deserialize_and_verify(&vec1![pubkey2.clone()], &bytes)?;
-        deserialize_and_verify(&vec1![pubkey.clone()], &bytes)?;
+        deserialize_and_verify(&vec1![pubkey.clone(), pubkey2.clone()], 1, &bytes)?;
+        deserialize_and_verify(&vec1![pubkey2.clone()], 1, &bytes)?;
+        deserialize_and_verify(&vec1![pubkey.clone()], 1, &bytes)?;
 
         // Ignore irrelevant key
         deserialize_and_verify(
             &vec1![pubkey.clone(), pubkey2.clone(), invalid_pubkey.clone()],
+            1,
             &bytes,
         )?;
-        deserialize_and_verify(&vec1![pubkey2, invalid_pubkey.clone()], &bytes)?;
-        deserialize_and_verify(&vec1![invalid_pubkey.clone(), pubkey], &bytes)?;
+        deserialize_and_verify(&vec1![pubkey2.clone(), invalid_pubkey.clone()], 1, &bytes)?;
+        deserialize_and_verify(&vec1![invalid_pubkey.clone(), pubkey.clone()], 1, &bytes)?;
 
         // Using wrong public key fails
-        deserialize_and_verify(&vec1![invalid_pubkey], &bytes).unwrap_err();
+        deserialize_and_verify(&vec1![invalid_pubkey.clone()], 1, &bytes).unwrap_err();
+
+        // Accept if the threshold is met
+        deserialize_and_verify(
+            &vec1![pubkey.clone(), pubkey2.clone(), invalid_pubkey.clone()],
+            2,
+            &bytes,
+        )?;
+
+        // Reject if too few trusted keys have signed
+        deserialize_and_verify(&vec1![pubkey.clone(), invalid_pubkey], 2, &bytes).unwrap_err();
+
+        // Reject if the same key has signed more than once
+        let mut partial = sign(&key, &data).context("Signing failed")?;
+        partial.signatures.extend(sign(&key, &data)?.signatures);
+        let bytes = serde_json::to_vec(&partial)?;
+        deserialize_and_verify(&vec1![pubkey, pubkey2], 2, &bytes).unwrap_err();
+
+        Ok(())
+    }
+
+    /// Test that signatures can be added to signed data, until the threshold is met
+    #[test]
+    fn test_add_signature() -> anyhow::Result<()> {
+        let key = key::SecretKey::generate();
+        let key2 = key::SecretKey::generate();
+        let keys = vec1![key.pubkey(), key2.pubkey()];
+
+        let response = Response {
+            metadata_version: 1,
+            metadata_expiry: chrono::Utc::now() + chrono::Duration::days(1),
+            releases: vec![],
+        };
+
+        let mut signed = SignedResponse::sign(key.clone(), response)?;
+        deserialize_and_verify(&keys, 2, &serde_json::to_vec(&signed)?).unwrap_err();
+
+        // The same key must not be counted twice
+        signed.add_signature(key).unwrap_err();
+
+        signed.add_signature(key2)?;
+        deserialize_and_verify(&keys, 2, &serde_json::to_vec(&signed)?)?;
 
         Ok(())
     }
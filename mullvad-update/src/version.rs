@@ -20,6 +20,8 @@ pub const MIN_VERIFY_METADATA_VERSION: usize = 0;
 pub struct VersionParameters {
     /// Architecture to retrieve data for
     pub architecture: VersionArchitecture,
+    /// Package format to retrieve data for. This must be `None` except on Linux
+    pub package_format: Option<format::PackageFormat>,
     /// Rollout threshold. Any version in the response below this threshold will be ignored
     pub rollout: Rollout,
     /// Lowest allowed `metadata_version` in the version data
@@ -87,8 +89,12 @@ impl VersionInfo {
         .flat_map(|format::Release { version, changelog, installers, .. }| {
             installers
                 .into_iter()
-                // Find installer for the requested architecture (assumed to be unique)
-                .find(|installer| params.architecture == installer.architecture)
+                // Find installer for the requested architecture and package format (assumed to be
+                // unique)
+                .find(|installer| {
+                    params.architecture == installer.architecture
+                        && params.package_format == installer.package
+                })
                 // Map each artifact to a [Version]
                 .map(|Installer { urls, size, sha256,.. }| {
                     anyhow::Ok(Version {
@@ -144,6 +150,7 @@ mod test {
 
         let params = VersionParameters {
             architecture: VersionArchitecture::X86,
+            package_format: None,
             rollout: 1.,
             lowest_metadata_version: 0,
         };
@@ -165,6 +172,7 @@ mod test {
 
         let params = VersionParameters {
             architecture: VersionArchitecture::Arm64,
+            package_format: None,
             rollout: 0.01,
             lowest_metadata_version: 0,
         };
@@ -176,4 +184,48 @@ mod test {
 
         Ok(())
     }
+
+    /// Test that installers are selected based on the package format
+    #[test]
+    fn test_version_info_package_format() -> anyhow::Result<()> {
+        let installer = |package, sha256: &str| format::Installer {
+            architecture: VersionArchitecture::X86,
+            urls: vec![],
+            size: 1,
+            sha256: sha256.repeat(32),
+            package,
+        };
+        let response = format::Response {
+            metadata_version: 0,
+            metadata_expiry: chrono::DateTime::UNIX_EPOCH,
+            releases: vec![format::Release {
+                version: "2025.2".parse().unwrap(),
+                changelog: "".to_owned(),
+                installers: vec![
+                    installer(Some(format::PackageFormat::Deb), "0d"),
+                    installer(Some(format::PackageFormat::Rpm), "0e"),
+                ],
+                rollout: 1.,
+            }],
+        };
+
+        let params = |package_format| VersionParameters {
+            architecture: VersionArchitecture::X86,
+            package_format,
+            rollout: 1.,
+            lowest_metadata_version: 0,
+        };
+
+        // Expect: The installer matching the package format is selected
+        let info = VersionInfo::try_from_response(
+            &params(Some(format::PackageFormat::Rpm)),
+            response.clone(),
+        )?;
+        assert_eq!(info.stable.sha256, [0x0e; 32]);
+
+        // Expect: Package installers are ignored when no package format is requested
+        VersionInfo::try_from_response(&params(None), response).unwrap_err();
+
+        Ok(())
+    }
 }
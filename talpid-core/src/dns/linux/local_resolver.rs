@@ -0,0 +1,92 @@
+use super::DnsMonitorHolder;
+use crate::{
+    dns::ResolvedDnsConfig,
+    resolver::{self, LocalResolverConfig, ResolverHandle},
+};
+use std::{fmt, net::IpAddr};
+use talpid_routing::RouteManagerHandle;
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+#[derive(thiserror::Error, Debug)]
+pub enum Error {
+    #[error("Failed to start local DNS resolver")]
+    Start(#[source] resolver::Error),
+
+    #[error("Failed to configure system DNS for the local DNS resolver")]
+    SetSystemDns(#[source] Box<super::Error>),
+}
+
+/// Runs a forwarding DNS resolver bound to a loopback address, and points the system resolver at
+/// it using another DNS monitor. This is used to reach encrypted DNS servers and to apply
+/// per-domain DNS rules, regardless of how the system manages DNS.
+pub struct LocalResolver {
+    resolver: ResolverHandle,
+    /// DNS monitor that configures the system to use `resolver`
+    system: Box<DnsMonitorHolder>,
+}
+
+impl LocalResolver {
+    pub fn new(handle: &tokio::runtime::Handle, system: DnsMonitorHolder) -> Result<Self> {
+        let resolver = handle
+            .block_on(resolver::start_resolver(LocalResolverConfig::default()))
+            .map_err(Error::Start)?;
+        Ok(LocalResolver {
+            resolver,
+            system: Box::new(system),
+        })
+    }
+
+    pub fn set_dns(
+        &mut self,
+        handle: &tokio::runtime::Handle,
+        route_manager: &RouteManagerHandle,
+        interface: &str,
+        config: &ResolvedDnsConfig,
+    ) -> Result<()> {
+        handle.block_on(self.resolver.enable_custom_forward(
+            config.tunnel_config().to_vec(),
+            config.encrypted_servers().to_vec(),
+            config.domain_rules().to_vec(),
+        ));
+        self.use_resolver(handle, route_manager, interface)
+    }
+
+    /// Forward queries to unencrypted DNS `servers`.
+    pub fn set_servers(
+        &mut self,
+        handle: &tokio::runtime::Handle,
+        route_manager: &RouteManagerHandle,
+        interface: &str,
+        servers: &[IpAddr],
+    ) -> Result<()> {
+        handle.block_on(self.resolver.enable_forward(servers.to_vec()));
+        self.use_resolver(handle, route_manager, interface)
+    }
+
+    /// Point the system at the local resolver.
+    fn use_resolver(
+        &mut self,
+        handle: &tokio::runtime::Handle,
+        route_manager: &RouteManagerHandle,
+        interface: &str,
+    ) -> Result<()> {
+        let resolver_ip = self.resolver.listening_addr().ip();
+        self.system
+            .set_servers(handle, route_manager, interface, &[resolver_ip])
+            .map_err(|error| Error::SetSystemDns(Box::new(error)))
+    }
+
+    pub fn reset(&mut self, handle: &tokio::runtime::Handle) -> Result<()> {
+        handle.block_on(self.resolver.disable_forward());
+        self.system
+            .reset(handle)
+            .map_err(|error| Error::SetSystemDns(Box::new(error)))
+    }
+}
+
+impl fmt::Display for LocalResolver {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "local resolver via {}", self.system)
+    }
+}
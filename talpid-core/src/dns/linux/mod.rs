@@ -1,11 +1,12 @@
+mod local_resolver;
 mod network_manager;
 mod resolvconf;
 mod static_resolv_conf;
 mod systemd_resolved;
 
 use self::{
-    network_manager::NetworkManager, resolvconf::Resolvconf, static_resolv_conf::StaticResolvConf,
-    systemd_resolved::SystemdResolved,
+    local_resolver::LocalResolver, network_manager::NetworkManager, resolvconf::Resolvconf,
+    static_resolv_conf::StaticResolvConf, systemd_resolved::SystemdResolved,
 };
 use std::{
     env,
@@ -37,6 +38,10 @@ pub enum Error {
     #[error("Error in static /etc/resolv.conf DNS monitor")]
     StaticResolvConf(#[from] static_resolv_conf::Error),
 
+    /// Error in local resolver DNS monitor
+    #[error("Error in local resolver DNS monitor")]
+    LocalResolver(#[from] local_resolver::Error),
+
     /// No suitable DNS monitor implementation detected
     #[error("No suitable DNS monitor implementation detected")]
     NoDnsMonitor,
@@ -60,12 +65,18 @@ impl super::DnsMonitorT for DnsMonitor {
     }
 
     fn set(&mut self, interface: &str, config: ResolvedDnsConfig) -> Result<()> {
-        let servers = config.tunnel_config();
         self.reset()?;
         // Creating a new DNS monitor for each set, in case the system changed how it manages DNS.
-        let mut inner = DnsMonitorHolder::new()?;
-        if !servers.is_empty() {
-            inner.set(&self.handle, &self.route_manager, interface, servers)?;
+        let mut inner = DnsMonitorHolder::new(&self.handle)?;
+        if inner.needs_local_resolver(&config) {
+            inner = DnsMonitorHolder::LocalResolver(LocalResolver::new(&self.handle, inner)?);
+            log::debug!("Managing DNS via {}", inner);
+        }
+        if !config.tunnel_config().is_empty()
+            || !config.encrypted_servers().is_empty()
+            || !config.domain_rules().is_empty()
+        {
+            inner.set(&self.handle, &self.route_manager, interface, &config)?;
             self.inner = Some(inner);
         }
         Ok(())
@@ -84,6 +95,8 @@ pub enum DnsMonitorHolder {
     NetworkManager(NetworkManager),
     Resolvconf(Resolvconf),
     StaticResolvConf(StaticResolvConf),
+    /// Forward queries through a local resolver, which the wrapped monitor points the system at
+    LocalResolver(LocalResolver),
 }
 
 impl fmt::Display for DnsMonitorHolder {
@@ -94,13 +107,14 @@ impl fmt::Display for DnsMonitorHolder {
             StaticResolvConf(..) => "/etc/resolv.conf",
             SystemdResolved(..) => "systemd-resolved",
             NetworkManager(..) => "NetworkManager",
+            LocalResolver(local_resolver) => return local_resolver.fmt(f),
         };
         f.write_str(name)
     }
 }
 
 impl DnsMonitorHolder {
-    fn new() -> Result<Self> {
+    fn new(handle: &tokio::runtime::Handle) -> Result<Self> {
         let dns_module = env::var_os("TALPID_DNS_MODULE");
 
         let manager = match dns_module.as_ref().and_then(|value| value.to_str()) {
@@ -108,6 +122,10 @@ impl DnsMonitorHolder {
             Some("resolvconf") => DnsMonitorHolder::Resolvconf(Resolvconf::new()?),
             Some("systemd") => DnsMonitorHolder::SystemdResolved(SystemdResolved::new()?),
             Some("network-manager") => DnsMonitorHolder::NetworkManager(NetworkManager::new()?),
+            Some("local-resolver") => DnsMonitorHolder::LocalResolver(LocalResolver::new(
+                handle,
+                Self::with_detected_dns_manager()?,
+            )?),
             Some(_) | None => Self::with_detected_dns_manager()?,
         };
         log::debug!("Managing DNS via {}", manager);
@@ -142,7 +160,51 @@ impl DnsMonitorHolder {
             .map_err(|_| Error::NoDnsMonitor)
     }
 
+    /// Returns whether `config` can only be applied by forwarding queries through a local
+    /// resolver. Encrypted DNS servers can only be reached that way. Domain rules can be applied
+    /// by systemd-resolved, as long as their servers are on a private network, since they would
+    /// otherwise be routed through the tunnel interface along with all other queries.
+    fn needs_local_resolver(&self, config: &ResolvedDnsConfig) -> bool {
+        match self {
+            DnsMonitorHolder::LocalResolver(..) => false,
+            DnsMonitorHolder::SystemdResolved(..) => {
+                !config.encrypted_servers().is_empty()
+                    || config
+                        .domain_rules()
+                        .iter()
+                        .flat_map(|rule| &rule.servers)
+                        .any(|server| !crate::firewall::is_local_address(server))
+            }
+            _ => !config.encrypted_servers().is_empty() || !config.domain_rules().is_empty(),
+        }
+    }
+
     fn set(
+        &mut self,
+        handle: &tokio::runtime::Handle,
+        route_manager: &RouteManagerHandle,
+        interface: &str,
+        config: &ResolvedDnsConfig,
+    ) -> Result<()> {
+        match self {
+            DnsMonitorHolder::LocalResolver(local_resolver) => {
+                local_resolver.set_dns(handle, route_manager, interface, config)?
+            }
+            DnsMonitorHolder::SystemdResolved(systemd_resolved) => {
+                handle.block_on(systemd_resolved.set_dns(
+                    route_manager.clone(),
+                    interface,
+                    config.tunnel_config(),
+                    config.domain_rules(),
+                ))?
+            }
+            _ => self.set_servers(handle, route_manager, interface, config.tunnel_config())?,
+        }
+        Ok(())
+    }
+
+    /// Use unencrypted DNS `servers`.
+    fn set_servers(
         &mut self,
         handle: &tokio::runtime::Handle,
         route_manager: &RouteManagerHandle,
@@ -157,8 +219,12 @@ impl DnsMonitorHolder {
                 route_manager.clone(),
                 interface,
                 servers,
+                &[],
             ))?,
             NetworkManager(network_manager) => network_manager.set_dns(interface, servers)?,
+            LocalResolver(local_resolver) => {
+                local_resolver.set_servers(handle, route_manager, interface, servers)?
+            }
         }
         Ok(())
     }
@@ -170,6 +236,7 @@ impl DnsMonitorHolder {
             StaticResolvConf(static_resolv_conf) => static_resolv_conf.reset()?,
             SystemdResolved(systemd_resolved) => handle.block_on(systemd_resolved.reset())?,
             NetworkManager(network_manager) => network_manager.reset()?,
+            LocalResolver(local_resolver) => local_resolver.reset(handle)?,
         }
         Ok(())
     }
@@ -1,8 +1,8 @@
 use crate::linux::{IfaceIndexLookupError, iface_index};
-use std::net::IpAddr;
-use talpid_dbus::systemd_resolved::{AsyncHandle, SystemdResolved as DbusInterface};
+use std::{collections::BTreeMap, net::IpAddr};
+use talpid_dbus::systemd_resolved::{AsyncHandle, DnsState, SystemdResolved as DbusInterface};
 use talpid_routing::RouteManagerHandle;
-use talpid_types::ErrorExt;
+use talpid_types::{ErrorExt, net::dns::DomainDnsRule};
 
 pub(crate) use talpid_dbus::systemd_resolved::Error as SystemdDbusError;
 
@@ -15,11 +15,22 @@ pub enum Error {
 
     #[error("Failed to resolve interface index with error {0}")]
     InterfaceNameError(#[from] IfaceIndexLookupError),
+
+    #[error("Failed to find the interface used to reach DNS server {0}")]
+    NoRouteToServer(IpAddr, #[source] Option<talpid_routing::Error>),
 }
 
 pub struct SystemdResolved {
     pub dbus_interface: AsyncHandle,
     tunnel_index: u32,
+    /// Original configuration of links that domain rules were applied to
+    rule_links: Vec<LinkBackup>,
+}
+
+/// DNS servers and domains of a link before they were replaced
+struct LinkBackup {
+    dns_state: DnsState,
+    domains: Vec<(String, bool)>,
 }
 
 impl SystemdResolved {
@@ -29,6 +40,7 @@ impl SystemdResolved {
         let systemd_resolved = SystemdResolved {
             dbus_interface,
             tunnel_index: 0,
+            rule_links: vec![],
         };
 
         Ok(systemd_resolved)
@@ -36,9 +48,10 @@ impl SystemdResolved {
 
     pub async fn set_dns(
         &mut self,
-        _route_manager: RouteManagerHandle,
+        route_manager: RouteManagerHandle,
         interface_name: &str,
         servers: &[IpAddr],
+        domain_rules: &[DomainDnsRule],
     ) -> Result<()> {
         let tunnel_index = iface_index(interface_name)?;
         self.tunnel_index = tunnel_index;
@@ -60,10 +73,99 @@ impl SystemdResolved {
             .set_dns(self.tunnel_index, servers.to_vec())
             .await?;
 
+        self.reset_rule_links().await;
+        self.set_domain_rules(&route_manager, domain_rules).await
+    }
+
+    /// Route the domains of each rule to the link that its servers are reachable on, using
+    /// routing-only domains. Since systemd-resolved prefers the link with the most specific
+    /// routing domain, queries for other domains still go to the tunnel interface. Links that are
+    /// shared by several rules get the servers of all of them.
+    ///
+    /// The servers and domains of the link are kept, after the servers of the rules, and are
+    /// restored by [Self::reset_rule_links].
+    async fn set_domain_rules(
+        &mut self,
+        route_manager: &RouteManagerHandle,
+        domain_rules: &[DomainDnsRule],
+    ) -> Result<()> {
+        let mut links: BTreeMap<u32, (Vec<IpAddr>, Vec<(String, bool)>)> = BTreeMap::new();
+
+        for rule in domain_rules {
+            for server in &rule.servers {
+                let index = link_for_server(route_manager, *server).await?;
+                if index == self.tunnel_index {
+                    // Queries for the domain are sent through the tunnel either way
+                    log::warn!(
+                        "DNS server {server} for {} is reached through the tunnel, ignoring it",
+                        rule.domain
+                    );
+                    continue;
+                }
+                let (servers, domains) = links.entry(index).or_default();
+                if !servers.contains(server) {
+                    servers.push(*server);
+                }
+                let domain = (rule.domain.clone(), true);
+                if !domains.contains(&domain) {
+                    domains.push(domain);
+                }
+            }
+        }
+
+        for (index, (mut servers, mut domains)) in links {
+            let backup = LinkBackup {
+                dns_state: self.dbus_interface.get_dns(index).await?,
+                domains: self.dbus_interface.get_domains(index).await?,
+            };
+
+            for server in &backup.dns_state.set_servers {
+                if !servers.contains(server) {
+                    servers.push(*server);
+                }
+            }
+            for domain in &backup.domains {
+                if !domains.contains(domain) {
+                    domains.push(domain.clone());
+                }
+            }
+            self.rule_links.push(backup);
+
+            self.dbus_interface.set_dns(index, servers).await?;
+            self.dbus_interface
+                .set_owned_domains(index, domains)
+                .await?;
+        }
+
         Ok(())
     }
 
+    /// Restore the links that domain rules were applied to.
+    async fn reset_rule_links(&mut self) {
+        for backup in self.rule_links.drain(..) {
+            let index = backup.dns_state.interface_index;
+            if let Err(error) = self
+                .dbus_interface
+                .set_owned_domains(index, backup.domains)
+                .await
+            {
+                log::error!(
+                    "{}",
+                    error.display_chain_with_msg("Failed to restore search domains")
+                );
+            }
+            if let Err(error) = self.dbus_interface.set_dns_state(backup.dns_state).await {
+                log::error!(
+                    "{}",
+                    error.display_chain_with_msg("Failed to restore DNS servers")
+                );
+            }
+        }
+    }
+
     pub async fn reset(&mut self) -> Result<()> {
+        self.reset_rule_links().await;
+
         if let Err(error) = self
             .dbus_interface
             .set_domains(self.tunnel_index, &[])
@@ -80,3 +182,17 @@ impl SystemdResolved {
         Ok(())
     }
 }
+
+/// Return the index of the interface that traffic to `server` is routed through.
+async fn link_for_server(route_manager: &RouteManagerHandle, server: IpAddr) -> Result<u32> {
+    let route = route_manager
+        .get_destination_route(server, None)
+        .await
+        .map_err(|error| Error::NoRouteToServer(server, Some(error)))?
+        .ok_or(Error::NoRouteToServer(server, None))?;
+    let device = route
+        .get_node()
+        .get_device()
+        .ok_or(Error::NoRouteToServer(server, None))?;
+    Ok(iface_index(device)?)
+}
@@ -1,5 +1,8 @@
 use std::fmt;
 use std::net::IpAddr;
+use talpid_types::net::dns::{DomainDnsRule, EncryptedDnsServer};
+
+use crate::firewall::is_local_address;
 
 #[cfg(target_os = "linux")]
 use talpid_routing::RouteManagerHandle;
@@ -29,12 +32,15 @@ pub use self::imp::Error;
 #[derive(Debug, Clone, PartialEq)]
 pub struct DnsConfig {
     config: InnerDnsConfig,
+    /// Servers to use for specific domains instead of those in `config`
+    domain_rules: Vec<DomainDnsRule>,
 }
 
 impl Default for DnsConfig {
     fn default() -> Self {
         Self {
             config: InnerDnsConfig::Default,
+            domain_rules: vec![],
         }
     }
 }
@@ -47,8 +53,27 @@ impl DnsConfig {
                 tunnel_config: tunnel_config.to_owned(),
                 non_tunnel_config: non_tunnel_config.to_owned(),
             },
+            domain_rules: vec![],
         }
     }
+
+    /// Use the specified DNS-over-HTTPS and DNS-over-TLS servers for DNS resolution. This is
+    /// only supported on Linux, where queries are forwarded to the servers by a local resolver.
+    pub fn from_encrypted_servers(servers: &[EncryptedDnsServer]) -> Self {
+        DnsConfig {
+            config: InnerDnsConfig::Encrypted {
+                servers: servers.to_owned(),
+            },
+            domain_rules: vec![],
+        }
+    }
+
+    /// Send queries for the domains in `domain_rules` to the servers of the rules. This is only
+    /// supported on Linux.
+    pub fn with_domain_rules(mut self, domain_rules: &[DomainDnsRule]) -> Self {
+        self.domain_rules = domain_rules.to_owned();
+        self
+    }
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -64,6 +89,8 @@ enum InnerDnsConfig {
         /// on non-tunnel interface, only allow them in the firewall.
         non_tunnel_config: Vec<IpAddr>,
     },
+    /// Forward DNS queries to the specified encrypted DNS servers
+    Encrypted { servers: Vec<EncryptedDnsServer> },
 }
 
 impl DnsConfig {
@@ -76,6 +103,8 @@ impl DnsConfig {
             InnerDnsConfig::Default => ResolvedDnsConfig {
                 tunnel_config: default_tun_config.to_owned(),
                 non_tunnel_config: vec![],
+                encrypted_servers: vec![],
+                domain_rules: self.domain_rules.clone(),
                 #[cfg(target_os = "macos")]
                 port,
             },
@@ -85,6 +114,16 @@ impl DnsConfig {
             } => ResolvedDnsConfig {
                 tunnel_config: tunnel_config.to_owned(),
                 non_tunnel_config: non_tunnel_config.to_owned(),
+                encrypted_servers: vec![],
+                domain_rules: self.domain_rules.clone(),
+                #[cfg(target_os = "macos")]
+                port,
+            },
+            InnerDnsConfig::Encrypted { servers } => ResolvedDnsConfig {
+                tunnel_config: vec![],
+                non_tunnel_config: vec![],
+                encrypted_servers: servers.to_owned(),
+                domain_rules: self.domain_rules.clone(),
                 #[cfg(target_os = "macos")]
                 port,
             },
@@ -101,6 +140,10 @@ pub struct ResolvedDnsConfig {
     /// For the most part, the tunnel state machine will not handle any of this configuration
     /// on non-tunnel interface, only allow them in the firewall.
     non_tunnel_config: Vec<IpAddr>,
+    /// Encrypted DNS servers that queries are forwarded to by a local resolver
+    encrypted_servers: Vec<EncryptedDnsServer>,
+    /// Servers to use for specific domains
+    domain_rules: Vec<DomainDnsRule>,
     /// Port to use
     #[cfg(target_os = "macos")]
     port: u16,
@@ -114,6 +157,28 @@ impl fmt::Display for ResolvedDnsConfig {
         f.write_str(" Non-tunnel DNS: ")?;
         Self::fmt_addr_set(f, &self.non_tunnel_config)?;
 
+        if !self.encrypted_servers.is_empty() {
+            f.write_str(" Encrypted DNS: {")?;
+            for (i, server) in self.encrypted_servers.iter().enumerate() {
+                if i > 0 {
+                    f.write_str(", ")?;
+                }
+                write!(f, "{server}")?;
+            }
+            f.write_str("}")?;
+        }
+
+        if !self.domain_rules.is_empty() {
+            f.write_str(" Domain rules: {")?;
+            for (i, rule) in self.domain_rules.iter().enumerate() {
+                if i > 0 {
+                    f.write_str("; ")?;
+                }
+                write!(f, "{rule}")?;
+            }
+            f.write_str("}")?;
+        }
+
         #[cfg(target_os = "macos")]
         write!(f, " Port: {}", self.port)?;
 
@@ -145,6 +210,38 @@ impl ResolvedDnsConfig {
         &self.non_tunnel_config
     }
 
+    /// Encrypted DNS servers that queries should be forwarded to by a local resolver
+    pub fn encrypted_servers(&self) -> &[EncryptedDnsServer] {
+        &self.encrypted_servers
+    }
+
+    /// Rules for sending queries for specific domains to specific servers
+    pub fn domain_rules(&self) -> &[DomainDnsRule] {
+        &self.domain_rules
+    }
+
+    /// Addresses to allow DNS traffic to on the tunnel interface. This includes the servers of
+    /// domain rules that are not on a private network.
+    pub fn allowed_tunnel_servers(&self) -> Vec<IpAddr> {
+        let rule_servers = self
+            .domain_rule_servers()
+            .filter(|ip| !is_local_address(ip));
+        dedup(self.tunnel_config.iter().copied().chain(rule_servers))
+    }
+
+    /// Addresses to allow DNS traffic to on non-tunnel interfaces. This includes the servers of
+    /// domain rules that are on a private network.
+    pub fn allowed_non_tunnel_servers(&self) -> Vec<IpAddr> {
+        let rule_servers = self.domain_rule_servers().filter(is_local_address);
+        dedup(self.non_tunnel_config.iter().copied().chain(rule_servers))
+    }
+
+    fn domain_rule_servers(&self) -> impl Iterator<Item = IpAddr> + '_ {
+        self.domain_rules
+            .iter()
+            .flat_map(|rule| rule.servers.iter().copied())
+    }
+
     /// Consume `self` and return a vector of all addresses
     pub fn addresses(self) -> impl Iterator<Item = IpAddr> {
         self.non_tunnel_config.into_iter().chain(self.tunnel_config)
@@ -164,6 +261,17 @@ impl ResolvedDnsConfig {
     }
 }
 
+/// Remove duplicate addresses while preserving their order.
+fn dedup(addresses: impl Iterator<Item = IpAddr>) -> Vec<IpAddr> {
+    let mut unique = vec![];
+    for address in addresses {
+        if !unique.contains(&address) {
+            unique.push(address);
+        }
+    }
+    unique
+}
+
 /// Sets and monitors system DNS settings. Makes sure the desired DNS servers are being used.
 pub struct DnsMonitor {
     inner: imp::DnsMonitor,
@@ -3,21 +3,23 @@ use crate::{split_tunnel, tunnel};
 use ipnetwork::IpNetwork;
 use nftnl::{
     Batch, Chain, FinalizedBatch, ProtoFamily, Rule, Table,
-    expr::{self, IcmpCode, Payload, RejectionType, Verdict},
+    expr::{self, IcmpCode, InterfaceName, Payload, RejectionType, Verdict},
     nft_expr, table,
 };
 use std::{
     env,
-    ffi::CStr,
+    ffi::{CStr, CString},
     fs, io,
     net::{IpAddr, Ipv4Addr},
+    os::unix::fs::MetadataExt,
     sync::LazyLock,
 };
 use talpid_types::{
-    cgroup::find_net_cls_mount,
+    ErrorExt,
+    cgroup::{SplitTunnelCgroup, find_split_tunnel_cgroup},
     net::{
-        ALLOWED_LAN_MULTICAST_NETS, ALLOWED_LAN_NETS, AllowedEndpoint, AllowedTunnelTraffic,
-        Endpoint, TransportProtocol,
+        ALLOWED_LAN_MULTICAST_NETS, ALLOWED_LAN_NETS, AllowedEndpoint, AllowedInterface,
+        AllowedTunnelTraffic, Endpoint, FirewallException, TransportProtocol,
     },
 };
 
@@ -57,9 +59,21 @@ pub enum Error {
     #[error("Unable to translate network interface name \"{0}\" into index")]
     LookupIfaceIndexError(String, #[source] crate::linux::IfaceIndexLookupError),
 
-    /// Failed to check if the net_cls mount exists.
-    #[error("An error occurred when checking for net_cls")]
-    FindNetClsMount(#[source] io::Error),
+    /// A firewall exception has an interface name that cannot be used.
+    #[error("Invalid network interface name \"{0}\"")]
+    InvalidIfaceName(String),
+
+    /// Failed to check which cgroup hierarchy is used for split tunneling.
+    #[error("An error occurred when checking for the split tunneling cgroup")]
+    FindCgroupMount(#[source] io::Error),
+
+    /// Failed to allocate an nftables expression.
+    #[error("Failed to allocate nftables {0} expression")]
+    AllocateExpression(&'static str),
+
+    /// The installed libnftnl cannot match cgroup v2 ancestors of sockets.
+    #[error("Matching sockets by cgroup v2 level requires libnftnl 1.2 or later")]
+    UnsupportedSocketLevel,
 }
 
 /// TODO(linus): This crate is not supposed to be Mullvad-aware. So at some point this should be
@@ -240,6 +254,85 @@ impl Firewall {
     }
 }
 
+/// How traffic from processes in the split tunneling cgroup is identified.
+#[derive(Debug, Clone, Copy)]
+enum ExclusionMatch {
+    /// Match the class ID of a cgroup v1 `net_cls` cgroup.
+    ClassId,
+    /// Match the ID of the cgroup v2 cgroup that the sending socket belongs to.
+    Cgroup2 { id: u64 },
+}
+
+/// Depth of the split tunneling cgroup in the cgroup v2 hierarchy. It is created directly below
+/// the root.
+const CGROUP2_EXCLUSION_LEVEL: u32 = 1;
+
+/// The nftables `socket cgroupv2` expression, which `nftnl` does not provide. It loads the ID of
+/// the ancestor at `level` of the cgroup that the socket of a packet belongs to into the first
+/// register. Requires Linux 5.13 or later.
+///
+/// The expression is allocated up front, so that allocation failures can be reported, and may
+/// only be added to a single rule.
+struct SocketCgroupV2 {
+    expr: std::cell::Cell<*mut nftnl::nftnl_sys::nftnl_expr>,
+}
+
+impl SocketCgroupV2 {
+    fn new(level: u32) -> Result<Self> {
+        use nftnl::nftnl_sys as sys;
+
+        // Attributes from libnftnl `expr.h`. `NFTNL_EXPR_SOCKET_LEVEL` is newer than the bindings.
+        const NFTNL_EXPR_SOCKET_KEY: u16 = 1;
+        const NFTNL_EXPR_SOCKET_DREG: u16 = 2;
+        const NFTNL_EXPR_SOCKET_LEVEL: u16 = 3;
+        // `enum nft_socket_keys` from the kernel's `nf_tables.h`
+        const NFT_SOCKET_CGROUPV2: u32 = 3;
+
+        // SAFETY: The attributes are only set if the allocation succeeded.
+        let expr = unsafe { sys::nftnl_expr_alloc(c"socket".as_ptr()) };
+        if expr.is_null() {
+            return Err(Error::AllocateExpression("socket"));
+        }
+        let socket_expr = Self {
+            expr: std::cell::Cell::new(expr),
+        };
+        // SAFETY: `expr` is a valid socket expression.
+        let level_is_set = unsafe {
+            sys::nftnl_expr_set_u32(expr, NFTNL_EXPR_SOCKET_KEY, NFT_SOCKET_CGROUPV2);
+            sys::nftnl_expr_set_u32(expr, NFTNL_EXPR_SOCKET_DREG, libc::NFT_REG_1 as u32);
+            sys::nftnl_expr_set_u32(expr, NFTNL_EXPR_SOCKET_LEVEL, level);
+            // Older versions of libnftnl silently ignore attributes that they do not know about
+            sys::nftnl_expr_is_set(expr, NFTNL_EXPR_SOCKET_LEVEL)
+        };
+        if !level_is_set {
+            return Err(Error::UnsupportedSocketLevel);
+        }
+        Ok(socket_expr)
+    }
+}
+
+impl expr::Expression for SocketCgroupV2 {
+    fn to_expr(&self, _rule: &Rule<'_>) -> *mut nftnl::nftnl_sys::nftnl_expr {
+        // The rule takes ownership of the expression
+        let expr = self.expr.replace(std::ptr::null_mut());
+        assert!(
+            !expr.is_null(),
+            "Socket expression added to more than one rule"
+        );
+        expr
+    }
+}
+
+impl Drop for SocketCgroupV2 {
+    fn drop(&mut self) {
+        let expr = self.expr.get();
+        if !expr.is_null() {
+            // SAFETY: The expression was never handed over to a rule, so it is still owned here.
+            unsafe { nftnl::nftnl_sys::nftnl_expr_free(expr) };
+        }
+    }
+}
+
 struct PolicyBatch<'a> {
     batch: Batch,
     in_chain: Chain<'a>,
@@ -311,16 +404,19 @@ impl<'a> PolicyBatch<'a> {
     pub fn finalize(mut self, policy: &FirewallPolicy, fwmark: u32) -> Result<FinalizedBatch> {
         self.add_loopback_rules()?;
 
-        // if cgroups v1 doesn't exist, split tunneling won't work.
-        // checking if the `net_cls` mount exists is a cheeky way of checking this.
-        if find_net_cls_mount()
-            .map_err(Error::FindNetClsMount)?
-            .is_some()
-        {
-            self.add_split_tunneling_rules(policy, fwmark)?;
-        } else {
-            // skipping add_split_tunneling_rules as it won't cause traffic to leak
-            log::warn!("net_cls mount not found, skipping add_split_tunneling_rules");
+        // skipping add_split_tunneling_rules if there is no cgroup hierarchy, as it won't cause
+        // traffic to leak
+        let split_tunnel_match = Self::split_tunnel_match()?;
+        match split_tunnel_match {
+            Some(exclusion_match) => {
+                self.add_split_tunneling_rules(policy, fwmark, exclusion_match)?
+            }
+            None => log::warn!("No split tunnel cgroup found, skipping add_split_tunneling_rules"),
+        }
+        let excluded_exceptions =
+            self.add_firewall_exception_mark_rules(policy.firewall_exceptions(), fwmark);
+        if split_tunnel_match.is_some() || excluded_exceptions {
+            self.add_excluded_traffic_rules(policy, fwmark)?;
         }
 
         self.add_dhcp_client_rules();
@@ -330,17 +426,53 @@ impl<'a> PolicyBatch<'a> {
         Ok(self.batch.finalize())
     }
 
+    /// Determine how to identify traffic from the split tunneling cgroup. Returns `None` if there
+    /// is no cgroup hierarchy to use for split tunneling, or if its traffic cannot be identified.
+    /// This is the case before the split tunneling cgroup v2 cgroup has been created, e.g. when the
+    /// early boot firewall is applied.
+    fn split_tunnel_match() -> Result<Option<ExclusionMatch>> {
+        let cgroup = match find_split_tunnel_cgroup().map_err(Error::FindCgroupMount)? {
+            Some(SplitTunnelCgroup::NetCls(_)) => return Ok(Some(ExclusionMatch::ClassId)),
+            Some(cgroup @ SplitTunnelCgroup::V2(_)) => cgroup,
+            None => return Ok(None),
+        };
+        // The ID of a cgroup v2 cgroup is the inode number of its directory
+        let id = match fs::metadata(cgroup.exclusion_path()) {
+            Ok(metadata) => metadata.ino(),
+            Err(error) if error.kind() == io::ErrorKind::NotFound => {
+                log::warn!("Split tunnel cgroup v2 cgroup does not exist");
+                return Ok(None);
+            }
+            Err(error) => return Err(Error::FindCgroupMount(error)),
+        };
+        // Check that the cgroup can be matched, rather than failing to apply the whole policy
+        if let Err(error) = SocketCgroupV2::new(CGROUP2_EXCLUSION_LEVEL) {
+            log::error!(
+                "{}",
+                error
+                    .display_chain_with_msg("Unable to match traffic from the split tunnel cgroup")
+            );
+            return Ok(None);
+        }
+        Ok(Some(ExclusionMatch::Cgroup2 { id }))
+    }
+
     /// Allow split-tunneled traffic outside the tunnel.
     ///
     /// This is acheived by setting `fwmark` on connections initated by processes in the cgroup
-    /// defined by [split_tunnel::NET_CLS_CLASSID].
-    fn add_split_tunneling_rules(&mut self, policy: &FirewallPolicy, fwmark: u32) -> Result<()> {
+    /// identified by `exclusion_match`.
+    fn add_split_tunneling_rules(
+        &mut self,
+        policy: &FirewallPolicy,
+        fwmark: u32,
+        exclusion_match: ExclusionMatch,
+    ) -> Result<()> {
         // Send select DNS requests in the tunnel
         if let FirewallPolicy::Connected {
             tunnel, dns_config, ..
         } = policy
         {
-            for server in dns_config.tunnel_config() {
+            for server in &dns_config.allowed_tunnel_servers() {
                 let allow_rule = allow_tunnel_dns_rule(
                     &self.mangle_chain,
                     &tunnel.interface,
@@ -358,15 +490,25 @@ impl<'a> PolicyBatch<'a> {
             }
         }
 
-        // Split tunneled processes have their PIDs added to a net_cls cgroup.
-        // This causes all packets sent by that process to be marked with the
-        // cgroups classid (`NET_CLS_CLASSID`). This rule checks incoming packets for that classid.
-        // If the packet has the classid set then the packet will have two new marks applied to it.
+        // Split tunneled processes have their PIDs added to a cgroup.
+        // With cgroup v1, this causes all packets sent by that process to be marked with the
+        // cgroups classid (`NET_CLS_CLASSID`). With cgroup v2, the socket that sent the packet
+        // belongs to the cgroup. This rule checks outgoing packets for either.
+        // If the packet matches then the packet will have two new marks applied to it.
         // The `split_tunnel::MARK` as a connection tracking mark and the `fwmark` as packet
         // metadata.
         let mut rule = Rule::new(&self.mangle_chain);
-        rule.add_expr(&nft_expr!(meta cgroup));
-        rule.add_expr(&nft_expr!(cmp == split_tunnel::NET_CLS_CLASSID));
+        match exclusion_match {
+            ExclusionMatch::ClassId => {
+                rule.add_expr(&nft_expr!(meta cgroup));
+                rule.add_expr(&nft_expr!(cmp == split_tunnel::NET_CLS_CLASSID));
+            }
+            ExclusionMatch::Cgroup2 { id } => {
+                rule.add_expr(&SocketCgroupV2::new(CGROUP2_EXCLUSION_LEVEL)?);
+                let id = id.to_ne_bytes();
+                rule.add_expr(&nft_expr!(cmp == &id[..]));
+            }
+        }
         // Loads `split_tunnel::MARK` into first nftnl register
         rule.add_expr(&nft_expr!(immediate data split_tunnel::MARK));
         // Sets `split_tunnel::MARK` as connection tracker mark
@@ -377,6 +519,36 @@ impl<'a> PolicyBatch<'a> {
         rule.add_expr(&nft_expr!(meta mark set));
         self.batch.add(&rule, nftnl::MsgType::Add);
 
+        Ok(())
+    }
+
+    /// Route traffic covered by firewall exceptions outside the tunnel, in the same way as
+    /// split-tunneled traffic. Exceptions that are bound to an interface are left to the routing
+    /// table. Returns whether any traffic is marked.
+    fn add_firewall_exception_mark_rules(
+        &mut self,
+        exceptions: &[FirewallException],
+        fwmark: u32,
+    ) -> bool {
+        let mut marked = false;
+        for exception in exceptions.iter().filter(|e| e.interface.is_none()) {
+            for transport in exception_transport_matches(exception) {
+                let mut rule = Rule::new(&self.mangle_chain);
+                check_exception(&mut rule, End::Dst, exception, transport);
+                rule.add_expr(&nft_expr!(immediate data split_tunnel::MARK));
+                rule.add_expr(&nft_expr!(ct mark set));
+                rule.add_expr(&nft_expr!(immediate data fwmark));
+                rule.add_expr(&nft_expr!(meta mark set));
+                self.batch.add(&rule, nftnl::MsgType::Add);
+            }
+            marked = true;
+        }
+        marked
+    }
+
+    /// Allow connections marked with `split_tunnel::MARK` outside the tunnel, and make sure that
+    /// they are routed and masqueraded correctly.
+    fn add_excluded_traffic_rules(&mut self, policy: &FirewallPolicy, fwmark: u32) -> Result<()> {
         for chain in &[&self.in_chain, &self.out_chain, &self.forward_chain] {
             let mut rule = Rule::new(chain);
             rule.add_expr(&nft_expr!(ct mark));
@@ -566,6 +738,8 @@ impl<'a> PolicyBatch<'a> {
                 allow_lan,
                 allowed_endpoint,
                 allowed_tunnel_traffic,
+                firewall_exceptions: _,
+                allowed_interfaces: _,
             } => {
                 self.add_allow_tunnel_endpoint_rules(peer_endpoint, fwmark);
                 self.add_allow_endpoint_rules(allowed_endpoint);
@@ -599,10 +773,12 @@ impl<'a> PolicyBatch<'a> {
                 tunnel,
                 allow_lan,
                 dns_config,
+                firewall_exceptions: _,
+                allowed_interfaces: _,
             } => {
                 self.add_allow_tunnel_endpoint_rules(peer_endpoint, fwmark);
 
-                for server in dns_config.tunnel_config() {
+                for server in &dns_config.allowed_tunnel_servers() {
                     self.add_allow_tunnel_dns_rule(
                         &tunnel.interface,
                         TransportProtocol::Udp,
@@ -614,7 +790,7 @@ impl<'a> PolicyBatch<'a> {
                         *server,
                     )?;
                 }
-                for server in dns_config.non_tunnel_config() {
+                for server in &dns_config.allowed_non_tunnel_servers() {
                     self.add_allow_local_dns_rule(
                         &tunnel.interface,
                         TransportProtocol::Udp,
@@ -639,6 +815,8 @@ impl<'a> PolicyBatch<'a> {
             FirewallPolicy::Blocked {
                 allow_lan,
                 allowed_endpoint,
+                firewall_exceptions: _,
+                allowed_interfaces: _,
             } => {
                 if let Some(endpoint) = allowed_endpoint {
                     self.add_allow_endpoint_rules(endpoint);
@@ -653,6 +831,8 @@ impl<'a> PolicyBatch<'a> {
         if allow_lan {
             self.add_allow_lan_rules();
         }
+        self.add_firewall_exception_rules(policy.firewall_exceptions())?;
+        self.add_allowed_interface_rules(policy.allowed_interfaces())?;
 
         // Reject any remaining outgoing traffic
         for chain in &[&self.out_chain, &self.forward_chain] {
@@ -911,6 +1091,56 @@ impl<'a> PolicyBatch<'a> {
         self.add_dhcp_server_rules();
     }
 
+    /// Allow traffic to and from the networks of the user-defined firewall exceptions. Incoming
+    /// connections are only allowed for exceptions without a port.
+    fn add_firewall_exception_rules(&mut self, exceptions: &[FirewallException]) -> Result<()> {
+        for exception in exceptions {
+            for transport in exception_transport_matches(exception) {
+                let mut out_rule = Rule::new(&self.out_chain);
+                if let Some(interface) = &exception.interface {
+                    check_iface_name(&mut out_rule, Direction::Out, interface)?;
+                }
+                check_exception(&mut out_rule, End::Dst, exception, transport);
+                add_verdict(&mut out_rule, &Verdict::Accept);
+                self.batch.add(&out_rule, nftnl::MsgType::Add);
+
+                let mut in_rule = Rule::new(&self.in_chain);
+                if let Some(interface) = &exception.interface {
+                    check_iface_name(&mut in_rule, Direction::In, interface)?;
+                }
+                check_exception(&mut in_rule, End::Src, exception, transport);
+                if exception.port.is_some() {
+                    let allowed_states = nftnl::expr::ct::States::ESTABLISHED.bits();
+                    in_rule.add_expr(&nft_expr!(ct state));
+                    in_rule.add_expr(&nft_expr!(bitwise mask allowed_states, xor 0u32));
+                    in_rule.add_expr(&nft_expr!(cmp != 0u32));
+                }
+                add_verdict(&mut in_rule, &Verdict::Accept);
+                self.batch.add(&in_rule, nftnl::MsgType::Add);
+            }
+        }
+        Ok(())
+    }
+
+    /// Allow all traffic on the interfaces of other VPNs or overlay networks, such as Tailscale or
+    /// Docker bridges.
+    fn add_allowed_interface_rules(&mut self, interfaces: &[AllowedInterface]) -> Result<()> {
+        for interface in interfaces {
+            for (chain, direction) in [
+                (&self.out_chain, Direction::Out),
+                (&self.in_chain, Direction::In),
+                (&self.forward_chain, Direction::Out),
+                (&self.forward_chain, Direction::In),
+            ] {
+                let mut rule = Rule::new(chain);
+                check_iface_name(&mut rule, direction, &interface.name)?;
+                add_verdict(&mut rule, &Verdict::Accept);
+                self.batch.add(&rule, nftnl::MsgType::Add);
+            }
+        }
+        Ok(())
+    }
+
     fn add_dhcp_server_rules(&mut self) {
         use TransportProtocol::Udp;
         // Outgoing DHCPv4 response
@@ -995,6 +1225,58 @@ fn check_not_iface(rule: &mut Rule<'_>, direction: Direction, iface: &str) -> Re
     Ok(())
 }
 
+/// Match on the interface name rather than the index, so that the rule applies to interfaces that
+/// are created after the policy is set, such as those of other VPNs.
+fn check_iface_name(rule: &mut Rule<'_>, direction: Direction, iface: &str) -> Result<()> {
+    let iface_name = CString::new(iface).map_err(|_| Error::InvalidIfaceName(iface.to_owned()))?;
+    rule.add_expr(&match direction {
+        Direction::In => nft_expr!(meta iifname),
+        Direction::Out => nft_expr!(meta oifname),
+    });
+    rule.add_expr(&nft_expr!(cmp == InterfaceName::Exact(iface_name)));
+    Ok(())
+}
+
+/// Match traffic covered by `exception`, where `end` is the end of the remote network.
+fn check_exception(
+    rule: &mut Rule<'_>,
+    end: End,
+    exception: &FirewallException,
+    transport: TransportMatch,
+) {
+    check_net(rule, end, exception.network);
+    match transport {
+        TransportMatch::Any => (),
+        TransportMatch::Protocol(protocol) => check_l4proto(rule, protocol),
+        TransportMatch::Port(protocol, port) => check_port(rule, protocol, end, port),
+    }
+}
+
+/// Transport layer part of a rule for a firewall exception.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum TransportMatch {
+    /// Any transport protocol.
+    Any,
+    /// Any port of a transport protocol.
+    Protocol(TransportProtocol),
+    /// A single port of a transport protocol.
+    Port(TransportProtocol, u16),
+}
+
+/// Transport layer parts of the rules needed for `exception`, with one rule for each. A port
+/// without a protocol applies to both UDP and TCP.
+fn exception_transport_matches(exception: &FirewallException) -> Vec<TransportMatch> {
+    match (exception.protocol, exception.port) {
+        (Some(protocol), Some(port)) => vec![TransportMatch::Port(protocol, port)],
+        (Some(protocol), None) => vec![TransportMatch::Protocol(protocol)],
+        (None, Some(port)) => vec![
+            TransportMatch::Port(TransportProtocol::Udp, port),
+            TransportMatch::Port(TransportProtocol::Tcp, port),
+        ],
+        (None, None) => vec![TransportMatch::Any],
+    }
+}
+
 fn check_net(rule: &mut Rule<'_>, end: End, net: impl Into<IpNetwork>) {
     let net = net.into();
     // Must check network layer protocol before loading network layer payload
@@ -1128,3 +1410,43 @@ fn batch_deprecated_tables(batch: &mut Batch) {
         batch.add(table, nftnl::MsgType::Del);
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn exception(protocol: Option<TransportProtocol>, port: Option<u16>) -> FirewallException {
+        FirewallException {
+            network: "192.0.2.0/24".parse().unwrap(),
+            protocol,
+            port,
+            interface: None,
+        }
+    }
+
+    #[test]
+    fn test_exception_transport_matches() {
+        use TransportProtocol::{Tcp, Udp};
+
+        assert_eq!(
+            exception_transport_matches(&exception(None, None)),
+            [TransportMatch::Any]
+        );
+        assert_eq!(
+            exception_transport_matches(&exception(Some(Tcp), None)),
+            [TransportMatch::Protocol(Tcp)]
+        );
+        assert_eq!(
+            exception_transport_matches(&exception(Some(Udp), Some(53))),
+            [TransportMatch::Port(Udp, 53)]
+        );
+        // A port without a protocol needs one rule per protocol
+        assert_eq!(
+            exception_transport_matches(&exception(None, Some(443))),
+            [
+                TransportMatch::Port(Udp, 443),
+                TransportMatch::Port(Tcp, 443)
+            ]
+        );
+    }
+}
@@ -7,6 +7,8 @@ use std::{
     sync::LazyLock,
 };
 use talpid_types::net::{ALLOWED_LAN_NETS, AllowedEndpoint, AllowedTunnelTraffic};
+#[cfg(target_os = "linux")]
+use talpid_types::net::{AllowedInterface, FirewallException};
 
 #[cfg(target_os = "macos")]
 #[path = "macos.rs"]
@@ -91,6 +93,12 @@ pub enum FirewallPolicy {
         allowed_endpoint: AllowedEndpoint,
         /// Networks for which to permit in-tunnel traffic.
         allowed_tunnel_traffic: AllowedTunnelTraffic,
+        /// User-defined exceptions that allow traffic outside the tunnel.
+        #[cfg(target_os = "linux")]
+        firewall_exceptions: Vec<FirewallException>,
+        /// Interfaces of other VPNs or overlay networks that traffic is always allowed on.
+        #[cfg(target_os = "linux")]
+        allowed_interfaces: Vec<AllowedInterface>,
         /// Interface to redirect (VPN tunnel) traffic to
         #[cfg(target_os = "macos")]
         redirect_interface: Option<String>,
@@ -107,6 +115,12 @@ pub enum FirewallPolicy {
         /// Servers that are allowed to respond to DNS requests.
         #[cfg(not(target_os = "android"))]
         dns_config: ResolvedDnsConfig,
+        /// User-defined exceptions that allow traffic outside the tunnel.
+        #[cfg(target_os = "linux")]
+        firewall_exceptions: Vec<FirewallException>,
+        /// Interfaces of other VPNs or overlay networks that traffic is always allowed on.
+        #[cfg(target_os = "linux")]
+        allowed_interfaces: Vec<AllowedInterface>,
         /// Interface to redirect (VPN tunnel) traffic to
         #[cfg(target_os = "macos")]
         redirect_interface: Option<String>,
@@ -118,6 +132,12 @@ pub enum FirewallPolicy {
         allow_lan: bool,
         /// Host that should be reachable while in the blocked state.
         allowed_endpoint: Option<AllowedEndpoint>,
+        /// User-defined exceptions that allow traffic outside the tunnel.
+        #[cfg(target_os = "linux")]
+        firewall_exceptions: Vec<FirewallException>,
+        /// Interfaces of other VPNs or overlay networks that traffic is always allowed on.
+        #[cfg(target_os = "linux")]
+        allowed_interfaces: Vec<AllowedInterface>,
     },
 }
 
@@ -178,6 +198,41 @@ impl FirewallPolicy {
         }
     }
 
+    /// Return the user-defined exceptions that allow traffic outside the tunnel.
+    #[cfg(target_os = "linux")]
+    pub fn firewall_exceptions(&self) -> &[FirewallException] {
+        match self {
+            FirewallPolicy::Connecting {
+                firewall_exceptions,
+                ..
+            }
+            | FirewallPolicy::Connected {
+                firewall_exceptions,
+                ..
+            }
+            | FirewallPolicy::Blocked {
+                firewall_exceptions,
+                ..
+            } => firewall_exceptions,
+        }
+    }
+
+    /// Return the interfaces that traffic is always allowed on.
+    #[cfg(target_os = "linux")]
+    pub fn allowed_interfaces(&self) -> &[AllowedInterface] {
+        match self {
+            FirewallPolicy::Connecting {
+                allowed_interfaces, ..
+            }
+            | FirewallPolicy::Connected {
+                allowed_interfaces, ..
+            }
+            | FirewallPolicy::Blocked {
+                allowed_interfaces, ..
+            } => allowed_interfaces,
+        }
+    }
+
     /// Return the interface to redirect (VPN tunnel) traffic to, if any.
     #[cfg(target_os = "macos")]
     pub fn redirect_interface(&self) -> Option<&str> {
@@ -39,8 +39,8 @@ pub mod tunnel_state_machine;
 #[cfg(target_os = "linux")]
 mod linux;
 
-/// A resolver that's controlled by the tunnel state machine
-#[cfg(target_os = "macos")]
+/// A local forwarding DNS resolver
+#[cfg(any(target_os = "macos", target_os = "linux"))]
 pub(crate) mod resolver;
 
 /// Connectivity monitor for Android
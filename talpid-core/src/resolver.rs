@@ -3,7 +3,14 @@
 //!   domains receive a spoofed answer. This fools the OS into thinking that it has connectivity.
 //! * In the `Forwarding` state, queries are forwarded to a set of configured DNS servers. This
 //!   lets us use the routing table to determine where to send them, instead of them being forced
-//!   out on the primary interface (in some cases).
+//!   out on the primary interface (in some cases). The servers may be DNS-over-HTTPS or
+//!   DNS-over-TLS servers, in which case queries are encrypted until they reach the server.
+//!   Queries for domains that match a domain rule are forwarded to the servers of that rule
+//!   instead.
+//!
+//! On macOS, the resolver is controlled by the tunnel state machine. On Linux, it is used by the
+//! DNS monitor to forward queries to encrypted DNS servers, and to emulate per-domain DNS rules
+//! when the system cannot route domains to specific servers.
 //!
 //! See [start_resolver].
 use std::{
@@ -43,7 +50,12 @@ use hickory_server::{
 use rand::random;
 use socket2::{Domain, Protocol, Socket, Type};
 use std::sync::LazyLock;
-use talpid_types::drop_guard::{OnDrop, on_drop};
+#[cfg(target_os = "macos")]
+use talpid_types::drop_guard::on_drop;
+use talpid_types::{
+    drop_guard::OnDrop,
+    net::dns::{DomainDnsRule, EncryptedDnsProtocol, EncryptedDnsServer},
+};
 use tokio::{
     net::{self, UdpSocket},
     task::JoinHandle,
@@ -54,6 +66,7 @@ use tokio::{
 /// This setting does not affect the error or blocked state. In those states, we will want to use
 /// the local DNS resoler to work around Apple's captive portals check. Exactly how this is done is
 /// documented elsewhere.
+#[cfg(target_os = "macos")]
 pub static LOCAL_DNS_RESOLVER: LazyLock<bool> = LazyLock::new(|| {
     let disable_local_dns_resolver = std::env::var("TALPID_DISABLE_LOCAL_DNS_RESOLVER")
         .map(|v| v != "0")
@@ -67,6 +80,7 @@ pub static LOCAL_DNS_RESOLVER: LazyLock<bool> = LazyLock::new(|| {
 });
 
 // Name of the loopback network device.
+#[cfg(target_os = "macos")]
 const LOOPBACK: &str = "lo0";
 
 /// The port we should bind the local DNS resolver to.
@@ -173,6 +187,10 @@ enum Config {
     Forwarding {
         /// Remote DNS server to use
         dns_servers: Vec<IpAddr>,
+        /// Remote DNS-over-HTTPS or DNS-over-TLS servers to use
+        encrypted_servers: Vec<EncryptedDnsServer>,
+        /// Remote DNS servers to use for specific domains
+        domain_rules: Vec<DomainDnsRule>,
     },
 }
 
@@ -181,7 +199,12 @@ enum Resolver {
     Blocking,
 
     /// Forward DNS queries to a configured server
-    Forwarding(TokioAsyncResolver),
+    Forwarding {
+        /// Resolver used for queries that do not match any domain rule
+        default: TokioAsyncResolver,
+        /// Resolvers for specific domains, ordered from most to least specific
+        domains: Vec<(LowerName, TokioAsyncResolver)>,
+    },
 }
 
 impl Resolver {
@@ -194,8 +217,13 @@ impl Resolver {
             Resolver::Blocking => {
                 let _ = tx.send(Self::resolve_blocked(query));
             }
-            Resolver::Forwarding(resolver) => {
-                let resolver = resolver.clone();
+            Resolver::Forwarding { default, domains } => {
+                let resolver = domains
+                    .iter()
+                    .find(|(domain, _)| domain.zone_of(query.name()))
+                    .map(|(_, resolver)| resolver)
+                    .unwrap_or(default)
+                    .clone();
                 tokio::spawn(async move {
                     let lookup = Self::resolve_forward(resolver, query);
                     let _ = tx.send(lookup.await);
@@ -277,7 +305,33 @@ impl ResolverHandle {
     pub async fn enable_forward(&self, dns_servers: Vec<IpAddr>) {
         let (response_tx, response_rx) = oneshot::channel();
         let _ = self.tx.unbounded_send(ResolverMessage::SetConfig {
-            new_config: Config::Forwarding { dns_servers },
+            new_config: Config::Forwarding {
+                dns_servers,
+                encrypted_servers: vec![],
+                domain_rules: vec![],
+            },
+            response_tx,
+        });
+
+        let _ = response_rx.await;
+    }
+
+    /// Forward queries to `dns_servers` and `encrypted_servers`, except for queries for domains
+    /// in `domain_rules`, which are forwarded to the servers of the most specific matching rule
+    #[cfg(target_os = "linux")]
+    pub async fn enable_custom_forward(
+        &self,
+        dns_servers: Vec<IpAddr>,
+        encrypted_servers: Vec<EncryptedDnsServer>,
+        domain_rules: Vec<DomainDnsRule>,
+    ) {
+        let (response_tx, response_rx) = oneshot::channel();
+        let _ = self.tx.unbounded_send(ResolverMessage::SetConfig {
+            new_config: Config::Forwarding {
+                dns_servers,
+                encrypted_servers,
+                domain_rules,
+            },
             response_tx,
         });
 
@@ -396,41 +450,10 @@ impl LocalResolver {
     async fn new_random_socket(config: &LocalResolverConfig) -> Result<(UdpSocket, OnDrop), Error> {
         use std::net::Ipv4Addr;
 
-        let random_loopback = || async move {
-            let addr = Ipv4Addr::new(127, 1u8.max(random()), random(), random());
-
-            // TODO: this command requires root privileges and will thus not work in `cargo test`.
-            // This means that the tests will fall back to 127.0.0.1, and will not assert that the
-            // ifconfig stuff actually works. We probably do want to test this, so what do?
-            talpid_macos::net::add_alias(LOOPBACK, IpAddr::from(addr))
-                .await
-                .inspect_err(|e| {
-                    log::warn!("Failed to add loopback {LOOPBACK} alias {addr}: {e}");
-                })
-                .ok()?;
-
-            log::debug!("Created loopback address {addr}");
-
-            // Clean up ip address when stopping the resolver
-            let cleanup_ifconfig = on_drop(move || {
-                tokio::task::spawn(async move {
-                    log::debug!("Cleaning up loopback address {addr}");
-                    if let Err(e) =
-                        talpid_macos::net::remove_alias(LOOPBACK, IpAddr::from(addr)).await
-                    {
-                        log::warn!("Failed to clean up {LOOPBACK} alias {addr}: {e}");
-                    }
-                });
-            })
-            .boxed();
-
-            Some((addr, cleanup_ifconfig))
-        };
-
         for attempt in 0.. {
             let (socket_addr, on_drop) = match attempt {
                 ..3 if !config.use_random_loopback => continue,
-                ..3 => match random_loopback().await {
+                ..3 => match Self::random_loopback().await {
                     Some(random) => random,
                     None => continue,
                 },
@@ -473,6 +496,47 @@ impl LocalResolver {
         Err(Error::UdpBind)
     }
 
+    /// Pick a random address in `127.1-255.0-255.0-255`, and add it as an alias to the loopback
+    /// interface.
+    #[cfg(target_os = "macos")]
+    async fn random_loopback() -> Option<(Ipv4Addr, OnDrop)> {
+        let addr = Ipv4Addr::new(127, 1u8.max(random()), random(), random());
+
+        // TODO: this command requires root privileges and will thus not work in `cargo test`.
+        // This means that the tests will fall back to 127.0.0.1, and will not assert that the
+        // ifconfig stuff actually works. We probably do want to test this, so what do?
+        talpid_macos::net::add_alias(LOOPBACK, IpAddr::from(addr))
+            .await
+            .inspect_err(|e| {
+                log::warn!("Failed to add loopback {LOOPBACK} alias {addr}: {e}");
+            })
+            .ok()?;
+
+        log::debug!("Created loopback address {addr}");
+
+        // Clean up ip address when stopping the resolver
+        let cleanup_ifconfig = on_drop(move || {
+            tokio::task::spawn(async move {
+                log::debug!("Cleaning up loopback address {addr}");
+                if let Err(e) = talpid_macos::net::remove_alias(LOOPBACK, IpAddr::from(addr)).await
+                {
+                    log::warn!("Failed to clean up {LOOPBACK} alias {addr}: {e}");
+                }
+            });
+        })
+        .boxed();
+
+        Some((addr, cleanup_ifconfig))
+    }
+
+    /// Pick a random address in `127.1-255.0-255.0-255`. On Linux, all of `127.0.0.0/8` is
+    /// assigned to the loopback interface, so no alias has to be added.
+    #[cfg(target_os = "linux")]
+    async fn random_loopback() -> Option<(Ipv4Addr, OnDrop)> {
+        let addr = Ipv4Addr::new(127, 1u8.max(random()), random(), random());
+        Some((addr, OnDrop::noop()))
+    }
+
     /// Runs the filtering resolver as an actor, listening for new queries instances.  When all
     /// related [ResolverHandle] instances are dropped, this function will return, closing the DNS
     /// server.
@@ -518,10 +582,18 @@ impl LocalResolver {
     fn update_config(&mut self, config: Config) {
         match config {
             Config::Blocking => self.blocking(),
-            Config::Forwarding { mut dns_servers } => {
+            Config::Forwarding {
+                mut dns_servers,
+                mut encrypted_servers,
+                mut domain_rules,
+            } => {
                 // make sure not to accidentally forward queries to ourselves
                 dns_servers.retain(|addr| *addr != self.bound_to.ip());
-                self.forwarding(dns_servers);
+                encrypted_servers.retain(|server| server.address != self.bound_to.ip());
+                for rule in &mut domain_rules {
+                    rule.servers.retain(|addr| *addr != self.bound_to.ip());
+                }
+                self.forwarding(dns_servers, encrypted_servers, domain_rules);
             }
         }
     }
@@ -531,30 +603,87 @@ impl LocalResolver {
         self.inner_resolver = Resolver::Blocking;
     }
 
-    /// Turn into a forwarding resolver (forward DNS queries to [dns_servers]).
-    fn forwarding(&mut self, dns_servers: Vec<IpAddr>) {
-        let forward_server_config =
+    /// Turn into a forwarding resolver (forward DNS queries to [dns_servers] and
+    /// [encrypted_servers], or to the servers of a matching rule in [domain_rules]).
+    fn forwarding(
+        &mut self,
+        dns_servers: Vec<IpAddr>,
+        encrypted_servers: Vec<EncryptedDnsServer>,
+        domain_rules: Vec<DomainDnsRule>,
+    ) {
+        let mut forward_server_config =
             NameServerConfigGroup::from_ips_clear(&dns_servers, DNS_PORT, true);
+        for server in encrypted_servers {
+            let address = [server.address];
+            let encrypted_config = match server.protocol {
+                EncryptedDnsProtocol::Https => NameServerConfigGroup::from_ips_https(
+                    &address,
+                    server.port,
+                    server.hostname,
+                    true,
+                ),
+                EncryptedDnsProtocol::Tls => NameServerConfigGroup::from_ips_tls(
+                    &address,
+                    server.port,
+                    server.hostname,
+                    true,
+                ),
+            };
+            forward_server_config.merge(encrypted_config);
+        }
 
         let forward_config = ResolverConfig::from_parts(None, vec![], forward_server_config);
         let resolver_opts = ResolverOpts::default();
 
-        let resolver = TokioAsyncResolver::tokio(forward_config, resolver_opts);
+        let default = TokioAsyncResolver::tokio(forward_config, resolver_opts);
 
-        self.inner_resolver = Resolver::Forwarding(resolver);
+        let mut domains = vec![];
+        for rule in domain_rules
+            .into_iter()
+            .filter(|rule| !rule.servers.is_empty())
+        {
+            let domain = match Name::from_str(&rule.domain) {
+                Ok(domain) => LowerName::from(domain),
+                Err(error) => {
+                    log::error!(
+                        "Ignoring DNS rule for invalid domain {}: {error}",
+                        rule.domain
+                    );
+                    continue;
+                }
+            };
+            let servers = NameServerConfigGroup::from_ips_clear(&rule.servers, DNS_PORT, true);
+            let config = ResolverConfig::from_parts(None, vec![], servers);
+            domains.push((
+                domain,
+                TokioAsyncResolver::tokio(config, ResolverOpts::default()),
+            ));
+        }
+        // Prefer the most specific domain when several rules match a query
+        domains.sort_by_key(|(domain, _)| std::cmp::Reverse(domain.num_labels()));
+
+        self.inner_resolver = Resolver::Forwarding { default, domains };
     }
 }
 
 /// Flush the DNS cache.
+#[cfg(target_os = "macos")]
 fn flush_system_cache() {
     if let Err(error) = kill_mdnsresponder() {
         log::error!("Failed to kill mDNSResponder: {error}");
     }
 }
 
+/// Flush the DNS cache. On Linux, the cache of the system resolver is flushed when its servers are
+/// updated by the DNS monitor, so there is nothing to do here.
+#[cfg(target_os = "linux")]
+fn flush_system_cache() {}
+
+#[cfg(target_os = "macos")]
 const MDNS_RESPONDER_PATH: &str = "/usr/sbin/mDNSResponder";
 
 /// Find and kill mDNSResponder. The OS will restart the service.
+#[cfg(target_os = "macos")]
 fn kill_mdnsresponder() -> io::Result<()> {
     if let Some(mdns_pid) = talpid_macos::process::pid_of_path(MDNS_RESPONDER_PATH) {
         nix::sys::signal::kill(
@@ -1,11 +1,15 @@
 use std::{
+    collections::{HashMap, HashSet},
     env, fs,
     io::{self, BufRead, BufReader, Write},
     path::{Path, PathBuf},
+    sync::{Arc, Mutex, mpsc},
+    thread,
+    time::Duration,
 };
 use talpid_types::{
     ErrorExt,
-    cgroup::{SPLIT_TUNNEL_CGROUP_NAME, find_net_cls_mount},
+    cgroup::{SplitTunnelCgroup, find_split_tunnel_cgroup},
 };
 
 const DEFAULT_NET_CLS_DIR: &str = "/sys/fs/cgroup/net_cls";
@@ -18,6 +22,9 @@ pub const NET_CLS_CLASSID: u32 = 0x4d9f41;
 /// This should be an arbitrary but unique integer.
 pub const MARK: i32 = 0xf41;
 
+/// How often `/proc` is scanned for processes belonging to excluded apps.
+const APP_SCAN_INTERVAL: Duration = Duration::from_secs(1);
+
 /// Errors related to split tunneling.
 #[derive(thiserror::Error, Debug)]
 pub enum Error {
@@ -57,21 +64,29 @@ pub enum Error {
 /// Manages PIDs in the Linux Cgroup excluded from the VPN tunnel.
 pub struct PidManager {
     inner: Inner,
+    /// Excludes processes belonging to excluded apps, if there are any.
+    app_monitor: Option<AppMonitor>,
 }
 
 enum Inner {
-    Ok { net_cls_path: PathBuf },
+    Ok { cgroup: ExclusionCgroup },
     Failed { err: Error },
 }
 
 impl Default for PidManager {
     /// Creates a new PID Cgroup manager.
     ///
-    /// Finds the corresponding Cgroup to use. Will mount a `net_cls` filesystem
-    /// if none exists.
+    /// Finds the corresponding Cgroup to use. A `net_cls` cgroup v1 hierarchy is used if one is
+    /// mounted, and a cgroup v2 hierarchy otherwise. Will mount a `net_cls` filesystem if neither
+    /// exists.
     fn default() -> Self {
         let inner = match Self::new_inner() {
-            Ok(net_cls_path) => Inner::Ok { net_cls_path },
+            Ok(cgroup) => {
+                log::debug!("Using cgroup for split tunneling: {cgroup:?}");
+                Inner::Ok {
+                    cgroup: ExclusionCgroup::new(cgroup),
+                }
+            }
             Err(err) => {
                 log::error!(
                     "{}",
@@ -80,21 +95,28 @@ impl Default for PidManager {
                 Inner::Failed { err }
             }
         };
-        PidManager { inner }
+        PidManager {
+            inner,
+            app_monitor: None,
+        }
     }
 }
 
 impl PidManager {
-    fn new_inner() -> Result<PathBuf, Error> {
-        let net_cls_path = Self::create_cgroup()?;
-        Self::setup_exclusion_group(&net_cls_path)?;
-        Ok(net_cls_path)
+    fn new_inner() -> Result<SplitTunnelCgroup, Error> {
+        let cgroup = Self::create_cgroup()?;
+        Self::setup_exclusion_group(&cgroup)?;
+        Ok(cgroup)
     }
 
     /// Set up cgroup used to track PIDs for split tunneling.
-    fn create_cgroup() -> Result<PathBuf, Error> {
-        if let Some(net_cls_path) = find_net_cls_mount().map_err(Error::ListMounts)? {
-            return Ok(net_cls_path);
+    fn create_cgroup() -> Result<SplitTunnelCgroup, Error> {
+        match find_split_tunnel_cgroup().map_err(Error::ListMounts)? {
+            // Explicitly asking for a `net_cls` mount takes precedence over cgroup v2
+            Some(SplitTunnelCgroup::V2(_))
+                if env::var_os(NET_CLS_DIR_OVERRIDE_ENV_VAR).is_some() => {}
+            Some(cgroup) => return Ok(cgroup),
+            None => (),
         }
 
         let net_cls_dir = env::var(NET_CLS_DIR_OVERRIDE_ENV_VAR)
@@ -115,25 +137,31 @@ impl PidManager {
         )
         .map_err(Error::InitNetClsCGroup)?;
 
-        Ok(net_cls_dir)
+        Ok(SplitTunnelCgroup::NetCls(net_cls_dir))
     }
 
-    fn setup_exclusion_group(net_cls_path: &Path) -> Result<(), Error> {
-        let exclusions_dir = net_cls_path.join(SPLIT_TUNNEL_CGROUP_NAME);
+    fn setup_exclusion_group(cgroup: &SplitTunnelCgroup) -> Result<(), Error> {
+        let exclusions_dir = cgroup.exclusion_path();
         if !exclusions_dir.exists() {
             fs::create_dir(exclusions_dir.clone()).map_err(Error::CreateCGroup)?;
         }
 
-        let classid_path = exclusions_dir.join("net_cls.classid");
-        fs::write(classid_path, NET_CLS_CLASSID.to_string().as_bytes())
-            .map_err(Error::SetCGroupClassId)
+        match cgroup {
+            SplitTunnelCgroup::NetCls(_) => {
+                let classid_path = exclusions_dir.join("net_cls.classid");
+                fs::write(classid_path, NET_CLS_CLASSID.to_string().as_bytes())
+                    .map_err(Error::SetCGroupClassId)
+            }
+            // The firewall identifies the cgroup by its ID, so there is nothing to configure
+            SplitTunnelCgroup::V2(_) => Ok(()),
+        }
     }
 
-    fn get_net_cls_path(&self) -> Result<&Path, Error> {
+    fn get_cgroup(&self) -> Result<&ExclusionCgroup, Error> {
         match &self.inner {
-            Inner::Ok { net_cls_path } => Ok(net_cls_path),
+            Inner::Ok { cgroup } => Ok(cgroup),
             Inner::Failed { err } => {
-                log::error!("Failed to get netcls path: {err}");
+                log::error!("Failed to get cgroup path: {err}");
                 Err(Error::Unavailable)
             }
         }
@@ -141,38 +169,46 @@ impl PidManager {
 
     /// Add a PID to the Cgroup to have it excluded from the tunnel.
     pub fn add(&self, pid: i32) -> Result<(), Error> {
-        let exclusions_path = self
-            .get_net_cls_path()?
-            .join(SPLIT_TUNNEL_CGROUP_NAME)
-            .join("cgroup.procs");
-
-        let mut file = fs::OpenOptions::new()
-            .write(true)
-            .create(true)
-            .truncate(false)
-            .open(exclusions_path)
-            .map_err(Error::AddCGroupPid)?;
-
-        file.write_all(pid.to_string().as_bytes())
-            .map_err(Error::AddCGroupPid)
+        self.get_cgroup()?.add_pid(pid)
     }
 
     /// Remove a PID from the Cgroup to have it included in the tunnel.
     pub fn remove(&self, pid: i32) -> Result<(), Error> {
-        // FIXME: We remove PIDs from our cgroup here by adding
-        //        them to the parent cgroup. This seems wrong.
-        let mut file = Self::open_parent_cgroup_handle(self.get_net_cls_path()?)
-            .map_err(Error::RemoveCGroupPid)?;
+        self.get_cgroup()?.remove_pid(pid)
+    }
+
+    /// Exclude all processes, current and future, that run any of the executables in `apps`.
+    /// Processes that were excluded because of an app that is no longer in `apps` are included
+    /// in the tunnel again.
+    pub fn set_excluded_apps(&mut self, apps: HashSet<PathBuf>) -> Result<(), Error> {
+        if apps.is_empty() {
+            // Dropping the monitor stops it
+            self.app_monitor = None;
+            return Ok(());
+        }
+
+        // `/proc/<pid>/exe` always refers to the resolved path
+        let apps = apps
+            .into_iter()
+            .map(|app| fs::canonicalize(&app).unwrap_or(app))
+            .collect();
 
-        file.write_all(pid.to_string().as_bytes())
-            .map_err(Error::RemoveCGroupPid)
+        match &self.app_monitor {
+            Some(monitor) => monitor.set_apps(apps),
+            None => {
+                let cgroup = self.get_cgroup()?.clone();
+                self.app_monitor = Some(AppMonitor::start(cgroup, apps));
+            }
+        }
+        Ok(())
     }
 
     /// Return a list of all PIDs currently in the Cgroup excluded from the tunnel.
     pub fn list(&self) -> Result<Vec<i32>, Error> {
         let exclusions_path = self
-            .get_net_cls_path()?
-            .join(SPLIT_TUNNEL_CGROUP_NAME)
+            .get_cgroup()?
+            .cgroup
+            .exclusion_path()
             .join("cgroup.procs");
 
         let file = fs::File::open(exclusions_path).map_err(Error::ListCGroupPids)?;
@@ -191,15 +227,10 @@ impl PidManager {
 
     /// Removes all PIDs from the Cgroup.
     pub fn clear(&self) -> Result<(), Error> {
-        let pids = self.list()?;
-
-        let mut file = Self::open_parent_cgroup_handle(self.get_net_cls_path()?)
-            .map_err(Error::RemoveCGroupPid)?;
-        for pid in pids {
-            file.write_all(pid.to_string().as_bytes())
-                .map_err(Error::RemoveCGroupPid)?;
+        let cgroup = self.get_cgroup()?;
+        for pid in self.list()? {
+            cgroup.remove_pid(pid)?;
         }
-
         Ok(())
     }
 
@@ -207,12 +238,167 @@ impl PidManager {
     pub fn is_enabled(&self) -> bool {
         matches!(self.inner, Inner::Ok { .. })
     }
+}
+
+/// The split tunneling cgroup, along with the cgroups that excluded processes were moved from.
+#[derive(Debug, Clone)]
+struct ExclusionCgroup {
+    cgroup: SplitTunnelCgroup,
+    /// The cgroup v2 cgroup that each excluded process belonged to before it was excluded.
+    original_cgroups: Arc<Mutex<HashMap<i32, PathBuf>>>,
+}
+
+impl ExclusionCgroup {
+    fn new(cgroup: SplitTunnelCgroup) -> Self {
+        Self {
+            cgroup,
+            original_cgroups: Arc::default(),
+        }
+    }
 
-    fn open_parent_cgroup_handle(net_cls_path: &Path) -> io::Result<fs::File> {
-        fs::OpenOptions::new()
-            .write(true)
-            .create(true)
-            .truncate(false)
-            .open(net_cls_path.join("cgroup.procs"))
+    /// Move `pid` into the exclusion cgroup.
+    fn add_pid(&self, pid: i32) -> Result<(), Error> {
+        if let SplitTunnelCgroup::V2(mount_path) = &self.cgroup {
+            let mut original_cgroups = self.original_cgroups.lock().unwrap();
+            // Forget processes that have exited
+            original_cgroups.retain(|pid, _| Path::new("/proc").join(pid.to_string()).exists());
+
+            match find_cgroup2(mount_path, pid) {
+                Ok(original) if original != self.cgroup.exclusion_path() => {
+                    original_cgroups.insert(pid, original);
+                }
+                Ok(_) => (),
+                Err(error) => log::debug!("Failed to find cgroup of PID {pid}: {error}"),
+            }
+        }
+        write_pid(&self.cgroup.exclusion_path(), pid).map_err(Error::AddCGroupPid)
+    }
+
+    /// Move `pid` out of the exclusion cgroup. With cgroup v2, it is moved back to the cgroup that
+    /// it was excluded from, if that still exists. Otherwise, it is moved to the root cgroup.
+    fn remove_pid(&self, pid: i32) -> Result<(), Error> {
+        let original = self
+            .original_cgroups
+            .lock()
+            .unwrap()
+            .remove(&pid)
+            .filter(|original| original.exists());
+        let target = original.unwrap_or_else(|| self.cgroup.mount_path().to_owned());
+        write_pid(&target, pid).map_err(Error::RemoveCGroupPid)
+    }
+}
+
+/// Move `pid` into the cgroup at `cgroup_path`.
+fn write_pid(cgroup_path: &Path, pid: i32) -> io::Result<()> {
+    let mut file = fs::OpenOptions::new()
+        .write(true)
+        .create(true)
+        .truncate(false)
+        .open(cgroup_path.join("cgroup.procs"))?;
+    file.write_all(pid.to_string().as_bytes())
+}
+
+/// Return the path of the cgroup v2 cgroup that `pid` belongs to.
+fn find_cgroup2(mount_path: &Path, pid: i32) -> io::Result<PathBuf> {
+    let cgroups = fs::read_to_string(format!("/proc/{pid}/cgroup"))?;
+    // The unified hierarchy is listed as `0::<path relative to the mount>`
+    let path = cgroups
+        .lines()
+        .find_map(|line| line.strip_prefix("0::"))
+        .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "No cgroup v2 cgroup"))?;
+    Ok(mount_path.join(path.trim_start_matches('/')))
+}
+
+/// Periodically scans `/proc` for processes running any of the excluded apps, and moves them
+/// into the exclusion cgroup. Child processes are excluded automatically, since they inherit the
+/// cgroup of their parent.
+///
+/// The monitor stops when it is dropped, and processes that it excluded are included in the
+/// tunnel again.
+struct AppMonitor {
+    apps_tx: mpsc::Sender<HashSet<PathBuf>>,
+}
+
+impl AppMonitor {
+    fn start(cgroup: ExclusionCgroup, apps: HashSet<PathBuf>) -> Self {
+        let (apps_tx, apps_rx) = mpsc::channel();
+        thread::spawn(move || Self::run(&cgroup, apps, apps_rx));
+        AppMonitor { apps_tx }
+    }
+
+    fn set_apps(&self, apps: HashSet<PathBuf>) {
+        let _ = self.apps_tx.send(apps);
+    }
+
+    fn run(
+        cgroup: &ExclusionCgroup,
+        mut apps: HashSet<PathBuf>,
+        apps_rx: mpsc::Receiver<HashSet<PathBuf>>,
+    ) {
+        // PIDs that have been excluded by the monitor
+        let mut excluded_pids = HashSet::new();
+
+        loop {
+            let app_pids = match find_app_pids(&apps) {
+                Ok(pids) => pids,
+                Err(error) => {
+                    log::error!(
+                        "{}",
+                        error.display_chain_with_msg("Failed to list running processes")
+                    );
+                    excluded_pids.clone()
+                }
+            };
+
+            for &pid in excluded_pids.difference(&app_pids) {
+                // The process may have exited, so this is expected to fail sometimes
+                if cgroup.remove_pid(pid).is_ok() {
+                    log::debug!("Stopped excluding PID {pid}");
+                }
+            }
+            for &pid in app_pids.difference(&excluded_pids) {
+                match cgroup.add_pid(pid) {
+                    Ok(()) => log::debug!("Excluding PID {pid}"),
+                    Err(error) => log::warn!(
+                        "{}",
+                        error.display_chain_with_msg(&format!("Failed to exclude PID {pid}"))
+                    ),
+                }
+            }
+            excluded_pids = app_pids;
+
+            match apps_rx.recv_timeout(APP_SCAN_INTERVAL) {
+                Ok(new_apps) => apps = new_apps,
+                Err(mpsc::RecvTimeoutError::Timeout) => (),
+                Err(mpsc::RecvTimeoutError::Disconnected) => break,
+            }
+        }
+
+        for pid in excluded_pids {
+            let _ = cgroup.remove_pid(pid);
+        }
+    }
+}
+
+/// Return the PIDs of all processes whose executable is in `apps`.
+fn find_app_pids(apps: &HashSet<PathBuf>) -> io::Result<HashSet<i32>> {
+    let mut pids = HashSet::new();
+    for entry in fs::read_dir("/proc")? {
+        let entry = entry?;
+        let Some(pid) = entry
+            .file_name()
+            .to_str()
+            .and_then(|name| name.parse::<i32>().ok())
+        else {
+            continue;
+        };
+        // Kernel threads have no executable, and processes may exit while we are scanning
+        let Ok(exe) = fs::read_link(entry.path().join("exe")) else {
+            continue;
+        };
+        if apps.contains(&exe) {
+            pids.insert(pid);
+        }
     }
+    Ok(pids)
 }
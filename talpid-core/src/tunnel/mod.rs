@@ -13,6 +13,7 @@ use talpid_types::{
     net::{TunnelParameters, wireguard as wireguard_types},
     tunnel::ErrorStateCause,
 };
+pub use talpid_wireguard::TunnelStatsHandle;
 
 #[cfg(not(target_os = "android"))]
 use talpid_tunnel::EventHook;
@@ -265,6 +266,15 @@ impl TunnelMonitor {
         }
     }
 
+    /// Returns a handle for reading traffic statistics, if the tunnel type provides any.
+    pub fn stats_handle(&self) -> Option<TunnelStatsHandle> {
+        match &self.monitor {
+            #[cfg(not(target_os = "android"))]
+            InternalTunnelMonitor::OpenVpn(_) => None,
+            InternalTunnelMonitor::Wireguard(monitor) => Some(monitor.stats_handle()),
+        }
+    }
+
     /// Consumes the monitor and blocks until the tunnel exits or there is an error.
     pub fn wait(self) -> Result<()> {
         self.monitor.wait()
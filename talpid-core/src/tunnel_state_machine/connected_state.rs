@@ -1,6 +1,7 @@
 use futures::StreamExt;
 use futures::channel::{mpsc, oneshot};
 use futures::stream::Fuse;
+use std::sync::{Arc, Mutex};
 
 use talpid_types::net::{AllowedClients, AllowedEndpoint, TunnelParameters};
 use talpid_types::tunnel::{ErrorStateCause, FirewallPolicyError};
@@ -12,9 +13,9 @@ use crate::firewall::FirewallPolicy;
 use crate::resolver::LOCAL_DNS_RESOLVER;
 #[cfg(windows)]
 use crate::tunnel::TunnelMonitor;
-use crate::tunnel::{TunnelEvent, TunnelMetadata};
+use crate::tunnel::{TunnelEvent, TunnelMetadata, TunnelStatsHandle};
 
-use super::connecting_state::TunnelCloseEvent;
+use super::connecting_state::{TunnelCloseEvent, final_tunnel_stats, send_tunnel_stats};
 use super::{
     AfterDisconnect, ConnectingState, DisconnectingState, ErrorState, EventConsequence,
     EventResult, SharedTunnelStateValues, TunnelCommand, TunnelCommandReceiver, TunnelState,
@@ -31,6 +32,7 @@ pub struct ConnectedState {
     tunnel_parameters: TunnelParameters,
     tunnel_close_event: TunnelCloseEvent,
     tunnel_close_tx: oneshot::Sender<()>,
+    stats_handle: Arc<Mutex<Option<TunnelStatsHandle>>>,
 }
 
 impl ConnectedState {
@@ -42,6 +44,7 @@ impl ConnectedState {
         tunnel_parameters: TunnelParameters,
         tunnel_close_event: TunnelCloseEvent,
         tunnel_close_tx: oneshot::Sender<()>,
+        stats_handle: Arc<Mutex<Option<TunnelStatsHandle>>>,
     ) -> (Box<dyn TunnelState>, TunnelStateTransition) {
         let connected_state = ConnectedState {
             metadata,
@@ -49,11 +52,13 @@ impl ConnectedState {
             tunnel_parameters,
             tunnel_close_event,
             tunnel_close_tx,
+            stats_handle,
         };
 
         let tunnel_interface = Some(connected_state.metadata.interface.clone());
         let tunnel_endpoint = talpid_types::net::TunnelEndpoint {
             tunnel_interface,
+            wireguard_backend: connected_state.metadata.wireguard_backend,
             ..connected_state.tunnel_parameters.get_tunnel_endpoint()
         };
 
@@ -62,6 +67,7 @@ impl ConnectedState {
                 connected_state.tunnel_close_tx,
                 connected_state.tunnel_close_event,
                 AfterDisconnect::Block(ErrorStateCause::SetFirewallPolicyError(error)),
+                None,
             )
         } else if let Err(error) = connected_state.set_dns(shared_values) {
             log::error!("{}", error.display_chain_with_msg("Failed to set DNS"));
@@ -69,6 +75,7 @@ impl ConnectedState {
                 connected_state.tunnel_close_tx,
                 connected_state.tunnel_close_event,
                 AfterDisconnect::Block(ErrorStateCause::SetDnsError),
+                None,
             )
         } else {
             (
@@ -135,6 +142,10 @@ impl ConnectedState {
             peer_endpoint,
             tunnel: self.metadata.clone(),
             allow_lan: shared_values.allow_lan,
+            #[cfg(target_os = "linux")]
+            firewall_exceptions: shared_values.firewall_exceptions.clone(),
+            #[cfg(target_os = "linux")]
+            allowed_interfaces: shared_values.allowed_interfaces.clone(),
             #[cfg(not(target_os = "android"))]
             dns_config: Self::resolve_dns(&self.metadata, shared_values),
             #[cfg(target_os = "macos")]
@@ -228,6 +239,7 @@ impl ConnectedState {
         shared_values: &mut SharedTunnelStateValues,
         after_disconnect: AfterDisconnect,
     ) -> EventConsequence {
+        let final_stats = final_tunnel_stats(shared_values, &self.stats_handle);
         Self::reset_dns(shared_values);
         Self::reset_routes(shared_values);
 
@@ -235,6 +247,7 @@ impl ConnectedState {
             self.tunnel_close_tx,
             self.tunnel_close_event,
             after_disconnect,
+            final_stats,
         ))
     }
 
@@ -271,6 +284,40 @@ impl ConnectedState {
                 let _ = complete_tx.send(());
                 consequence
             }
+            #[cfg(target_os = "linux")]
+            Some(TunnelCommand::FirewallExceptions(exceptions, complete_tx)) => {
+                let consequence = if shared_values.set_firewall_exceptions(exceptions) {
+                    match self.set_firewall_policy(shared_values) {
+                        Ok(()) => SameState(self),
+                        Err(error) => self.disconnect(
+                            shared_values,
+                            AfterDisconnect::Block(ErrorStateCause::SetFirewallPolicyError(error)),
+                        ),
+                    }
+                } else {
+                    SameState(self)
+                };
+
+                let _ = complete_tx.send(());
+                consequence
+            }
+            #[cfg(target_os = "linux")]
+            Some(TunnelCommand::AllowedInterfaces(interfaces, complete_tx)) => {
+                let consequence = if shared_values.set_allowed_interfaces(interfaces) {
+                    match self.set_firewall_policy(shared_values) {
+                        Ok(()) => SameState(self),
+                        Err(error) => self.disconnect(
+                            shared_values,
+                            AfterDisconnect::Block(ErrorStateCause::SetFirewallPolicyError(error)),
+                        ),
+                    }
+                } else {
+                    SameState(self)
+                };
+
+                let _ = complete_tx.send(());
+                consequence
+            }
             #[cfg(not(target_os = "android"))]
             Some(TunnelCommand::AllowEndpoint(endpoint, tx)) => {
                 shared_values.allowed_endpoint = endpoint;
@@ -340,6 +387,10 @@ impl ConnectedState {
             Some(TunnelCommand::Block(reason)) => {
                 self.disconnect(shared_values, AfterDisconnect::Block(reason))
             }
+            Some(TunnelCommand::GetTunnelStats(tx)) => {
+                send_tunnel_stats(shared_values, &self.stats_handle, tx);
+                SameState(self)
+            }
             #[cfg(target_os = "android")]
             Some(TunnelCommand::BypassSocket(fd, done_tx)) => {
                 shared_values.bypass_socket(fd, done_tx);
@@ -389,7 +440,7 @@ impl ConnectedState {
     }
 
     fn handle_tunnel_events(
-        self: Box<Self>,
+        mut self: Box<Self>,
         event: Option<(TunnelEvent, oneshot::Sender<()>)>,
         shared_values: &mut SharedTunnelStateValues,
     ) -> EventConsequence {
@@ -399,6 +450,18 @@ impl ConnectedState {
             Some((TunnelEvent::Down, _)) | None => {
                 self.disconnect(shared_values, AfterDisconnect::Reconnect(0))
             }
+            Some((TunnelEvent::PeerChanged(endpoint), _done_tx)) => {
+                if let TunnelParameters::Wireguard(params) = &mut self.tunnel_parameters {
+                    params.fail_over_to(endpoint);
+                }
+                match self.set_firewall_policy(shared_values) {
+                    Ok(()) => SameState(self),
+                    Err(error) => self.disconnect(
+                        shared_values,
+                        AfterDisconnect::Block(ErrorStateCause::SetFirewallPolicyError(error)),
+                    ),
+                }
+            }
             Some(_) => SameState(self),
         }
     }
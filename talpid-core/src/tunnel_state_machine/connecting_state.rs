@@ -10,7 +10,9 @@ use talpid_routing::RouteManagerHandle;
 use talpid_tunnel::tun_provider::TunProvider;
 use talpid_tunnel::{EventHook, TunnelArgs, TunnelEvent, TunnelMetadata};
 use talpid_types::ErrorExt;
-use talpid_types::net::{AllowedClients, AllowedEndpoint, AllowedTunnelTraffic, TunnelParameters};
+use talpid_types::net::{
+    AllowedClients, AllowedEndpoint, AllowedTunnelTraffic, TunnelParameters, wireguard::TunnelStats,
+};
 use talpid_types::tunnel::{ErrorStateCause, FirewallPolicyError};
 
 use super::connected_state::TunnelEventsReceiver;
@@ -25,13 +27,15 @@ use crate::dns::DnsConfig;
 use crate::firewall::FirewallPolicy;
 #[cfg(target_os = "macos")]
 use crate::resolver::LOCAL_DNS_RESOLVER;
-use crate::tunnel::{self, TunnelMonitor};
+use crate::tunnel::{self, TunnelMonitor, TunnelStatsHandle};
 
 pub(crate) type TunnelCloseEvent = Fuse<oneshot::Receiver<Option<ErrorStateCause>>>;
 
 #[cfg(target_os = "android")]
 const MAX_ATTEMPTS_WITH_SAME_TUN: u32 = 5;
 const MIN_TUNNEL_ALIVE_TIME: Duration = Duration::from_millis(1000);
+/// How long to wait for the traffic stats of a tunnel before closing it.
+const FINAL_TUNNEL_STATS_TIMEOUT: Duration = Duration::from_millis(500);
 #[cfg(target_os = "windows")]
 const MAX_ATTEMPT_CREATE_TUN: u32 = 4;
 
@@ -45,6 +49,7 @@ pub struct ConnectingState {
     allowed_tunnel_traffic: AllowedTunnelTraffic,
     tunnel_close_event: TunnelCloseEvent,
     tunnel_close_tx: oneshot::Sender<()>,
+    stats_handle: Arc<Mutex<Option<TunnelStatsHandle>>>,
     retry_attempt: u32,
 }
 
@@ -196,6 +201,10 @@ impl ConnectingState {
             allow_lan: shared_values.allow_lan,
             allowed_endpoint: shared_values.allowed_endpoint.clone(),
             allowed_tunnel_traffic,
+            #[cfg(target_os = "linux")]
+            firewall_exceptions: shared_values.firewall_exceptions.clone(),
+            #[cfg(target_os = "linux")]
+            allowed_interfaces: shared_values.allowed_interfaces.clone(),
             #[cfg(target_os = "macos")]
             redirect_interface,
         };
@@ -238,6 +247,9 @@ impl ConnectingState {
 
         let tunnel_parameters = parameters.clone();
 
+        let stats_handle = Arc::new(Mutex::new(None));
+        let monitor_stats_handle = stats_handle.clone();
+
         tokio::task::spawn_blocking(move || {
             let start = Instant::now();
 
@@ -253,6 +265,7 @@ impl ConnectingState {
 
             let block_reason = match TunnelMonitor::start(&tunnel_parameters, &log_dir, args) {
                 Ok(monitor) => {
+                    *monitor_stats_handle.lock().unwrap() = monitor.stats_handle();
                     let reason = Self::wait_for_tunnel_monitor(monitor, retry_attempt);
                     log::debug!("Tunnel monitor exited with block reason: {:?}", reason);
                     reason
@@ -292,6 +305,7 @@ impl ConnectingState {
             allowed_tunnel_traffic: INITIAL_ALLOWED_TUNNEL_TRAFFIC,
             tunnel_close_event: tunnel_close_event_rx.fuse(),
             tunnel_close_tx,
+            stats_handle,
             retry_attempt,
         }
     }
@@ -359,6 +373,7 @@ impl ConnectingState {
             self.tunnel_close_tx,
             self.tunnel_close_event,
             after_disconnect,
+            None,
         ))
     }
 
@@ -403,6 +418,26 @@ impl ConnectingState {
                 let _ = complete_tx.send(());
                 consequence
             }
+            #[cfg(target_os = "linux")]
+            Some(TunnelCommand::FirewallExceptions(exceptions, complete_tx)) => {
+                let consequence = if shared_values.set_firewall_exceptions(exceptions) {
+                    self.reset_firewall(shared_values)
+                } else {
+                    SameState(self)
+                };
+                let _ = complete_tx.send(());
+                consequence
+            }
+            #[cfg(target_os = "linux")]
+            Some(TunnelCommand::AllowedInterfaces(interfaces, complete_tx)) => {
+                let consequence = if shared_values.set_allowed_interfaces(interfaces) {
+                    self.reset_firewall(shared_values)
+                } else {
+                    SameState(self)
+                };
+                let _ = complete_tx.send(());
+                consequence
+            }
             #[cfg(not(target_os = "android"))]
             Some(TunnelCommand::AllowEndpoint(endpoint, tx)) => {
                 if shared_values.allowed_endpoint != endpoint {
@@ -464,6 +499,10 @@ impl ConnectingState {
             Some(TunnelCommand::Block(reason)) => {
                 self.disconnect(shared_values, AfterDisconnect::Block(reason))
             }
+            Some(TunnelCommand::GetTunnelStats(tx)) => {
+                send_tunnel_stats(shared_values, &self.stats_handle, tx);
+                SameState(self)
+            }
             #[cfg(target_os = "android")]
             Some(TunnelCommand::BypassSocket(fd, done_tx)) => {
                 shared_values.bypass_socket(fd, done_tx);
@@ -568,6 +607,23 @@ impl ConnectingState {
                     ),
                 }
             }
+            Some((TunnelEvent::PeerChanged(endpoint), _done_tx)) => {
+                if let TunnelParameters::Wireguard(params) = &mut self.tunnel_parameters {
+                    params.fail_over_to(endpoint);
+                }
+                match Self::set_firewall_policy(
+                    shared_values,
+                    &self.tunnel_parameters,
+                    &self.tunnel_metadata,
+                    self.allowed_tunnel_traffic.clone(),
+                ) {
+                    Ok(()) => SameState(self),
+                    Err(error) => self.disconnect(
+                        shared_values,
+                        AfterDisconnect::Block(ErrorStateCause::SetFirewallPolicyError(error)),
+                    ),
+                }
+            }
             Some((TunnelEvent::Up(metadata), _)) => NewState(ConnectedState::enter(
                 shared_values,
                 metadata,
@@ -575,6 +631,7 @@ impl ConnectingState {
                 self.tunnel_parameters,
                 self.tunnel_close_event,
                 self.tunnel_close_tx,
+                self.stats_handle,
             )),
             Some((TunnelEvent::Down, _)) => {
                 // It is important to reset this before the tunnel device is down,
@@ -618,6 +675,52 @@ impl ConnectingState {
     }
 }
 
+/// Read the current tunnel stats in the background and send them to `tx`.
+pub(super) fn send_tunnel_stats(
+    shared_values: &SharedTunnelStateValues,
+    stats_handle: &Mutex<Option<TunnelStatsHandle>>,
+    tx: oneshot::Sender<Option<TunnelStats>>,
+) {
+    let Some(stats_handle) = stats_handle.lock().unwrap().clone() else {
+        let _ = tx.send(None);
+        return;
+    };
+    shared_values.runtime.spawn(async move {
+        let _ = tx.send(get_tunnel_stats(&stats_handle).await);
+    });
+}
+
+/// Read the tunnel stats of a tunnel that is about to be closed, so that its last moments of
+/// traffic can still be accounted for while disconnecting.
+pub(super) fn final_tunnel_stats(
+    shared_values: &SharedTunnelStateValues,
+    stats_handle: &Mutex<Option<TunnelStatsHandle>>,
+) -> Option<TunnelStats> {
+    let stats_handle = stats_handle.lock().unwrap().clone()?;
+    let stats = shared_values.runtime.block_on(tokio::time::timeout(
+        FINAL_TUNNEL_STATS_TIMEOUT,
+        get_tunnel_stats(&stats_handle),
+    ));
+    stats.unwrap_or_else(|_| {
+        log::warn!("Timed out reading the final tunnel stats");
+        None
+    })
+}
+
+async fn get_tunnel_stats(stats_handle: &TunnelStatsHandle) -> Option<TunnelStats> {
+    match stats_handle.get_stats().await {
+        Some(Ok(stats)) => Some(stats),
+        Some(Err(error)) => {
+            log::error!(
+                "{}",
+                error.display_chain_with_msg("Failed to obtain tunnel stats")
+            );
+            None
+        }
+        None => None,
+    }
+}
+
 #[cfg_attr(not(target_os = "windows"), allow(unused_variables))]
 fn should_retry(error: &tunnel::Error, retry_attempt: u32) -> bool {
     #[cfg(target_os = "windows")]
@@ -86,6 +86,10 @@ impl DisconnectedState {
             let policy = FirewallPolicy::Blocked {
                 allow_lan: shared_values.allow_lan,
                 allowed_endpoint: Some(shared_values.allowed_endpoint.clone()),
+                #[cfg(target_os = "linux")]
+                firewall_exceptions: shared_values.firewall_exceptions.clone(),
+                #[cfg(target_os = "linux")]
+                allowed_interfaces: shared_values.allowed_interfaces.clone(),
             };
 
             shared_values.firewall.apply_policy(policy).map_err(|e| {
@@ -177,6 +181,22 @@ impl TunnelState for DisconnectedState {
                 let _ = complete_tx.send(());
                 SameState(self)
             }
+            #[cfg(target_os = "linux")]
+            Some(TunnelCommand::FirewallExceptions(exceptions, complete_tx)) => {
+                if shared_values.set_firewall_exceptions(exceptions) {
+                    Self::set_firewall_policy(shared_values, false);
+                }
+                let _ = complete_tx.send(());
+                SameState(self)
+            }
+            #[cfg(target_os = "linux")]
+            Some(TunnelCommand::AllowedInterfaces(interfaces, complete_tx)) => {
+                if shared_values.set_allowed_interfaces(interfaces) {
+                    Self::set_firewall_policy(shared_values, false);
+                }
+                let _ = complete_tx.send(());
+                SameState(self)
+            }
             #[cfg(not(target_os = "android"))]
             Some(TunnelCommand::AllowEndpoint(endpoint, tx)) => {
                 if shared_values.allowed_endpoint != endpoint {
@@ -233,6 +253,10 @@ impl TunnelState for DisconnectedState {
             }
             Some(TunnelCommand::Connect) => NewState(ConnectingState::enter(shared_values, 0)),
             Some(TunnelCommand::Block(_reason)) => SameState(self),
+            Some(TunnelCommand::GetTunnelStats(tx)) => {
+                let _ = tx.send(None);
+                SameState(self)
+            }
             #[cfg(target_os = "android")]
             Some(TunnelCommand::BypassSocket(fd, done_tx)) => {
                 shared_values.bypass_socket(fd, done_tx);
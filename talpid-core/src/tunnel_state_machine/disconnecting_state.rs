@@ -4,13 +4,18 @@ use super::{
     TunnelStateTransition, connecting_state::TunnelCloseEvent,
 };
 use futures::{StreamExt, channel::oneshot, future::FusedFuture};
-use talpid_types::tunnel::{ActionAfterDisconnect, ErrorStateCause};
+use talpid_types::{
+    net::wireguard::TunnelStats,
+    tunnel::{ActionAfterDisconnect, ErrorStateCause},
+};
 
 /// This state is active from when we manually trigger a tunnel kill until the tunnel wait
 /// operation (TunnelExit) returned.
 pub struct DisconnectingState {
     tunnel_close_event: TunnelCloseEvent,
     after_disconnect: AfterDisconnect,
+    /// Traffic statistics of the tunnel right before it was closed.
+    final_stats: Option<TunnelStats>,
 }
 
 impl DisconnectingState {
@@ -18,6 +23,7 @@ impl DisconnectingState {
         tunnel_close_tx: oneshot::Sender<()>,
         tunnel_close_event: TunnelCloseEvent,
         after_disconnect: AfterDisconnect,
+        final_stats: Option<TunnelStats>,
     ) -> (Box<dyn TunnelState>, TunnelStateTransition) {
         let _ = tunnel_close_tx.send(());
         let action_after_disconnect = after_disconnect.action();
@@ -26,6 +32,7 @@ impl DisconnectingState {
             Box::new(DisconnectingState {
                 tunnel_close_event,
                 after_disconnect,
+                final_stats,
             }),
             TunnelStateTransition::Disconnecting(action_after_disconnect),
         )
@@ -41,6 +48,16 @@ impl DisconnectingState {
                 let _ = shared_values.set_allow_lan(allow_lan);
                 let _ = complete_tx.send(());
             }
+            #[cfg(target_os = "linux")]
+            Some(TunnelCommand::FirewallExceptions(exceptions, complete_tx)) => {
+                let _ = shared_values.set_firewall_exceptions(exceptions);
+                let _ = complete_tx.send(());
+            }
+            #[cfg(target_os = "linux")]
+            Some(TunnelCommand::AllowedInterfaces(interfaces, complete_tx)) => {
+                let _ = shared_values.set_allowed_interfaces(interfaces);
+                let _ = complete_tx.send(());
+            }
             #[cfg(not(target_os = "android"))]
             Some(TunnelCommand::AllowEndpoint(endpoint, tx)) => {
                 shared_values.allowed_endpoint = endpoint;
@@ -87,6 +104,9 @@ impl DisconnectingState {
                     _ => AfterDisconnect::Block(reason),
                 }
             }
+            Some(TunnelCommand::GetTunnelStats(tx)) => {
+                let _ = tx.send(self.final_stats.clone());
+            }
             None => {
                 if let AfterDisconnect::Reconnect(_) = self.after_disconnect {
                     self.after_disconnect = AfterDisconnect::Nothing;
@@ -25,7 +25,7 @@ use talpid_routing::RouteManagerHandle;
 #[cfg(target_os = "macos")]
 use talpid_tunnel::TunnelMetadata;
 use talpid_tunnel::{TunnelEvent, tun_provider::TunProvider};
-#[cfg(target_os = "macos")]
+#[cfg(any(target_os = "linux", target_os = "macos"))]
 use talpid_types::ErrorExt;
 
 use futures::{
@@ -43,10 +43,14 @@ use std::{
     sync::{Arc, Mutex},
     time::Duration,
 };
+#[cfg(target_os = "linux")]
+use talpid_types::net::{AllowedInterface, FirewallException};
 #[cfg(target_os = "android")]
 use talpid_types::{ErrorExt, android::AndroidContext};
 use talpid_types::{
-    net::{AllowedEndpoint, Connectivity, IpAvailability, TunnelParameters},
+    net::{
+        AllowedEndpoint, Connectivity, IpAvailability, TunnelParameters, wireguard::TunnelStats,
+    },
     tunnel::{ErrorStateCause, ParameterGenerationError, TunnelStateTransition},
 };
 
@@ -93,6 +97,13 @@ pub enum Error {
 pub struct InitialTunnelState {
     /// Whether to allow LAN traffic when not in the (non-blocking) disconnected state.
     pub allow_lan: bool,
+    /// User-defined exceptions that allow traffic outside the tunnel.
+    #[cfg(target_os = "linux")]
+    pub firewall_exceptions: Vec<FirewallException>,
+    /// Interfaces of other VPNs or overlay networks that should not be blocked or routed through
+    /// the tunnel.
+    #[cfg(target_os = "linux")]
+    pub allowed_interfaces: Vec<AllowedInterface>,
     /// Block traffic unless connected to the VPN.
     #[cfg(not(target_os = "android"))]
     pub block_when_disconnected: BlockWhenDisconnected,
@@ -192,6 +203,12 @@ pub async fn spawn(
 pub enum TunnelCommand {
     /// Enable or disable LAN access in the firewall.
     AllowLan(bool, oneshot::Sender<()>),
+    /// Set the user-defined exceptions in the firewall.
+    #[cfg(target_os = "linux")]
+    FirewallExceptions(Vec<FirewallException>, oneshot::Sender<()>),
+    /// Set the interfaces that are exempt from the firewall and the tunnel routing table.
+    #[cfg(target_os = "linux")]
+    AllowedInterfaces(Vec<AllowedInterface>, oneshot::Sender<()>),
     /// Endpoint that should never be blocked. `()` is sent to the
     /// channel after attempting to set the firewall policy, regardless
     /// of whether it succeeded.
@@ -210,6 +227,9 @@ pub enum TunnelCommand {
     Disconnect,
     /// Block all network access unless tunnel is disconnecting or disconnected
     Block(ErrorStateCause),
+    /// Request traffic statistics for the current WireGuard tunnel. `None` is sent if there is
+    /// no running tunnel that reports statistics.
+    GetTunnelStats(oneshot::Sender<Option<TunnelStats>>),
     /// Bypass a socket, allowing traffic to flow through outside the tunnel.
     #[cfg(target_os = "android")]
     BypassSocket(RawFd, oneshot::Sender<()>),
@@ -445,6 +465,18 @@ impl TunnelStateMachine {
             );
         }
 
+        #[cfg(target_os = "linux")]
+        if let Err(error) = args
+            .route_manager
+            .set_allowed_interfaces(args.settings.allowed_interfaces.clone())
+            .await
+        {
+            log::error!(
+                "{}",
+                error.display_chain_with_msg("Failed to set initial allowed interfaces")
+            );
+        }
+
         let mut shared_values = SharedTunnelStateValues {
             #[cfg(any(target_os = "windows", target_os = "macos"))]
             split_tunnel,
@@ -456,6 +488,10 @@ impl TunnelStateMachine {
             route_manager: args.route_manager,
             _offline_monitor: offline_monitor,
             allow_lan: args.settings.allow_lan,
+            #[cfg(target_os = "linux")]
+            firewall_exceptions: args.settings.firewall_exceptions,
+            #[cfg(target_os = "linux")]
+            allowed_interfaces: args.settings.allowed_interfaces,
             #[cfg(not(target_os = "android"))]
             block_when_disconnected: args.settings.block_when_disconnected,
             connectivity,
@@ -550,6 +586,12 @@ struct SharedTunnelStateValues {
     _offline_monitor: offline::MonitorHandle,
     /// Should LAN access be allowed outside the tunnel.
     allow_lan: bool,
+    /// User-defined exceptions that allow traffic outside the tunnel.
+    #[cfg(target_os = "linux")]
+    firewall_exceptions: Vec<FirewallException>,
+    /// Interfaces that are exempt from the firewall and the tunnel routing table.
+    #[cfg(target_os = "linux")]
+    allowed_interfaces: Vec<AllowedInterface>,
     /// Should network access be allowed when in the disconnected state.
     #[cfg(not(target_os = "android"))]
     block_when_disconnected: BlockWhenDisconnected,
@@ -645,6 +687,36 @@ impl SharedTunnelStateValues {
         }
     }
 
+    #[cfg(target_os = "linux")]
+    pub fn set_firewall_exceptions(&mut self, exceptions: Vec<FirewallException>) -> bool {
+        if self.firewall_exceptions != exceptions {
+            self.firewall_exceptions = exceptions;
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Update the allowed interfaces and the routes for their networks. Returns whether the
+    /// firewall policy needs to be reapplied.
+    #[cfg(target_os = "linux")]
+    pub fn set_allowed_interfaces(&mut self, interfaces: Vec<AllowedInterface>) -> bool {
+        if self.allowed_interfaces == interfaces {
+            return false;
+        }
+        if let Err(error) = self.runtime.block_on(
+            self.route_manager
+                .set_allowed_interfaces(interfaces.clone()),
+        ) {
+            log::error!(
+                "{}",
+                error.display_chain_with_msg("Failed to set routes for allowed interfaces")
+            );
+        }
+        self.allowed_interfaces = interfaces;
+        true
+    }
+
     pub fn set_dns_config(&mut self, dns_config: DnsConfig) -> bool {
         if self.dns_config != dns_config {
             self.dns_config = dns_config;
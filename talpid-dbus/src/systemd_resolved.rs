@@ -570,6 +570,31 @@ impl AsyncHandle {
             .map_err(Error::AsyncTaskError)?
     }
 
+    pub async fn get_domains(&self, interface_index: u32) -> Result<Vec<(String, bool)>> {
+        let interface = self.dbus_interface.clone();
+        tokio::task::spawn_blocking(move || interface.get_domains(interface_index))
+            .await
+            .map_err(Error::AsyncTaskError)?
+    }
+
+    /// Like [`Self::set_domains`], but for domains that are not known at compile time.
+    pub async fn set_owned_domains(
+        &self,
+        interface_index: u32,
+        domains: Vec<(String, bool)>,
+    ) -> Result<()> {
+        let interface = self.dbus_interface.clone();
+        tokio::task::spawn_blocking(move || {
+            let domains: Vec<_> = domains
+                .iter()
+                .map(|(domain, routing_only)| (domain.as_str(), *routing_only))
+                .collect();
+            interface.set_domains(interface_index, &domains)
+        })
+        .await
+        .map_err(Error::AsyncTaskError)?
+    }
+
     pub async fn revert_link(&self, state: DnsState) -> Result<()> {
         let mut interface = self.dbus_interface.clone();
         tokio::task::spawn_blocking(move || interface.revert_link(&state))
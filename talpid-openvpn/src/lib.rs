@@ -947,6 +947,7 @@ mod event_server {
                 ips,
                 ipv4_gateway,
                 ipv6_gateway,
+                wireguard_backend: None,
             })
         }
     }
@@ -8,7 +8,7 @@ use std::{
     io,
     net::{IpAddr, Ipv4Addr, Ipv6Addr},
 };
-use talpid_types::ErrorExt;
+use talpid_types::{ErrorExt, net::AllowedInterface};
 
 use futures::{
     StreamExt, TryStream, TryStreamExt,
@@ -143,6 +143,12 @@ pub struct RouteManagerImpl {
     /// Firewall mark identifies traffic which shouldn't be routed via the tunnel routing table. It
     /// is used to construct a routing rule.
     fwmark: u32,
+    /// Whether the routing rules that send traffic to the tunnel routing table are in place.
+    routing_rules_created: bool,
+    /// Interfaces whose networks are routed through the interface rather than the tunnel.
+    allowed_interfaces: Vec<AllowedInterface>,
+    /// Routes for `allowed_interfaces` in the tunnel routing table.
+    allowed_interface_routes: HashSet<Route>,
 }
 
 impl RouteManagerImpl {
@@ -170,6 +176,9 @@ impl RouteManagerImpl {
             added_routes: HashSet::new(),
             table_id,
             fwmark,
+            routing_rules_created: false,
+            allowed_interfaces: vec![],
+            allowed_interface_routes: HashSet::new(),
         };
 
         monitor.clear_routing_rules().await?;
@@ -197,10 +206,13 @@ impl RouteManagerImpl {
                 }
             }
         }
+        self.routing_rules_created = true;
+        self.add_allowed_interface_routes().await;
         Ok(())
     }
 
     async fn clear_routing_rules(&mut self) -> Result<()> {
+        self.routing_rules_created = false;
         let rules = self.get_rules().await?;
         for rule in all_rules(self.fwmark, self.table_id) {
             let mut matching_rule = None;
@@ -307,6 +319,41 @@ impl RouteManagerImpl {
         Ok(())
     }
 
+    async fn set_allowed_interfaces(&mut self, interfaces: Vec<AllowedInterface>) {
+        for route in std::mem::take(&mut self.allowed_interface_routes) {
+            if let Err(error) = self.delete_route_if_exists(&route).await {
+                log::error!("Failed to remove route: {}: {}", route, error);
+            }
+            self.added_routes.remove(&route);
+        }
+        self.allowed_interfaces = interfaces;
+        if self.routing_rules_created {
+            self.add_allowed_interface_routes().await;
+        }
+    }
+
+    /// Add routes for the networks of the allowed interfaces to the tunnel routing table, so that
+    /// they take precedence over the default route through the tunnel. Interfaces that do not
+    /// exist yet are skipped, and retried when their links appear.
+    async fn add_allowed_interface_routes(&mut self) {
+        let routes =
+            allowed_interface_routes(&self.allowed_interfaces, &self.iface_map, self.table_id);
+        for route in routes {
+            if self.allowed_interface_routes.contains(&route) {
+                continue;
+            }
+            match self.add_route(route.clone()).await {
+                Ok(()) => {
+                    self.allowed_interface_routes.insert(route);
+                }
+                Err(error) => log::error!(
+                    "{}",
+                    error.display_chain_with_msg("Failed to add route for allowed interface")
+                ),
+            }
+        }
+    }
+
     async fn initialize_link_map(
         handle: &rtnetlink::Handle,
     ) -> Result<BTreeMap<u32, NetworkInterface>> {
@@ -330,10 +377,13 @@ impl RouteManagerImpl {
 
     fn process_deleted_route(&mut self, route: &Route) -> Result<()> {
         self.added_routes.remove(route);
+        // The kernel removes the routes of links that go down or are deleted
+        self.allowed_interface_routes.remove(route);
         Ok(())
     }
 
     async fn cleanup_routes(&mut self) {
+        self.allowed_interface_routes.clear();
         for route in self.added_routes.drain().collect::<Vec<_>>().iter() {
             if let Err(e) = self.delete_route_if_exists(route).await {
                 log::error!("Failed to remove route: {}: {}", route, e);
@@ -352,9 +402,15 @@ impl RouteManagerImpl {
                     self.process_command(command).await?;
                 },
                 (route_change, _socket) = self.messages.select_next_some().fuse() => {
+                    let allowed_link_changed =
+                        is_allowed_interface_link(&route_change, &self.allowed_interfaces);
                     if let Err(error) = self.process_netlink_message(route_change) {
                         log::error!("{}", error.display_chain_with_msg("Failed to process netlink message"));
                     }
+                    // Routes for an allowed interface can only be added once it exists and is up
+                    if allowed_link_changed && self.routing_rules_created {
+                        self.add_allowed_interface_routes().await;
+                    }
                 }
             };
         }
@@ -388,6 +444,11 @@ impl RouteManagerImpl {
             RouteManagerCommand::GetMtuForRoute(ip, result_tx) => {
                 let _ = result_tx.send(self.get_mtu_for_route(ip).await);
             }
+            RouteManagerCommand::SetAllowedInterfaces(interfaces, result_tx) => {
+                log::debug!("Setting allowed interfaces: {:?}", interfaces);
+                self.set_allowed_interfaces(interfaces).await;
+                let _ = result_tx.send(());
+            }
             RouteManagerCommand::ClearRoutes => {
                 log::debug!("Clearing routes");
                 self.cleanup_routes().await;
@@ -894,6 +955,38 @@ fn execute_route_get_request(
     }
 }
 
+/// Routes in the routing table `table_id` for the networks of the allowed interfaces in
+/// `iface_map`.
+fn allowed_interface_routes(
+    interfaces: &[AllowedInterface],
+    iface_map: &BTreeMap<u32, NetworkInterface>,
+    table_id: u32,
+) -> HashSet<Route> {
+    interfaces
+        .iter()
+        .filter(|interface| iface_map.values().any(|iface| iface.name == interface.name))
+        .flat_map(|interface| {
+            interface.networks.iter().map(|network| {
+                Route::new(Node::device(interface.name.clone()), *network).table(table_id)
+            })
+        })
+        .collect()
+}
+
+/// Return whether `msg` announces a new link, or a change to a link, of an allowed interface.
+fn is_allowed_interface_link(
+    msg: &NetlinkMessage<RtnlMessage>,
+    interfaces: &[AllowedInterface],
+) -> bool {
+    let NetlinkPayload::InnerMessage(RtnlMessage::NewLink(link)) = &msg.payload else {
+        return false;
+    };
+    link.nlas.iter().any(|nla| match nla {
+        LinkNla::IfName(name) => interfaces.iter().any(|interface| &interface.name == name),
+        _ => false,
+    })
+}
+
 #[derive(Debug)]
 struct NetworkInterface {
     name: String,
@@ -910,6 +1003,71 @@ impl NetworkInterface {
 mod test {
     use super::*;
 
+    fn allowed_interface(name: &str, networks: &[&str]) -> AllowedInterface {
+        AllowedInterface {
+            name: name.to_owned(),
+            networks: networks
+                .iter()
+                .map(|network| network.parse().unwrap())
+                .collect(),
+        }
+    }
+
+    fn new_link(name: &str) -> NetlinkMessage<RtnlMessage> {
+        let mut link = LinkMessage::default();
+        link.nlas.push(LinkNla::IfName(name.to_owned()));
+        NetlinkMessage::from(RtnlMessage::NewLink(link))
+    }
+
+    /// Only interfaces that exist should get routes, in the given routing table
+    #[test]
+    fn test_allowed_interface_routes() {
+        let interfaces = [
+            allowed_interface("tailscale0", &["100.64.0.0/10", "fd7a:115c:a1e0::/48"]),
+            allowed_interface("docker0", &["172.17.0.0/16"]),
+        ];
+        let mut iface_map = BTreeMap::new();
+        iface_map.insert(
+            3,
+            NetworkInterface {
+                name: "tailscale0".to_owned(),
+                link_layer_type: 0,
+            },
+        );
+
+        let expected: HashSet<_> = ["100.64.0.0/10", "fd7a:115c:a1e0::/48"]
+            .into_iter()
+            .map(|network| {
+                Route::new(
+                    Node::device("tailscale0".to_owned()),
+                    network.parse().unwrap(),
+                )
+                .table(1000)
+            })
+            .collect();
+        assert_eq!(
+            allowed_interface_routes(&interfaces, &iface_map, 1000),
+            expected
+        );
+        assert!(allowed_interface_routes(&interfaces, &BTreeMap::new(), 1000).is_empty());
+    }
+
+    /// Routes should be retried when the link of an allowed interface appears or changes
+    #[test]
+    fn test_is_allowed_interface_link() {
+        let interfaces = [allowed_interface("tailscale0", &["100.64.0.0/10"])];
+
+        assert!(is_allowed_interface_link(
+            &new_link("tailscale0"),
+            &interfaces
+        ));
+        assert!(!is_allowed_interface_link(&new_link("eth0"), &interfaces));
+        assert!(!is_allowed_interface_link(
+            &NetlinkMessage::from(RtnlMessage::DelLink(LinkMessage::default())),
+            &interfaces
+        ));
+    }
+
     /// Tests if dropping inside a tokio runtime panics
     #[test]
     fn test_drop_in_executor() {
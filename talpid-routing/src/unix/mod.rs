@@ -13,6 +13,8 @@ use futures::channel::{
 use std::sync::Arc;
 #[cfg(target_os = "android")]
 use talpid_types::android::AndroidContext;
+#[cfg(target_os = "linux")]
+use talpid_types::net::AllowedInterface;
 
 #[cfg(any(target_os = "linux", target_os = "macos"))]
 use futures::stream::Stream;
@@ -93,6 +95,7 @@ pub(crate) enum RouteManagerCommand {
     ClearRoutingRules(oneshot::Sender<Result<(), PlatformError>>),
     NewChangeListener(oneshot::Sender<mpsc::UnboundedReceiver<CallbackMessage>>),
     GetMtuForRoute(IpAddr, oneshot::Sender<Result<u16, PlatformError>>),
+    SetAllowedInterfaces(Vec<AllowedInterface>, oneshot::Sender<()>),
     /// Attempt to fetch a route for the given destination with an optional firewall mark.
     GetDestinationRoute(
         IpAddr,
@@ -331,6 +334,23 @@ impl RouteManagerHandle {
             .map_err(Error::PlatformError)
     }
 
+    /// Route the networks of the given interfaces through those interfaces rather than the
+    /// tunnel, while the routing rules created by [Self::create_routing_rules] are in place.
+    #[cfg(target_os = "linux")]
+    pub async fn set_allowed_interfaces(
+        &self,
+        interfaces: Vec<AllowedInterface>,
+    ) -> Result<(), Error> {
+        let (response_tx, response_rx) = oneshot::channel();
+        self.tx
+            .unbounded_send(RouteManagerCommand::SetAllowedInterfaces(
+                interfaces,
+                response_tx,
+            ))
+            .map_err(|_| Error::RouteManagerDown)?;
+        response_rx.await.map_err(|_| Error::ManagerChannelDown)
+    }
+
     /// Remove any routing rules created by [Self::create_routing_rules].
     #[cfg(target_os = "linux")]
     pub async fn clear_routing_rules(&self) -> Result<(), Error> {
@@ -33,6 +33,5 @@ async fn main() {
     .unwrap();
 
     println!("Private key: {ephemeral_private_key}");
-    // Use fmt::Debug since Serialize is not implemented for PresharedKey.
     println!("PSK: {:?}", ephemeral_peer.psk.unwrap());
 }
@@ -1,5 +1,5 @@
 use std::{
-    net::{IpAddr, Ipv4Addr, Ipv6Addr},
+    net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr},
     path::Path,
     sync::{Arc, Mutex},
 };
@@ -17,7 +17,7 @@ use futures::{
     },
 };
 use talpid_routing::RouteManagerHandle;
-use talpid_types::net::AllowedTunnelTraffic;
+use talpid_types::net::{AllowedTunnelTraffic, wireguard};
 use tun_provider::TunProvider;
 
 /// Size of IPv4 header in bytes
@@ -80,6 +80,8 @@ pub struct TunnelMetadata {
     pub ipv4_gateway: Ipv4Addr,
     /// The IP to the IPv6 default gateway on the tunnel interface.
     pub ipv6_gateway: Option<Ipv6Addr>,
+    /// The WireGuard implementation that the tunnel is running on, if it is a WireGuard tunnel.
+    pub wireguard_backend: Option<wireguard::Backend>,
 }
 
 impl TunnelMetadata {
@@ -102,6 +104,9 @@ pub enum TunnelEvent {
     InterfaceUp(TunnelMetadata, AllowedTunnelTraffic),
     /// Sent when the tunnel comes up and is ready for traffic.
     Up(TunnelMetadata),
+    /// Sent when a WireGuard tunnel switches to one of its fallback peers, before any traffic is
+    /// sent to it. Contains the endpoint of the new peer.
+    PeerChanged(SocketAddr),
     /// Sent when the tunnel goes down, but before destroying the tunnel device.
     Down,
 }
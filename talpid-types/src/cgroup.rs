@@ -1,7 +1,50 @@
-use std::{ffi::OsStr, fs, os::unix::ffi::OsStrExt, path::PathBuf};
+use std::{
+    ffi::OsStr,
+    fs,
+    os::unix::ffi::OsStrExt,
+    path::{Path, PathBuf},
+};
 
 pub const SPLIT_TUNNEL_CGROUP_NAME: &str = "mullvad-exclusions";
 
+/// A cgroup hierarchy in which the split tunneling cgroup can be created.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SplitTunnelCgroup {
+    /// A cgroup v1 hierarchy with the `net_cls` controller. Traffic from excluded processes is
+    /// identified by the class ID of the cgroup.
+    NetCls(PathBuf),
+    /// A cgroup v2 (unified) hierarchy. Traffic from excluded processes is identified by the ID
+    /// of the cgroup that the sending socket belongs to.
+    V2(PathBuf),
+}
+
+impl SplitTunnelCgroup {
+    /// Path where the hierarchy is mounted.
+    pub fn mount_path(&self) -> &Path {
+        match self {
+            SplitTunnelCgroup::NetCls(path) | SplitTunnelCgroup::V2(path) => path,
+        }
+    }
+
+    /// Path of the cgroup that excluded processes are added to.
+    pub fn exclusion_path(&self) -> PathBuf {
+        self.mount_path().join(SPLIT_TUNNEL_CGROUP_NAME)
+    }
+}
+
+/// Find the cgroup hierarchy to use for split tunneling. A mounted cgroup v1 `net_cls` controller
+/// is preferred, and a cgroup v2 hierarchy is used otherwise.
+pub fn find_split_tunnel_cgroup() -> std::io::Result<Option<SplitTunnelCgroup>> {
+    let mounts = fs::read("/proc/mounts")?;
+    Ok(find_split_tunnel_cgroup_inner(&mounts))
+}
+
+fn find_split_tunnel_cgroup_inner(mounts: &[u8]) -> Option<SplitTunnelCgroup> {
+    find_net_cls_mount_inner(mounts)
+        .map(SplitTunnelCgroup::NetCls)
+        .or_else(|| find_cgroup2_mount_inner(mounts).map(SplitTunnelCgroup::V2))
+}
+
 /// Find the path of the cgroup v1 net_cls controller mount if it exists
 pub fn find_net_cls_mount() -> std::io::Result<Option<PathBuf>> {
     let mounts = fs::read("/proc/mounts")?;
@@ -14,6 +57,20 @@ fn find_net_cls_mount_inner(mounts: &[u8]) -> Option<PathBuf> {
         .find_map(parse_mount_line)
 }
 
+fn find_cgroup2_mount_inner(mounts: &[u8]) -> Option<PathBuf> {
+    mounts.split(|byte| *byte == b'\n').find_map(|line| {
+        // See `parse_mount_line` for the format
+        let mut parts = line.split(|byte| *byte == b' ');
+        let _device_type = parts.next()?;
+        let mount_path = parts.next()?;
+        let filesystem_type = parts.next()?;
+        if filesystem_type != b"cgroup2" {
+            return None;
+        }
+        Some(PathBuf::from(OsStr::from_bytes(mount_path)))
+    })
+}
+
 fn parse_mount_line(line: &[u8]) -> Option<PathBuf> {
     // Each line contains multiple values separated by space.
     // `cgroup /sys/fs/cgroup/net_cls,net_prio cgroup
@@ -72,4 +129,30 @@ cgroup /nope
 
         assert_eq!(find_net_cls_mount_inner(input), None)
     }
+
+    #[test]
+    fn test_find_cgroup2_path() {
+        let input = br#"proc /proc proc rw,nosuid,nodev,noexec,relatime 0 0
+cgroup2 /sys/fs/cgroup cgroup2 rw,nosuid,nodev,noexec,relatime,nsdelegate,memory_recursiveprot 0 0
+"#;
+
+        assert_eq!(
+            find_split_tunnel_cgroup_inner(input),
+            Some(SplitTunnelCgroup::V2(PathBuf::from("/sys/fs/cgroup")))
+        )
+    }
+
+    #[test]
+    fn test_prefer_net_cls_path() {
+        let input = br#"cgroup2 /sys/fs/cgroup/unified cgroup2 rw,nosuid,nodev,noexec,relatime,nsdelegate 0 0
+cgroup /sys/fs/cgroup/net_cls,net_prio cgroup rw,nosuid,nodev,noexec,relatime,net_cls,net_prio 0 0
+"#;
+
+        assert_eq!(
+            find_split_tunnel_cgroup_inner(input),
+            Some(SplitTunnelCgroup::NetCls(PathBuf::from(
+                "/sys/fs/cgroup/net_cls,net_prio"
+            )))
+        )
+    }
 }
@@ -0,0 +1,156 @@
+use serde::{Deserialize, Serialize};
+use std::{
+    fmt,
+    net::{IpAddr, SocketAddr},
+};
+
+/// Protocol used to reach an [`EncryptedDnsServer`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EncryptedDnsProtocol {
+    /// DNS-over-HTTPS
+    Https,
+    /// DNS-over-TLS
+    Tls,
+}
+
+impl EncryptedDnsProtocol {
+    /// Port that servers using this protocol usually listen on.
+    pub fn default_port(self) -> u16 {
+        match self {
+            EncryptedDnsProtocol::Https => 443,
+            EncryptedDnsProtocol::Tls => 853,
+        }
+    }
+}
+
+impl fmt::Display for EncryptedDnsProtocol {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            EncryptedDnsProtocol::Https => f.write_str("DNS-over-HTTPS"),
+            EncryptedDnsProtocol::Tls => f.write_str("DNS-over-TLS"),
+        }
+    }
+}
+
+/// A DNS server that is queried over an encrypted transport.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct EncryptedDnsServer {
+    pub protocol: EncryptedDnsProtocol,
+    pub address: IpAddr,
+    pub port: u16,
+    /// Name used to verify the certificate of the server.
+    pub hostname: String,
+}
+
+impl EncryptedDnsServer {
+    pub fn endpoint(&self) -> SocketAddr {
+        SocketAddr::new(self.address, self.port)
+    }
+}
+
+impl fmt::Display for EncryptedDnsServer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} ({}, {})",
+            self.hostname,
+            self.endpoint(),
+            self.protocol
+        )
+    }
+}
+
+/// Sends DNS queries for a domain and its subdomains to specific servers.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct DomainDnsRule {
+    /// Domain that the rule applies to, without any leading `*.`, e.g. `corp.example`.
+    pub domain: String,
+    /// Servers to send queries for the domain to.
+    pub servers: Vec<IpAddr>,
+}
+
+/// The domain of a [`DomainDnsRule`] is not a valid domain name.
+#[derive(thiserror::Error, Debug, Clone, PartialEq, Eq)]
+#[error("Invalid domain for DNS rule: \"{0}\"")]
+pub struct InvalidDomain(pub String);
+
+impl DomainDnsRule {
+    /// Maximum length of a domain name, excluding the trailing `.`.
+    const MAX_DOMAIN_LEN: usize = 253;
+    /// Maximum length of a single label of a domain name.
+    const MAX_LABEL_LEN: usize = 63;
+
+    /// Create a rule for `domain`. A leading `*.` and trailing `.` are removed, since the rule
+    /// always applies to subdomains as well.
+    ///
+    /// This fails if `domain` is empty or the root zone, since such a rule would match every
+    /// query, or if it is not a valid domain name.
+    pub fn new(domain: &str, servers: Vec<IpAddr>) -> Result<Self, InvalidDomain> {
+        let normalized = domain.strip_prefix("*.").unwrap_or(domain);
+        let normalized = normalized.strip_suffix('.').unwrap_or(normalized);
+        let rule = DomainDnsRule {
+            domain: normalized.to_ascii_lowercase(),
+            servers,
+        };
+        rule.validate()
+            .map_err(|_| InvalidDomain(domain.to_owned()))?;
+        Ok(rule)
+    }
+
+    /// Fail if the domain of the rule is empty, the root zone, or not a valid domain name.
+    pub fn validate(&self) -> Result<(), InvalidDomain> {
+        let is_valid_label = |label: &str| {
+            (1..=Self::MAX_LABEL_LEN).contains(&label.len())
+                && !label.starts_with('-')
+                && !label.ends_with('-')
+                && label
+                    .bytes()
+                    .all(|byte| byte.is_ascii_alphanumeric() || byte == b'-' || byte == b'_')
+        };
+        if self.domain.is_empty()
+            || self.domain.len() > Self::MAX_DOMAIN_LEN
+            || !self.domain.split('.').all(is_valid_label)
+        {
+            return Err(InvalidDomain(self.domain.clone()));
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for DomainDnsRule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "*.{} -> ", self.domain)?;
+        for (i, server) in self.servers.iter().enumerate() {
+            if i > 0 {
+                f.write_str(", ")?;
+            }
+            write!(f, "{server}")?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_domain_rule_domain() {
+        let rule = DomainDnsRule::new("*.Corp.Example.", vec![]).unwrap();
+        assert_eq!(rule.domain, "corp.example");
+
+        // Rules that would match every query must be rejected
+        for domain in ["", ".", "*.", "*.*."] {
+            assert!(DomainDnsRule::new(domain, vec![]).is_err(), "{domain:?}");
+        }
+        for domain in [
+            "corp..example",
+            "-corp.example",
+            "corp example",
+            "*.corp.*.example",
+        ] {
+            assert!(DomainDnsRule::new(domain, vec![]).is_err(), "{domain:?}");
+        }
+    }
+}
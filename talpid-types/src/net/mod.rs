@@ -13,6 +13,7 @@ use std::{
     sync::LazyLock,
 };
 
+pub mod dns;
 pub mod obfuscation;
 pub mod openvpn;
 pub mod proxy;
@@ -74,6 +75,7 @@ impl TunnelParameters {
                 obfuscation: None,
                 entry_endpoint: None,
                 tunnel_interface: None,
+                wireguard_backend: None,
                 #[cfg(daita)]
                 daita: false,
             },
@@ -91,6 +93,7 @@ impl TunnelParameters {
                     .get_exit_endpoint()
                     .map(|_| params.connection.get_endpoint()),
                 tunnel_interface: None,
+                wireguard_backend: None,
                 #[cfg(daita)]
                 daita: params.options.daita,
             },
@@ -203,6 +206,9 @@ pub struct TunnelEndpoint {
     pub obfuscation: Option<ObfuscationEndpoint>,
     pub entry_endpoint: Option<Endpoint>,
     pub tunnel_interface: Option<String>,
+    /// WireGuard implementation that the tunnel is running on.
+    #[serde(default)]
+    pub wireguard_backend: Option<wireguard::Backend>,
     #[cfg(daita)]
     pub daita: bool,
 }
@@ -412,6 +418,112 @@ impl AllowedClients {
     }
 }
 
+/// User-defined exception that allows traffic between the device and `network` outside the
+/// tunnel, in every tunnel state.
+#[derive(Debug, Clone, Eq, PartialEq, Hash, Serialize, Deserialize)]
+pub struct FirewallException {
+    /// Remote network that the exception applies to.
+    pub network: IpNetwork,
+    /// Only allow this transport protocol. Any protocol is allowed if this is `None`.
+    pub protocol: Option<TransportProtocol>,
+    /// Only allow connections to this port on the remote hosts. Incoming connections are only
+    /// allowed if this is `None`.
+    pub port: Option<u16>,
+    /// Only allow traffic over this interface. If this is `None`, traffic is routed outside the
+    /// tunnel.
+    pub interface: Option<String>,
+}
+
+impl fmt::Display for FirewallException {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match (self.protocol, self.port) {
+            (Some(protocol), Some(port)) => write!(f, "{protocol} {port} to ")?,
+            (Some(protocol), None) => write!(f, "{protocol} to ")?,
+            (None, Some(port)) => write!(f, "Port {port} to ")?,
+            (None, None) => (),
+        }
+        write!(f, "{}", self.network)?;
+        if let Some(interface) = &self.interface {
+            write!(f, " via {interface}")?;
+        }
+        Ok(())
+    }
+}
+
+impl FirewallException {
+    /// Fail if the exception covers all addresses, which would disable the kill switch, or if it
+    /// has an interface name that cannot exist.
+    pub fn validate(&self) -> Result<(), InvalidException> {
+        if self.network.prefix() == 0 {
+            return Err(InvalidException::CoversAllAddresses(self.network));
+        }
+        if let Some(interface) = &self.interface {
+            validate_iface_name(interface)?;
+        }
+        Ok(())
+    }
+}
+
+/// Returned when a [`FirewallException`] or an [`AllowedInterface`] cannot be used.
+#[derive(thiserror::Error, Debug, Clone, PartialEq, Eq)]
+pub enum InvalidException {
+    #[error("The network {0} covers all addresses")]
+    CoversAllAddresses(IpNetwork),
+    #[error("Invalid network interface name \"{0}\"")]
+    InvalidInterfaceName(String),
+}
+
+/// Maximum length of a network interface name on Linux, excluding the terminating NUL byte.
+const MAX_IFACE_NAME_LEN: usize = 15;
+
+/// Fail unless `name` is accepted as an interface name by Linux.
+fn validate_iface_name(name: &str) -> Result<(), InvalidException> {
+    let invalid_char = |c: char| c.is_whitespace() || matches!(c, '/' | ':' | '\0');
+    if name.is_empty()
+        || name.len() > MAX_IFACE_NAME_LEN
+        || name == "."
+        || name == ".."
+        || name.contains(invalid_char)
+    {
+        return Err(InvalidException::InvalidInterfaceName(name.to_owned()));
+    }
+    Ok(())
+}
+
+/// Network interface, such as that of another VPN or a container bridge, that may be used
+/// alongside the tunnel. Traffic on the interface is not blocked, and traffic to `networks` is
+/// routed through it rather than the tunnel.
+#[derive(Debug, Clone, Eq, PartialEq, Hash, Serialize, Deserialize)]
+pub struct AllowedInterface {
+    /// Name of the interface.
+    pub name: String,
+    /// Networks that are reachable through the interface.
+    pub networks: Vec<IpNetwork>,
+}
+
+impl fmt::Display for AllowedInterface {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.name)?;
+        if !self.networks.is_empty() {
+            let networks: Vec<_> = self.networks.iter().map(|net| net.to_string()).collect();
+            write!(f, " ({})", networks.join(", "))?;
+        }
+        Ok(())
+    }
+}
+
+impl AllowedInterface {
+    /// Fail if the interface name cannot exist, or if any of the networks covers all addresses,
+    /// which would route all traffic outside the tunnel.
+    pub fn validate(&self) -> Result<(), InvalidException> {
+        validate_iface_name(&self.name)?;
+        match self.networks.iter().find(|network| network.prefix() == 0) {
+            Some(network) => Err(InvalidException::CoversAllAddresses(*network)),
+            None => Ok(()),
+        }
+    }
+}
+
 /// What [`Endpoint`]s to allow the client to send traffic to and receive from.
 ///
 /// In some cases we want to restrict what IP addresses the client may communicate with even
@@ -655,3 +767,70 @@ impl IpAvailability {
         matches!(self, Self::Ipv6 | Self::Ipv4AndIpv6)
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn exception(network: &str, interface: Option<&str>) -> FirewallException {
+        FirewallException {
+            network: network.parse().unwrap(),
+            protocol: None,
+            port: None,
+            interface: interface.map(str::to_owned),
+        }
+    }
+
+    #[test]
+    fn test_validate_firewall_exception() {
+        assert_eq!(exception("192.0.2.0/24", None).validate(), Ok(()));
+        assert_eq!(exception("2001:db8::/32", Some("eth0")).validate(), Ok(()));
+        assert_eq!(
+            exception("192.0.2.0/24", Some("fifteen-chars-0")).validate(),
+            Ok(())
+        );
+
+        assert!(exception("0.0.0.0/0", None).validate().is_err());
+        assert!(exception("::/0", None).validate().is_err());
+
+        for name in [
+            "",
+            "sixteen-chars-00",
+            ".",
+            "..",
+            "eth/0",
+            "eth 0",
+            "eth0:1",
+            "eth\00",
+        ] {
+            assert_eq!(
+                exception("192.0.2.0/24", Some(name)).validate(),
+                Err(InvalidException::InvalidInterfaceName(name.to_owned())),
+                "{name:?} should be rejected"
+            );
+        }
+    }
+
+    #[test]
+    fn test_validate_allowed_interface() {
+        let interface = |name: &str, networks: &[&str]| AllowedInterface {
+            name: name.to_owned(),
+            networks: networks.iter().map(|net| net.parse().unwrap()).collect(),
+        };
+
+        assert_eq!(interface("tailscale0", &[]).validate(), Ok(()));
+        assert_eq!(
+            interface("tailscale0", &["100.64.0.0/10", "fd7a:115c:a1e0::/48"]).validate(),
+            Ok(())
+        );
+
+        assert!(interface("", &[]).validate().is_err());
+        assert!(interface("sixteen-chars-00", &[]).validate().is_err());
+        assert_eq!(
+            interface("wg0", &["100.64.0.0/10", "::/0"]).validate(),
+            Err(InvalidException::CoversAllAddresses(
+                "::/0".parse().unwrap()
+            ))
+        );
+    }
+}
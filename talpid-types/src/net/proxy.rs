@@ -63,6 +63,15 @@ impl CustomProxy {
             },
         }
     }
+
+    /// Remove the password and any other credentials for the proxy.
+    pub fn redact_secrets(&mut self) {
+        match self {
+            CustomProxy::Shadowsocks(settings) => settings.password.clear(),
+            CustomProxy::Socks5Local(_) => (),
+            CustomProxy::Socks5Remote(settings) => settings.auth = None,
+        }
+    }
 }
 
 impl From<Socks5Remote> for CustomProxy {
@@ -6,6 +6,8 @@ use std::{
     cmp, fmt,
     hash::{Hash, Hasher},
     net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr},
+    str::FromStr,
+    time::SystemTime,
 };
 use zeroize::{Zeroize, ZeroizeOnDrop};
 
@@ -17,6 +19,9 @@ pub struct TunnelParameters {
     pub options: TunnelOptions,
     pub generic_options: GenericTunnelOptions,
     pub obfuscation: Option<super::obfuscation::ObfuscatorConfig>,
+    /// Peers that replace `connection.peer`, in order, if it stops responding. Only used for
+    /// single-hop tunnels without obfuscation.
+    pub fallback_peers: Vec<PeerConfig>,
 }
 
 impl TunnelParameters {
@@ -27,6 +32,21 @@ impl TunnelParameters {
             .map(|proxy| proxy.get_obfuscator_endpoint())
             .unwrap_or_else(|| self.connection.get_endpoint())
     }
+
+    /// Make the fallback peer with the given endpoint the current peer. The previous peer becomes
+    /// the last fallback peer.
+    pub fn fail_over_to(&mut self, endpoint: SocketAddr) {
+        let Some(index) = self
+            .fallback_peers
+            .iter()
+            .position(|peer| peer.endpoint == endpoint)
+        else {
+            return;
+        };
+        let peer = self.fallback_peers.remove(index);
+        let previous_peer = std::mem::replace(&mut self.connection.peer, peer);
+        self.fallback_peers.push(previous_peer);
+    }
 }
 
 /// Connection-specific configuration in [`TunnelParameters`].
@@ -94,6 +114,76 @@ pub struct TunnelOptions {
     /// Enable DAITA during tunnel config
     #[cfg(daita)]
     pub daita: bool,
+    /// WireGuard implementation to use
+    pub backend: Backend,
+}
+
+/// WireGuard implementation used for a tunnel.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Backend {
+    /// Pick an implementation based on the platform and the enabled features.
+    #[default]
+    Auto,
+    /// The in-kernel implementation, or WireGuardNT on Windows.
+    Kernel,
+    /// The userspace wireguard-go implementation. Not available in builds that use boringtun.
+    WireguardGo,
+    /// The userspace boringtun implementation. Only available in builds that use boringtun,
+    /// instead of wireguard-go.
+    Boringtun,
+}
+
+impl FromStr for Backend {
+    type Err = BackendParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "auto" => Ok(Backend::Auto),
+            "kernel" => Ok(Backend::Kernel),
+            "wireguard-go" | "wireguard_go" => Ok(Backend::WireguardGo),
+            "boringtun" => Ok(Backend::Boringtun),
+            _ => Err(BackendParseError),
+        }
+    }
+}
+
+impl fmt::Display for Backend {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Backend::Auto => f.write_str("auto"),
+            Backend::Kernel => f.write_str("kernel"),
+            Backend::WireguardGo => f.write_str("wireguard-go"),
+            Backend::Boringtun => f.write_str("boringtun"),
+        }
+    }
+}
+
+/// Returned when `Backend::from_str` fails to convert a string into a [`Backend`].
+#[derive(thiserror::Error, Debug, Clone, PartialEq, Eq)]
+#[error("Not a valid WireGuard backend")]
+pub struct BackendParseError;
+
+/// Traffic statistics for a single WireGuard peer.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PeerStats {
+    /// Peer's public key.
+    pub public_key: PublicKey,
+    /// Number of bytes sent to the peer.
+    pub tx_bytes: u64,
+    /// Number of bytes received from the peer.
+    pub rx_bytes: u64,
+    /// Time of the most recent successful handshake with the peer, if any.
+    pub last_handshake: Option<SystemTime>,
+}
+
+/// Traffic statistics for the peers of a running WireGuard tunnel.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TunnelStats {
+    /// Statistics for the entry peer. This is only set when multihop is used.
+    pub entry: Option<PeerStats>,
+    /// Statistics for the exit peer, if the tunnel reported any.
+    pub exit: Option<PeerStats>,
 }
 
 /// Wireguard x25519 private key
@@ -273,6 +363,10 @@ impl PresharedKey {
     pub fn as_bytes(&self) -> &[u8; 32] {
         &self.0
     }
+
+    pub fn from_base64(key: &str) -> Result<Self, InvalidKey> {
+        key_from_base64(key)
+    }
 }
 
 impl From<Box<[u8; 32]>> for PresharedKey {
@@ -281,12 +375,45 @@ impl From<Box<[u8; 32]>> for PresharedKey {
     }
 }
 
+impl From<[u8; 32]> for PresharedKey {
+    fn from(key: [u8; 32]) -> PresharedKey {
+        PresharedKey(Box::new(key))
+    }
+}
+
+impl TryFrom<&[u8]> for PresharedKey {
+    type Error = InvalidKey;
+
+    fn try_from(key: &[u8]) -> Result<PresharedKey, Self::Error> {
+        let key: [u8; 32] = <[u8; 32]>::try_from(key).map_err(|_| InvalidKey::Length(key.len()))?;
+        Ok(PresharedKey::from(key))
+    }
+}
+
 impl fmt::Debug for PresharedKey {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         write!(f, "{}", &STANDARD.encode(self.as_bytes()))
     }
 }
 
+impl Serialize for PresharedKey {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serialize_key(self.as_bytes(), serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for PresharedKey {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserialize_key(deserializer)
+    }
+}
+
 fn serialize_key<S>(key: &[u8; 32], serializer: S) -> Result<S::Ok, S::Error>
 where
     S: Serializer,
@@ -1,7 +1,8 @@
+use serde::Serialize;
 use std::path::PathBuf;
 
 /// A process that is being excluded from the tunnel.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize)]
 pub struct ExcludedProcess {
     /// Process identifier.
     pub pid: u32,
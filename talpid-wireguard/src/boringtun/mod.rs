@@ -131,6 +131,10 @@ impl Tunnel for BoringTun {
         self.interface_name.clone()
     }
 
+    fn backend(&self) -> talpid_types::net::wireguard::Backend {
+        talpid_types::net::wireguard::Backend::Boringtun
+    }
+
     fn stop(self: Box<Self>) -> Result<(), TunnelError> {
         log::info!("BoringTun::stop"); // remove me
         tokio::runtime::Handle::current().block_on(self.device_handle.stop());
@@ -154,6 +158,7 @@ impl Tunnel for BoringTun {
                     Stats {
                         tx_bytes: peer.tx_bytes.unwrap_or_default(),
                         rx_bytes: peer.rx_bytes.unwrap_or_default(),
+                        last_handshake: None,
                     },
                 )
             },
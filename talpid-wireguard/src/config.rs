@@ -19,6 +19,8 @@ pub struct Config {
     pub entry_peer: wireguard::PeerConfig,
     /// Multihop exit peer
     pub exit_peer: Option<wireguard::PeerConfig>,
+    /// Peers to switch to if the entry peer stops responding
+    pub fallback_peers: Vec<wireguard::PeerConfig>,
     /// IPv4 gateway
     pub ipv4_gateway: Ipv4Addr,
     /// IPv6 gateway
@@ -59,6 +61,7 @@ impl Config {
     ) -> Result<Config, Error> {
         Self::new(
             &params.connection,
+            &params.fallback_peers,
             &params.options,
             &params.generic_options,
             &params.obfuscation,
@@ -69,6 +72,7 @@ impl Config {
     /// Constructs a new Config struct
     fn new(
         connection: &wireguard::ConnectionConfig,
+        fallback_peers: &[wireguard::PeerConfig],
         wg_options: &wireguard::TunnelOptions,
         generic_options: &GenericTunnelOptions,
         obfuscator_config: &Option<ObfuscatorConfig>,
@@ -93,6 +97,7 @@ impl Config {
             tunnel,
             entry_peer: connection.peer.clone(),
             exit_peer: connection.exit_peer.clone(),
+            fallback_peers: fallback_peers.to_vec(),
             ipv4_gateway: connection.ipv4_gateway,
             ipv6_gateway,
             mtu,
@@ -108,13 +113,19 @@ impl Config {
             daita: false,
         };
 
-        for peer in config.peers_mut() {
+        let retain_allowed_ips = |peer: &mut wireguard::PeerConfig| {
             peer.allowed_ips
                 .retain(|ip| ip.is_ipv4() || generic_options.enable_ipv6);
             if peer.allowed_ips.is_empty() {
                 return Err(Error::InvalidPeerIpError);
             }
-        }
+            Ok(())
+        };
+        config.peers_mut().try_for_each(retain_allowed_ips)?;
+        config
+            .fallback_peers
+            .iter_mut()
+            .try_for_each(retain_allowed_ips)?;
 
         Ok(config)
     }
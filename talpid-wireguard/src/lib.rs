@@ -13,9 +13,9 @@ use std::{
     convert::Infallible,
     path::Path,
     pin::Pin,
-    sync::{Arc, mpsc as sync_mpsc},
+    sync::{Arc, Weak, mpsc as sync_mpsc},
 };
-#[cfg(not(target_os = "android"))]
+#[cfg(any(target_os = "linux", target_os = "windows"))]
 use std::{env, sync::LazyLock};
 #[cfg(not(target_os = "android"))]
 use talpid_routing::{self, RequiredRoute};
@@ -25,7 +25,10 @@ use talpid_tunnel::{EventHook, TunnelArgs, TunnelEvent, TunnelMetadata, tun_prov
 use talpid_tunnel_config_client::DaitaSettings;
 use talpid_types::{
     BoxedError, ErrorExt,
-    net::{AllowedTunnelTraffic, Endpoint, TransportProtocol, wireguard::TunnelParameters},
+    net::{
+        AllowedTunnelTraffic, Endpoint, TransportProtocol,
+        wireguard::{Backend, PeerStats, PublicKey, TunnelParameters, TunnelStats},
+    },
 };
 use tokio::sync::Mutex as AsyncMutex;
 
@@ -52,6 +55,13 @@ mod mtu_detection;
 
 type TunnelType = Box<dyn Tunnel>;
 
+/// The userspace WireGuard implementation included in this build.
+const USERSPACE_BACKEND: Backend = if cfg!(feature = "boringtun") {
+    Backend::Boringtun
+} else {
+    Backend::WireguardGo
+};
+
 type Result<T> = std::result::Result<T, Error>;
 
 /// Errors that can happen in the Wireguard tunnel monitor.
@@ -85,6 +95,10 @@ pub enum Error {
     #[error("Failed while negotiating ephemeral peer")]
     EphemeralPeerNegotiationError(#[source] talpid_tunnel_config_client::Error),
 
+    /// The requested WireGuard implementation is not available in this build or on this platform
+    #[error("WireGuard backend \"{0}\" is not available")]
+    BackendUnavailable(Backend),
+
     /// Failed to set up IP interfaces.
     #[cfg(windows)]
     #[error("Failed to set up IP interfaces")]
@@ -138,10 +152,60 @@ pub struct WireguardMonitor {
     close_msg_receiver: sync_mpsc::Receiver<CloseMsg>,
     pinger_stop_sender: connectivity::CancelToken,
     obfuscator: Arc<AsyncMutex<Option<ObfuscatorHandle>>>,
+    stats_handle: TunnelStatsHandle,
 }
 
-#[cfg(not(target_os = "android"))]
-/// Overrides the preference for the kernel module for WireGuard.
+/// Handle used to read traffic statistics from a running WireGuard tunnel.
+#[derive(Clone)]
+pub struct TunnelStatsHandle {
+    tunnel: Weak<AsyncMutex<Option<TunnelType>>>,
+    entry_peer: Option<PublicKey>,
+    exit_peer: PublicKey,
+}
+
+impl TunnelStatsHandle {
+    fn new(tunnel: &Arc<AsyncMutex<Option<TunnelType>>>, params: &TunnelParameters) -> Self {
+        let connection = &params.connection;
+        let (entry_peer, exit_peer) = match &connection.exit_peer {
+            Some(exit_peer) => (
+                Some(connection.peer.public_key.clone()),
+                exit_peer.public_key.clone(),
+            ),
+            None => (None, connection.peer.public_key.clone()),
+        };
+        Self {
+            tunnel: Arc::downgrade(tunnel),
+            entry_peer,
+            exit_peer,
+        }
+    }
+
+    /// Returns the current traffic statistics for the entry and exit peers, or `None` if the
+    /// tunnel is no longer running.
+    pub async fn get_stats(&self) -> Option<std::result::Result<TunnelStats, TunnelError>> {
+        let tunnel = self.tunnel.upgrade()?;
+        let lock = tunnel.lock().await;
+        let stats = match lock.as_ref()?.get_tunnel_stats().await {
+            Ok(stats) => stats,
+            Err(error) => return Some(Err(error)),
+        };
+        let peer_stats = |public_key: &PublicKey| {
+            stats.get(public_key.as_bytes()).map(|stats| PeerStats {
+                public_key: public_key.clone(),
+                tx_bytes: stats.tx_bytes,
+                rx_bytes: stats.rx_bytes,
+                last_handshake: stats.last_handshake,
+            })
+        };
+        Some(Ok(TunnelStats {
+            entry: self.entry_peer.as_ref().and_then(peer_stats),
+            exit: peer_stats(&self.exit_peer),
+        }))
+    }
+}
+
+#[cfg(any(target_os = "linux", target_os = "windows"))]
+/// Overrides the preference for the kernel module for WireGuard when the backend is `Auto`.
 static FORCE_USERSPACE_WIREGUARD: LazyLock<bool> = LazyLock::new(|| {
     env::var("TALPID_FORCE_USERSPACE_WIREGUARD")
         .map(|v| v != "0")
@@ -164,6 +228,18 @@ impl WireguardMonitor {
         let desired_mtu = get_desired_mtu(params);
         let mut config = crate::config::Config::from_parameters(params, desired_mtu)
             .map_err(Error::WireguardConfigError)?;
+        if !config.fallback_peers.is_empty()
+            && (config.is_multihop()
+                || config.obfuscator_config.is_some()
+                || config.quantum_resistant
+                || config.daita)
+        {
+            log::warn!(
+                "Ignoring fallback peers, since they are not supported with multihop, obfuscation, \
+                 quantum-resistant tunnels or DAITA"
+            );
+            config.fallback_peers.clear();
+        }
 
         let endpoint_addrs = [params.get_next_hop_endpoint().address.ip()];
 
@@ -183,11 +259,7 @@ impl WireguardMonitor {
             config.mtu = clamp_mtu(params, config.mtu);
         }
 
-        // NOTE: We force userspace WireGuard while boringtun is enabled to more easily test
-        // the implementation, as DAITA is not currently supported by boringtun.
-        // TODO: Remove `cfg!(feature = "boringtun")`.
-        let userspace_wireguard =
-            *FORCE_USERSPACE_WIREGUARD || config.daita || cfg!(feature = "boringtun");
+        let backend = Self::select_backend(params.options.backend, &config)?;
 
         #[cfg(target_os = "windows")]
         let (setup_done_tx, setup_done_rx) = mpsc::channel(0);
@@ -202,10 +274,14 @@ impl WireguardMonitor {
             args.route_manager.clone(),
             #[cfg(target_os = "windows")]
             setup_done_tx,
-            userspace_wireguard,
+            backend,
+            #[cfg(target_os = "linux")]
+            (params.options.backend == Backend::Auto),
             _log_path,
         )?;
         let iface_name = tunnel.get_interface_name();
+        let backend = tunnel.backend();
+        log::info!("Using WireGuard backend: {backend}");
 
         let obfuscator = Arc::new(AsyncMutex::new(obfuscator));
 
@@ -216,13 +292,15 @@ impl WireguardMonitor {
             #[cfg(any(target_os = "macos", target_os = "linux"))]
             iface_name.clone(),
             args.retry_attempt,
-            cancel_receiver,
+            cancel_receiver.clone(),
         )
         .map_err(Error::ConnectivityMonitorError)?;
 
+        let tunnel = Arc::new(AsyncMutex::new(Some(tunnel)));
         let monitor = WireguardMonitor {
             runtime: args.runtime.clone(),
-            tunnel: Arc::new(AsyncMutex::new(Some(tunnel))),
+            stats_handle: TunnelStatsHandle::new(&tunnel, params),
+            tunnel,
             event_hook: args.event_hook.clone(),
             close_msg_receiver: close_obfs_listener,
             pinger_stop_sender: cancel_token,
@@ -239,7 +317,7 @@ impl WireguardMonitor {
             let close_obfs_sender: sync_mpsc::Sender<CloseMsg> = moved_close_obfs_sender;
             let obfuscator = moved_obfuscator;
             #[cfg(windows)]
-            if cfg!(feature = "boringtun") && userspace_wireguard {
+            if backend == Backend::Boringtun {
                 // NOTE: For boringtun, we use the `tun` crate to create our tunnel interface.
                 // It will automatically configure the IP address and DNS servers using `netsh`.
                 // This is quite slow, so we need to wait for the interface to be created.
@@ -249,7 +327,7 @@ impl WireguardMonitor {
                     .await?;
             }
 
-            let metadata = Self::tunnel_metadata(&iface_name, &config);
+            let metadata = Self::tunnel_metadata(&iface_name, &config, backend);
             let allowed_traffic = Self::allowed_traffic_during_tunnel_config(&config);
             event_hook
                 .on_event(TunnelEvent::InterfaceUp(metadata.clone(), allowed_traffic))
@@ -292,7 +370,7 @@ impl WireguardMonitor {
                     return Err(e);
                 }
 
-                let metadata = Self::tunnel_metadata(&iface_name, &config);
+                let metadata = Self::tunnel_metadata(&iface_name, &config, backend);
                 event_hook
                     .on_event(TunnelEvent::InterfaceUp(
                         metadata,
@@ -331,45 +409,54 @@ impl WireguardMonitor {
                 });
             }
 
-            let lock = tunnel.lock().await;
-            let borrowed_tun = lock.as_ref().expect("The tunnel was dropped unexpectedly");
-            match connectivity_monitor
-                .establish_connectivity(borrowed_tun.as_ref())
-                .await
-            {
-                Ok(true) => Ok(()),
-                Ok(false) => {
-                    log::warn!("Timeout while checking tunnel connection");
-                    Err(CloseMsg::PingErr)
-                }
-                Err(error) => {
-                    log::error!(
-                        "{}",
-                        error.display_chain_with_msg("Failed to check tunnel connection")
-                    );
-                    Err(CloseMsg::PingErr)
-                }
-            }?;
-            drop(lock);
+            // Switch to the next fallback peer whenever the current peer stops responding. Give up
+            // once all peers have failed in a row.
+            let mut tunnel_up = false;
+            let mut failed_peers = 0;
+            loop {
+                if Self::establish_connectivity(&tunnel, &mut connectivity_monitor).await {
+                    failed_peers = 0;
+                    if !tunnel_up {
+                        tunnel_up = true;
+
+                        // Add any default route(s) that may exist.
+                        args.route_manager
+                            .add_routes(
+                                Self::get_post_tunnel_routes(&iface_name, &config).collect(),
+                            )
+                            .await
+                            .map_err(Error::SetupRoutingError)
+                            .map_err(CloseMsg::SetupError)?;
 
-            // Add any default route(s) that may exist.
-            args.route_manager
-                .add_routes(Self::get_post_tunnel_routes(&iface_name, &config).collect())
-                .await
-                .map_err(Error::SetupRoutingError)
-                .map_err(CloseMsg::SetupError)?;
+                        let metadata = Self::tunnel_metadata(&iface_name, &config, backend);
+                        event_hook.on_event(TunnelEvent::Up(metadata)).await;
+                    }
 
-            let metadata = Self::tunnel_metadata(&iface_name, &config);
-            event_hook.on_event(TunnelEvent::Up(metadata)).await;
+                    if let Err(error) = connectivity::Monitor::init(connectivity_monitor)
+                        .run(Arc::downgrade(&tunnel))
+                        .await
+                    {
+                        log::error!(
+                            "{}",
+                            error.display_chain_with_msg("Connectivity monitor failed")
+                        );
+                    }
+                }
 
-            if let Err(error) = connectivity::Monitor::init(connectivity_monitor)
-                .run(Arc::downgrade(&tunnel))
-                .await
-            {
-                log::error!(
-                    "{}",
-                    error.display_chain_with_msg("Connectivity monitor failed")
-                );
+                failed_peers += 1;
+                if failed_peers > config.fallback_peers.len() || cancel_receiver.closed() {
+                    break;
+                }
+                Self::fail_over(&tunnel, &mut config, &mut event_hook, &args.route_manager).await?;
+                connectivity_monitor = connectivity::Check::new(
+                    gateway,
+                    #[cfg(any(target_os = "macos", target_os = "linux"))]
+                    iface_name.clone(),
+                    args.retry_attempt,
+                    cancel_receiver.clone(),
+                )
+                .map_err(Error::ConnectivityMonitorError)
+                .map_err(CloseMsg::SetupError)?;
             }
 
             Err::<Infallible, CloseMsg>(CloseMsg::PingErr)
@@ -426,6 +513,7 @@ impl WireguardMonitor {
             config.mtu = clamp_mtu(params, config.mtu);
         }
 
+        Self::select_backend(params.options.backend, &config)?;
         let should_negotiate_ephemeral_peer = config.quantum_resistant || config.daita;
 
         let (cancel_token, cancel_receiver) = connectivity::CancelToken::new();
@@ -464,10 +552,12 @@ impl WireguardMonitor {
             .map(Box::new)? as Box<dyn Tunnel>;
 
         let iface_name = tunnel.get_interface_name();
+        let backend = tunnel.backend();
         let tunnel = Arc::new(AsyncMutex::new(Some(tunnel)));
         let mut event_hook = args.event_hook;
         let monitor = WireguardMonitor {
             runtime: args.runtime.clone(),
+            stats_handle: TunnelStatsHandle::new(&tunnel, params),
             tunnel: Arc::clone(&tunnel),
             event_hook: event_hook.clone(),
             close_msg_receiver: close_obfs_listener,
@@ -481,7 +571,7 @@ impl WireguardMonitor {
             let close_obfs_sender: sync_mpsc::Sender<CloseMsg> = moved_close_obfs_sender;
             let obfuscator = moved_obfuscator;
 
-            let metadata = Self::tunnel_metadata(&iface_name, &config);
+            let metadata = Self::tunnel_metadata(&iface_name, &config, backend);
             let allowed_traffic = Self::allowed_traffic_during_tunnel_config(&config);
             event_hook
                 .on_event(TunnelEvent::InterfaceUp(metadata.clone(), allowed_traffic))
@@ -531,7 +621,7 @@ impl WireguardMonitor {
                     return Err(e);
                 }
 
-                let metadata = Self::tunnel_metadata(&iface_name, &config);
+                let metadata = Self::tunnel_metadata(&iface_name, &config, backend);
                 event_hook
                     .on_event(TunnelEvent::InterfaceUp(
                         metadata,
@@ -540,7 +630,7 @@ impl WireguardMonitor {
                     .await;
             }
 
-            let metadata = Self::tunnel_metadata(&iface_name, &config);
+            let metadata = Self::tunnel_metadata(&iface_name, &config, backend);
             event_hook.on_event(TunnelEvent::Up(metadata)).await;
 
             if let Err(error) = connectivity::Monitor::init(connectivity_monitor)
@@ -603,6 +693,74 @@ impl WireguardMonitor {
         AllowedTunnelTraffic::All
     }
 
+    /// Wait for the current peer to respond. Returns whether it did.
+    #[cfg(not(target_os = "android"))]
+    async fn establish_connectivity(
+        tunnel: &Arc<AsyncMutex<Option<TunnelType>>>,
+        connectivity_monitor: &mut connectivity::Check,
+    ) -> bool {
+        let lock = tunnel.lock().await;
+        let borrowed_tun = lock.as_ref().expect("The tunnel was dropped unexpectedly");
+        match connectivity_monitor
+            .establish_connectivity(borrowed_tun.as_ref())
+            .await
+        {
+            Ok(true) => true,
+            Ok(false) => {
+                log::warn!("Timeout while checking tunnel connection");
+                false
+            }
+            Err(error) => {
+                log::error!(
+                    "{}",
+                    error.display_chain_with_msg("Failed to check tunnel connection")
+                );
+                false
+            }
+        }
+    }
+
+    /// Replace the entry peer with the first fallback peer, and allow traffic to it. The previous
+    /// peer becomes the last fallback peer.
+    #[cfg(not(target_os = "android"))]
+    async fn fail_over(
+        tunnel: &Arc<AsyncMutex<Option<TunnelType>>>,
+        config: &mut Config,
+        event_hook: &mut EventHook,
+        route_manager: &talpid_routing::RouteManagerHandle,
+    ) -> std::result::Result<(), CloseMsg> {
+        let peer = config.fallback_peers.remove(0);
+        log::info!(
+            "Peer {} is not responding. Switching to fallback peer {}",
+            config.entry_peer.endpoint,
+            peer.endpoint
+        );
+
+        // Let the firewall allow traffic to the new peer before sending any
+        event_hook
+            .on_event(TunnelEvent::PeerChanged(peer.endpoint))
+            .await;
+        route_manager
+            .add_routes(Self::get_endpoint_routes(&[peer.endpoint.ip()]).collect())
+            .await
+            .map_err(Error::SetupRoutingError)
+            .map_err(CloseMsg::SetupError)?;
+
+        let previous_peer = std::mem::replace(&mut config.entry_peer, peer);
+        config.fallback_peers.push(previous_peer);
+
+        let mut lock = tunnel.lock().await;
+        let Some(tunnel) = lock.as_mut() else {
+            // The tunnel is being stopped
+            return Err(CloseMsg::Stop);
+        };
+        tunnel
+            .set_config(config.clone())
+            .await
+            .map_err(Error::TunnelError)
+            .map_err(CloseMsg::SetupError)
+    }
+
     #[cfg(windows)]
     async fn wait_for_ip_addresses(
         config: &Config,
@@ -675,12 +833,12 @@ impl WireguardMonitor {
         >,
         #[cfg(not(feature = "boringtun"))] route_manager: talpid_routing::RouteManagerHandle,
         setup_done_tx: mpsc::Sender<std::result::Result<(), BoxedError>>,
-        userspace_wireguard: bool,
+        backend: Backend,
         _log_path: Option<&Path>,
     ) -> Result<TunnelType> {
         log::debug!("Tunnel MTU: {}", config.mtu);
 
-        if userspace_wireguard {
+        if backend != Backend::Kernel {
             log::debug!("Using userspace WireGuard implementation");
 
             #[cfg(feature = "boringtun")]
@@ -712,7 +870,7 @@ impl WireguardMonitor {
         runtime: tokio::runtime::Handle,
         config: &Config,
         tun_provider: Arc<std::sync::Mutex<tun_provider::TunProvider>>,
-        _userspace_wireguard: bool,
+        _backend: Backend,
         _log_path: Option<&Path>,
     ) -> Result<TunnelType> {
         log::debug!("Tunnel MTU: {}", config.mtu);
@@ -740,12 +898,13 @@ impl WireguardMonitor {
         runtime: tokio::runtime::Handle,
         config: &Config,
         tun_provider: Arc<std::sync::Mutex<tun_provider::TunProvider>>,
-        userspace_wireguard: bool,
+        backend: Backend,
+        userspace_fallback: bool,
         _log_path: Option<&Path>,
     ) -> Result<TunnelType> {
         log::debug!("Tunnel MTU: {}", config.mtu);
 
-        if userspace_wireguard {
+        if backend != Backend::Kernel {
             log::debug!("Using userspace WireGuard implementation");
 
             #[cfg(not(feature = "boringtun"))]
@@ -767,6 +926,12 @@ impl WireguardMonitor {
                     .map(|tunnel| Box::new(tunnel) as TunnelType)
             };
 
+            if !userspace_fallback {
+                return res.map_err(|err| {
+                    Error::TunnelError(TunnelError::FatalStartWireguardError(Box::new(err)))
+                });
+            }
+
             res.or_else(|err| {
                     log::warn!("Failed to initialize kernel WireGuard tunnel, falling back to userspace WireGuard implementation:\n{}",err.display_chain() );
 
@@ -790,6 +955,11 @@ impl WireguardMonitor {
         }
     }
 
+    /// Returns a handle that can be used to read traffic statistics while the tunnel is running.
+    pub fn stats_handle(&self) -> TunnelStatsHandle {
+        self.stats_handle.clone()
+    }
+
     /// Blocks the current thread until tunnel disconnects
     pub fn wait(mut self) -> Result<()> {
         let wait_result = match self.close_msg_receiver.recv() {
@@ -961,14 +1131,42 @@ impl WireguardMonitor {
         }
     }
 
-    fn tunnel_metadata(interface_name: &str, config: &Config) -> TunnelMetadata {
+    fn tunnel_metadata(interface_name: &str, config: &Config, backend: Backend) -> TunnelMetadata {
         TunnelMetadata {
             interface: interface_name.to_string(),
             ips: config.tunnel.addresses.clone(),
             ipv4_gateway: config.ipv4_gateway,
             ipv6_gateway: config.ipv6_gateway,
+            wireguard_backend: Some(backend),
         }
     }
+
+    /// Resolve the requested WireGuard implementation to one that is available in this build and
+    /// on this platform.
+    fn select_backend(requested: Backend, config: &Config) -> Result<Backend> {
+        #[cfg(any(target_os = "linux", target_os = "windows"))]
+        let force_userspace = *FORCE_USERSPACE_WIREGUARD;
+        #[cfg(not(any(target_os = "linux", target_os = "windows")))]
+        let force_userspace = false;
+
+        resolve_backend(requested, config.daita, force_userspace)
+    }
+}
+
+/// Resolve `requested` to an available WireGuard implementation. A build only includes one of the
+/// userspace implementations, [USERSPACE_BACKEND], and the kernel implementation is only used on
+/// Linux and Windows.
+fn resolve_backend(requested: Backend, daita: bool, force_userspace: bool) -> Result<Backend> {
+    // DAITA is only implemented for the userspace backends
+    let kernel_available = cfg!(any(target_os = "linux", target_os = "windows")) && !daita;
+
+    match requested {
+        Backend::Auto if kernel_available && !force_userspace => Ok(Backend::Kernel),
+        Backend::Auto => Ok(USERSPACE_BACKEND),
+        Backend::Kernel if kernel_available => Ok(Backend::Kernel),
+        backend if backend == USERSPACE_BACKEND => Ok(backend),
+        backend => Err(Error::BackendUnavailable(backend)),
+    }
 }
 
 /// Log the tunnel stats from the current tunnel.
@@ -1009,6 +1207,8 @@ enum CloseMsg {
 #[async_trait::async_trait]
 pub(crate) trait Tunnel: Send + Sync {
     fn get_interface_name(&self) -> String;
+    /// Return the WireGuard implementation that the tunnel runs on.
+    fn backend(&self) -> Backend;
     fn stop(self: Box<Self>) -> std::result::Result<(), TunnelError>;
     async fn get_tunnel_stats(&self) -> std::result::Result<stats::StatsMap, TunnelError>;
     fn set_config<'a>(
@@ -1180,3 +1380,73 @@ fn clamp_mtu(params: &TunnelParameters, peer_mtu: u16) -> u16 {
         .saturating_sub(total_header_size)
         .clamp(min_mtu, max_peer_mtu)
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const HAS_KERNEL_BACKEND: bool = cfg!(any(target_os = "linux", target_os = "windows"));
+
+    /// The userspace implementation that is not included in this build.
+    const OTHER_USERSPACE_BACKEND: Backend = match USERSPACE_BACKEND {
+        Backend::Boringtun => Backend::WireguardGo,
+        _ => Backend::Boringtun,
+    };
+
+    #[test]
+    fn test_resolve_auto_backend() {
+        let expected = if HAS_KERNEL_BACKEND {
+            Backend::Kernel
+        } else {
+            USERSPACE_BACKEND
+        };
+        assert_eq!(
+            resolve_backend(Backend::Auto, false, false).unwrap(),
+            expected
+        );
+        assert_eq!(
+            resolve_backend(Backend::Auto, false, true).unwrap(),
+            USERSPACE_BACKEND
+        );
+    }
+
+    /// DAITA tunnels must use the userspace implementation
+    #[test]
+    fn test_resolve_daita_backend() {
+        assert_eq!(
+            resolve_backend(Backend::Auto, true, false).unwrap(),
+            USERSPACE_BACKEND
+        );
+        assert_eq!(
+            resolve_backend(USERSPACE_BACKEND, true, false).unwrap(),
+            USERSPACE_BACKEND
+        );
+        assert!(matches!(
+            resolve_backend(Backend::Kernel, true, false),
+            Err(Error::BackendUnavailable(Backend::Kernel))
+        ));
+    }
+
+    /// Explicitly requested backends must be used as is, or fail if they are unavailable
+    #[test]
+    fn test_resolve_requested_backend() {
+        let kernel = resolve_backend(Backend::Kernel, false, true);
+        if HAS_KERNEL_BACKEND {
+            assert_eq!(kernel.unwrap(), Backend::Kernel);
+        } else {
+            assert!(matches!(
+                kernel,
+                Err(Error::BackendUnavailable(Backend::Kernel))
+            ));
+        }
+
+        assert_eq!(
+            resolve_backend(USERSPACE_BACKEND, false, false).unwrap(),
+            USERSPACE_BACKEND
+        );
+        assert!(matches!(
+            resolve_backend(OTHER_USERSPACE_BACKEND, false, false),
+            Err(Error::BackendUnavailable(backend)) if backend == OTHER_USERSPACE_BACKEND
+        ));
+    }
+}
@@ -708,6 +708,10 @@ impl Tunnel for WgGoTunnel {
         self.handle().interface_name.clone()
     }
 
+    fn backend(&self) -> talpid_types::net::wireguard::Backend {
+        talpid_types::net::wireguard::Backend::WireguardGo
+    }
+
     fn stop(mut self: Box<Self>) -> Result<()> {
         WgGoTunnel::stop(&mut self)?;
         Ok(())
@@ -758,6 +762,7 @@ impl Tunnel for WgGoTunnel {
 
 mod stats {
     use super::{Stats, StatsMap};
+    use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
     #[derive(thiserror::Error, Debug, PartialEq)]
     pub enum Error {
@@ -775,6 +780,8 @@ mod stats {
             let mut peer = None;
             let mut tx_bytes = None;
             let mut rx_bytes = None;
+            let mut handshake_sec = 0;
+            let mut handshake_nsec = 0;
 
             // parts iterates over keys and values
             let parts = config.split('\n').filter_map(|line| {
@@ -793,6 +800,20 @@ mod stats {
                         peer = Some(buffer);
                         tx_bytes = None;
                         rx_bytes = None;
+                        handshake_sec = 0;
+                        handshake_nsec = 0;
+                    }
+                    "last_handshake_time_sec" => {
+                        handshake_sec = value
+                            .trim()
+                            .parse()
+                            .map_err(|err| Error::IntParse(value.to_string(), err))?;
+                    }
+                    "last_handshake_time_nsec" => {
+                        handshake_nsec = value
+                            .trim()
+                            .parse()
+                            .map_err(|err| Error::IntParse(value.to_string(), err))?;
                     }
                     "rx_bytes" => {
                         rx_bytes = Some(
@@ -822,20 +843,33 @@ mod stats {
                         Self {
                             tx_bytes: tx_bytes_val,
                             rx_bytes: rx_bytes_val,
+                            last_handshake: handshake_time(handshake_sec, handshake_nsec),
                         },
                     );
                     peer = None;
                     tx_bytes = None;
                     rx_bytes = None;
+                    handshake_sec = 0;
+                    handshake_nsec = 0;
                 }
             }
             Ok(map)
         }
     }
 
+    /// Convert a handshake timestamp from the UAPI config to a [SystemTime].
+    /// A zero timestamp means that no handshake has occurred.
+    fn handshake_time(secs: u64, nsecs: u32) -> Option<SystemTime> {
+        if secs == 0 && nsecs == 0 {
+            return None;
+        }
+        Some(UNIX_EPOCH + Duration::new(secs, nsecs))
+    }
+
     #[cfg(test)]
     mod test {
         use super::super::stats::{Error, Stats};
+        use std::time::{Duration, UNIX_EPOCH};
 
         #[test]
         fn test_parsing() {
@@ -848,6 +882,10 @@ mod stats {
             assert_eq!(actual_keys, [pubkey]);
             assert_eq!(stats[&pubkey].rx_bytes, 2396);
             assert_eq!(stats[&pubkey].tx_bytes, 2740);
+            assert_eq!(
+                stats[&pubkey].last_handshake,
+                Some(UNIX_EPOCH + Duration::new(1578420649, 369416131))
+            );
         }
 
         #[test]
@@ -69,6 +69,10 @@ impl Tunnel for NetworkManagerTunnel {
         self.interface_name.clone()
     }
 
+    fn backend(&self) -> talpid_types::net::wireguard::Backend {
+        talpid_types::net::wireguard::Backend::Kernel
+    }
+
     fn stop(mut self: Box<Self>) -> std::result::Result<(), TunnelError> {
         if let Some(tunnel) = self.tunnel.take() {
             if let Err(err) = self.network_manager.remove_tunnel(tunnel) {
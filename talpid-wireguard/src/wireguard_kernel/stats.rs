@@ -1,5 +1,6 @@
 use super::wg_message::{DeviceMessage, DeviceNla, PeerNla};
 use crate::stats::{Stats, StatsMap};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
 impl Stats {
     pub fn parse_device_message(message: &DeviceMessage) -> StatsMap {
@@ -11,17 +12,28 @@ impl Stats {
                     let mut tx_bytes = 0;
                     let mut rx_bytes = 0;
                     let mut pub_key = None;
+                    let mut last_handshake = None;
 
                     for nla in &msg.0 {
                         match nla {
                             PeerNla::TxBytes(bytes) => tx_bytes = *bytes,
                             PeerNla::RxBytes(bytes) => rx_bytes = *bytes,
                             PeerNla::PublicKey(key) => pub_key = Some(*key),
+                            PeerNla::LastHandshakeTime(time) => {
+                                last_handshake = handshake_time(Duration::from(*time))
+                            }
                             _ => continue,
                         }
                     }
                     if let Some(key) = pub_key {
-                        map.insert(key, Stats { tx_bytes, rx_bytes });
+                        map.insert(
+                            key,
+                            Stats {
+                                tx_bytes,
+                                rx_bytes,
+                                last_handshake,
+                            },
+                        );
                     }
                 }
             }
@@ -30,3 +42,13 @@ impl Stats {
         map
     }
 }
+
+/// Convert a last handshake time reported by the kernel, relative to the Unix epoch, to a
+/// [SystemTime].
+/// A zero timestamp means that no handshake has occurred.
+fn handshake_time(since_epoch: Duration) -> Option<SystemTime> {
+    if since_epoch.is_zero() {
+        return None;
+    }
+    Some(UNIX_EPOCH + since_epoch)
+}
@@ -22,6 +22,7 @@ use std::{
     pin::Pin,
     ptr,
     sync::{Arc, LazyLock, Mutex},
+    time::{Duration, SystemTime, UNIX_EPOCH},
 };
 #[cfg(daita)]
 use std::{ffi::c_uchar, path::PathBuf};
@@ -190,6 +191,9 @@ pub struct WgNtTunnel {
 
 const WIREGUARD_KEY_LENGTH: usize = 32;
 
+/// Number of 100-nanosecond intervals between 1601-01-01 and the Unix epoch.
+const FILETIME_UNIX_EPOCH_OFFSET: u64 = 116_444_736_000_000_000;
+
 /// See `WIREGUARD_ALLOWED_IP` at <https://git.zx2c4.com/wireguard-nt/tree/api/wireguard.h>.
 #[derive(Clone, Copy)]
 #[repr(C, align(8))]
@@ -919,6 +923,13 @@ impl Drop for WgNtDll {
     }
 }
 
+/// Convert a `FILETIME` timestamp, as reported for the last handshake, to a [SystemTime].
+/// Zero means that no handshake has occurred.
+fn filetime_to_system_time(filetime: u64) -> Option<SystemTime> {
+    let intervals = filetime.checked_sub(FILETIME_UNIX_EPOCH_OFFSET)?;
+    Some(UNIX_EPOCH + Duration::from_nanos(intervals.saturating_mul(100)))
+}
+
 fn load_wg_nt_dll(resource_dir: &Path) -> Result<&'static WgNtDll> {
     WG_NT_DLL.get_or_try_init(|| WgNtDll::new(resource_dir).map_err(Error::LoadDll))
 }
@@ -1077,6 +1088,7 @@ impl Tunnel for WgNtTunnel {
                     Stats {
                         tx_bytes: peer.tx_bytes,
                         rx_bytes: peer.rx_bytes,
+                        last_handshake: filetime_to_system_time(peer.last_handshake),
                     },
                 );
             }
@@ -1086,6 +1098,10 @@ impl Tunnel for WgNtTunnel {
         .unwrap()
     }
 
+    fn backend(&self) -> talpid_types::net::wireguard::Backend {
+        talpid_types::net::wireguard::Backend::Kernel
+    }
+
     fn stop(mut self: Box<Self>) -> std::result::Result<(), super::TunnelError> {
         self.stop_tunnel();
         Ok(())
@@ -1164,6 +1180,7 @@ mod tests {
             constant_packet_size: false,
         },
         exit_peer: None,
+        fallback_peers: vec![],
         ipv4_gateway: "0.0.0.0".parse().unwrap(),
         ipv6_gateway: None,
         mtu: 0,
@@ -83,8 +83,10 @@ pub async fn test_dns_leak_custom_public_ip(
             default_options: settings::DefaultDnsOptions::default(),
             custom_options: settings::CustomDnsOptions {
                 addresses: vec![CONFIG_IP],
+                ..Default::default()
             },
             state: settings::DnsState::Custom,
+            domain_rules: vec![],
         })
         .await
         .expect("failed to configure DNS server");
@@ -115,8 +117,10 @@ pub async fn test_dns_leak_custom_private_ip(
             default_options: settings::DefaultDnsOptions::default(),
             custom_options: settings::CustomDnsOptions {
                 addresses: vec![CONFIG_IP],
+                ..Default::default()
             },
             state: settings::DnsState::Custom,
+            domain_rules: vec![],
         })
         .await
         .expect("failed to configure DNS server");
@@ -366,8 +370,10 @@ pub async fn test_dns_config_custom_private(
             default_options: settings::DefaultDnsOptions::default(),
             custom_options: settings::CustomDnsOptions {
                 addresses: vec![IpAddr::V4(TEST_CONFIG.host_bridge_ip)],
+                ..Default::default()
             },
             state: settings::DnsState::Custom,
+            domain_rules: vec![],
         })
         .await
         .context("failed to configure DNS server")?;
@@ -400,8 +406,10 @@ pub async fn test_dns_config_custom_public(
             default_options: settings::DefaultDnsOptions::default(),
             custom_options: settings::CustomDnsOptions {
                 addresses: vec![custom_ip],
+                ..Default::default()
             },
             state: settings::DnsState::Custom,
+            domain_rules: vec![],
         })
         .await
         .context("failed to configure DNS server")?;
@@ -506,6 +514,7 @@ pub async fn test_content_blockers(
                 default_options: test_opts,
                 custom_options: settings::CustomDnsOptions::default(),
                 state: settings::DnsState::Default,
+                domain_rules: vec![],
             })
             .await
             .context("failed to configure DNS server")?;
@@ -676,6 +685,9 @@ async fn connect_local_wg_relay(mullvad_client: &mut MullvadProxyClient) -> Resu
             fwmark: None,
             ipv6_gateway: None,
         }),
+        psk: None,
+        dns_servers: vec![],
+        fallback_peers: vec![],
     };
     set_custom_endpoint(mullvad_client, custom_tunnel_endpoint)
         .await
@@ -192,6 +192,9 @@ pub async fn test_connecting_state(
         CustomTunnelEndpoint {
             host: "1.3.3.7".to_owned(),
             config: mullvad_types::ConnectionConfig::Wireguard(unreachable_wireguard_tunnel()),
+            psk: None,
+            dns_servers: vec![],
+            fallback_peers: vec![],
         },
     )
     .await
@@ -367,6 +370,7 @@ pub async fn test_connected_state(
                     obfuscation: _,
                     entry_endpoint: None,
                     tunnel_interface: _,
+                    wireguard_backend: _,
                     daita: _,
                 },
             ..